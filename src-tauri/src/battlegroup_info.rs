@@ -1,4 +1,5 @@
 use crate::config::{BATTLEGROUP_INFO_API_URL, BATTLEGROUP_INFO_CACHE_FILENAME};
+use crate::net::{Net, NetError};
 use log::{error, info, warn};
 use serde_json::Value;
 use std::fs;
@@ -16,20 +17,12 @@ pub struct BattlegroupInfoState {
 }
 
 /// Fetches battlegroup info from the API
-pub async fn fetch_battlegroup_info() -> Result<Value, reqwest::Error> {
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
-        .build()?;
-
-    let response = client
-        .get(BATTLEGROUP_INFO_API_URL)
-        .header("Accept-Encoding", "gzip, deflate, br")
-        .header("Accept", "application/json")
-        .send()
-        .await?
-        .error_for_status()?;
-
-    response.json::<Value>().await
+pub async fn fetch_battlegroup_info(net: &Net) -> Result<Value, NetError> {
+    net.get_json(
+        BATTLEGROUP_INFO_API_URL,
+        Duration::from_secs(REQUEST_TIMEOUT_SECS),
+    )
+    .await
 }
 
 /// Gets the cache file path
@@ -117,7 +110,7 @@ pub fn init_battlegroup_info<R: Runtime>(handle: AppHandle<R>) {
         }
 
         // Cache is stale or doesn't exist, fetch from API
-        match fetch_battlegroup_info().await {
+        match fetch_battlegroup_info(&crate::net::net(&handle)).await {
             Ok(data) => {
                 info!("Successfully fetched battlegroup info from API");
                 // Save to cache
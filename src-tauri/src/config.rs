@@ -1,5 +1,7 @@
-// Streamer overlay local PORT
-pub const OVERLAY_PORT: &str = "47824";
+// Streamer overlay local PORT, used unless another one is set in the settings
+pub const OVERLAY_PORT: u16 = 47824;
+pub const STREAMER_OVERLAY_FILENAME: &str = "streamerOverlay.html";
+pub const OVERLAY_THEME_DIRNAME: &str = "overlay-theme";
 
 // Game process name (lowercase for case-insensitive comparison)
 pub const GAME_PROCESS_NAME: &str = "reliccoh3.exe";
@@ -7,7 +9,24 @@ pub const GAME_PROCESS_NAME: &str = "reliccoh3.exe";
 // Map Stats API and Cache
 pub const MAP_STATS_CACHE_FILENAME: &str = "map_stats.json";
 pub const MAP_STATS_API_URL: &str = "https://coh3stats.com/api/getLatestPatchMapStats";
+pub const MAP_STATS_HISTORY_DIRNAME: &str = "map_stats_history";
 
 // Battlegroup Info API and Cache
 pub const BATTLEGROUP_INFO_CACHE_FILENAME: &str = "battlegroup_info.json";
 pub const BATTLEGROUP_INFO_API_URL: &str = "https://coh3stats.com/api/getBattlegroupInfo";
+
+// Relic API, used for player profile lookups
+pub const RELIC_API_BASE_URL: &str = "https://coh3-api.reliclink.com";
+
+// Streamer session scoreboard
+pub const SESSION_FILENAME: &str = "session.json";
+/// A restarted app continues the session if its last match is at most this long ago.
+pub const SESSION_WINDOW_HOURS: u64 = 12;
+
+// Twitch chat bot
+pub const TWITCH_IRC_HOST: &str = "irc.chat.twitch.tv";
+pub const TWITCH_IRC_TLS_PORT: u16 = 6697;
+
+// OBS WebSocket, built into OBS 28 and newer
+pub const OBS_WEBSOCKET_HOST: &str = "127.0.0.1";
+pub const OBS_WEBSOCKET_PORT: u16 = 4455;
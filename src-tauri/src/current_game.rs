@@ -0,0 +1,131 @@
+//! The backend's copy of the game the frontend is looking at.
+//!
+//! The log file is parsed on the frontend's schedule (`parse_log_file_reverse` is polled
+//! from `useRawGameData`). Every parse also lands here, so backend features can work
+//! with the current lobby without a round trip through the webview. When the main
+//! window's polling stalls - minimised, or its webview throttled - `refresh_if_stale`
+//! lets the backend parse the same file itself.
+//!
+//! The parse only ever describes the latest match, so the last one with a result is
+//! kept separately - it would otherwise be gone as soon as the next lobby loads.
+//!
+//! What changed since the previous parse is published to `LiveEvents` for the overlay,
+//! and the session scoreboard follows the local player's matches from it.
+//! OBS, the overlay hotkey and the in-game results panel follow its game state too.
+
+use crate::game_overlay::{self, hotkey as overlay_hotkey};
+use crate::live_events::{self, LiveEvents};
+use crate::obs_websocket;
+use crate::parse_log_file::{self, LogFileData, TeamPosition};
+use crate::session;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, Runtime};
+
+/// A finished match and how it ended.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LastMatch {
+    pub winner: TeamPosition,
+    /// Whether the local player's team won, `None` when they were not in the match.
+    pub local_player_won: Option<bool>,
+    pub game: LogFileData,
+}
+
+impl LastMatch {
+    /// The result of `game`, if the log has reported one yet.
+    pub fn from_game(game: &LogFileData) -> Option<Self> {
+        let winner = game.winner?;
+        Some(Self {
+            winner,
+            local_player_won: game.local_team().map(|team| team == winner),
+            game: game.clone(),
+        })
+    }
+}
+
+/// State for storing the latest parsed log file
+#[derive(Debug, Default)]
+pub struct CurrentGameState {
+    pub data: Mutex<Option<LogFileData>>,
+    pub last_match: Mutex<Option<LastMatch>>,
+    /// The log file last parsed, and when.
+    parsed: Mutex<Option<(String, Instant)>>,
+}
+
+/// Helper to safely lock the mutex, recovering from poison if needed
+fn lock_state_data(state: &CurrentGameState) -> std::sync::MutexGuard<'_, Option<LogFileData>> {
+    state.data.lock().unwrap_or_else(|poisoned| {
+        warn!("CurrentGameState mutex was poisoned, recovering");
+        poisoned.into_inner()
+    })
+}
+
+fn lock_last_match(state: &CurrentGameState) -> std::sync::MutexGuard<'_, Option<LastMatch>> {
+    state.last_match.lock().unwrap_or_else(|poisoned| {
+        warn!("CurrentGameState last match mutex was poisoned, recovering");
+        poisoned.into_inner()
+    })
+}
+
+fn lock_parsed(state: &CurrentGameState) -> std::sync::MutexGuard<'_, Option<(String, Instant)>> {
+    state.parsed.lock().unwrap_or_else(|poisoned| {
+        warn!("CurrentGameState parsed mutex was poisoned, recovering");
+        poisoned.into_inner()
+    })
+}
+
+/// The latest parsed log file, if the log has been read yet
+pub fn current<R: Runtime>(handle: &AppHandle<R>) -> Option<LogFileData> {
+    let state = handle.state::<CurrentGameState>();
+    let data = lock_state_data(&state).clone();
+    data
+}
+
+/// The most recent match with a known result
+pub fn last_match<R: Runtime>(handle: &AppHandle<R>) -> Option<LastMatch> {
+    let state = handle.state::<CurrentGameState>();
+    let last_match = lock_last_match(&state).clone();
+    last_match
+}
+
+/// Parses the log file and keeps the result as the current game.
+#[tauri::command]
+pub fn parse_log_file_reverse<R: Runtime>(handle: AppHandle<R>, path: String) -> LogFileData {
+    refresh(&handle, path)
+}
+
+/// Parses the log file last parsed again, unless that was within `max_age`. Nothing
+/// to do before the frontend has parsed it once.
+// Only the overlay follows the log, and only where there is one.
+#[cfg_attr(not(any(target_os = "windows", target_os = "linux")), allow(dead_code))]
+pub fn refresh_if_stale<R: Runtime>(handle: &AppHandle<R>, max_age: Duration) {
+    let parsed = lock_parsed(&handle.state::<CurrentGameState>()).clone();
+    if let Some((path, at)) = parsed {
+        if at.elapsed() >= max_age {
+            refresh(handle, path);
+        }
+    }
+}
+
+fn refresh<R: Runtime>(handle: &AppHandle<R>, path: String) -> LogFileData {
+    let data = parse_log_file::parse_log_file_reverse(path.clone());
+    let state = handle.state::<CurrentGameState>();
+    *lock_parsed(&state) = Some((path, Instant::now()));
+    if let Some(finished) = LastMatch::from_game(&data) {
+        *lock_last_match(&state) = Some(finished);
+    }
+    let previous = lock_state_data(&state).replace(data.clone());
+
+    let live_events = handle.state::<LiveEvents>();
+    for event in live_events::changes(previous.as_ref(), &data) {
+        live_events.publish(event);
+    }
+    session::observe(handle, previous.as_ref(), &data);
+    obs_websocket::observe(handle, previous.as_ref(), &data);
+    overlay_hotkey::observe(handle, previous.as_ref(), &data);
+    // After the hotkey, which takes down an overlay it brought up as the match ends.
+    game_overlay::observe(handle, previous.as_ref(), &data);
+    data
+}
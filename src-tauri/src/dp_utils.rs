@@ -1,4 +1,5 @@
 use serde::de::DeserializeOwned;
+use serde::Serialize;
 // COH3 Desktop App Utils
 use log::{error, info};
 use tauri::{AppHandle, Runtime};
@@ -46,3 +47,33 @@ pub fn load_from_store<R: Runtime, T: DeserializeOwned>(
         }
     }
 }
+
+pub fn save_to_store<R: Runtime, T: Serialize>(
+    handle: AppHandle<R>,
+    key: &str,
+    value: T,
+) -> Result<(), String> {
+    let store = handle.store("config.dat").map_err(|e| {
+        error!("Failed to get store 'config.dat': {}", e);
+        sentry::capture_message(
+            &format!("Store access error for 'config.dat': {}", e),
+            sentry::Level::Warning,
+        );
+        e.to_string()
+    })?;
+
+    let value = serde_json::to_value(value).map_err(|e| {
+        error!("Failed to serialize value for key '{}': {}", key, e);
+        e.to_string()
+    })?;
+    store.set(key, value);
+    // Written right away, so the value survives a crash
+    store.save().map_err(|e| {
+        error!("Failed to save store after setting '{}': {}", key, e);
+        sentry::capture_message(
+            &format!("Store save error for key '{}': {}", key, e),
+            sentry::Level::Warning,
+        );
+        e.to_string()
+    })
+}
@@ -2,8 +2,11 @@
 //!
 //! Deliberately free of any Win32 call so they compile and unit-test everywhere.
 
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+
 /// A window rectangle in physical pixels.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
 pub struct Bounds {
     pub x: i32,
     pub y: i32,
@@ -20,40 +23,170 @@ impl Bounds {
             height,
         }
     }
+
+    /// Whether the point `(x, y)` lies inside, right and bottom edges excluded.
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        (self.x..self.x + self.width).contains(&x) && (self.y..self.y + self.height).contains(&y)
+    }
 }
 
-/// Fraction of the game window the overlay covers.
+/// Fraction of the game window the default layout covers.
 const WIDTH_RATIO: f64 = 0.90;
 const HEIGHT_RATIO: f64 = 0.48;
-/// Clamps at 96 DPI - scaled by the game window's DPI before being applied.
+/// Clamps at 96 DPI for the default layout - scaled by the game window's DPI before
+/// being applied, and by the preset's ratios for any other layout.
 const MIN_WIDTH_96: i32 = 840;
 const MAX_WIDTH_96: i32 = 2880;
 const MIN_HEIGHT_96: i32 = 312;
 const MAX_HEIGHT_96: i32 = 1200;
 
+/// Bounds for user-supplied presets, so a stored layout can never make the overlay
+/// vanish or turn it into an invisible sheet over the game.
+const MIN_RATIO: f64 = 0.05;
+const MIN_OPACITY: f64 = 0.2;
+const MAX_MARGIN_96: i32 = 960;
+
+/// Which part of the game window the overlay sits against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Anchor {
+    /// Horizontally centred, top edge on the vertical middle. The loading art has its
+    /// subject in the upper half, so the tables begin at the middle rather than being
+    /// centred on it.
+    #[default]
+    Centre,
+    Top,
+    Bottom,
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+/// Where the overlay goes and how big it is, relative to the game window.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LayoutPreset {
+    pub anchor: Anchor,
+    /// Fraction of the safe area's width and height the overlay covers.
+    pub width_ratio: f64,
+    pub height_ratio: f64,
+    /// Distance from the anchored edges at 96 DPI. Ignored along a centred axis.
+    pub margin_x: i32,
+    pub margin_y: i32,
+    /// Opacity the overlay draws its content with. Only a hint for the webview - the
+    /// window itself stays fully transparent.
+    pub opacity: f64,
+}
+
+impl Default for LayoutPreset {
+    fn default() -> Self {
+        Self {
+            anchor: Anchor::Centre,
+            width_ratio: WIDTH_RATIO,
+            height_ratio: HEIGHT_RATIO,
+            margin_x: 0,
+            margin_y: 0,
+            opacity: 1.0,
+        }
+    }
+}
+
+impl LayoutPreset {
+    /// The preset with every value brought into a usable range. Non-finite numbers fall
+    /// back to the default layout's.
+    pub fn sanitised(self) -> Self {
+        let default = Self::default();
+        let ratio = |value: f64, fallback: f64| {
+            if value.is_finite() {
+                value.clamp(MIN_RATIO, 1.0)
+            } else {
+                fallback
+            }
+        };
+        Self {
+            anchor: self.anchor,
+            width_ratio: ratio(self.width_ratio, default.width_ratio),
+            height_ratio: ratio(self.height_ratio, default.height_ratio),
+            margin_x: self.margin_x.clamp(0, MAX_MARGIN_96),
+            margin_y: self.margin_y.clamp(0, MAX_MARGIN_96),
+            opacity: if self.opacity.is_finite() {
+                self.opacity.clamp(MIN_OPACITY, 1.0)
+            } else {
+                default.opacity
+            },
+        }
+    }
+}
+
 /// Scale a length expressed at 96 DPI to the given DPI.
 pub fn scale_for_dpi(length_96: i32, dpi: u32) -> i32 {
     let dpi = if dpi == 0 { 96 } else { dpi };
     ((length_96 as f64) * (dpi as f64 / 96.0)).round() as i32
 }
 
+/// Scale a 96 DPI clamp of the default layout to a preset's ratio.
+fn clamp_for_ratio(length_96: i32, ratio: f64, default_ratio: f64) -> i32 {
+    (length_96 as f64 * ratio / default_ratio).round() as i32
+}
+
 /// Overlay size for a game window of `game` size at `dpi`.
 ///
-/// A fixed fraction of the game window, clamped to DPI-scaled min/max, and never
-/// larger than the game window itself.
-pub fn overlay_size(game: Bounds, dpi: u32) -> (i32, i32) {
-    let min_w = scale_for_dpi(MIN_WIDTH_96, dpi);
-    let max_w = scale_for_dpi(MAX_WIDTH_96, dpi);
-    let min_h = scale_for_dpi(MIN_HEIGHT_96, dpi);
-    let max_h = scale_for_dpi(MAX_HEIGHT_96, dpi);
+/// The preset's fraction of the game window, clamped to DPI-scaled min/max, and never
+/// larger than the game window itself. The clamps grow and shrink with the preset's
+/// ratios, so a narrow strip stays a strip instead of being raised to the default size.
+pub fn overlay_size(game: Bounds, dpi: u32, layout: &LayoutPreset) -> (i32, i32) {
+    let clamp = |length_96, ratio, default_ratio| {
+        scale_for_dpi(clamp_for_ratio(length_96, ratio, default_ratio), dpi)
+    };
+    let min_w = clamp(MIN_WIDTH_96, layout.width_ratio, WIDTH_RATIO);
+    let max_w = clamp(MAX_WIDTH_96, layout.width_ratio, WIDTH_RATIO);
+    let min_h = clamp(MIN_HEIGHT_96, layout.height_ratio, HEIGHT_RATIO);
+    let max_h = clamp(MAX_HEIGHT_96, layout.height_ratio, HEIGHT_RATIO);
 
-    let w = ((game.width as f64 * WIDTH_RATIO).round() as i32).clamp(min_w.min(max_w), max_w);
-    let h = ((game.height as f64 * HEIGHT_RATIO).round() as i32).clamp(min_h.min(max_h), max_h);
+    let w =
+        ((game.width as f64 * layout.width_ratio).round() as i32).clamp(min_w.min(max_w), max_w);
+    let h =
+        ((game.height as f64 * layout.height_ratio).round() as i32).clamp(min_h.min(max_h), max_h);
 
     // A tiny windowed game must not get an overlay bigger than itself.
     (w.min(game.width.max(1)), h.min(game.height.max(1)))
 }
 
+/// Widest aspect ratio the game lays its loading screen and HUD out for. Anything wider
+/// is pillarboxed art with the HUD spread to the far edges.
+const WIDEST_ASPECT: (i32, i32) = (16, 9);
+/// Tallest aspect ratio placed against the whole window. Taller and portrait windows
+/// letterbox the art, so the overlay goes against the middle band instead.
+const TALLEST_ASPECT: (i32, i32) = (4, 3);
+
+/// How the window's aspect ratio compares to `w:h`. Cross-multiplied in `i64`, so
+/// there is no rounding at the boundaries.
+fn compare_aspect(game: Bounds, (w, h): (i32, i32)) -> Ordering {
+    (game.width as i64 * h as i64).cmp(&(game.height as i64 * w as i64))
+}
+
+/// The part of the game window the overlay is placed in.
+///
+/// Windows between 4:3 and 16:9 use all of it. Wider ones (21:9, 32:9) get the
+/// centred 16:9 area, so the overlay is not stretched over the whole HUD; taller and
+/// portrait ones get the centred 4:3 band.
+pub fn safe_area(game: Bounds) -> Bounds {
+    let (wide_w, wide_h) = WIDEST_ASPECT;
+    let (tall_w, tall_h) = TALLEST_ASPECT;
+    if compare_aspect(game, WIDEST_ASPECT) == Ordering::Greater {
+        let w = ((game.height as f64) * wide_w as f64 / wide_h as f64).round() as i32;
+        let (x, _) = centre_in(game, w, game.height);
+        Bounds::new(x, game.y, w, game.height)
+    } else if compare_aspect(game, TALLEST_ASPECT) == Ordering::Less {
+        let h = ((game.width as f64) * tall_h as f64 / tall_w as f64).round() as i32;
+        let (_, y) = centre_in(game, game.width, h);
+        Bounds::new(game.x, y, game.width, h)
+    } else {
+        game
+    }
+}
+
 /// Centre a `w x h` rectangle inside `outer`.
 pub fn centre_in(outer: Bounds, w: i32, h: i32) -> (i32, i32) {
     (
@@ -62,18 +195,88 @@ pub fn centre_in(outer: Bounds, w: i32, h: i32) -> (i32, i32) {
     )
 }
 
-/// Where the overlay starts vertically, as a fraction of the game window height.
-/// The loading art has its subject in the upper half, so the tables begin at the
-/// vertical middle rather than being centred.
+/// Where the centred overlay starts vertically, as a fraction of the safe area height.
 const TOP_RATIO: f64 = 0.50;
 
-/// Final overlay rectangle for a given game window: sized, horizontally centred and
-/// starting at the vertical middle, clamped so it never spills outside the game window.
-pub fn overlay_rect(game: Bounds, dpi: u32) -> Bounds {
-    let (w, h) = overlay_size(game, dpi);
-    let (x, _) = centre_in(game, w, h);
+/// Final overlay rectangle for a given game window: sized and placed against the
+/// preset's anchor within the window's `safe_area`, clamped so it never spills
+/// outside the game window.
+pub fn overlay_rect(game: Bounds, dpi: u32, layout: &LayoutPreset) -> Bounds {
+    let area = safe_area(game);
+    let (w, h) = overlay_size(area, dpi, layout);
+    let (centre_x, _) = centre_in(area, w, h);
+    let margin_x = scale_for_dpi(layout.margin_x, dpi);
+    let margin_y = scale_for_dpi(layout.margin_y, dpi);
+    let left = area.x + margin_x;
+    let right = area.x + area.width - w - margin_x;
+    let top = area.y + margin_y;
+    let bottom = area.y + area.height - h - margin_y;
+
+    let (x, y) = match layout.anchor {
+        Anchor::Centre => (
+            centre_x,
+            area.y + (area.height as f64 * TOP_RATIO).round() as i32,
+        ),
+        Anchor::Top => (centre_x, top),
+        Anchor::Bottom => (centre_x, bottom),
+        Anchor::TopLeft => (left, top),
+        Anchor::TopRight => (right, top),
+        Anchor::BottomLeft => (left, bottom),
+        Anchor::BottomRight => (right, bottom),
+    };
+    // Margins too big for the area give way before the overlay leaves the window.
     let x = x.clamp(game.x, game.x + (game.width - w).max(0));
-    let y = game.y + (game.height as f64 * TOP_RATIO).round() as i32;
     let y = y.clamp(game.y, game.y + (game.height - h).max(0));
     Bounds::new(x, y, w, h)
 }
+
+/// How the game puts itself on screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DisplayMode {
+    /// The game owns the display. Nothing else is composed over it, so the overlay
+    /// cannot be seen - and showing it could knock the game out of fullscreen.
+    ExclusiveFullscreen,
+    /// A frameless window covering its whole monitor.
+    BorderlessFullscreen,
+    Windowed,
+}
+
+impl DisplayMode {
+    /// Whether the overlay can be drawn over the game in this mode.
+    pub fn supports_overlay(&self) -> bool {
+        *self != Self::ExclusiveFullscreen
+    }
+}
+
+/// What the game window's styles say, as far as telling the display mode goes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct WindowStyle {
+    /// Has a title bar or a sizing border.
+    pub framed: bool,
+    /// The system reports an app running exclusive fullscreen. Only ever set while
+    /// the game is in the foreground - the report is about whichever app is.
+    pub exclusive: bool,
+}
+
+/// Tell the display mode from the game window's bounds, the bounds of the monitor it is
+/// on, and its styles.
+///
+/// A window that covers its monitor without a frame is fullscreen - a few pixels of
+/// overhang included, which borderless games often have. It is exclusive when the
+/// system says so; the bounds alone cannot tell, as an exclusive mode switch changes
+/// the monitor's bounds to match. Anything framed or smaller is windowed, a maximised
+/// window included.
+pub fn display_mode(window: Bounds, monitor: Bounds, style: WindowStyle) -> DisplayMode {
+    let covers = window.x <= monitor.x
+        && window.y <= monitor.y
+        && window.x + window.width >= monitor.x + monitor.width
+        && window.y + window.height >= monitor.y + monitor.height;
+    if !covers || style.framed {
+        DisplayMode::Windowed
+    } else if style.exclusive {
+        DisplayMode::ExclusiveFullscreen
+    } else {
+        DisplayMode::BorderlessFullscreen
+    }
+}
@@ -0,0 +1,545 @@
+//! Global hotkey bringing the overlay back during a match.
+//!
+//! The overlay is otherwise only up on the loading screen. While the log says the match
+//! is running, the configured key combination shows it again - for as long as the key
+//! is held, or until it is pressed again - using the same watcher and positioning.
+//!
+//! The shortcut is only registered while in a match, since a registered combination
+//! is swallowed system-wide, and a press only counts while the game has the foreground.
+//! A second combination switches the overlay into `interactive` mode; it is registered
+//! whenever the overlay is up, loading screen and results included.
+//! Parsing, conflict checks and the hold/toggle logic are plain functions, tested
+//! everywhere; the rest is the Tauri glue.
+
+use super::lock;
+use crate::dp_utils::{load_from_store, save_to_store};
+use crate::parse_log_file::{GameState, LogFileData};
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, Runtime};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutEvent, ShortcutState};
+
+const HOTKEY_STORE_KEY: &str = "gameOverlayHotkey";
+
+/// A parsed key combination: modifiers plus exactly one key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Binding {
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+    pub super_key: bool,
+    /// Canonical key name, as the shortcut plugin reads it.
+    pub key: &'static str,
+}
+
+impl fmt::Display for Binding {
+    /// The canonical form, e.g. `Ctrl+Shift+O`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (held, name) in [
+            (self.ctrl, "Ctrl"),
+            (self.alt, "Alt"),
+            (self.shift, "Shift"),
+            (self.super_key, "Super"),
+        ] {
+            if held {
+                write!(f, "{}+", name)?;
+            }
+        }
+        f.write_str(self.key)
+    }
+}
+
+/// Keys a binding can use, as `(canonical name, other spellings)`. Limited to keys
+/// every keyboard layout has in the same place.
+const KEYS: &[(&str, &[&str])] = &[
+    ("A", &[]),
+    ("B", &[]),
+    ("C", &[]),
+    ("D", &[]),
+    ("E", &[]),
+    ("F", &[]),
+    ("G", &[]),
+    ("H", &[]),
+    ("I", &[]),
+    ("J", &[]),
+    ("K", &[]),
+    ("L", &[]),
+    ("M", &[]),
+    ("N", &[]),
+    ("O", &[]),
+    ("P", &[]),
+    ("Q", &[]),
+    ("R", &[]),
+    ("S", &[]),
+    ("T", &[]),
+    ("U", &[]),
+    ("V", &[]),
+    ("W", &[]),
+    ("X", &[]),
+    ("Y", &[]),
+    ("Z", &[]),
+    ("0", &["Digit0"]),
+    ("1", &["Digit1"]),
+    ("2", &["Digit2"]),
+    ("3", &["Digit3"]),
+    ("4", &["Digit4"]),
+    ("5", &["Digit5"]),
+    ("6", &["Digit6"]),
+    ("7", &["Digit7"]),
+    ("8", &["Digit8"]),
+    ("9", &["Digit9"]),
+    ("F1", &[]),
+    ("F2", &[]),
+    ("F3", &[]),
+    ("F4", &[]),
+    ("F5", &[]),
+    ("F6", &[]),
+    ("F7", &[]),
+    ("F8", &[]),
+    ("F9", &[]),
+    ("F10", &[]),
+    ("F11", &[]),
+    ("F12", &[]),
+    ("Numpad0", &["Num0"]),
+    ("Numpad1", &["Num1"]),
+    ("Numpad2", &["Num2"]),
+    ("Numpad3", &["Num3"]),
+    ("Numpad4", &["Num4"]),
+    ("Numpad5", &["Num5"]),
+    ("Numpad6", &["Num6"]),
+    ("Numpad7", &["Num7"]),
+    ("Numpad8", &["Num8"]),
+    ("Numpad9", &["Num9"]),
+    ("Backquote", &["`"]),
+    ("Tab", &[]),
+    ("Space", &[]),
+    ("Enter", &["Return"]),
+    ("Escape", &["Esc"]),
+    ("Insert", &["Ins"]),
+    ("Delete", &["Del"]),
+    ("Home", &[]),
+    ("End", &[]),
+    ("PageUp", &["PgUp"]),
+    ("PageDown", &["PgDn"]),
+    ("ArrowUp", &["Up"]),
+    ("ArrowDown", &["Down"]),
+    ("ArrowLeft", &["Left"]),
+    ("ArrowRight", &["Right"]),
+    ("Pause", &[]),
+    ("ScrollLock", &[]),
+];
+
+/// Why a key combination was refused.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BindingError {
+    Empty,
+    /// `Ctrl++O` and the like.
+    EmptyPart,
+    UnknownKey(String),
+    DuplicateModifier(&'static str),
+    /// Only modifiers, e.g. `Ctrl+Shift`.
+    NoKey,
+    MoreThanOneKey,
+    /// Windows keeps the combination for itself.
+    ReservedBySystem,
+    /// The game or its chat needs the key, and a global shortcut would swallow it.
+    UsedByTheGame,
+    /// The other overlay hotkey has the combination already.
+    AlreadyUsed,
+}
+
+impl fmt::Display for BindingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => f.write_str("No key combination given"),
+            Self::EmptyPart => f.write_str("The key combination has an empty part"),
+            Self::UnknownKey(key) => write!(f, "Unknown key \"{}\"", key),
+            Self::DuplicateModifier(modifier) => write!(f, "{} is given twice", modifier),
+            Self::NoKey => f.write_str("The key combination needs a key besides modifiers"),
+            Self::MoreThanOneKey => f.write_str("Only one key besides modifiers can be used"),
+            Self::ReservedBySystem => f.write_str("Windows reserves this key combination"),
+            Self::UsedByTheGame => {
+                f.write_str("The game needs this key - add Ctrl or Alt, or use a function key")
+            }
+            Self::AlreadyUsed => f.write_str("The other overlay hotkey uses this combination"),
+        }
+    }
+}
+
+/// Parse a combination such as `ctrl+shift+o`. Case, spacing and the order of the parts
+/// do not matter.
+pub fn parse_binding(text: &str) -> Result<Binding, BindingError> {
+    if text.trim().is_empty() {
+        return Err(BindingError::Empty);
+    }
+    let mut binding = Binding {
+        ctrl: false,
+        alt: false,
+        shift: false,
+        super_key: false,
+        key: "",
+    };
+    for part in text.split('+').map(str::trim) {
+        if part.is_empty() {
+            return Err(BindingError::EmptyPart);
+        }
+        let modifier = match part.to_ascii_lowercase().as_str() {
+            "ctrl" | "control" => Some((&mut binding.ctrl, "Ctrl")),
+            "alt" | "option" => Some((&mut binding.alt, "Alt")),
+            "shift" => Some((&mut binding.shift, "Shift")),
+            "super" | "win" | "windows" | "meta" | "cmd" => Some((&mut binding.super_key, "Super")),
+            _ => None,
+        };
+        if let Some((held, name)) = modifier {
+            if *held {
+                return Err(BindingError::DuplicateModifier(name));
+            }
+            *held = true;
+            continue;
+        }
+        if !binding.key.is_empty() {
+            return Err(BindingError::MoreThanOneKey);
+        }
+        binding.key = KEYS
+            .iter()
+            .find(|(name, aliases)| {
+                name.eq_ignore_ascii_case(part)
+                    || aliases.iter().any(|alias| alias.eq_ignore_ascii_case(part))
+            })
+            .map(|(name, _)| *name)
+            .ok_or_else(|| BindingError::UnknownKey(part.to_string()))?;
+    }
+    if binding.key.is_empty() {
+        return Err(BindingError::NoKey);
+    }
+    Ok(binding)
+}
+
+/// Whether the combination clashes with Windows or with the game.
+pub fn check_conflicts(binding: &Binding) -> Result<(), BindingError> {
+    let Binding {
+        ctrl,
+        alt,
+        shift: _,
+        super_key,
+        key,
+    } = *binding;
+
+    // The Windows key combinations, task switching and the secure attention sequence
+    // are never delivered to a registered hotkey - or worse, are taken away from Windows.
+    let reserved = super_key
+        || (alt && !ctrl && matches!(key, "Tab" | "F4" | "Escape" | "Space"))
+        || (ctrl && !alt && matches!(key, "Escape"))
+        || (ctrl && alt && key == "Delete");
+    if reserved {
+        return Err(BindingError::ReservedBySystem);
+    }
+
+    // Without Ctrl or Alt, anything but the function and navigation keys is a unit
+    // command, camera control or chat input. Ctrl and Shift with a digit are control
+    // groups.
+    let game_key = key.len() == 1
+        || matches!(
+            key,
+            "Backquote"
+                | "Tab"
+                | "Space"
+                | "Enter"
+                | "Escape"
+                | "Delete"
+                | "ArrowUp"
+                | "ArrowDown"
+                | "ArrowLeft"
+                | "ArrowRight"
+        );
+    let control_group = key.len() == 1 && key.as_bytes()[0].is_ascii_digit() && !alt;
+    if (game_key && !ctrl && !alt) || control_group {
+        return Err(BindingError::UsedByTheGame);
+    }
+    Ok(())
+}
+
+/// Parse and check a combination, returning its canonical form.
+pub fn validate_binding(text: &str) -> Result<Binding, BindingError> {
+    let binding = parse_binding(text)?;
+    check_conflicts(&binding)?;
+    Ok(binding)
+}
+
+/// How the hotkey brings the overlay up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HotkeyMode {
+    /// Shown while the key is held.
+    Hold,
+    /// Shown and hidden on alternate presses.
+    #[default]
+    Toggle,
+}
+
+/// The stored hotkey settings.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HotkeyConfig {
+    pub enabled: bool,
+    pub binding: String,
+    pub mode: HotkeyMode,
+    /// Switches the overlay in and out of interactive mode while it is up.
+    pub interact_binding: String,
+}
+
+impl Default for HotkeyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            binding: "Ctrl+Shift+O".to_string(),
+            mode: HotkeyMode::Toggle,
+            interact_binding: "Ctrl+Shift+I".to_string(),
+        }
+    }
+}
+
+impl HotkeyConfig {
+    /// The settings with both combinations checked and in their canonical form. Errors
+    /// about the interact combination say so.
+    pub fn validated(self) -> Result<Self, String> {
+        let binding = validate_binding(&self.binding).map_err(|e| e.to_string())?;
+        let interact = validate_binding(&self.interact_binding)
+            .and_then(|interact| {
+                if interact == binding {
+                    Err(BindingError::AlreadyUsed)
+                } else {
+                    Ok(interact)
+                }
+            })
+            .map_err(|e| format!("Interact hotkey: {}", e))?;
+        Ok(Self {
+            binding: binding.to_string(),
+            interact_binding: interact.to_string(),
+            ..self
+        })
+    }
+}
+
+/// The overlay's state as far as the hotkey is concerned.
+#[derive(Debug, Default)]
+pub struct Peek {
+    /// The key is down. Held keys repeat, and only the first press of a hold counts.
+    down: bool,
+    /// The hotkey has the overlay up.
+    pub shown: bool,
+}
+
+impl Peek {
+    /// Feed a key event; returns whether to show (`Some(true)`) or hide (`Some(false)`)
+    /// the overlay, or `None` to leave it.
+    pub fn key(&mut self, mode: HotkeyMode, pressed: bool) -> Option<bool> {
+        let first_press = pressed && !self.down;
+        self.down = pressed;
+        let show = match mode {
+            HotkeyMode::Hold => pressed,
+            HotkeyMode::Toggle if first_press => !self.shown,
+            HotkeyMode::Toggle => return None,
+        };
+        (show != self.shown).then(|| {
+            self.shown = show;
+            show
+        })
+    }
+
+    /// The match ended or the hotkey was turned off: forget the key and the overlay.
+    /// Returns whether the overlay was up and has to be hidden.
+    pub fn reset(&mut self) -> bool {
+        self.down = false;
+        std::mem::take(&mut self.shown)
+    }
+}
+
+/// State for the overlay hotkey
+#[derive(Default)]
+pub struct HotkeyState {
+    config: Mutex<HotkeyConfig>,
+    /// The shortcut as registered, while in a match.
+    registered: Mutex<Option<Shortcut>>,
+    peek: Mutex<Peek>,
+    /// The interact shortcut as registered, while the overlay is up.
+    interact: Mutex<Option<Shortcut>>,
+    /// Held for a whole `sync_interact`, so two of them cannot interleave.
+    interact_sync: Mutex<()>,
+}
+
+/// Load the stored hotkey settings. Called once at startup.
+pub fn load<R: Runtime>(handle: &AppHandle<R>) {
+    let config =
+        load_from_store::<R, HotkeyConfig>(handle.clone(), HOTKEY_STORE_KEY).unwrap_or_default();
+    *lock(&handle.state::<HotkeyState>().config, "hotkey config") = config;
+}
+
+fn in_match(game: Option<&LogFileData>) -> bool {
+    matches!(game.map(|game| &game.game_state), Some(GameState::InGame))
+}
+
+/// Register the shortcut if the overlay and the hotkey are on, replacing any earlier one.
+fn register<R: Runtime>(handle: &AppHandle<R>) {
+    unregister(handle);
+    let state = handle.state::<HotkeyState>();
+    let config = lock(&state.config, "hotkey config").clone();
+    if !config.enabled || !super::is_enabled(handle) {
+        return;
+    }
+    // Non-store builds have no overlay window to show.
+    if handle
+        .get_webview_window(super::OVERLAY_WINDOW_LABEL)
+        .is_none()
+    {
+        return;
+    }
+    let Some(shortcut) = shortcut_for(&config.binding) else {
+        return;
+    };
+    match handle.global_shortcut().register(shortcut) {
+        Ok(()) => {
+            info!("Overlay hotkey {} registered", config.binding);
+            *lock(&state.registered, "hotkey registration") = Some(shortcut);
+        }
+        // Most likely another app holds the same combination.
+        Err(e) => error!("Overlay hotkey {} not registered: {}", config.binding, e),
+    }
+}
+
+/// The shortcut for a stored combination, `None` when it is not usable.
+fn shortcut_for(binding: &str) -> Option<Shortcut> {
+    let shortcut = validate_binding(binding)
+        .map_err(|e| e.to_string())
+        .and_then(|binding| {
+            binding
+                .to_string()
+                .parse::<Shortcut>()
+                .map_err(|e| e.to_string())
+        });
+    match shortcut {
+        Ok(shortcut) => Some(shortcut),
+        Err(e) => {
+            warn!("Overlay hotkey {:?} not usable: {}", binding, e);
+            None
+        }
+    }
+}
+
+/// Register the interact shortcut while the overlay is up and the hotkeys are on, and
+/// drop it otherwise.
+///
+/// Runs on its own thread: the overlay is shown and hidden from the shortcut handler
+/// as well, and the plugin cannot be called from there - it holds its shortcut table
+/// while a handler runs.
+pub fn sync_interact<R: Runtime>(handle: &AppHandle<R>) {
+    let handle = handle.clone();
+    std::thread::spawn(move || {
+        let state = handle.state::<HotkeyState>();
+        let _sync = lock(&state.interact_sync, "interact sync");
+        let config = lock(&state.config, "hotkey config").clone();
+        let wanted = if config.enabled && super::is_wanted(&handle) && super::is_enabled(&handle) {
+            shortcut_for(&config.interact_binding)
+        } else {
+            None
+        };
+        // Never held across a plugin call, which the shortcut handler would wait on.
+        let current = *lock(&state.interact, "interact");
+        if current == wanted {
+            return;
+        }
+        if let Some(shortcut) = current {
+            if let Err(e) = handle.global_shortcut().unregister(shortcut) {
+                error!("Overlay interact hotkey could not be unregistered: {}", e);
+            }
+            *lock(&state.interact, "interact") = None;
+        }
+        if let Some(shortcut) = wanted {
+            match handle.global_shortcut().register(shortcut) {
+                Ok(()) => *lock(&state.interact, "interact") = Some(shortcut),
+                Err(e) => error!(
+                    "Overlay interact hotkey {} not registered: {}",
+                    config.interact_binding, e
+                ),
+            }
+        }
+    });
+}
+
+/// Unregister the shortcut and take down an overlay it brought up.
+fn unregister<R: Runtime>(handle: &AppHandle<R>) {
+    let state = handle.state::<HotkeyState>();
+    if let Some(shortcut) = lock(&state.registered, "hotkey registration").take() {
+        if let Err(e) = handle.global_shortcut().unregister(shortcut) {
+            error!("Overlay hotkey could not be unregistered: {}", e);
+        }
+    }
+    if lock(&state.peek, "peek").reset() {
+        super::hide(handle);
+    }
+}
+
+/// Registers the hotkey when a match starts and drops it when the match is over.
+pub fn observe<R: Runtime>(handle: &AppHandle<R>, prev: Option<&LogFileData>, next: &LogFileData) {
+    match (in_match(prev), in_match(Some(next))) {
+        (false, true) => register(handle),
+        (true, false) => unregister(handle),
+        _ => {}
+    }
+}
+
+/// Handler for the global shortcut plugin.
+pub fn on_shortcut<R: Runtime>(handle: &AppHandle<R>, shortcut: &Shortcut, event: ShortcutEvent) {
+    let state = handle.state::<HotkeyState>();
+    if *lock(&state.interact, "interact") == Some(*shortcut) {
+        if event.state == ShortcutState::Pressed && super::game_has_focus() {
+            super::toggle_interactive(handle);
+        }
+        return;
+    }
+    if *lock(&state.registered, "hotkey registration") != Some(*shortcut) {
+        return;
+    }
+    let pressed = event.state == ShortcutState::Pressed;
+    // Presses while another app is in front are not meant for the overlay.
+    if pressed && !super::game_has_focus() {
+        return;
+    }
+    let mode = lock(&state.config, "hotkey config").mode;
+    let show = lock(&state.peek, "peek").key(mode, pressed);
+    match show {
+        Some(true) => {
+            super::show(handle, super::modes::OverlayMode::Matchup);
+        }
+        Some(false) => super::hide(handle),
+        None => {}
+    }
+}
+
+/// Tauri command returning the hotkey settings
+#[tauri::command]
+pub fn game_overlay_hotkey<R: Runtime>(handle: AppHandle<R>) -> HotkeyConfig {
+    lock(&handle.state::<HotkeyState>().config, "hotkey config").clone()
+}
+
+/// Tauri command checking, storing and applying the hotkey settings. The bindings are
+/// returned in their canonical form.
+#[tauri::command]
+pub fn game_overlay_set_hotkey<R: Runtime>(
+    handle: AppHandle<R>,
+    config: HotkeyConfig,
+) -> Result<HotkeyConfig, String> {
+    let config = config.validated()?;
+    save_to_store(handle.clone(), HOTKEY_STORE_KEY, &config)?;
+    *lock(&handle.state::<HotkeyState>().config, "hotkey config") = config.clone();
+    // Applied right away when in a match, otherwise when the next one starts.
+    if in_match(crate::current_game::current(&handle).as_ref()) {
+        register(&handle);
+    } else {
+        unregister(&handle);
+    }
+    sync_interact(&handle);
+    Ok(config)
+}
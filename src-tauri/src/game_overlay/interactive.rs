@@ -0,0 +1,85 @@
+//! Interactive mode: the overlay takes clicks for a little while.
+//!
+//! Normally the overlay is click-through, so it can never get in the way of the game.
+//! Interactive mode drops `WS_EX_TRANSPARENT`, so a player in it can be clicked, but
+//! keeps `WS_EX_NOACTIVATE`: entering it, and clicking, leave the game with the
+//! foreground. Only what a click asks for - a profile opening in the browser - takes
+//! it away.
+//!
+//! `Interaction::check` runs on every watcher tick and says when to go back to
+//! click-through.
+
+use std::time::{Duration, Instant};
+
+/// Interactive mode never outlasts this, whatever the pointer does.
+pub const TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Why interactive mode ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Exit {
+    /// Asked for, by the hotkey or the command.
+    Requested,
+    /// The pointer was over the overlay and has left it.
+    PointerLeft,
+    TimedOut,
+    /// The game got the foreground back after something else had it.
+    GameFocused,
+    /// The overlay went off screen.
+    Hidden,
+}
+
+/// One tick's look at the things interactive mode ends on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Look {
+    /// Whether the overlay is on screen.
+    pub shown: bool,
+    /// `None` when the pointer or the overlay's place cannot be told.
+    pub pointer_inside: Option<bool>,
+    /// `None` when the foreground cannot be told.
+    pub game_in_foreground: Option<bool>,
+}
+
+/// An interactive spell, from entering it until `check` says it is over.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Interaction {
+    since: Instant,
+    /// The pointer has been over the overlay. Until then it cannot leave it - entering
+    /// by hotkey leaves the pointer wherever the game had it.
+    pointer_seen: bool,
+    /// Something other than the game has had the foreground.
+    away_seen: bool,
+}
+
+impl Interaction {
+    pub fn new(now: Instant) -> Self {
+        Self {
+            since: now,
+            pointer_seen: false,
+            away_seen: false,
+        }
+    }
+
+    /// Whether interactive mode is over at `now`, given what the desktop looks like.
+    pub fn check(&mut self, now: Instant, look: Look) -> Option<Exit> {
+        if !look.shown {
+            return Some(Exit::Hidden);
+        }
+        if now.saturating_duration_since(self.since) >= TIMEOUT {
+            return Some(Exit::TimedOut);
+        }
+        match look.pointer_inside {
+            Some(true) => self.pointer_seen = true,
+            Some(false) if self.pointer_seen => return Some(Exit::PointerLeft),
+            _ => {}
+        }
+        // Entered from the app's own window, the game is away to begin with; entered by
+        // hotkey, it goes away once a click opens something. Either way its coming back
+        // means the user is done here.
+        match look.game_in_foreground {
+            Some(false) => self.away_seen = true,
+            Some(true) if self.away_seen => return Some(Exit::GameFocused),
+            _ => {}
+        }
+        None
+    }
+}
@@ -1,34 +1,103 @@
 //! In-Game Matchup Overlay
 //!
 //! Shows the matchup on top of the CoH3 window while the game is on its loading
-//! screen. This module owns only the *window mechanics*: a single transparent,
-//! always-on-top, click-through webview window that is created hidden at startup and
-//! afterwards only shown, hidden and repositioned.
+//! screen, and the result once the match is over. This module owns the *window
+//! mechanics*: a single transparent, always-on-top, click-through webview window that
+//! is created hidden at startup and afterwards only shown, hidden and repositioned.
 //!
-//! The content is pushed in from the frontend (`game-overlay:data` event); nothing in
-//! here knows about players or matches.
+//! What it shows is the backend's own: `observe` follows the parsed log, puts the
+//! matchup together on a loading screen (`payload`) and the results panel after the
+//! match, and pushes them straight to the overlay window. The main window is not needed
+//! for it - should its log polling stall, the backend reads the log itself. Either
+//! `modes::OverlayMode` stays up no longer than its stored timeout. Where the window
+//! goes is the stored `geometry::LayoutPreset`, which can be switched while the overlay
+//! is up. What the overlay is doing, and why, is published as `game-overlay:status`.
+//! While it is up the watcher also looks at how the game is displayed, and warns when
+//! that is exclusive fullscreen, which nothing can be drawn over.
+//!
+//! The overlay is click-through, except in `interactive` mode, which the interact
+//! hotkey or `game_overlay_interactive` turn on for a short while.
 //!
 //! Not to be confused with `overlay_server.rs`, which serves the OBS *streamer*
 //! overlay over HTTP - a different feature.
 //!
-//! Windows-only. Every public function has a no-op stub on other platforms.
+//! Windows, and Linux through X11 - see `window_detector`. Every public function has a
+//! no-op stub on other platforms.
 
 pub mod geometry;
-mod window_detector;
-
-use geometry::Bounds;
-use log::{error, info};
+pub mod hotkey;
+pub mod interactive;
+pub mod modes;
+pub mod payload;
+pub mod watcher;
+pub(crate) mod window_detector;
+
+use crate::current_game::{self, LastMatch};
+use crate::dp_utils::{load_from_store, save_to_store};
+use crate::parse_log_file::LogFileData;
+use crate::player_profiles::PlayerProfilesState;
+use crate::session;
+use geometry::{Bounds, DisplayMode, LayoutPreset};
+use interactive::{Exit, Interaction};
+use log::{error, info, warn};
+use modes::{ModeTimeouts, OverlayMode, Showing};
+use payload::MatchupPayload;
+use std::collections::HashMap;
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
-use std::sync::Arc;
-use tauri::{AppHandle, Manager, Runtime};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+use watcher::{
+    Decision, Observation, OverlayStatus, OverlaySurface, Reason, Tick, TickState, Visibility,
+};
 
 /// Label of the overlay window. `src/main.tsx` branches on it, and
 /// `game-overlay-capabilities.json` scopes permissions to it.
 pub const OVERLAY_WINDOW_LABEL: &str = "game-overlay";
 
+/// Backend -> overlay window: the `payload::MatchupPayload` to draw.
+const DATA_EVENT: &str = "game-overlay:data";
+
+/// Overlay window -> backend: it has mounted, and wants what it may have missed.
+#[cfg(any(target_os = "windows", target_os = "linux"))]
+const READY_EVENT: &str = "game-overlay:ready";
+
+/// Backend -> overlay window: the layout in use, for the hints the webview applies
+/// itself (opacity, which edge the content hangs from).
+const LAYOUT_EVENT: &str = "game-overlay:layout";
+
+/// Backend -> all windows: the overlay's `OverlayStatus`, on every change.
+const STATUS_EVENT: &str = "game-overlay:status";
+
+/// Backend -> overlay window: the `OverlayMode` to draw, whenever the overlay is shown.
+const MODE_EVENT: &str = "game-overlay:mode";
+
+/// Backend -> overlay window: the results panel's `MatchResults`.
+const RESULTS_EVENT: &str = "game-overlay:results";
+
+/// Backend -> all windows: whether the overlay is interactive, on every change.
+const INTERACTIVE_EVENT: &str = "game-overlay:interactive";
+
+/// Backend -> all windows: the game switched to a `DisplayMode` the overlay cannot be
+/// drawn over.
+const DISPLAY_MODE_WARNING_EVENT: &str = "game-overlay:display-mode-warning";
+
+const LAYOUT_STORE_KEY: &str = "gameOverlayLayout";
+const TIMEOUTS_STORE_KEY: &str = "gameOverlayTimeouts";
+/// The frontend's switch for the whole overlay feature.
+const ENABLED_STORE_KEY: &str = "gameOverlayEnabled";
+
 /// The game window does not move during a loading screen, so a slow tick is plenty.
 const WATCHER_TICK_MS: u64 = 500;
 
+/// How often the backend checks the log is still being read.
+#[cfg(any(target_os = "windows", target_os = "linux"))]
+const LOG_FOLLOW_MS: u64 = 1000;
+/// The main window reads the log every 2 s. Left this long, it is minimised or
+/// throttled, and the backend reads it instead.
+#[cfg(any(target_os = "windows", target_os = "linux"))]
+const LOG_STALE_SECS: u64 = 5;
+
 #[derive(Default)]
 pub struct GameOverlayState {
     /// The feature wants the overlay up (i.e. we are on a loading screen).
@@ -42,6 +111,70 @@ pub struct GameOverlayState {
     /// exits without touching shared state once the token no longer matches, so a
     /// hide-then-show cannot leave the outgoing watcher stopping the incoming one.
     watcher_generation: Arc<AtomicU64>,
+    /// Where the overlay goes. Read by the watcher on every tick, so a new preset is
+    /// picked up without restarting it.
+    layout: Mutex<LayoutPreset>,
+    /// Last published status, so only changes are sent.
+    status: Mutex<OverlayStatus>,
+    /// The mode the overlay is up in, `None` while it is not wanted.
+    showing: Mutex<Option<Showing>>,
+    timeouts: Mutex<ModeTimeouts>,
+    /// The matchup last put together, kept for the hotkey and for an overlay window
+    /// that mounts late.
+    matchup: Mutex<Option<MatchupPayload>>,
+    /// The match the results panel is for, kept to refresh it when its rating comes in.
+    results: Mutex<Option<LastMatch>>,
+    /// Set while the overlay takes clicks.
+    interaction: Mutex<Option<Interaction>>,
+    /// How the game was last seen displayed, so only changes are warned about.
+    display_mode: Mutex<Option<DisplayMode>>,
+}
+
+pub(super) fn lock<'a, T>(mutex: &'a Mutex<T>, name: &str) -> std::sync::MutexGuard<'a, T> {
+    mutex.lock().unwrap_or_else(|poisoned| {
+        warn!("Game overlay {} mutex was poisoned, recovering", name);
+        poisoned.into_inner()
+    })
+}
+
+/// Record the overlay's status and tell the frontend when it changed.
+fn publish_status<R: Runtime>(handle: &AppHandle<R>, status: OverlayStatus) {
+    {
+        let state = handle.state::<GameOverlayState>();
+        let mut current = lock(&state.status, "status");
+        if *current == status {
+            return;
+        }
+        *current = status;
+    }
+    if let Err(e) = handle.emit(STATUS_EVENT, status) {
+        error!("Game overlay: failed to send the status: {}", e);
+    }
+}
+
+/// Load the stored layout preset and mode timeouts into the state. Called once at
+/// startup.
+pub fn load_settings<R: Runtime>(handle: &AppHandle<R>) {
+    let state = handle.state::<GameOverlayState>();
+    let layout = load_from_store::<R, LayoutPreset>(handle.clone(), LAYOUT_STORE_KEY)
+        .unwrap_or_default()
+        .sanitised();
+    *lock(&state.layout, "layout") = layout;
+    let timeouts = load_from_store::<R, ModeTimeouts>(handle.clone(), TIMEOUTS_STORE_KEY)
+        .unwrap_or_default()
+        .sanitised();
+    *lock(&state.timeouts, "timeouts") = timeouts;
+}
+
+/// Whether the overlay feature is switched on in the settings.
+pub fn is_enabled<R: Runtime>(handle: &AppHandle<R>) -> bool {
+    load_from_store(handle.clone(), ENABLED_STORE_KEY).unwrap_or(true)
+}
+
+fn emit_layout<R: Runtime>(handle: &AppHandle<R>, layout: &LayoutPreset) {
+    if let Err(e) = handle.emit_to(OVERLAY_WINDOW_LABEL, LAYOUT_EVENT, layout) {
+        error!("Game overlay: failed to send the layout: {}", e);
+    }
 }
 
 /// Whether this build is the Microsoft Store edition.
@@ -52,11 +185,14 @@ pub struct GameOverlayState {
 /// edition is the one that ships without the updater, because the store does the
 /// updating. See `tauri.microsoftstore.conf.json` (`createUpdaterArtifacts: false`).
 ///
-/// The Linux config disables the updater too, but the overlay is Windows-only anyway.
+/// The Linux config disables the updater too, and the Linux build gets the overlay
+/// as well - its frontend is built with the same flag.
+///
 /// Note `Updater`'s own default is `Bool(false)` - only ever a concern for a config that
 /// was defaulted rather than read from one of the three files, which does not happen.
-// Only the Windows `create_overlay_window` consults it; the tests cover it everywhere.
-#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+// Only the Windows and Linux `create_overlay_window` consult it; the tests cover it
+// everywhere.
+#[cfg_attr(not(any(target_os = "windows", target_os = "linux")), allow(dead_code))]
 pub fn is_ms_store_edition(config: &tauri::Config) -> bool {
     matches!(
         config.bundle.create_updater_artifacts,
@@ -64,9 +200,9 @@ pub fn is_ms_store_edition(config: &tauri::Config) -> bool {
     )
 }
 
-#[cfg(target_os = "windows")]
+#[cfg(any(target_os = "windows", target_os = "linux"))]
 pub fn create_overlay_window<R: Runtime>(app: &AppHandle<R>) {
-    use tauri::{WebviewUrl, WebviewWindowBuilder};
+    use tauri::{Listener, WebviewUrl, WebviewWindowBuilder};
 
     // Non-store builds can never show the overlay, so they must not pay for a second
     // webview that would sit hidden for the whole session.
@@ -81,7 +217,7 @@ pub fn create_overlay_window<R: Runtime>(app: &AppHandle<R>) {
 
     // Created once, hidden, at startup. Creating it per match would take the
     // foreground while WebView2 initialises and could minimise the game.
-    let window = match WebviewWindowBuilder::new(
+    let builder = WebviewWindowBuilder::new(
         app,
         OVERLAY_WINDOW_LABEL,
         WebviewUrl::App("index.html".into()),
@@ -95,11 +231,11 @@ pub fn create_overlay_window<R: Runtime>(app: &AppHandle<R>) {
     .resizable(false)
     .focused(false)
     .visible(false)
-    .shadow(false)
+    .shadow(false);
     // Has to match the main window - all webviews share one WebView2 environment.
-    .additional_browser_args(&crate::webview_browser_args())
-    .build()
-    {
+    #[cfg(target_os = "windows")]
+    let builder = builder.additional_browser_args(&crate::webview_browser_args());
+    let window = match builder.build() {
         Ok(w) => w,
         Err(e) => {
             error!("Game overlay: failed to create window: {}", e);
@@ -111,44 +247,64 @@ pub fn create_overlay_window<R: Runtime>(app: &AppHandle<R>) {
         }
     };
 
-    match window.hwnd() {
-        Ok(hwnd) => window_detector::apply_overlay_styles(hwnd.0 as isize),
-        Err(e) => error!("Game overlay: could not get HWND: {}", e),
+    // On Linux the X11 window may not exist until the first show, which styles it again.
+    match native_window(&window) {
+        Some(native) => window_detector::apply_overlay_styles(native, true),
+        None => warn!("Game overlay: could not get the native window yet"),
     }
 
     if let Err(e) = window.set_ignore_cursor_events(true) {
         error!("Game overlay: set_ignore_cursor_events failed: {}", e);
     }
 
+    let handle = app.clone();
+    app.listen_any(READY_EVENT, move |_| emit_matchup(&handle));
+    start_log_follower(app);
+
     info!("Game overlay: window created (hidden)");
 }
 
-#[cfg(not(target_os = "windows"))]
-pub fn create_overlay_window<R: Runtime>(_app: &AppHandle<R>) {
-    log::info!("Game overlay is only supported on Windows");
+/// Keep the parsed log current for the overlay while the main window does not - only
+/// while the overlay is switched on and the game is running. Stops with the overlay
+/// window.
+#[cfg(any(target_os = "windows", target_os = "linux"))]
+fn start_log_follower<R: Runtime>(handle: &AppHandle<R>) {
+    use std::time::Duration;
+    use watcher::WindowSystem;
+
+    let handle = handle.clone();
+    std::thread::spawn(move || {
+        loop {
+            std::thread::sleep(Duration::from_millis(LOG_FOLLOW_MS));
+            if handle.get_webview_window(OVERLAY_WINDOW_LABEL).is_none() {
+                break;
+            }
+            if !is_enabled(&handle) || window_detector::Desktop.game_pid().is_none() {
+                continue;
+            }
+            current_game::refresh_if_stale(&handle, Duration::from_secs(LOG_STALE_SECS));
+        }
+        info!("Game overlay: log follower stopped");
+    });
 }
 
-/// Outcome of one watcher tick.
-#[derive(PartialEq)]
-enum Tick {
-    Continue,
-    /// Nothing left to track - the watcher thread exits.
-    Stop,
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+pub fn create_overlay_window<R: Runtime>(_app: &AppHandle<R>) {
+    log::info!("Game overlay is only supported on Windows and Linux");
 }
 
-/// Carried across watcher ticks.
-#[derive(Default)]
-struct TickState {
-    last_rect: Option<Bounds>,
-    /// Consecutive ticks where another process held the foreground.
-    away_ticks: u8,
-    /// Last logged visibility decision, so the log records changes and not every tick.
-    last_decision: Option<bool>,
+/// The overlay's native window: its HWND.
+#[cfg(target_os = "windows")]
+fn native_window<R: Runtime>(window: &tauri::WebviewWindow<R>) -> Option<isize> {
+    window.hwnd().ok().map(|hwnd| hwnd.0 as isize)
 }
 
-/// How many consecutive ticks the game may be out of the foreground before the overlay
-/// is pulled. One tick of tolerance rides out the brief handovers a loading screen does.
-const AWAY_TICKS_BEFORE_HIDE: u8 = 2;
+/// The overlay's native window: its X11 window, found by its title - Tauri only hands
+/// out the GTK one.
+#[cfg(target_os = "linux")]
+fn native_window<R: Runtime>(window: &tauri::WebviewWindow<R>) -> Option<isize> {
+    window_detector::find_own_window(&window.title().ok()?)
+}
 
 fn show_window<R: Runtime>(window: &tauri::WebviewWindow<R>, state: &GameOverlayState) {
     if state.shown.load(Ordering::SeqCst) {
@@ -158,14 +314,14 @@ fn show_window<R: Runtime>(window: &tauri::WebviewWindow<R>, state: &GameOverlay
         error!("Game overlay: show failed: {}", e);
         return;
     }
-    // `hwnd()` only exists on Windows; everything it feeds is a no-op elsewhere.
-    #[cfg(target_os = "windows")]
-    if let Ok(hwnd) = window.hwnd() {
+    #[cfg(any(target_os = "windows", target_os = "linux"))]
+    if let Some(native) = native_window(window) {
         // Re-apply after show(): the styles are what keep it click-through and
-        // non-activating, and they must never be lost.
-        window_detector::apply_overlay_styles(hwnd.0 as isize);
+        // non-activating, and they must never be lost. Interactive mode ends whenever
+        // the overlay is hidden, so a show is always click-through.
+        window_detector::apply_overlay_styles(native, true);
         // Deliberately not `set_always_on_top` - see raise_without_activating.
-        window_detector::raise_without_activating(hwnd.0 as isize);
+        window_detector::raise_without_activating(native);
     }
     state.shown.store(true, Ordering::SeqCst);
 }
@@ -180,100 +336,189 @@ fn hide_window<R: Runtime>(window: &tauri::WebviewWindow<R>, state: &GameOverlay
     state.shown.store(false, Ordering::SeqCst);
 }
 
-/// Position the overlay over the game window and decide whether it should be on
-/// screen right now. Called once by `show`, then on every watcher tick.
-fn apply<R: Runtime>(handle: &AppHandle<R>, tick: &mut TickState) -> Tick {
+/// Let clicks through the overlay, or have it take them. Never activates it either way.
+fn set_click_through<R: Runtime>(window: &tauri::WebviewWindow<R>, click_through: bool) {
+    // Tauri's own flag first: it rewrites the extended styles, which are then put right.
+    if let Err(e) = window.set_ignore_cursor_events(click_through) {
+        error!("Game overlay: set_ignore_cursor_events failed: {}", e);
+    }
+    #[cfg(any(target_os = "windows", target_os = "linux"))]
+    if let Some(native) = native_window(window) {
+        window_detector::apply_overlay_styles(native, click_through);
+    }
+}
+
+/// Make the overlay take clicks, if it is on screen. Returns whether it is interactive.
+pub fn enter_interactive<R: Runtime>(handle: &AppHandle<R>) -> bool {
+    let state = handle.state::<GameOverlayState>();
     let Some(window) = handle.get_webview_window(OVERLAY_WINDOW_LABEL) else {
-        return Tick::Stop;
+        return false;
     };
-    let state = handle.state::<GameOverlayState>();
-
-    if !state.wanted.load(Ordering::SeqCst) {
-        hide_window(&window, &state);
-        return Tick::Stop;
+    if !state.shown.load(Ordering::SeqCst) {
+        return false;
+    }
+    let mut interaction = lock(&state.interaction, "interaction");
+    if interaction.is_none() {
+        set_click_through(&window, false);
+        *interaction = Some(Interaction::new(Instant::now()));
+        info!("Game overlay: interactive");
+        if let Err(e) = handle.emit(INTERACTIVE_EVENT, true) {
+            error!("Game overlay: failed to send interactive mode: {}", e);
+        }
     }
+    true
+}
 
-    #[cfg(target_os = "windows")]
-    let pid = crate::process_watcher::find_game_process_id();
-    #[cfg(not(target_os = "windows"))]
-    let pid: Option<u32> = None;
+/// Back to click-through, if the overlay was interactive.
+pub fn leave_interactive<R: Runtime>(handle: &AppHandle<R>, exit: Exit) {
+    let state = handle.state::<GameOverlayState>();
+    if lock(&state.interaction, "interaction").take().is_none() {
+        return;
+    }
+    if let Some(window) = handle.get_webview_window(OVERLAY_WINDOW_LABEL) {
+        set_click_through(&window, true);
+    }
+    info!("Game overlay: click-through again ({:?})", exit);
+    if let Err(e) = handle.emit(INTERACTIVE_EVENT, false) {
+        error!("Game overlay: failed to send interactive mode: {}", e);
+    }
+}
 
-    // Game gone - drop the overlay and stop wanting it.
-    let Some(pid) = pid else {
-        hide_window(&window, &state);
-        state.wanted.store(false, Ordering::SeqCst);
-        return Tick::Stop;
-    };
+/// Flip interactive mode, for the interact hotkey.
+pub fn toggle_interactive<R: Runtime>(handle: &AppHandle<R>) {
+    let interactive = lock(
+        &handle.state::<GameOverlayState>().interaction,
+        "interaction",
+    )
+    .is_some();
+    if interactive {
+        leave_interactive(handle, Exit::Requested);
+    } else {
+        enter_interactive(handle);
+    }
+}
 
-    // Process alive but no usable window yet (still starting, or between modes):
-    // keep the overlay off screen and try again next tick.
-    let Some(hwnd) = window_detector::find_window_for_pid(pid) else {
-        hide_window(&window, &state);
-        return Tick::Continue;
-    };
-    let Some(bounds) = window_detector::get_window_bounds(hwnd) else {
-        hide_window(&window, &state);
-        return Tick::Continue;
-    };
+/// The overlay window, driven by the watcher.
+struct Surface<'a, R: Runtime> {
+    window: &'a tauri::WebviewWindow<R>,
+    state: &'a GameOverlayState,
+}
 
-    let rect = geometry::overlay_rect(bounds, window_detector::get_dpi(hwnd));
-    if tick.last_rect != Some(rect) {
-        let sized = window.set_size(tauri::PhysicalSize::new(
+impl<R: Runtime> OverlaySurface for Surface<'_, R> {
+    fn place(&mut self, rect: Bounds) -> bool {
+        let sized = self.window.set_size(tauri::PhysicalSize::new(
             rect.width as u32,
             rect.height as u32,
         ));
         if let Err(e) = &sized {
             error!("Game overlay: set_size failed: {}", e);
         }
-        let positioned = window.set_position(tauri::PhysicalPosition::new(rect.x, rect.y));
+        let positioned = self
+            .window
+            .set_position(tauri::PhysicalPosition::new(rect.x, rect.y));
         if let Err(e) = &positioned {
             error!("Game overlay: set_position failed: {}", e);
         }
-        // Only remember the rect once it actually took, so a failed call is retried
-        // on the next tick instead of being cached as done.
-        if sized.is_ok() && positioned.is_ok() {
-            tick.last_rect = Some(rect);
-        }
+        sized.is_ok() && positioned.is_ok()
     }
 
-    // Only draw while the game owns the foreground - otherwise the overlay would float
-    // over the desktop or over other apps.
-    //
-    // Compared by process, not by HWND: the game has more than one top-level window and
-    // the one that holds activation is not always the big render window we track. An
-    // unknown foreground (null during an activation handover) is not treated as "away".
-    //
-    // Our own process counts as the game having focus: the overlay window lives in it,
-    // and if activation lands on it - or the user tabs over to check the app - that is
-    // not a reason to tear the overlay down.
-    let foreground = window_detector::foreground_pid();
-    let own_pid = std::process::id();
-    let away = matches!(foreground, Some(fg) if fg != pid && fg != own_pid);
-    tick.away_ticks = if away {
-        tick.away_ticks.saturating_add(1)
-    } else {
-        0
-    };
+    fn show(&mut self) {
+        show_window(self.window, self.state);
+    }
+
+    fn hide(&mut self) {
+        hide_window(self.window, self.state);
+    }
+}
+
+/// The mode the overlay is up in, if it has been up for longer than that mode's
+/// timeout.
+fn timed_out(state: &GameOverlayState) -> Option<OverlayMode> {
+    let showing = (*lock(&state.showing, "showing"))?;
+    let timeouts = *lock(&state.timeouts, "timeouts");
+    showing
+        .timed_out(Instant::now(), &timeouts)
+        .then_some(showing.mode)
+}
 
-    let visible = tick.away_ticks < AWAY_TICKS_BEFORE_HIDE && !window_detector::is_minimised(hwnd);
-    if tick.last_decision != Some(visible) {
-        info!(
-            "Game overlay: {} (game pid {}, own pid {}, foreground pid {:?})",
-            if visible { "showing" } else { "hiding" },
-            pid,
-            own_pid,
-            foreground
+/// Position the overlay over the game window and decide whether it should be on
+/// screen right now. Called once by `show`, then on every watcher tick.
+fn apply<R: Runtime>(handle: &AppHandle<R>, tick: &mut TickState) -> Tick {
+    let Some(window) = handle.get_webview_window(OVERLAY_WINDOW_LABEL) else {
+        return Tick::Stop;
+    };
+    let state = handle.state::<GameOverlayState>();
+    if let Some(mode) = timed_out(&state) {
+        info!("Game overlay: {:?} timed out", mode);
+        stand_down(
+            handle,
+            OverlayStatus {
+                visibility: Visibility::NotWanted,
+                reason: Reason::TimedOut { mode },
+                rect: None,
+            },
         );
-        tick.last_decision = Some(visible);
+        return Tick::Stop;
+    }
+    let layout = *lock(&state.layout, "layout");
+    let mut surface = Surface {
+        window: &window,
+        state: &state,
+    };
+
+    let interaction = *lock(&state.interaction, "interaction");
+    let step = watcher::tick(
+        tick,
+        state.wanted.load(Ordering::SeqCst),
+        interaction,
+        &window_detector::Desktop,
+        &mut surface,
+        &layout,
+        Instant::now(),
+    );
+    let decision = step.decision;
+    // Game gone - stop wanting the overlay until the next loading screen asks again.
+    if decision == Decision::GameGone {
+        state.wanted.store(false, Ordering::SeqCst);
+        *lock(&state.showing, "showing") = None;
+        hotkey::sync_interact(handle);
+        publish_status(handle, tick.status());
+    } else if state.wanted.load(Ordering::SeqCst) {
+        // Otherwise a `hide` that raced this tick has published, and its status stands.
+        publish_status(handle, tick.status());
     }
 
-    if visible {
-        show_window(&window, &state);
-    } else {
-        hide_window(&window, &state);
+    *lock(&state.display_mode, "display mode") = tick.display_mode();
+    if let Some(mode) = step.unsupported_display_mode {
+        warn_display_mode(handle, mode);
+    }
+    match (step.exit, step.interaction) {
+        (Some(exit), _) => leave_interactive(handle, exit),
+        (None, Some(updated)) => {
+            // Only the flags `check` keeps - a concurrent leave is not undone.
+            if let Some(current) = lock(&state.interaction, "interaction").as_mut() {
+                *current = updated;
+            }
+        }
+        (None, None) => {}
     }
+    decision.tick()
+}
 
-    Tick::Continue
+/// Tell the frontend the game has switched to `mode`, which the overlay cannot be drawn
+/// over, while the overlay is switched on.
+fn warn_display_mode<R: Runtime>(handle: &AppHandle<R>, mode: DisplayMode) {
+    // Non-store builds have no overlay to warn about.
+    if handle.get_webview_window(OVERLAY_WINDOW_LABEL).is_none() || !is_enabled(handle) {
+        return;
+    }
+    warn!("Game overlay: cannot be drawn over {:?}", mode);
+    if let Err(e) = handle.emit(DISPLAY_MODE_WARNING_EVENT, mode) {
+        error!(
+            "Game overlay: failed to send the display mode warning: {}",
+            e
+        );
+    }
 }
 
 fn start_watcher<R: Runtime>(handle: &AppHandle<R>, initial: TickState) {
@@ -323,40 +568,227 @@ fn start_watcher<R: Runtime>(handle: &AppHandle<R>, initial: TickState) {
     });
 }
 
-/// Show the overlay over the game window and keep it there.
+/// Want the overlay up in `mode` and start the watcher on it. `false` when the game
+/// window could not be placed yet.
 ///
-/// Returns `false` when the game window could not be located yet - the watcher stays
-/// armed and the overlay appears as soon as it can be found.
-#[tauri::command]
-pub async fn game_overlay_show<R: Runtime>(handle: AppHandle<R>) -> Result<bool, String> {
-    if handle.get_webview_window(OVERLAY_WINDOW_LABEL).is_none() {
-        return Err("Game overlay window does not exist".to_string());
+/// Showing again, in the same mode or another, restarts the mode's timeout.
+pub fn show<R: Runtime>(handle: &AppHandle<R>, mode: OverlayMode) -> bool {
+    let state = handle.state::<GameOverlayState>();
+    *lock(&state.showing, "showing") = Some(Showing::new(mode));
+    state.wanted.store(true, Ordering::SeqCst);
+    hotkey::sync_interact(handle);
+    emit_layout(handle, &lock(&state.layout, "layout"));
+    if let Err(e) = handle.emit_to(OVERLAY_WINDOW_LABEL, MODE_EVENT, mode) {
+        error!("Game overlay: failed to send the mode: {}", e);
     }
 
+    // Place and show immediately so there is no visible delay, then let the watcher
+    // take over.
+    let mut tick = TickState::with_display_mode(*lock(&state.display_mode, "display mode"));
+    let placed = apply(handle, &mut tick) == Tick::Continue && tick.last_rect().is_some();
+    start_watcher(handle, tick);
+    placed
+}
+
+/// Whether the overlay is wanted up, even if it is hidden for now.
+pub fn is_wanted<R: Runtime>(handle: &AppHandle<R>) -> bool {
     handle
         .state::<GameOverlayState>()
         .wanted
-        .store(true, Ordering::SeqCst);
+        .load(Ordering::SeqCst)
+}
 
-    // Place and show immediately so there is no visible delay, then let the watcher
-    // take over.
-    let mut tick = TickState::default();
-    let placed = apply(&handle, &mut tick) == Tick::Continue && tick.last_rect.is_some();
-    start_watcher(&handle, tick);
+/// Make the overlay take clicks, or let them through again. Entering needs the overlay
+/// on screen and never takes the foreground. Returns whether it is interactive now.
+#[tauri::command]
+pub fn game_overlay_interactive<R: Runtime>(handle: AppHandle<R>, interactive: bool) -> bool {
+    if interactive {
+        enter_interactive(&handle)
+    } else {
+        leave_interactive(&handle, Exit::Requested);
+        false
+    }
+}
 
-    Ok(placed)
+/// Whether the game holds the foreground right now.
+pub fn game_has_focus() -> bool {
+    use watcher::WindowSystem;
+    let desktop = window_detector::Desktop;
+    matches!(
+        (desktop.game_pid(), desktop.foreground_pid()),
+        (Some(game), Some(foreground)) if game == foreground
+    )
 }
 
-/// Hide the overlay and stand the watcher down.
+/// How the game is displayed, `None` while it has no window.
+#[tauri::command]
+pub fn game_overlay_display_mode() -> Option<DisplayMode> {
+    match Observation::gather(&window_detector::Desktop) {
+        Observation::Window { display_mode, .. } => display_mode,
+        Observation::NoGame | Observation::NoWindow { .. } => None,
+    }
+}
+
+/// What the overlay is doing, and why.
 #[tauri::command]
-pub async fn game_overlay_hide<R: Runtime>(handle: AppHandle<R>) -> Result<(), String> {
-    hide(&handle);
-    Ok(())
+pub fn game_overlay_status<R: Runtime>(handle: AppHandle<R>) -> OverlayStatus {
+    *lock(&handle.state::<GameOverlayState>().status, "status")
 }
 
+/// Send the matchup in the state to the overlay window.
+fn emit_matchup<R: Runtime>(handle: &AppHandle<R>) {
+    let Some(matchup) = lock(&handle.state::<GameOverlayState>().matchup, "matchup").clone() else {
+        return;
+    };
+    if let Err(e) = handle.emit_to(OVERLAY_WINDOW_LABEL, DATA_EVENT, matchup) {
+        error!("Game overlay: failed to send the matchup: {}", e);
+    }
+}
+
+/// Put the matchup for the lobby `game` together, send it, and show it - unless the
+/// loading screen is over by the time the profiles are in.
+fn assemble_matchup<R: Runtime>(handle: &AppHandle<R>, game: LogFileData) {
+    let handle = handle.clone();
+    tauri::async_runtime::spawn(async move {
+        let profiles = handle.state::<PlayerProfilesState>();
+        let mut found = HashMap::new();
+        for player in game.left.players.iter().chain(&game.right.players) {
+            if player.ai || found.contains_key(&player.relic_id) {
+                continue;
+            }
+            match profiles.service.get(&player.relic_id).await {
+                Ok(profile) => {
+                    found.insert(player.relic_id.clone(), profile);
+                }
+                Err(e) => warn!("Game overlay could not look up {}: {}", player.name, e),
+            }
+        }
+        let map_stats = crate::map_stats::get_map_stats(handle.clone());
+        let battlegroup_info = crate::battlegroup_info::get_battlegroup_info(handle.clone());
+        let matchup =
+            payload::matchup(&game, &found, map_stats.as_ref(), battlegroup_info.as_ref());
+
+        let still_loading = current_game::current(&handle).is_some_and(|current| {
+            payload::wants_matchup(&current) && payload::unique_id(&current) == matchup.unique_id
+        });
+        if !still_loading {
+            info!("Game overlay: loading screen over before the matchup was ready");
+            return;
+        }
+        *lock(&handle.state::<GameOverlayState>().matchup, "matchup") = Some(matchup);
+        // Before show, so the first paint already has it.
+        emit_matchup(&handle);
+        show(&handle, OverlayMode::Matchup);
+    });
+}
+
+/// Send the results panel for the match in the state, with what the session has on it
+/// by now.
+fn emit_results<R: Runtime>(handle: &AppHandle<R>) {
+    let Some(last) = lock(&handle.state::<GameOverlayState>().results, "results").clone() else {
+        return;
+    };
+    let results = modes::match_results(&last, &session::current(handle));
+    if let Err(e) = handle.emit_to(OVERLAY_WINDOW_LABEL, RESULTS_EVENT, results) {
+        error!("Game overlay: failed to send the results: {}", e);
+    }
+}
+
+/// Brings the matchup up on a loading screen and takes it down when that ends. Brings
+/// the results panel up when a match ends, and takes it down once the log has moved on
+/// from the score screen.
+pub fn observe<R: Runtime>(handle: &AppHandle<R>, prev: Option<&LogFileData>, next: &LogFileData) {
+    let state = handle.state::<GameOverlayState>();
+    let showing_matchup =
+        lock(&state.showing, "showing").is_some_and(|showing| showing.mode == OverlayMode::Matchup);
+    if showing_matchup && payload::matchup_ended(prev, next) {
+        hide(handle);
+    }
+    if payload::matchup_started(prev, next) {
+        // Non-store builds have no overlay window to show.
+        if handle.get_webview_window(OVERLAY_WINDOW_LABEL).is_some() && is_enabled(handle) {
+            assemble_matchup(handle, next.clone());
+        }
+        return;
+    }
+    if let Some(last) = modes::finished(prev, next) {
+        // Non-store builds have no overlay window to show.
+        if handle.get_webview_window(OVERLAY_WINDOW_LABEL).is_none() || !is_enabled(handle) {
+            return;
+        }
+        *lock(&state.results, "results") = Some(last);
+        emit_results(handle);
+        show(handle, OverlayMode::Results);
+        return;
+    }
+    let showing_results =
+        lock(&state.showing, "showing").is_some_and(|showing| showing.mode == OverlayMode::Results);
+    if showing_results && modes::left_results(next) {
+        hide(handle);
+    }
+}
+
+/// The session has news, such as the rating after the match - refresh a results panel
+/// that is up.
+pub fn session_changed<R: Runtime>(handle: &AppHandle<R>) {
+    let state = handle.state::<GameOverlayState>();
+    let showing_results =
+        lock(&state.showing, "showing").is_some_and(|showing| showing.mode == OverlayMode::Results);
+    if showing_results {
+        emit_results(handle);
+    }
+}
+
+/// The timeout of each overlay mode.
+#[tauri::command]
+pub fn game_overlay_timeouts<R: Runtime>(handle: AppHandle<R>) -> ModeTimeouts {
+    *lock(&handle.state::<GameOverlayState>().timeouts, "timeouts")
+}
+
+/// Store new timeouts. They apply to an overlay already up as well.
+#[tauri::command]
+pub fn game_overlay_set_timeouts<R: Runtime>(
+    handle: AppHandle<R>,
+    timeouts: ModeTimeouts,
+) -> Result<ModeTimeouts, String> {
+    let timeouts = timeouts.sanitised();
+    save_to_store(handle.clone(), TIMEOUTS_STORE_KEY, timeouts)?;
+    *lock(&handle.state::<GameOverlayState>().timeouts, "timeouts") = timeouts;
+    info!("Game overlay: timeouts set to {:?}", timeouts);
+    Ok(timeouts)
+}
+
+/// The layout preset in use.
+#[tauri::command]
+pub fn game_overlay_layout<R: Runtime>(handle: AppHandle<R>) -> LayoutPreset {
+    *lock(&handle.state::<GameOverlayState>().layout, "layout")
+}
+
+/// Store a new layout preset and switch to it. A visible overlay moves on the
+/// watcher's next tick.
+#[tauri::command]
+pub fn game_overlay_set_layout<R: Runtime>(
+    handle: AppHandle<R>,
+    layout: LayoutPreset,
+) -> Result<LayoutPreset, String> {
+    let layout = layout.sanitised();
+    save_to_store(handle.clone(), LAYOUT_STORE_KEY, layout)?;
+    *lock(&handle.state::<GameOverlayState>().layout, "layout") = layout;
+    info!("Game overlay: layout set to {:?}", layout);
+    emit_layout(&handle, &layout);
+    Ok(layout)
+}
+
+/// Hide the overlay and stand the watcher down.
 pub fn hide<R: Runtime>(handle: &AppHandle<R>) {
+    stand_down(handle, OverlayStatus::not_wanted());
+}
+
+/// Hide the overlay, stop the watcher and publish `status` as the reason.
+fn stand_down<R: Runtime>(handle: &AppHandle<R>, status: OverlayStatus) {
     let state = handle.state::<GameOverlayState>();
     state.wanted.store(false, Ordering::SeqCst);
+    *lock(&state.showing, "showing") = None;
     // Signal only. Never join here: the watcher locks nothing, it just exits next tick.
     // Bumping the generation first retires the running watcher even if a `show` races
     // in and starts a new one before it woke up.
@@ -366,4 +798,7 @@ pub fn hide<R: Runtime>(handle: &AppHandle<R>) {
     if let Some(window) = handle.get_webview_window(OVERLAY_WINDOW_LABEL) {
         hide_window(&window, &state);
     }
+    leave_interactive(handle, Exit::Hidden);
+    hotkey::sync_interact(handle);
+    publish_status(handle, status);
 }
@@ -0,0 +1,141 @@
+//! What the overlay window is up for, and for how long.
+//!
+//! The one window shows the matchup on the loading screen and, once the game reports
+//! the result, a results panel. Each mode has its own timeout, checked by the watcher
+//! on every tick, so nothing is left to timers in the webview.
+//!
+//! The log says `Menu` both on the score screen and back in the main menu, so the
+//! results panel also goes as soon as the log moves on to anything else: the next
+//! lobby loading, or the game closing.
+
+use crate::current_game::LastMatch;
+use crate::parse_log_file::{GameState, GameType, LogFileData, TeamPosition};
+use crate::session::{self, Record, Scoreboard};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverlayMode {
+    /// The teams, on the loading screen.
+    #[default]
+    Matchup,
+    /// How the match ended, after `Game Over`.
+    Results,
+}
+
+/// How long each mode may stay up, in seconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModeTimeouts {
+    /// A safety net: the matchup normally goes when the loading screen ends, but a
+    /// stalled log parse must never leave it up. Generous, so a slow disk loading a 4v4
+    /// map does not lose the overlay early.
+    pub matchup_secs: u64,
+    pub results_secs: u64,
+}
+
+impl Default for ModeTimeouts {
+    fn default() -> Self {
+        Self {
+            matchup_secs: 180,
+            results_secs: 30,
+        }
+    }
+}
+
+impl ModeTimeouts {
+    /// The timeouts brought into a usable range: long enough to be read, short enough
+    /// not to outstay the screen they belong to.
+    pub fn sanitised(self) -> Self {
+        Self {
+            matchup_secs: self.matchup_secs.clamp(30, 600),
+            results_secs: self.results_secs.clamp(5, 300),
+        }
+    }
+
+    pub fn of(&self, mode: OverlayMode) -> Duration {
+        Duration::from_secs(match mode {
+            OverlayMode::Matchup => self.matchup_secs,
+            OverlayMode::Results => self.results_secs,
+        })
+    }
+}
+
+/// The mode the overlay was shown in, and when.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Showing {
+    pub mode: OverlayMode,
+    pub since: Instant,
+}
+
+impl Showing {
+    pub fn new(mode: OverlayMode) -> Self {
+        Self {
+            mode,
+            since: Instant::now(),
+        }
+    }
+
+    /// Whether the mode's timeout has run out by `now`. Read against the current
+    /// timeouts, so a change applies to an overlay already up.
+    pub fn timed_out(&self, now: Instant, timeouts: &ModeTimeouts) -> bool {
+        now.saturating_duration_since(self.since) >= timeouts.of(self.mode)
+    }
+}
+
+/// The results panel's content.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MatchResults {
+    pub map: String,
+    /// `1v1` .. `4v4` for automatch games, `custom` otherwise.
+    pub mode: String,
+    pub winner: TeamPosition,
+    /// `None` when the local player was not in the match.
+    pub local_player_won: Option<bool>,
+    /// Game time in seconds.
+    pub duration: u64,
+    pub rating_before: Option<i64>,
+    /// `None` until the Relic API has counted the match.
+    pub rating_after: Option<i64>,
+    pub rating_change: Option<i64>,
+    /// The session so far, this match included.
+    pub session: Record,
+}
+
+/// The results panel for `last`, with the ratings and totals `scoreboard` has for it.
+pub fn match_results(last: &LastMatch, scoreboard: &Scoreboard) -> MatchResults {
+    let key = session::match_key(&last.game);
+    let counted = scoreboard.matches.iter().find(|m| m.key == key);
+    MatchResults {
+        map: last.game.map.clone(),
+        mode: session::mode_of(&last.game),
+        winner: last.winner,
+        local_player_won: last.local_player_won,
+        duration: last.game.duration,
+        rating_before: counted.and_then(|m| m.rating_before),
+        rating_after: counted.and_then(|m| m.rating_after),
+        rating_change: counted.and_then(|m| m.rating_change()),
+        session: scoreboard.total.clone(),
+    }
+}
+
+/// The match `next` finished, if its result came in since `prev` and it is one the
+/// matchup would have been shown for. As with the session, a result already in the log
+/// when the app started does not count.
+pub fn finished(prev: Option<&LogFileData>, next: &LogFileData) -> Option<LastMatch> {
+    let prev = prev?;
+    if prev.winner.is_some() || session::match_key(prev) != session::match_key(next) {
+        return None;
+    }
+    // Single-player and vs-bots games have no matchup, and no results panel either.
+    if next.game_type == GameType::AI || next.win_condition == "no_win_condition" {
+        return None;
+    }
+    LastMatch::from_game(next)
+}
+
+/// Whether the log has moved on from the score screen.
+pub fn left_results(next: &LogFileData) -> bool {
+    next.game_state != GameState::Menu
+}
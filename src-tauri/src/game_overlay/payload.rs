@@ -0,0 +1,185 @@
+//! The matchup the overlay draws, put together on the backend.
+//!
+//! Everything comes from what the backend already holds: the parsed log, the shared
+//! Relic profile lookups, the coh3stats map stats and its battlegroup info. The main
+//! window is not involved, so a minimised or throttled main window cannot leave the
+//! overlay empty or a lobby behind.
+//!
+//! Which sides are arranged teams or friends groups is found by the main window, which
+//! sends it to the overlay itself when it is awake. Without it the teams show as random.
+
+use crate::parse_log_file::{GameState, GameType, LogFileData, PlayerData, TeamSide};
+use crate::session;
+use crate::win_probability::{self, WinProbability};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// One player line.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OverlayPlayer {
+    pub ai: bool,
+    /// The local player.
+    #[serde(rename = "self")]
+    pub local: bool,
+    /// As the leaderboards name it, e.g. `british`.
+    pub faction: String,
+    #[serde(rename = "relicID")]
+    pub relic_id: String,
+    pub name: String,
+    pub position: u8,
+    /// ISO country code, lower case.
+    pub country: Option<String>,
+    /// The rest is the player's entry on this mode and faction's leaderboard. `None`
+    /// for AI, custom games, or when the profile could not be looked up.
+    pub rank: Option<i64>,
+    pub rating: Option<i64>,
+    pub wins: Option<u64>,
+    pub losses: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OverlayTeam {
+    pub side: TeamSide,
+    pub players: Vec<OverlayPlayer>,
+}
+
+/// Sent to the overlay window as `game-overlay:data`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MatchupPayload {
+    /// The frontend's ID for the match, so what the main window adds can be matched up.
+    #[serde(rename = "uniqueID")]
+    pub unique_id: String,
+    pub map: String,
+    pub left: OverlayTeam,
+    pub right: OverlayTeam,
+    pub win_probability: WinProbability,
+    /// The coh3stats battlegroup info for each faction in the lobby, by faction. Empty
+    /// until it has been fetched.
+    pub battlegroups: HashMap<String, Value>,
+}
+
+/// The match ID the frontend builds in `useFullGameData`.
+pub fn unique_id(game: &LogFileData) -> String {
+    let players: Vec<&str> = game
+        .left
+        .players
+        .iter()
+        .chain(&game.right.players)
+        .map(|p| p.relic_id.as_str())
+        .collect();
+    format!(
+        "{}{}{}{}",
+        game.timestamp,
+        game.map,
+        game.win_condition,
+        players.join(",")
+    )
+}
+
+/// Whether `game` is a lobby the matchup is shown for: loading, and against people.
+/// Single-player and vs-bots games have no matchup worth showing.
+pub fn wants_matchup(game: &LogFileData) -> bool {
+    game.game_state == GameState::Loading
+        && game.game_type != GameType::AI
+        && game.win_condition != "no_win_condition"
+}
+
+/// Whether `next` is a lobby to show the matchup for that `prev` did not already show.
+pub fn matchup_started(prev: Option<&LogFileData>, next: &LogFileData) -> bool {
+    wants_matchup(next)
+        && !prev.is_some_and(|prev| wants_matchup(prev) && unique_id(prev) == unique_id(next))
+}
+
+/// Whether the loading screen `prev` showed the matchup for is over.
+pub fn matchup_ended(prev: Option<&LogFileData>, next: &LogFileData) -> bool {
+    prev.is_some_and(|prev| {
+        wants_matchup(prev) && !(wants_matchup(next) && unique_id(prev) == unique_id(next))
+    })
+}
+
+/// The country `profile` gives for `relic_id`.
+fn country(profile: &Value, relic_id: &str) -> Option<String> {
+    profile["statGroups"]
+        .as_array()?
+        .iter()
+        .filter_map(|group| group["members"].as_array())
+        .flatten()
+        .find(|member| {
+            member["profile_id"]
+                .as_u64()
+                .is_some_and(|id| id.to_string() == relic_id)
+        })?["country"]
+        .as_str()
+        .map(str::to_string)
+}
+
+fn player(player: &PlayerData, local: bool, mode: &str, profile: Option<&Value>) -> OverlayPlayer {
+    let faction = session::normalize_faction(&player.faction);
+    let profile = profile.filter(|_| !player.ai);
+    let entry = session::leaderboard_id(mode, &faction).and_then(|leaderboard_id| {
+        profile?["leaderboardStats"]
+            .as_array()?
+            .iter()
+            .find(|s| s["leaderboard_id"].as_u64() == Some(leaderboard_id))
+    });
+    OverlayPlayer {
+        ai: player.ai,
+        local,
+        relic_id: player.relic_id.clone(),
+        name: player.name.clone(),
+        position: player.position,
+        country: profile.and_then(|profile| country(profile, &player.relic_id)),
+        rank: entry.and_then(|e| e["rank"].as_i64()),
+        rating: entry.and_then(|e| e["rating"].as_i64()),
+        wins: entry.and_then(|e| e["wins"].as_u64()),
+        losses: entry.and_then(|e| e["losses"].as_u64()),
+        faction,
+    }
+}
+
+/// The entries of `battlegroup_info` for the factions in `game`.
+fn battlegroups(game: &LogFileData, battlegroup_info: Option<&Value>) -> HashMap<String, Value> {
+    let Some(info) = battlegroup_info else {
+        return HashMap::new();
+    };
+    game.left
+        .players
+        .iter()
+        .chain(&game.right.players)
+        .map(|p| session::normalize_faction(&p.faction))
+        .filter_map(|faction| Some((faction.clone(), info.get(&faction)?.clone())))
+        .collect()
+}
+
+/// The matchup for `game`, with the `getpersonalstat` responses in `profiles` (by relic
+/// ID), the win probability from `map_stats` and the factions' `battlegroup_info`.
+pub fn matchup(
+    game: &LogFileData,
+    profiles: &HashMap<String, Value>,
+    map_stats: Option<&Value>,
+    battlegroup_info: Option<&Value>,
+) -> MatchupPayload {
+    let mode = session::mode_of(game);
+    let local = session::local_player(game);
+    let team = |players: &[PlayerData], side: &TeamSide| OverlayTeam {
+        side: side.clone(),
+        players: players
+            .iter()
+            .map(|p| {
+                let is_local = local.is_some_and(|local| std::ptr::eq(local, p));
+                player(p, is_local, &mode, profiles.get(&p.relic_id))
+            })
+            .collect(),
+    };
+    MatchupPayload {
+        unique_id: unique_id(game),
+        map: game.map.clone(),
+        left: team(&game.left.players, &game.left.side),
+        right: team(&game.right.players, &game.right.side),
+        win_probability: win_probability::estimate(game, map_stats),
+        battlegroups: battlegroups(game, battlegroup_info),
+    }
+}
@@ -0,0 +1,452 @@
+//! The overlay watcher's per-tick decision.
+//!
+//! The desktop and the overlay window sit behind `WindowSystem` and `OverlaySurface`,
+//! and what to do on a tick is worked out by `TickState::step` from a plain
+//! `Observation`. Nothing in here calls Win32 or Tauri, so the visibility rules are
+//! unit-tested everywhere with scripted timelines.
+//!
+//! Every step also settles on a `Visibility` and the `Reason` for it, which is what
+//! the frontend is shown when the overlay does not appear. It notices when the game
+//! switches to a display mode the overlay cannot be drawn over, and says when
+//! interactive mode is over.
+
+use super::geometry::{self, Bounds, DisplayMode, LayoutPreset};
+use super::interactive::{Exit, Interaction, Look};
+use super::modes::OverlayMode;
+use log::info;
+use serde::Serialize;
+use std::time::Instant;
+
+/// What the watcher needs to know about the desktop. `window_detector::Desktop` on
+/// Windows.
+pub trait WindowSystem {
+    /// The running game's process, if any.
+    fn game_pid(&self) -> Option<u32>;
+    /// The game's render window. Handles are opaque to the watcher - an HWND on Windows.
+    fn find_window(&self, pid: u32) -> Option<isize>;
+    fn bounds(&self, window: isize) -> Option<Bounds>;
+    fn dpi(&self, window: isize) -> u32;
+    fn is_minimised(&self, window: isize) -> bool;
+    /// How the game window is displayed, `None` when that cannot be told.
+    fn display_mode(&self, window: isize) -> Option<DisplayMode>;
+    /// Process owning the foreground window, `None` when that cannot be told.
+    fn foreground_pid(&self) -> Option<u32>;
+    /// This app's process, which the overlay window lives in.
+    fn own_pid(&self) -> u32;
+    /// The pointer, in physical screen pixels.
+    fn cursor_position(&self) -> Option<(i32, i32)>;
+}
+
+/// The overlay window, as the watcher drives it. Showing and hiding must be idempotent:
+/// every tracking tick asks for one or the other.
+pub trait OverlaySurface {
+    /// Resize and move the window. `false` when either failed, so it is tried again on
+    /// the next tick instead of being remembered as done.
+    fn place(&mut self, rect: Bounds) -> bool;
+    fn show(&mut self);
+    fn hide(&mut self);
+}
+
+/// One tick's look at the game.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Observation {
+    /// The game process is not running.
+    NoGame,
+    /// The process is alive but has no usable window yet (still starting, or between
+    /// display modes).
+    NoWindow { pid: u32 },
+    Window {
+        pid: u32,
+        window: isize,
+        bounds: Bounds,
+        dpi: u32,
+        minimised: bool,
+        display_mode: Option<DisplayMode>,
+        foreground_pid: Option<u32>,
+        own_pid: u32,
+        cursor: Option<(i32, i32)>,
+    },
+}
+
+impl Observation {
+    /// Look at the desktop, only as far as the answer needs.
+    pub fn gather(system: &impl WindowSystem) -> Self {
+        let Some(pid) = system.game_pid() else {
+            return Self::NoGame;
+        };
+        let Some((window, bounds)) = system
+            .find_window(pid)
+            .and_then(|window| system.bounds(window).map(|bounds| (window, bounds)))
+        else {
+            return Self::NoWindow { pid };
+        };
+        Self::Window {
+            pid,
+            window,
+            bounds,
+            dpi: system.dpi(window),
+            minimised: system.is_minimised(window),
+            display_mode: system.display_mode(window),
+            foreground_pid: system.foreground_pid(),
+            own_pid: system.own_pid(),
+            cursor: system.cursor_position(),
+        }
+    }
+}
+
+/// What the overlay is doing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+pub enum Visibility {
+    /// Nothing wants the overlay up.
+    #[default]
+    NotWanted,
+    /// Wanted, but the game is not running yet.
+    WaitingForGame,
+    /// Wanted, but the game has no usable window yet.
+    WaitingForWindow,
+    Shown,
+    /// Hidden because another app holds the foreground.
+    HiddenAway,
+    /// Hidden because the game is minimised.
+    HiddenMinimised,
+}
+
+/// Why the overlay is in its `Visibility`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(tag = "kind")]
+pub enum Reason {
+    /// Not asked for, or no longer - the loading screen is over.
+    #[default]
+    NotRequested,
+    /// Asked for before the game process could be found.
+    GameNotRunning,
+    /// The game process the overlay was following exited.
+    GameExited {
+        pid: u32,
+    },
+    /// The game process runs without a usable window (starting, or between display
+    /// modes).
+    NoGameWindow {
+        pid: u32,
+    },
+    GameInForeground,
+    /// This app holds the foreground, which counts as the game having it.
+    AppInForeground,
+    /// The foreground could not be told, so the game is assumed to still have it.
+    ForegroundUnknown,
+    /// Another app holds the foreground, but not for long enough to hide yet.
+    AnotherAppBriefly {
+        pid: u32,
+    },
+    AnotherAppInForeground {
+        pid: u32,
+    },
+    GameMinimised,
+    /// Up for longer than the mode's timeout.
+    TimedOut {
+        mode: OverlayMode,
+    },
+}
+
+/// The overlay's visibility with its reason, as the frontend sees it.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize)]
+pub struct OverlayStatus {
+    pub visibility: Visibility,
+    pub reason: Reason,
+    /// Where the overlay was last placed.
+    pub rect: Option<Bounds>,
+}
+
+impl OverlayStatus {
+    /// The overlay stood down on request.
+    pub fn not_wanted() -> Self {
+        Self::default()
+    }
+}
+
+/// Outcome of one watcher tick.
+#[derive(Debug, PartialEq)]
+pub enum Tick {
+    Continue,
+    /// Nothing left to track - the watcher thread exits.
+    Stop,
+}
+
+/// What to do with the overlay this tick.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Decision {
+    /// The overlay is no longer wanted: hide it and stop.
+    StandDown,
+    /// The game is gone: hide it, stop, and stop wanting it.
+    GameGone,
+    /// Keep it off screen and look again next tick.
+    Wait,
+    /// Move it to `place` when set, then show or hide it.
+    Track {
+        place: Option<Bounds>,
+        visible: bool,
+    },
+}
+
+impl Decision {
+    pub fn tick(&self) -> Tick {
+        match self {
+            Self::StandDown | Self::GameGone => Tick::Stop,
+            Self::Wait | Self::Track { .. } => Tick::Continue,
+        }
+    }
+}
+
+/// Everything one tick decided.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Step {
+    pub decision: Decision,
+    /// The game has just switched to this display mode, which the overlay cannot be
+    /// drawn over.
+    pub unsupported_display_mode: Option<DisplayMode>,
+    /// Interactive mode as it goes on after this tick, `None` when it is off or over.
+    pub interaction: Option<Interaction>,
+    /// Interactive mode ended this tick, and why.
+    pub exit: Option<Exit>,
+}
+
+/// How many consecutive ticks the game may be out of the foreground before the overlay
+/// is pulled. One tick of tolerance rides out the brief handovers a loading screen does.
+const AWAY_TICKS_BEFORE_HIDE: u8 = 2;
+
+/// Carried across watcher ticks.
+#[derive(Debug, Default)]
+pub struct TickState {
+    last_rect: Option<Bounds>,
+    /// Consecutive ticks where another process held the foreground.
+    away_ticks: u8,
+    /// The game process this watcher has seen. Until there is one, a missing game is
+    /// waited for; afterwards it means the game exited.
+    game_pid: Option<u32>,
+    visibility: Visibility,
+    reason: Reason,
+    /// How the game window was last seen displayed, so only changes are reported.
+    display_mode: Option<DisplayMode>,
+}
+
+impl TickState {
+    /// A fresh watcher's state, knowing how an earlier one last saw the game displayed.
+    pub fn with_display_mode(display_mode: Option<DisplayMode>) -> Self {
+        Self {
+            display_mode,
+            ..Self::default()
+        }
+    }
+
+    /// Where the overlay was last placed successfully.
+    pub fn last_rect(&self) -> Option<Bounds> {
+        self.last_rect
+    }
+
+    pub fn display_mode(&self) -> Option<DisplayMode> {
+        self.display_mode
+    }
+
+    pub fn status(&self) -> OverlayStatus {
+        OverlayStatus {
+            visibility: self.visibility,
+            reason: self.reason,
+            rect: self.last_rect,
+        }
+    }
+
+    /// Settle on a new visibility, logging only actual changes.
+    fn settle(&mut self, visibility: Visibility, reason: Reason) {
+        if (self.visibility, self.reason) != (visibility, reason) {
+            info!("Game overlay: {:?} ({:?})", visibility, reason);
+            self.visibility = visibility;
+            self.reason = reason;
+        }
+    }
+
+    /// Decide what to do with the overlay, given whether it is wanted, whether it is
+    /// interactive and what the desktop looks like.
+    pub fn step(
+        &mut self,
+        wanted: bool,
+        observation: Observation,
+        layout: &LayoutPreset,
+        interaction: Option<Interaction>,
+        now: Instant,
+    ) -> Step {
+        let decision = self.decide(wanted, observation, layout);
+        let unsupported_display_mode = match (decision, observation) {
+            (Decision::Track { .. }, Observation::Window { display_mode, .. }) => {
+                self.see_display_mode(display_mode)
+            }
+            _ => None,
+        };
+        let (interaction, exit) = match interaction {
+            Some(mut interaction) => match interaction.check(now, self.look(decision, observation))
+            {
+                Some(exit) => (None, Some(exit)),
+                None => (Some(interaction), None),
+            },
+            None => (None, None),
+        };
+        Step {
+            decision,
+            unsupported_display_mode,
+            interaction,
+            exit,
+        }
+    }
+
+    /// Note how the game is displayed. Returns the mode when it is news and the overlay
+    /// cannot be drawn over it.
+    fn see_display_mode(&mut self, mode: Option<DisplayMode>) -> Option<DisplayMode> {
+        if mode == self.display_mode {
+            return None;
+        }
+        info!("Game overlay: game display mode {:?}", mode);
+        self.display_mode = mode;
+        mode.filter(|mode| !mode.supports_overlay())
+    }
+
+    /// What interactive mode ends on, after `decision`.
+    fn look(&self, decision: Decision, observation: Observation) -> Look {
+        match (decision, observation) {
+            (
+                Decision::Track { place, visible },
+                Observation::Window {
+                    pid,
+                    foreground_pid,
+                    cursor,
+                    ..
+                },
+            ) => Look {
+                shown: visible,
+                pointer_inside: place
+                    .or(self.last_rect)
+                    .zip(cursor)
+                    .map(|(rect, (x, y))| rect.contains(x, y)),
+                game_in_foreground: foreground_pid.map(|foreground| foreground == pid),
+            },
+            _ => Look {
+                shown: false,
+                pointer_inside: None,
+                game_in_foreground: None,
+            },
+        }
+    }
+
+    fn decide(
+        &mut self,
+        wanted: bool,
+        observation: Observation,
+        layout: &LayoutPreset,
+    ) -> Decision {
+        if !wanted {
+            self.settle(Visibility::NotWanted, Reason::NotRequested);
+            return Decision::StandDown;
+        }
+        let (pid, bounds, dpi, minimised, foreground, own_pid) = match observation {
+            Observation::NoGame => {
+                return match self.game_pid {
+                    Some(pid) => {
+                        self.settle(Visibility::NotWanted, Reason::GameExited { pid });
+                        Decision::GameGone
+                    }
+                    None => {
+                        self.settle(Visibility::WaitingForGame, Reason::GameNotRunning);
+                        Decision::Wait
+                    }
+                };
+            }
+            Observation::NoWindow { pid } => {
+                self.game_pid = Some(pid);
+                self.settle(Visibility::WaitingForWindow, Reason::NoGameWindow { pid });
+                return Decision::Wait;
+            }
+            Observation::Window {
+                pid,
+                bounds,
+                dpi,
+                minimised,
+                foreground_pid,
+                own_pid,
+                ..
+            } => (pid, bounds, dpi, minimised, foreground_pid, own_pid),
+        };
+        self.game_pid = Some(pid);
+
+        let rect = geometry::overlay_rect(bounds, dpi, layout);
+        let place = (self.last_rect != Some(rect)).then_some(rect);
+
+        // Only draw while the game owns the foreground - otherwise the overlay would
+        // float over the desktop or over other apps.
+        //
+        // Compared by process, not by window: the game has more than one top-level window
+        // and the one that holds activation is not always the big render window we
+        // track. An unknown foreground (null during an activation handover) is not
+        // treated as "away".
+        //
+        // Our own process counts as the game having focus: the overlay window lives in
+        // it, and if activation lands on it - or the user tabs over to check the app -
+        // that is not a reason to tear the overlay down.
+        let away = matches!(foreground, Some(fg) if fg != pid && fg != own_pid);
+        self.away_ticks = if away {
+            self.away_ticks.saturating_add(1)
+        } else {
+            0
+        };
+
+        let (visibility, reason) = match foreground {
+            _ if minimised => (Visibility::HiddenMinimised, Reason::GameMinimised),
+            Some(fg) if away && self.away_ticks >= AWAY_TICKS_BEFORE_HIDE => (
+                Visibility::HiddenAway,
+                Reason::AnotherAppInForeground { pid: fg },
+            ),
+            Some(fg) if away => (Visibility::Shown, Reason::AnotherAppBriefly { pid: fg }),
+            Some(fg) if fg == pid => (Visibility::Shown, Reason::GameInForeground),
+            Some(_) => (Visibility::Shown, Reason::AppInForeground),
+            None => (Visibility::Shown, Reason::ForegroundUnknown),
+        };
+        self.settle(visibility, reason);
+        let visible = visibility == Visibility::Shown;
+
+        Decision::Track { place, visible }
+    }
+}
+
+/// One watcher tick: look at the desktop, decide, and carry the decision out on the
+/// overlay window. Warning about the display mode and leaving interactive mode are left
+/// to the caller.
+pub fn tick(
+    state: &mut TickState,
+    wanted: bool,
+    interaction: Option<Interaction>,
+    system: &impl WindowSystem,
+    surface: &mut impl OverlaySurface,
+    layout: &LayoutPreset,
+    now: Instant,
+) -> Step {
+    // Nothing is looked up for an overlay that is not wanted.
+    let observation = if wanted {
+        Observation::gather(system)
+    } else {
+        Observation::NoGame
+    };
+    let step = state.step(wanted, observation, layout, interaction, now);
+    match step.decision {
+        Decision::StandDown | Decision::GameGone | Decision::Wait => surface.hide(),
+        Decision::Track { place, visible } => {
+            if let Some(rect) = place {
+                if surface.place(rect) {
+                    state.last_rect = Some(rect);
+                }
+            }
+            if visible {
+                surface.show();
+            } else {
+                surface.hide();
+            }
+        }
+    }
+    step
+}
@@ -1,24 +1,32 @@
-//! Win32 window lookup and inspection for the in-game overlay.
+//! Window lookup and inspection for the in-game overlay: Win32 on Windows, X11 with the
+//! EWMH hints on Linux.
 //!
 //! HWNDs are passed around as `isize` because `HWND` is not `Send` - the watcher
-//! thread needs to carry one across ticks.
+//! thread needs to carry one across ticks. X11 window IDs go the same way.
 
-use super::geometry::Bounds;
+use super::geometry::{Bounds, DisplayMode};
+use super::watcher::WindowSystem;
 
 #[cfg(target_os = "windows")]
 mod imp {
-    use super::Bounds;
+    use super::{Bounds, DisplayMode};
+    use crate::game_overlay::geometry::{self, WindowStyle};
     use log::debug;
     use std::ffi::c_void;
     use windows::core::BOOL;
-    use windows::Win32::Foundation::{HWND, LPARAM, RECT};
+    use windows::Win32::Foundation::{HWND, LPARAM, POINT, RECT};
     use windows::Win32::Graphics::Dwm::{DwmGetWindowAttribute, DWMWA_EXTENDED_FRAME_BOUNDS};
+    use windows::Win32::Graphics::Gdi::{
+        GetMonitorInfoW, MonitorFromWindow, MONITORINFO, MONITOR_DEFAULTTONEAREST,
+    };
     use windows::Win32::UI::HiDpi::GetDpiForWindow;
+    use windows::Win32::UI::Shell::{SHQueryUserNotificationState, QUNS_RUNNING_D3D_FULL_SCREEN};
     use windows::Win32::UI::WindowsAndMessaging::{
-        EnumWindows, GetForegroundWindow, GetWindowLongPtrW, GetWindowRect,
+        EnumWindows, GetCursorPos, GetForegroundWindow, GetWindowLongPtrW, GetWindowRect,
         GetWindowThreadProcessId, IsIconic, IsWindowVisible, SetWindowLongPtrW, SetWindowPos,
-        GWL_EXSTYLE, HWND_TOPMOST, SWP_NOACTIVATE, SWP_NOMOVE, SWP_NOSIZE, SWP_SHOWWINDOW,
-        WS_EX_NOACTIVATE, WS_EX_TOOLWINDOW, WS_EX_TRANSPARENT,
+        GWL_EXSTYLE, GWL_STYLE, HWND_TOPMOST, SWP_NOACTIVATE, SWP_NOMOVE, SWP_NOSIZE,
+        SWP_SHOWWINDOW, WS_CAPTION, WS_EX_NOACTIVATE, WS_EX_TOOLWINDOW, WS_EX_TRANSPARENT,
+        WS_THICKFRAME,
     };
 
     struct EnumCtx {
@@ -150,6 +158,13 @@ mod imp {
         unsafe { IsIconic(hwnd).as_bool() }
     }
 
+    /// The mouse pointer, in physical screen pixels.
+    pub fn cursor_position() -> Option<(i32, i32)> {
+        let mut point = POINT::default();
+        unsafe { GetCursorPos(&mut point) }.ok()?;
+        Some((point.x, point.y))
+    }
+
     pub fn get_dpi(hwnd_raw: isize) -> u32 {
         let hwnd = HWND(hwnd_raw as *mut c_void);
         let dpi = unsafe { GetDpiForWindow(hwnd) };
@@ -160,19 +175,68 @@ mod imp {
         }
     }
 
+    /// How the game window is displayed, from its bounds against those of the monitor
+    /// it is on and from its styles.
+    ///
+    /// Exclusive fullscreen is what the shell reports as a Direct3D app running full
+    /// screen. That report is about the foreground app, so it only counts while the
+    /// window's own process holds the foreground.
+    pub fn display_mode(hwnd_raw: isize) -> Option<DisplayMode> {
+        let hwnd = HWND(hwnd_raw as *mut c_void);
+        let window = get_window_bounds(hwnd_raw)?;
+        unsafe {
+            let mut info = MONITORINFO {
+                cbSize: std::mem::size_of::<MONITORINFO>() as u32,
+                ..Default::default()
+            };
+            let monitor = MonitorFromWindow(hwnd, MONITOR_DEFAULTTONEAREST);
+            if !GetMonitorInfoW(monitor, &mut info).as_bool() {
+                return None;
+            }
+            let rect = info.rcMonitor;
+            let monitor = Bounds::new(
+                rect.left,
+                rect.top,
+                rect.right - rect.left,
+                rect.bottom - rect.top,
+            );
+
+            let style = GetWindowLongPtrW(hwnd, GWL_STYLE);
+            let caption = WS_CAPTION.0 as isize;
+            let framed = (style & caption) == caption || (style & (WS_THICKFRAME.0 as isize)) != 0;
+
+            let mut pid: u32 = 0;
+            GetWindowThreadProcessId(hwnd, Some(&mut pid));
+            let exclusive = foreground_pid() == Some(pid)
+                && SHQueryUserNotificationState()
+                    .is_ok_and(|state| state == QUNS_RUNNING_D3D_FULL_SCREEN);
+
+            Some(geometry::display_mode(
+                window,
+                monitor,
+                WindowStyle { framed, exclusive },
+            ))
+        }
+    }
+
     /// Make the overlay HWND itself ignore mouse input and refuse activation.
     ///
     /// This is the part CSS `pointer-events: none` cannot do: without
     /// `WS_EX_TRANSPARENT` the WebView2 HWND still swallows every click, and without
     /// `WS_EX_NOACTIVATE` showing the window can pull focus off the game.
-    pub fn apply_overlay_styles(hwnd_raw: isize) {
+    ///
+    /// Without `click_through` only `WS_EX_TRANSPARENT` is dropped, for interactive
+    /// mode: clicks land on the overlay, but still never activate it.
+    pub fn apply_overlay_styles(hwnd_raw: isize, click_through: bool) {
         let hwnd = HWND(hwnd_raw as *mut c_void);
         unsafe {
             let current = GetWindowLongPtrW(hwnd, GWL_EXSTYLE);
-            let wanted = current
-                | (WS_EX_TRANSPARENT.0 as isize)
-                | (WS_EX_NOACTIVATE.0 as isize)
-                | (WS_EX_TOOLWINDOW.0 as isize);
+            let always = current | (WS_EX_NOACTIVATE.0 as isize) | (WS_EX_TOOLWINDOW.0 as isize);
+            let wanted = if click_through {
+                always | (WS_EX_TRANSPARENT.0 as isize)
+            } else {
+                always & !(WS_EX_TRANSPARENT.0 as isize)
+            };
             if current != wanted {
                 SetWindowLongPtrW(hwnd, GWL_EXSTYLE, wanted);
                 debug!(
@@ -184,9 +248,415 @@ mod imp {
     }
 }
 
-#[cfg(not(target_os = "windows"))]
+/// Under Proton the game is an X client like any other - Wine sets `_NET_WM_PID` to the
+/// process running `RelicCoH3.exe`. A Wayland session runs it through XWayland, so this
+/// works there too, as long as the app's own windows are X11 ones as well.
+#[cfg(target_os = "linux")]
 mod imp {
-    use super::Bounds;
+    use super::{Bounds, DisplayMode};
+    use crate::game_overlay::geometry::{self, WindowStyle};
+    use log::{debug, warn};
+    use std::sync::OnceLock;
+    use x11rb::connection::{Connection, RequestConnection};
+    use x11rb::properties::WmHints;
+    use x11rb::protocol::randr::ConnectionExt as _;
+    use x11rb::protocol::shape::{self, ConnectionExt as _, SK, SO};
+    use x11rb::protocol::xproto::{
+        AtomEnum, ClientMessageEvent, ClipOrdering, ConfigureWindowAux, ConnectionExt as _,
+        EventMask, MapState, PropMode, StackMode, Window,
+    };
+    use x11rb::rust_connection::RustConnection;
+    use x11rb::wrapper::ConnectionExt as _;
+
+    x11rb::atom_manager! {
+        Atoms: AtomsCookie {
+            _NET_ACTIVE_WINDOW,
+            _NET_CLIENT_LIST,
+            _NET_FRAME_EXTENTS,
+            _NET_SUPPORTING_WM_CHECK,
+            _NET_WM_NAME,
+            _NET_WM_PID,
+            _NET_WM_STATE,
+            _NET_WM_STATE_ABOVE,
+            _NET_WM_STATE_HIDDEN,
+            _NET_WM_STATE_SKIP_PAGER,
+            _NET_WM_STATE_SKIP_TASKBAR,
+            UTF8_STRING,
+            WM_STATE,
+        }
+    }
+
+    /// ICCCM `WM_STATE` of a minimised window.
+    const ICONIC_STATE: u32 = 3;
+    /// `_NET_WM_STATE` client message action.
+    const NET_WM_STATE_ADD: u32 = 1;
+
+    struct Display {
+        conn: RustConnection,
+        root: Window,
+        atoms: Atoms,
+    }
+
+    /// The X display in `DISPLAY`, connected on first use. `None` for good when there is
+    /// none, e.g. a Wayland session without XWayland.
+    fn display() -> Option<&'static Display> {
+        static DISPLAY: OnceLock<Option<Display>> = OnceLock::new();
+        DISPLAY
+            .get_or_init(|| {
+                let connect = || -> Result<Display, Box<dyn std::error::Error>> {
+                    let (conn, screen) = RustConnection::connect(None)?;
+                    let root = conn.setup().roots[screen].root;
+                    let atoms = Atoms::new(&conn)?.reply()?;
+                    Ok(Display { conn, root, atoms })
+                };
+                connect()
+                    .map_err(|e| warn!("Game overlay: no X display: {}", e))
+                    .ok()
+            })
+            .as_ref()
+    }
+
+    fn window_of(handle: isize) -> Option<Window> {
+        Window::try_from(handle).ok()
+    }
+
+    /// The 32-bit values of `property` on `window`, empty when it is not set.
+    fn property32(display: &Display, window: Window, property: u32, type_: AtomEnum) -> Vec<u32> {
+        let reply = display
+            .conn
+            .get_property(false, window, property, type_, 0, 1024)
+            .ok()
+            .and_then(|cookie| cookie.reply().ok());
+        reply
+            .and_then(|reply| reply.value32().map(|values| values.collect()))
+            .unwrap_or_default()
+    }
+
+    fn pid_of(display: &Display, window: Window) -> Option<u32> {
+        property32(
+            display,
+            window,
+            display.atoms._NET_WM_PID,
+            AtomEnum::CARDINAL,
+        )
+        .first()
+        .copied()
+        .filter(|pid| *pid != 0)
+    }
+
+    /// The top-level windows: the window manager's client list, or without one - a bare
+    /// X server such as Xvfb - the root's children.
+    fn top_level_windows(display: &Display) -> Vec<Window> {
+        let clients = property32(
+            display,
+            display.root,
+            display.atoms._NET_CLIENT_LIST,
+            AtomEnum::WINDOW,
+        );
+        if !clients.is_empty() {
+            return clients;
+        }
+        display
+            .conn
+            .query_tree(display.root)
+            .ok()
+            .and_then(|cookie| cookie.reply().ok())
+            .map(|tree| tree.children)
+            .unwrap_or_default()
+    }
+
+    fn is_viewable(display: &Display, window: Window) -> bool {
+        display
+            .conn
+            .get_window_attributes(window)
+            .ok()
+            .and_then(|cookie| cookie.reply().ok())
+            .is_some_and(|attributes| attributes.map_state == MapState::VIEWABLE)
+    }
+
+    fn title_of(display: &Display, window: Window) -> Option<String> {
+        [
+            (display.atoms._NET_WM_NAME, display.atoms.UTF8_STRING),
+            (AtomEnum::WM_NAME.into(), AtomEnum::STRING.into()),
+        ]
+        .into_iter()
+        .find_map(|(property, type_)| {
+            let reply = display
+                .conn
+                .get_property(false, window, property, type_, 0, 1024)
+                .ok()?
+                .reply()
+                .ok()?;
+            (!reply.value.is_empty()).then(|| String::from_utf8_lossy(&reply.value).into_owned())
+        })
+    }
+
+    /// Largest viewable top-level window belonging to `pid` - the game's render window.
+    pub fn find_window_for_pid(pid: u32) -> Option<isize> {
+        let display = display()?;
+        top_level_windows(display)
+            .into_iter()
+            .filter(|window| pid_of(display, *window) == Some(pid))
+            .filter(|window| is_viewable(display, *window))
+            .filter_map(|window| {
+                let bounds = get_window_bounds(window as isize)?;
+                Some((window, bounds.width as i64 * bounds.height as i64))
+            })
+            .max_by_key(|(_, area)| *area)
+            .map(|(window, _)| window as isize)
+    }
+
+    /// This process's top-level window titled `title`, mapped or not - the overlay's
+    /// own window, which Tauri does not hand out on Linux.
+    pub fn find_own_window(title: &str) -> Option<isize> {
+        let display = display()?;
+        let pid = std::process::id();
+        top_level_windows(display)
+            .into_iter()
+            .find(|window| {
+                pid_of(display, *window) == Some(pid)
+                    && title_of(display, *window).as_deref() == Some(title)
+            })
+            .map(|window| window as isize)
+    }
+
+    /// Window bounds in physical pixels, without the window manager's frame.
+    pub fn get_window_bounds(handle: isize) -> Option<Bounds> {
+        let display = display()?;
+        let window = window_of(handle)?;
+        let geometry = display.conn.get_geometry(window).ok()?.reply().ok()?;
+        // The geometry is relative to the parent, which is the frame once a window
+        // manager has reparented the window.
+        let origin = display
+            .conn
+            .translate_coordinates(window, display.root, 0, 0)
+            .ok()?
+            .reply()
+            .ok()?;
+        if geometry.width == 0 || geometry.height == 0 {
+            return None;
+        }
+        Some(Bounds::new(
+            origin.dst_x.into(),
+            origin.dst_y.into(),
+            geometry.width.into(),
+            geometry.height.into(),
+        ))
+    }
+
+    /// The monitor the centre of `bounds` is on: one of RandR's, or the whole screen
+    /// without them.
+    fn monitor_of(display: &Display, bounds: Bounds) -> Option<Bounds> {
+        let (x, y) = (bounds.x + bounds.width / 2, bounds.y + bounds.height / 2);
+        let monitors = display
+            .conn
+            .randr_get_monitors(display.root, true)
+            .ok()
+            .and_then(|cookie| cookie.reply().ok())
+            .map(|reply| reply.monitors)
+            .unwrap_or_default();
+        let monitor = monitors
+            .iter()
+            .map(|m| Bounds::new(m.x.into(), m.y.into(), m.width.into(), m.height.into()))
+            .find(|monitor| monitor.contains(x, y));
+        if monitor.is_some() {
+            return monitor;
+        }
+        get_window_bounds(display.root as isize)
+    }
+
+    /// How the game window is displayed, from its bounds against those of the monitor
+    /// it is on and from the frame the window manager gave it.
+    ///
+    /// Never exclusive: a fullscreen game under X11 is still a window the compositor
+    /// can put the overlay over.
+    pub fn display_mode(handle: isize) -> Option<DisplayMode> {
+        let display = display()?;
+        let window = get_window_bounds(handle)?;
+        let monitor = monitor_of(display, window)?;
+        let framed = property32(
+            display,
+            window_of(handle)?,
+            display.atoms._NET_FRAME_EXTENTS,
+            AtomEnum::CARDINAL,
+        )
+        .iter()
+        .any(|extent| *extent != 0);
+        Some(geometry::display_mode(
+            window,
+            monitor,
+            WindowStyle {
+                framed,
+                exclusive: false,
+            },
+        ))
+    }
+
+    /// Process owning the active window, `None` when no window is active or it does not
+    /// say whose it is.
+    pub fn foreground_pid() -> Option<u32> {
+        let display = display()?;
+        let active = property32(
+            display,
+            display.root,
+            display.atoms._NET_ACTIVE_WINDOW,
+            AtomEnum::WINDOW,
+        );
+        let window = active.first().copied().filter(|window| *window != 0)?;
+        pid_of(display, window)
+    }
+
+    /// Sends an EWMH request to the window manager to add `states` to `window`, or -
+    /// without one to ask, or before the window is mapped - sets them itself.
+    fn add_states(display: &Display, window: Window, states: &[u32]) {
+        let atoms = &display.atoms;
+        let managed = !property32(
+            display,
+            display.root,
+            atoms._NET_SUPPORTING_WM_CHECK,
+            AtomEnum::WINDOW,
+        )
+        .is_empty();
+        if managed && is_viewable(display, window) {
+            for pair in states.chunks(2) {
+                let event = ClientMessageEvent::new(
+                    32,
+                    window,
+                    atoms._NET_WM_STATE,
+                    [
+                        NET_WM_STATE_ADD,
+                        pair[0],
+                        pair.get(1).copied().unwrap_or(0),
+                        1,
+                        0,
+                    ],
+                );
+                let _ = display.conn.send_event(
+                    false,
+                    display.root,
+                    EventMask::SUBSTRUCTURE_REDIRECT | EventMask::SUBSTRUCTURE_NOTIFY,
+                    event,
+                );
+            }
+            return;
+        }
+        let mut current = property32(display, window, atoms._NET_WM_STATE, AtomEnum::ATOM);
+        for state in states {
+            if !current.contains(state) {
+                current.push(*state);
+            }
+        }
+        let _ = display.conn.change_property32(
+            PropMode::REPLACE,
+            window,
+            atoms._NET_WM_STATE,
+            AtomEnum::ATOM,
+            &current,
+        );
+    }
+
+    /// Keep the overlay above the game. Showing it never takes the focus: its
+    /// `WM_HINTS` say it does not want any - see `apply_overlay_styles`.
+    pub fn raise_without_activating(handle: isize) {
+        let Some((display, window)) = display().zip(window_of(handle)) else {
+            return;
+        };
+        add_states(display, window, &[display.atoms._NET_WM_STATE_ABOVE]);
+        let _ = display.conn.configure_window(
+            window,
+            &ConfigureWindowAux::new().stack_mode(StackMode::ABOVE),
+        );
+        let _ = display.conn.flush();
+    }
+
+    pub fn is_minimised(handle: isize) -> bool {
+        let Some((display, window)) = display().zip(window_of(handle)) else {
+            return false;
+        };
+        let atoms = &display.atoms;
+        property32(display, window, atoms._NET_WM_STATE, AtomEnum::ATOM)
+            .contains(&atoms._NET_WM_STATE_HIDDEN)
+            || property32(display, window, atoms.WM_STATE, AtomEnum::ANY).first()
+                == Some(&ICONIC_STATE)
+    }
+
+    /// The mouse pointer, in physical screen pixels.
+    pub fn cursor_position() -> Option<(i32, i32)> {
+        let display = display()?;
+        let pointer = display
+            .conn
+            .query_pointer(display.root)
+            .ok()?
+            .reply()
+            .ok()?;
+        Some((pointer.root_x.into(), pointer.root_y.into()))
+    }
+
+    /// X11 has no per-window DPI, and bounds are already in the pixels the overlay is
+    /// placed in.
+    pub fn get_dpi(_handle: isize) -> u32 {
+        96
+    }
+
+    /// Keep the overlay out of the taskbar and the focus, and - with `click_through` -
+    /// out of the way of the pointer.
+    ///
+    /// `WM_HINTS` with no input is what `WS_EX_NOACTIVATE` is on Windows. Click-through
+    /// is an empty input shape: clicks land on whatever is below. Without it the input
+    /// shape goes back to the whole window, for interactive mode.
+    pub fn apply_overlay_styles(handle: isize, click_through: bool) {
+        let Some((display, window)) = display().zip(window_of(handle)) else {
+            return;
+        };
+        let atoms = &display.atoms;
+        let hints = WmHints {
+            input: Some(false),
+            ..WmHints::default()
+        };
+        let _ = hints.set(&display.conn, window);
+        add_states(
+            display,
+            window,
+            &[
+                atoms._NET_WM_STATE_ABOVE,
+                atoms._NET_WM_STATE_SKIP_TASKBAR,
+                atoms._NET_WM_STATE_SKIP_PAGER,
+            ],
+        );
+        if display
+            .conn
+            .extension_information(shape::X11_EXTENSION_NAME)
+            .ok()
+            .flatten()
+            .is_none()
+        {
+            warn!("Game overlay: no X shape extension, the overlay cannot let clicks through");
+        } else if click_through {
+            let _ = display.conn.shape_rectangles(
+                SO::SET,
+                SK::INPUT,
+                ClipOrdering::UNSORTED,
+                window,
+                0,
+                0,
+                &[],
+            );
+        } else {
+            let _ = display
+                .conn
+                .shape_mask(SO::SET, SK::INPUT, window, 0, 0, x11rb::NONE);
+        }
+        let _ = display.conn.flush();
+        debug!(
+            "Game overlay window 0x{:x} styled, click-through {}",
+            window, click_through
+        );
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+mod imp {
+    use super::{Bounds, DisplayMode};
 
     pub fn find_window_for_pid(_pid: u32) -> Option<isize> {
         None
@@ -204,10 +674,55 @@ mod imp {
     pub fn get_dpi(_hwnd: isize) -> u32 {
         96
     }
-    pub fn apply_overlay_styles(_hwnd: isize) {}
+    pub fn apply_overlay_styles(_hwnd: isize, _click_through: bool) {}
+    pub fn cursor_position() -> Option<(i32, i32)> {
+        None
+    }
+    pub fn display_mode(_hwnd: isize) -> Option<DisplayMode> {
+        None
+    }
 }
 
+#[cfg(target_os = "linux")]
+pub use imp::find_own_window;
 pub use imp::{
-    apply_overlay_styles, find_window_for_pid, foreground_pid, get_dpi, get_window_bounds,
-    is_minimised, raise_without_activating,
+    apply_overlay_styles, cursor_position, display_mode, find_window_for_pid, foreground_pid,
+    get_dpi, get_window_bounds, is_minimised, raise_without_activating,
 };
+
+/// The real desktop, as the overlay watcher sees it.
+pub struct Desktop;
+
+impl WindowSystem for Desktop {
+    fn game_pid(&self) -> Option<u32> {
+        #[cfg(any(target_os = "windows", target_os = "linux"))]
+        let pid = crate::process_watcher::find_game_process_id();
+        #[cfg(not(any(target_os = "windows", target_os = "linux")))]
+        let pid: Option<u32> = None;
+        pid
+    }
+    fn find_window(&self, pid: u32) -> Option<isize> {
+        find_window_for_pid(pid)
+    }
+    fn bounds(&self, window: isize) -> Option<Bounds> {
+        get_window_bounds(window)
+    }
+    fn dpi(&self, window: isize) -> u32 {
+        get_dpi(window)
+    }
+    fn is_minimised(&self, window: isize) -> bool {
+        is_minimised(window)
+    }
+    fn display_mode(&self, window: isize) -> Option<DisplayMode> {
+        display_mode(window)
+    }
+    fn foreground_pid(&self) -> Option<u32> {
+        foreground_pid()
+    }
+    fn own_pid(&self) -> u32 {
+        std::process::id()
+    }
+    fn cursor_position(&self) -> Option<(i32, i32)> {
+        cursor_position()
+    }
+}
@@ -8,21 +8,33 @@ extern crate machine_uid;
 mod audio_manager;
 mod battlegroup_info;
 mod config;
+mod current_game;
 mod dp_utils;
 mod game_overlay;
+mod live_events;
 mod map_stats;
+mod map_stats_history;
+mod matchup_card;
+mod net;
+mod obs_websocket;
+mod overlay_access;
+mod overlay_assets;
+mod overlay_http;
+mod overlay_reload;
 mod overlay_server;
 mod parse_log_file;
+mod player_profiles;
 mod plugins;
 mod process_watcher;
+mod session;
 #[cfg(test)]
 mod tests;
+mod twitch_chat;
+mod win_probability;
 
 use dp_utils::load_from_store;
 use log::{error, info};
-use overlay_server::run_http_server;
 use std::path::{Path, PathBuf};
-use std::thread;
 use tauri::Runtime;
 use tauri::{AppHandle, Emitter, Manager};
 // use tauri_plugin_log::Target; // Unused for now
@@ -115,9 +127,17 @@ pub fn run() {
     let builder = tauri::Builder::default()
         .manage(audio_manager::AudioManagerState::default())
         .manage(process_watcher::ProcessWatcherState::default())
+        .manage(current_game::CurrentGameState::default())
+        .manage(live_events::LiveEvents::default())
+        .manage(matchup_card::CardCache::default())
+        .manage(overlay_server::OverlayServerState::default())
         .manage(map_stats::MapStatsState::default())
         .manage(battlegroup_info::BattlegroupInfoState::default())
         .manage(game_overlay::GameOverlayState::default())
+        .manage(game_overlay::hotkey::HotkeyState::default())
+        .manage(session::SessionState::default())
+        .manage(twitch_chat::ChatBotState::default())
+        .manage(obs_websocket::ObsState::default())
         .plugin(
             tauri_plugin_log::Builder::new()
                 .level(log::LevelFilter::Info)
@@ -132,16 +152,43 @@ pub fn run() {
             default_playback_path,
             check_path_exists,
             get_machine_id,
-            parse_log_file::parse_log_file_reverse,
+            current_game::parse_log_file_reverse,
             enable_audio_muting,
             disable_audio_muting,
             update_audio_mute_settings,
             start_process_watcher,
             stop_process_watcher,
             map_stats::get_map_stats,
+            map_stats_history::list_map_stats_snapshots,
+            map_stats_history::diff_map_stats,
             battlegroup_info::get_battlegroup_info,
-            game_overlay::game_overlay_show,
-            game_overlay::game_overlay_hide
+            net::network_status,
+            player_profiles::get_player_profile,
+            session::get_session_scoreboard,
+            session::reset_session,
+            twitch_chat::start_chat_bot,
+            twitch_chat::stop_chat_bot,
+            twitch_chat::chat_bot_status,
+            obs_websocket::start_obs_connection,
+            obs_websocket::stop_obs_connection,
+            obs_websocket::obs_connection_status,
+            win_probability::estimate_win_probability,
+            matchup_card::save_matchup_card,
+            matchup_card::copy_matchup_card,
+            overlay_server::start_overlay_server,
+            overlay_server::stop_overlay_server,
+            overlay_server::overlay_server_status,
+            overlay_server::rotate_overlay_token,
+            overlay_server::overlay_lan_addresses,
+            game_overlay::game_overlay_display_mode,
+            game_overlay::game_overlay_interactive,
+            game_overlay::game_overlay_layout,
+            game_overlay::game_overlay_set_layout,
+            game_overlay::game_overlay_status,
+            game_overlay::game_overlay_timeouts,
+            game_overlay::game_overlay_set_timeouts,
+            game_overlay::hotkey::game_overlay_hotkey,
+            game_overlay::hotkey::game_overlay_set_hotkey
         ])
         .plugin(tauri_plugin_single_instance::init(|app, argv, cwd| {
             let window = match app.get_webview_window("main") {
@@ -213,6 +260,7 @@ pub fn run() {
                 audio_manager::cleanup_on_exit(app_handle);
                 // Never leave the overlay on screen after the app is gone
                 game_overlay::hide(app_handle);
+                overlay_server::shut_down(app_handle);
             }
             // The overlay window outlives the main one and would keep the process running
             // with nothing on screen - closing the main window has to close the app.
@@ -281,28 +329,32 @@ fn setup(app: &mut tauri::App) -> Result<(), Box<dyn std::error::Error>> {
             // Don't fail the entire setup if updater fails
             info!("Continuing without updater plugin");
         }
+
+        // The overlay hotkey; shortcuts are only registered while in a match
+        let shortcuts = tauri_plugin_global_shortcut::Builder::new()
+            .with_handler(game_overlay::hotkey::on_shortcut)
+            .build();
+        if let Err(e) = handle.plugin(shortcuts) {
+            error!("Failed to initialize global shortcut plugin: {}", e);
+            sentry::capture_message(
+                &format!("Global shortcut plugin initialization error: {}", e),
+                sentry::Level::Error,
+            );
+        }
     }
 
+    // Shared HTTP client - everything below that fetches goes through it. Before the
+    // overlays, which look up profiles as soon as they are up.
+    let net = net::init(handle);
+    handle.manage(player_profiles::PlayerProfilesState::new(net));
+
     if load_from_store(handle.clone(), "streamerOverlayEnabled").unwrap_or(false) {
         info!("Streamer overlay server is enabled");
-        match handle.path().app_data_dir() {
-            Ok(mut file_path) => {
-                file_path.push("streamerOverlay.html");
-                info!("Expecting the streamerOverlay at {:?}", file_path);
-
-                let _handle = thread::spawn(|| {
-                    run_http_server(file_path);
-                });
-            }
-            Err(e) => {
-                error!("Failed to get app data directory for overlay: {}", e);
-                sentry::capture_message(
-                    &format!("App data directory access error (overlay): {}", e),
-                    sentry::Level::Error,
-                );
-                // Don't fail setup, just skip overlay server
-                info!("Continuing without streamer overlay server");
-            }
+        // Don't fail setup, just skip overlay server
+        let started = overlay_server::listen_settings(handle, None, None, None)
+            .and_then(|settings| overlay_server::start(handle, settings));
+        if let Err(e) = started {
+            info!("Continuing without streamer overlay server: {}", e);
         }
     } else {
         info!("Streamer overlay server is disabled");
@@ -339,8 +391,22 @@ fn setup(app: &mut tauri::App) -> Result<(), Box<dyn std::error::Error>> {
 
     // In-game matchup overlay: create the window once, hidden. It is only ever
     // shown/hidden afterwards - see game_overlay/mod.rs.
+    game_overlay::load_settings(handle);
+    game_overlay::hotkey::load(handle);
     game_overlay::create_overlay_window(handle);
 
+    // Twitch chat bot, it answers from the profile lookups
+    if twitch_chat::is_enabled(handle) {
+        if let Err(e) = twitch_chat::start(handle) {
+            info!("Continuing without chat bot: {}", e);
+        }
+    }
+
+    // OBS scene switching, it keeps retrying until OBS is up
+    if obs_websocket::is_enabled(handle) {
+        obs_websocket::start(handle);
+    }
+
     // Initialize map stats fetching (non-blocking)
     map_stats::init_map_stats(handle.clone());
 
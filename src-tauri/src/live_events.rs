@@ -0,0 +1,156 @@
+//! Live updates for streamer overlays.
+//!
+//! Every parse of the log file is compared with the previous one and the differences are
+//! published here as `LiveEvent`s: the game state moving on, a new lobby, a match
+//! result. The session scoreboard publishes its updates here as well. The overlay
+//! server streams them to its clients as Server-Sent Events at `/api/v1/events`, so
+//! browser sources can update without reloading the page.
+//!
+//! The latest event of each kind is kept and replayed to every new subscriber first, so
+//! an overlay that connects mid-game starts from the current state. `Reload` is the
+//! exception, it only concerns pages open at the time.
+
+use crate::current_game::LastMatch;
+use crate::parse_log_file::{GameState, LogFileData};
+use crate::session::Scoreboard;
+use log::warn;
+use serde::Serialize;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Mutex;
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(untagged)]
+pub enum LiveEvent {
+    GameState(GameState),
+    Lobby(LogFileData),
+    MatchResult(LastMatch),
+    Session(Scoreboard),
+    /// The overlay files changed, pages should reload. Not replayed.
+    Reload,
+}
+
+impl LiveEvent {
+    /// SSE event name, what overlays pass to `addEventListener`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            LiveEvent::GameState(_) => "game-state",
+            LiveEvent::Lobby(_) => "lobby",
+            LiveEvent::MatchResult(_) => "match-result",
+            LiveEvent::Session(_) => "session",
+            LiveEvent::Reload => "reload",
+        }
+    }
+
+    /// The event as an SSE message, with the payload as JSON data.
+    pub fn to_sse(&self) -> Result<String, serde_json::Error> {
+        // Serialized JSON never contains raw newlines, so one `data:` line is enough.
+        let data = match self {
+            LiveEvent::GameState(state) => serde_json::json!({ "game_state": state }).to_string(),
+            LiveEvent::Lobby(game) => serde_json::to_string(game)?,
+            LiveEvent::MatchResult(last_match) => serde_json::to_string(last_match)?,
+            LiveEvent::Session(scoreboard) => serde_json::to_string(scoreboard)?,
+            LiveEvent::Reload => "{}".to_string(),
+        };
+        Ok(format!("event: {}\ndata: {}\n\n", self.name(), data))
+    }
+}
+
+/// Identifies a lobby: the same match parsed again has the same key.
+fn lobby_key(game: &LogFileData) -> (&str, &str, Vec<&str>) {
+    let players = game
+        .left
+        .players
+        .iter()
+        .chain(game.right.players.iter())
+        .map(|p| p.relic_id.as_str())
+        .collect();
+    (game.timestamp.as_str(), game.map.as_str(), players)
+}
+
+fn has_players(game: &LogFileData) -> bool {
+    !game.left.players.is_empty() || !game.right.players.is_empty()
+}
+
+/// Events describing how `next` differs from the previous parse `prev`.
+pub fn changes(prev: Option<&LogFileData>, next: &LogFileData) -> Vec<LiveEvent> {
+    let mut events = Vec::new();
+
+    if prev.map(|p| &p.game_state) != Some(&next.game_state) {
+        events.push(LiveEvent::GameState(next.game_state.clone()));
+    }
+
+    let same_lobby = prev.is_some_and(|p| lobby_key(p) == lobby_key(next));
+    if !same_lobby && has_players(next) {
+        events.push(LiveEvent::Lobby(next.clone()));
+    }
+
+    let already_reported = same_lobby && prev.is_some_and(|p| p.winner.is_some());
+    if !already_reported {
+        if let Some(last_match) = LastMatch::from_game(next) {
+            events.push(LiveEvent::MatchResult(last_match));
+        }
+    }
+
+    events
+}
+
+#[derive(Default)]
+struct Hub {
+    subscribers: Vec<Sender<LiveEvent>>,
+    game_state: Option<LiveEvent>,
+    lobby: Option<LiveEvent>,
+    match_result: Option<LiveEvent>,
+    session: Option<LiveEvent>,
+}
+
+/// Fan-out of live events to any number of subscribers
+#[derive(Default)]
+pub struct LiveEvents {
+    hub: Mutex<Hub>,
+}
+
+impl LiveEvents {
+    fn lock_hub(&self) -> std::sync::MutexGuard<'_, Hub> {
+        self.hub.lock().unwrap_or_else(|poisoned| {
+            warn!("LiveEvents mutex was poisoned, recovering");
+            poisoned.into_inner()
+        })
+    }
+
+    /// Registers a subscriber. Returns the current state to replay, followed on the
+    /// receiver by every event published from now on.
+    pub fn subscribe(&self) -> (Vec<LiveEvent>, Receiver<LiveEvent>) {
+        let (sender, receiver) = mpsc::channel();
+        let mut hub = self.lock_hub();
+        hub.subscribers.push(sender);
+        let replay = [&hub.game_state, &hub.lobby, &hub.match_result, &hub.session]
+            .into_iter()
+            .flatten()
+            .cloned()
+            .collect();
+        (replay, receiver)
+    }
+
+    /// Sends `event` to every subscriber, dropping the ones that went away.
+    pub fn publish(&self, event: LiveEvent) {
+        let mut hub = self.lock_hub();
+        hub.subscribers.retain(|s| s.send(event.clone()).is_ok());
+        let latest = match event {
+            LiveEvent::GameState(_) => &mut hub.game_state,
+            LiveEvent::Lobby(_) => &mut hub.lobby,
+            LiveEvent::MatchResult(_) => &mut hub.match_result,
+            LiveEvent::Session(_) => &mut hub.session,
+            LiveEvent::Reload => return,
+        };
+        *latest = Some(event);
+    }
+
+    /// Ends every subscription, closing their streams.
+    pub fn disconnect_all(&self) {
+        self.lock_hub().subscribers.clear();
+    }
+
+    pub fn subscriber_count(&self) -> usize {
+        self.lock_hub().subscribers.len()
+    }
+}
@@ -1,4 +1,5 @@
 use crate::config::{MAP_STATS_API_URL, MAP_STATS_CACHE_FILENAME};
+use crate::net::{Net, NetError};
 use log::{error, info, warn};
 use serde_json::Value;
 use std::fs;
@@ -16,20 +17,9 @@ pub struct MapStatsState {
 }
 
 /// Fetches map stats from the API
-pub async fn fetch_map_stats() -> Result<Value, reqwest::Error> {
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
-        .build()?;
-
-    let response = client
-        .get(MAP_STATS_API_URL)
-        .header("Accept-Encoding", "gzip, deflate, br")
-        .header("Accept", "application/json")
-        .send()
-        .await?
-        .error_for_status()?;
-
-    response.json::<Value>().await
+pub async fn fetch_map_stats(net: &Net) -> Result<Value, NetError> {
+    net.get_json(MAP_STATS_API_URL, Duration::from_secs(REQUEST_TIMEOUT_SECS))
+        .await
 }
 
 /// Gets the cache file path
@@ -111,13 +101,14 @@ pub fn init_map_stats<R: Runtime>(handle: AppHandle<R>) {
                     "Loaded map stats from fresh cache (less than {} hours old)",
                     CACHE_MAX_AGE_HOURS
                 );
+                crate::map_stats_history::record(&handle, &cached_data, true);
                 *lock_state_data(&state) = Some(cached_data);
                 return;
             }
         }
 
         // Cache is stale or doesn't exist, fetch from API
-        match fetch_map_stats().await {
+        match fetch_map_stats(&crate::net::net(&handle)).await {
             Ok(data) => {
                 info!("Successfully fetched map stats from API");
                 // Save to cache
@@ -128,6 +119,7 @@ pub fn init_map_stats<R: Runtime>(handle: AppHandle<R>) {
                         sentry::Level::Warning,
                     );
                 }
+                crate::map_stats_history::record(&handle, &data, false);
                 *lock_state_data(&state) = Some(data);
             }
             Err(e) => {
@@ -140,6 +132,7 @@ pub fn init_map_stats<R: Runtime>(handle: AppHandle<R>) {
                 // Try to load from cache (even if stale, better than nothing)
                 if let Some(cached_data) = load_from_cache(&handle) {
                     info!("Loaded map stats from stale cache after API failure");
+                    crate::map_stats_history::record(&handle, &cached_data, true);
                     *lock_state_data(&state) = Some(cached_data);
                 } else {
                     error!("Failed to load map stats from cache");
@@ -0,0 +1,349 @@
+//! Per-patch archive of map stats snapshots.
+//!
+//! `map_stats.json` only ever holds the latest fetch, so every snapshot is also copied
+//! into `map_stats_history/<patch>.json` - one file per patch, overwritten while that
+//! patch is current and left alone once a newer one drops. `diff_snapshots` compares two
+//! of them for patch-review notes.
+
+use crate::config::MAP_STATS_HISTORY_DIRNAME;
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager, Runtime};
+
+/// Describes one archived snapshot without its (large) payload.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SnapshotMeta {
+    /// `latestPatchInfo.value` - the key snapshots are stored and diffed by.
+    pub patch: String,
+    /// `latestPatchInfo.label`, for display.
+    pub label: String,
+    /// Unix seconds of the fetch that last wrote this snapshot.
+    pub archived_at: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct Snapshot {
+    #[serde(flatten)]
+    meta: SnapshotMeta,
+    data: Value,
+}
+
+/// A rate in two snapshots. `None` where the snapshot has no games to compute it from.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RateChange {
+    pub before: Option<f64>,
+    pub after: Option<f64>,
+    pub delta: Option<f64>,
+}
+
+impl RateChange {
+    fn new(before: Option<f64>, after: Option<f64>) -> Self {
+        let delta = match (before, after) {
+            (Some(b), Some(a)) => Some(a - b),
+            _ => None,
+        };
+        Self {
+            before,
+            after,
+            delta,
+        }
+    }
+}
+
+/// One faction matchup on one map, e.g. `"WxA"` - axis factions first, as coh3stats
+/// keys its `factionMatrix`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MatchupDiff {
+    pub matchup: String,
+    /// Win rate of the axis side of the matchup.
+    pub win_rate: RateChange,
+    /// Share of the map's games played as this matchup.
+    pub pick_rate: RateChange,
+    pub games_before: u64,
+    pub games_after: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MapDiff {
+    /// `1v1` .. `4v4`
+    pub mode: String,
+    pub map: String,
+    /// Share of the mode's games played on this map.
+    pub pick_rate: RateChange,
+    pub matchups: Vec<MatchupDiff>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MapStatsDiff {
+    pub from_patch: String,
+    pub to_patch: String,
+    pub maps: Vec<MapDiff>,
+}
+
+/// Patch key of a map stats payload, if it carries one.
+pub fn patch_of(data: &Value) -> Option<&str> {
+    data.get("latestPatchInfo")?
+        .get("value")?
+        .as_str()
+        .filter(|p| !p.is_empty())
+}
+
+/// File name for a patch key. Patch values are things like `"1.5.0"`, but they come
+/// from the network, so anything that is not safe in a file name is percent-encoded:
+/// distinct patches keep distinct files. That includes leading dots, so nothing maps to
+/// a hidden or relative name, and upper case letters, which Windows does not tell apart.
+/// `None` for an empty patch.
+pub fn snapshot_file_name(patch: &str) -> Option<String> {
+    if patch.is_empty() {
+        return None;
+    }
+    let leading_dots = patch.len() - patch.trim_start_matches('.').len();
+    let mut name = String::with_capacity(patch.len());
+    for (i, byte) in patch.bytes().enumerate() {
+        let safe = byte.is_ascii_lowercase()
+            || byte.is_ascii_digit()
+            || matches!(byte, b'.' | b'-' | b'_');
+        if safe && i >= leading_dots {
+            name.push(byte as char);
+        } else {
+            name.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    Some(format!("{}.json", name))
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Writes `data` into the archive in `dir`, replacing any snapshot of the same patch.
+pub fn archive_snapshot(dir: &Path, data: &Value) -> Result<SnapshotMeta, std::io::Error> {
+    let patch = patch_of(data).ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "Map stats carry no latestPatchInfo.value",
+        )
+    })?;
+
+    let meta = SnapshotMeta {
+        patch: patch.to_string(),
+        label: data["latestPatchInfo"]["label"]
+            .as_str()
+            .unwrap_or(patch)
+            .to_string(),
+        archived_at: unix_now(),
+    };
+    let snapshot = Snapshot {
+        meta: meta.clone(),
+        data: data.clone(),
+    };
+
+    fs::create_dir_all(dir)?;
+    let json_string = serde_json::to_string(&snapshot)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    let file_name = snapshot_file_name(patch).ok_or_else(|| {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, "Map stats patch is empty")
+    })?;
+    fs::write(dir.join(file_name), json_string)?;
+    Ok(meta)
+}
+
+fn read_snapshot(path: &Path) -> Option<Snapshot> {
+    let content = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// Whether the archive in `dir` already holds a snapshot of `patch`.
+pub fn has_snapshot(dir: &Path, patch: &str) -> bool {
+    snapshot_file_name(patch).is_some_and(|name| dir.join(name).exists())
+}
+
+/// Every readable snapshot in `dir`, oldest first.
+pub fn list_snapshots(dir: &Path) -> Vec<SnapshotMeta> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut snapshots: Vec<SnapshotMeta> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|path| match read_snapshot(&path) {
+            Some(snapshot) => Some(snapshot.meta),
+            None => {
+                warn!("Skipping unreadable map stats snapshot {:?}", path);
+                None
+            }
+        })
+        .collect();
+    snapshots.sort_by(|a, b| {
+        a.archived_at
+            .cmp(&b.archived_at)
+            .then_with(|| a.patch.cmp(&b.patch))
+    });
+    snapshots
+}
+
+/// The stored payload of `patch`.
+pub fn load_snapshot(dir: &Path, patch: &str) -> Option<Value> {
+    read_snapshot(&dir.join(snapshot_file_name(patch)?)).map(|s| s.data)
+}
+
+fn wins_losses(entry: &Value) -> (u64, u64) {
+    (
+        entry["wins"].as_u64().unwrap_or(0),
+        entry["losses"].as_u64().unwrap_or(0),
+    )
+}
+
+fn ratio(part: u64, whole: u64) -> Option<f64> {
+    if whole == 0 {
+        None
+    } else {
+        Some(part as f64 / whole as f64)
+    }
+}
+
+fn object_keys<'a>(a: &'a Value, b: &'a Value) -> BTreeSet<&'a str> {
+    let keys = |v: &'a Value| {
+        v.as_object()
+            .into_iter()
+            .flat_map(|o| o.keys().map(String::as_str))
+    };
+    keys(a).chain(keys(b)).collect()
+}
+
+fn mode_total(maps: &Value) -> u64 {
+    maps.as_object()
+        .map(|o| {
+            o.values()
+                .map(|m| m["matchCount"].as_u64().unwrap_or(0))
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+fn diff_map(mode: &str, map: &str, old: &Value, new: &Value, totals: (u64, u64)) -> MapDiff {
+    let old_map = &old[map];
+    let new_map = &new[map];
+    let old_count = old_map["matchCount"].as_u64().unwrap_or(0);
+    let new_count = new_map["matchCount"].as_u64().unwrap_or(0);
+
+    let matchups = object_keys(&old_map["factionMatrix"], &new_map["factionMatrix"])
+        .into_iter()
+        .map(|matchup| {
+            let (old_wins, old_losses) = wins_losses(&old_map["factionMatrix"][matchup]);
+            let (new_wins, new_losses) = wins_losses(&new_map["factionMatrix"][matchup]);
+            let games_before = old_wins + old_losses;
+            let games_after = new_wins + new_losses;
+            MatchupDiff {
+                matchup: matchup.to_string(),
+                win_rate: RateChange::new(
+                    ratio(old_wins, games_before),
+                    ratio(new_wins, games_after),
+                ),
+                pick_rate: RateChange::new(
+                    ratio(games_before, old_count),
+                    ratio(games_after, new_count),
+                ),
+                games_before,
+                games_after,
+            }
+        })
+        .collect();
+
+    MapDiff {
+        mode: mode.to_string(),
+        map: map.to_string(),
+        pick_rate: RateChange::new(ratio(old_count, totals.0), ratio(new_count, totals.1)),
+        matchups,
+    }
+}
+
+/// Win and pick rate changes per mode, map and faction matchup between two map stats
+/// payloads. Maps or matchups present in only one of them are still listed, with the
+/// missing side as `None`.
+pub fn diff_snapshots(old: &Value, new: &Value) -> MapStatsDiff {
+    let old_analysis = &old["mapStats"]["analysis"];
+    let new_analysis = &new["mapStats"]["analysis"];
+
+    let mut maps = Vec::new();
+    for mode in object_keys(old_analysis, new_analysis) {
+        let old_mode = &old_analysis[mode];
+        let new_mode = &new_analysis[mode];
+        let totals = (mode_total(old_mode), mode_total(new_mode));
+        for map in object_keys(old_mode, new_mode) {
+            maps.push(diff_map(mode, map, old_mode, new_mode, totals));
+        }
+    }
+
+    MapStatsDiff {
+        from_patch: patch_of(old).unwrap_or_default().to_string(),
+        to_patch: patch_of(new).unwrap_or_default().to_string(),
+        maps,
+    }
+}
+
+/// Gets the archive directory path
+pub fn get_history_dir<R: Runtime>(handle: &AppHandle<R>) -> Option<PathBuf> {
+    handle.path().app_data_dir().ok().map(|mut p| {
+        p.push(MAP_STATS_HISTORY_DIRNAME);
+        p
+    })
+}
+
+/// Archives freshly fetched map stats. With `only_if_missing` an existing snapshot of
+/// the same patch is kept - used for data coming out of the cache, which is never newer
+/// than what was archived when it was fetched.
+pub fn record<R: Runtime>(handle: &AppHandle<R>, data: &Value, only_if_missing: bool) {
+    let Some(dir) = get_history_dir(handle) else {
+        error!("Could not determine app data directory for map stats history");
+        return;
+    };
+    if only_if_missing && patch_of(data).is_some_and(|patch| has_snapshot(&dir, patch)) {
+        return;
+    }
+
+    match archive_snapshot(&dir, data) {
+        Ok(meta) => info!("Archived map stats snapshot for patch {}", meta.patch),
+        Err(e) => {
+            error!("Failed to archive map stats snapshot: {}", e);
+            sentry::capture_message(
+                &format!("Map stats history archive error: {}", e),
+                sentry::Level::Warning,
+            );
+        }
+    }
+}
+
+/// Tauri command listing the archived map stats snapshots, oldest first
+#[tauri::command]
+pub fn list_map_stats_snapshots<R: Runtime>(handle: AppHandle<R>) -> Vec<SnapshotMeta> {
+    get_history_dir(&handle)
+        .map(|dir| list_snapshots(&dir))
+        .unwrap_or_default()
+}
+
+/// Tauri command comparing the snapshots of two patches
+#[tauri::command]
+pub fn diff_map_stats<R: Runtime>(
+    handle: AppHandle<R>,
+    from_patch: String,
+    to_patch: String,
+) -> Result<MapStatsDiff, String> {
+    let dir = get_history_dir(&handle)
+        .ok_or("Could not determine app data directory for map stats history")?;
+    let old = load_snapshot(&dir, &from_patch)
+        .ok_or_else(|| format!("No map stats snapshot for patch {}", from_patch))?;
+    let new = load_snapshot(&dir, &to_patch)
+        .ok_or_else(|| format!("No map stats snapshot for patch {}", to_patch))?;
+    Ok(diff_snapshots(&old, &new))
+}
@@ -0,0 +1,708 @@
+//! Matchup card: the current or last lobby drawn to a PNG for posting in Discord.
+//!
+//! Drawn on the CPU from the parsed log and the shared Relic profile lookups - map,
+//! players, factions, ratings and ranks, and the result once there is one. Faction and
+//! rank icons are the ones bundled with the frontend, text uses the system's fonts with
+//! fallbacks for names in other scripts (Chinese, Korean, Japanese, ...).
+//!
+//! The card can be saved to a file, copied to the clipboard, or fetched from the overlay
+//! server at `/card.png`.
+
+use crate::current_game::{self, LastMatch};
+use crate::parse_log_file::{LogFileData, PlayerData, TeamPosition};
+use crate::player_profiles::PlayerProfilesState;
+use crate::session::{self, Standing};
+use crate::twitch_chat::faction_name;
+use ab_glyph::{point, Font, FontVec, GlyphId, PxScale, ScaleFont};
+use image::imageops::{self, FilterType};
+use image::{ImageFormat, Rgba, RgbaImage};
+use log::{info, warn};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, OnceLock};
+use tauri::{AppHandle, Manager, Runtime};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use tauri_plugin_dialog::DialogExt;
+use tokio::sync::OnceCell;
+
+const WIDTH: u32 = 960;
+const PADDING: u32 = 24;
+const HEADER_HEIGHT: u32 = 72;
+const ROW_HEIGHT: u32 = 64;
+const FOOTER_HEIGHT: u32 = 36;
+/// Between the two teams, where "VS" goes.
+const GAP: u32 = 60;
+const COLUMN_WIDTH: u32 = (WIDTH - 2 * PADDING - GAP) / 2;
+const ICON_SIZE: u32 = 44;
+
+const BACKGROUND: Rgba<u8> = Rgba([26, 27, 30, 255]);
+const ROW: Rgba<u8> = Rgba([37, 38, 43, 255]);
+const ROW_WON: Rgba<u8> = Rgba([30, 58, 42, 255]);
+const ROW_LOST: Rgba<u8> = Rgba([58, 31, 34, 255]);
+const ACCENT: Rgba<u8> = Rgba([34, 139, 230, 255]);
+const TEXT: Rgba<u8> = Rgba([233, 236, 239, 255]);
+const DIMMED: Rgba<u8> = Rgba([144, 146, 150, 255]);
+const WON: Rgba<u8> = Rgba([81, 207, 102, 255]);
+const LOST: Rgba<u8> = Rgba([255, 107, 107, 255]);
+
+/// Rank icons below 1600 ELO by the lowest rating they start at, as the frontend's
+/// `calculatePlayerTier` picks them.
+const RATING_TIERS: [(i64, &str); 14] = [
+    (0, "01_brass_03"),
+    (300, "01_brass_02"),
+    (600, "01_brass_01"),
+    (800, "02_bronze_03"),
+    (1000, "02_bronze_02"),
+    (1050, "02_bronze_01"),
+    (1100, "03_iron_03"),
+    (1150, "03_iron_02"),
+    (1200, "03_iron_01"),
+    (1250, "04_silver_03"),
+    (1300, "04_silver_02"),
+    (1350, "04_silver_01"),
+    (1400, "05_gold_03"),
+    (1500, "05_gold_02"),
+];
+/// Above 1600 ELO the top 50 are challengers, by the worst rank of each tier.
+const CHALLENGER_TIERS: [(i64, &str); 5] = [
+    (1, "06_master_01"),
+    (5, "06_master_02"),
+    (10, "06_master_03"),
+    (25, "06_master_04"),
+    (50, "06_master_05"),
+];
+const GOLD_1: &str = "05_gold_01";
+const PLACEMENT: &str = "00_placement";
+
+/// Path of the bundled rank icon for a leaderboard standing.
+pub fn rank_icon(standing: &Standing) -> String {
+    let tier = match (standing.rating, standing.rank) {
+        (Some(rating), Some(rank)) if rank > 0 && rating >= 1600 => CHALLENGER_TIERS
+            .iter()
+            .find(|(worst, _)| rank <= *worst)
+            .map_or(GOLD_1, |(_, tier)| tier),
+        (Some(rating), Some(rank)) if rank > 0 => RATING_TIERS
+            .iter()
+            .rev()
+            .find(|(min, _)| rating >= *min)
+            .map_or(PLACEMENT, |(_, tier)| tier),
+        _ => PLACEMENT,
+    };
+    format!("icons/ranks/{}_medium.webp", tier)
+}
+
+/// Path of the bundled icon for a faction as the leaderboards name it.
+pub fn faction_icon(faction: &str) -> String {
+    format!("factions/{}.webp", faction)
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct CardPlayer {
+    pub name: String,
+    /// As the leaderboards name it, e.g. `british`.
+    pub faction: String,
+    pub ai: bool,
+    /// The local player.
+    pub local: bool,
+    /// `None` when it could not be looked up, or for AI and custom games.
+    pub standing: Option<Standing>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct MatchupCard {
+    pub mode: String,
+    pub map: String,
+    pub left: Vec<CardPlayer>,
+    pub right: Vec<CardPlayer>,
+    pub winner: Option<TeamPosition>,
+    /// Whether the local player's team won, `None` without a result or local player.
+    pub local_player_won: Option<bool>,
+}
+
+impl MatchupCard {
+    /// The card for `game`, without the result unless `with_result`.
+    pub fn from_game(
+        game: &LogFileData,
+        with_result: bool,
+        standing: impl Fn(&PlayerData) -> Option<Standing>,
+    ) -> Self {
+        let local = session::local_player(game);
+        let players = |players: &[PlayerData]| {
+            players
+                .iter()
+                .map(|player| CardPlayer {
+                    name: player.name.clone(),
+                    faction: session::normalize_faction(&player.faction),
+                    ai: player.ai,
+                    local: local.is_some_and(|local| std::ptr::eq(local, player)),
+                    standing: if player.ai { None } else { standing(player) },
+                })
+                .collect()
+        };
+        let winner = game.winner.filter(|_| with_result);
+        Self {
+            mode: session::mode_of(game),
+            map: game.map.clone(),
+            left: players(&game.left.players),
+            right: players(&game.right.players),
+            winner,
+            local_player_won: winner.and_then(|winner| Some(game.local_team()? == winner)),
+        }
+    }
+}
+
+/// The lobby a card shows: the current one, or the last finished one before any was
+/// seen.
+pub fn card_game(
+    current: Option<LogFileData>,
+    last_match: Option<LastMatch>,
+) -> Option<LogFileData> {
+    current
+        .filter(|game| !game.left.players.is_empty() || !game.right.players.is_empty())
+        .or_else(|| last_match.map(|last_match| last_match.game))
+}
+
+/// Fonts text is drawn with. Characters missing from the first are taken from the
+/// first of the others that has them.
+pub struct Fonts {
+    fonts: Vec<FontVec>,
+}
+
+/// System fonts worth trying, most preferred first. Collections (`.ttc`) use their
+/// first font.
+fn system_font_paths() -> Vec<PathBuf> {
+    let windows = std::env::var("WINDIR").unwrap_or_else(|_| "C:\\Windows".to_string());
+    let windows_fonts = PathBuf::from(windows).join("Fonts");
+    let mut paths: Vec<PathBuf> = [
+        "segoeui.ttf",
+        "arial.ttf",
+        "msyh.ttc",
+        "malgun.ttf",
+        "YuGothM.ttc",
+    ]
+    .iter()
+    .map(|name| windows_fonts.join(name))
+    .collect();
+    paths.extend(
+        [
+            "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf",
+            "/usr/share/fonts/TTF/DejaVuSans.ttf",
+            "/usr/share/fonts/truetype/liberation/LiberationSans-Regular.ttf",
+            "/usr/share/fonts/noto/NotoSans-Regular.ttf",
+            "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
+            "/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc",
+            "/System/Library/Fonts/Supplemental/Arial.ttf",
+            "/System/Library/Fonts/Helvetica.ttc",
+            "/System/Library/Fonts/PingFang.ttc",
+        ]
+        .iter()
+        .map(PathBuf::from),
+    );
+    paths
+}
+
+impl Fonts {
+    /// Fonts from font files' contents, skipping what is not a font.
+    pub fn from_data(data: Vec<Vec<u8>>) -> Result<Self, String> {
+        let fonts: Vec<FontVec> = data
+            .into_iter()
+            .filter_map(|data| FontVec::try_from_vec(data).ok())
+            .collect();
+        if fonts.is_empty() {
+            return Err("No usable font found".to_string());
+        }
+        Ok(Self { fonts })
+    }
+
+    /// The system's fonts, read once.
+    pub fn system() -> Result<&'static Fonts, String> {
+        static SYSTEM: OnceLock<Result<Fonts, String>> = OnceLock::new();
+        SYSTEM
+            .get_or_init(|| {
+                let data = system_font_paths()
+                    .into_iter()
+                    .filter_map(|path| std::fs::read(path).ok())
+                    .collect();
+                Fonts::from_data(data)
+            })
+            .as_ref()
+            .map_err(Clone::clone)
+    }
+
+    fn font_for(&self, c: char) -> usize {
+        self.fonts
+            .iter()
+            .position(|font| font.glyph_id(c).0 != 0)
+            .unwrap_or(0)
+    }
+
+    /// Calls `place` with each character's font and x offset, returns the total width.
+    fn layout(&self, text: &str, size: f32, mut place: impl FnMut(&FontVec, char, f32)) -> f32 {
+        let scale = PxScale::from(size);
+        let mut x = 0.0;
+        let mut previous: Option<(usize, GlyphId)> = None;
+        for c in text.chars() {
+            let index = self.font_for(c);
+            let font = &self.fonts[index];
+            let scaled = font.as_scaled(scale);
+            let id = font.glyph_id(c);
+            // Kerning only applies within a font
+            if let Some((previous_index, previous_id)) = previous {
+                if previous_index == index {
+                    x += scaled.kern(previous_id, id);
+                }
+            }
+            place(font, c, x);
+            x += scaled.h_advance(id);
+            previous = Some((index, id));
+        }
+        x
+    }
+
+    pub fn text_width(&self, text: &str, size: f32) -> f32 {
+        self.layout(text, size, |_, _, _| {})
+    }
+
+    /// `text`, shortened with an ellipsis to fit `max_width`.
+    pub fn fit(&self, text: &str, size: f32, max_width: f32) -> String {
+        if self.text_width(text, size) <= max_width {
+            return text.to_string();
+        }
+        let mut chars: Vec<char> = text.chars().collect();
+        while !chars.is_empty() {
+            chars.pop();
+            let shortened = format!("{}…", chars.iter().collect::<String>().trim_end());
+            if self.text_width(&shortened, size) <= max_width {
+                return shortened;
+            }
+        }
+        String::new()
+    }
+
+    /// Draws `text` with its baseline at `y`, returns its width.
+    fn draw(
+        &self,
+        image: &mut RgbaImage,
+        text: &str,
+        x: f32,
+        y: f32,
+        size: f32,
+        color: Rgba<u8>,
+    ) -> f32 {
+        let scale = PxScale::from(size);
+        self.layout(text, size, |font, c, offset| {
+            let glyph = font
+                .glyph_id(c)
+                .with_scale_and_position(scale, point(x + offset, y));
+            let Some(outlined) = font.outline_glyph(glyph) else {
+                return;
+            };
+            let bounds = outlined.px_bounds();
+            outlined.draw(|gx, gy, coverage| {
+                blend(
+                    image,
+                    bounds.min.x as i64 + gx as i64,
+                    bounds.min.y as i64 + gy as i64,
+                    color,
+                    coverage,
+                );
+            });
+        })
+    }
+}
+
+fn blend(image: &mut RgbaImage, x: i64, y: i64, color: Rgba<u8>, alpha: f32) {
+    if x < 0 || y < 0 || x >= image.width() as i64 || y >= image.height() as i64 {
+        return;
+    }
+    let alpha = alpha.clamp(0.0, 1.0);
+    let pixel = image.get_pixel_mut(x as u32, y as u32);
+    for channel in 0..3 {
+        let under = pixel[channel] as f32;
+        pixel[channel] = (under + (color[channel] as f32 - under) * alpha).round() as u8;
+    }
+}
+
+fn fill(image: &mut RgbaImage, x: u32, y: u32, width: u32, height: u32, color: Rgba<u8>) {
+    for py in y..(y + height).min(image.height()) {
+        for px in x..(x + width).min(image.width()) {
+            image.put_pixel(px, py, color);
+        }
+    }
+}
+
+/// Draws a bundled icon scaled to `ICON_SIZE`, nothing when it is missing or broken.
+fn draw_icon(image: &mut RgbaImage, bytes: Option<Vec<u8>>, x: u32, y: u32) {
+    let Some(icon) = bytes.and_then(|bytes| image::load_from_memory(&bytes).ok()) else {
+        return;
+    };
+    let icon = imageops::resize(&icon.to_rgba8(), ICON_SIZE, ICON_SIZE, FilterType::Triangle);
+    imageops::overlay(image, &icon, x as i64, y as i64);
+}
+
+fn describe_standing(player: &CardPlayer) -> String {
+    if player.ai {
+        return "AI".to_string();
+    }
+    match player.standing {
+        Some(Standing {
+            rating: Some(rating),
+            rank: Some(rank),
+            ..
+        }) => format!("{} ELO · #{}", rating, rank),
+        Some(Standing {
+            rating: Some(rating),
+            ..
+        }) => format!("{} ELO · unranked", rating),
+        Some(_) => "No games yet".to_string(),
+        None => String::new(),
+    }
+}
+
+fn draw_team(
+    image: &mut RgbaImage,
+    fonts: &Fonts,
+    icon: &dyn Fn(&str) -> Option<Vec<u8>>,
+    players: &[CardPlayer],
+    x: u32,
+    row_color: Rgba<u8>,
+) {
+    for (index, player) in players.iter().enumerate() {
+        let y = HEADER_HEIGHT + index as u32 * ROW_HEIGHT;
+        fill(image, x, y + 4, COLUMN_WIDTH, ROW_HEIGHT - 8, row_color);
+        if player.local {
+            fill(image, x, y + 4, 4, ROW_HEIGHT - 8, ACCENT);
+        }
+        let icon_y = y + (ROW_HEIGHT - ICON_SIZE) / 2;
+        draw_icon(image, icon(&faction_icon(&player.faction)), x + 12, icon_y);
+        if let Some(standing) = &player.standing {
+            draw_icon(image, icon(&rank_icon(standing)), x + 62, icon_y);
+        }
+
+        let text_x = x + 116;
+        let max_width = (x + COLUMN_WIDTH - 12 - text_x) as f32;
+        let name = fonts.fit(&player.name, 20.0, max_width);
+        let name_color = if player.local { ACCENT } else { TEXT };
+        fonts.draw(
+            image,
+            &name,
+            text_x as f32,
+            (y + 30) as f32,
+            20.0,
+            name_color,
+        );
+        let details = [faction_name(&player.faction), &describe_standing(player)]
+            .into_iter()
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join(" · ");
+        let details = fonts.fit(&details, 15.0, max_width);
+        fonts.draw(
+            image,
+            &details,
+            text_x as f32,
+            (y + 50) as f32,
+            15.0,
+            DIMMED,
+        );
+    }
+}
+
+/// Draws `card`. `icon` returns the bytes of a bundled icon by its path.
+pub fn render(
+    card: &MatchupCard,
+    fonts: &Fonts,
+    icon: impl Fn(&str) -> Option<Vec<u8>>,
+) -> RgbaImage {
+    let rows = card.left.len().max(card.right.len()).max(1) as u32;
+    let height = HEADER_HEIGHT + rows * ROW_HEIGHT + FOOTER_HEIGHT;
+    let mut image = RgbaImage::from_pixel(WIDTH, height, BACKGROUND);
+
+    let result = match (card.winner, card.local_player_won) {
+        (Some(_), Some(true)) => Some(("Victory".to_string(), WON)),
+        (Some(_), Some(false)) => Some(("Defeat".to_string(), LOST)),
+        (Some(TeamPosition::Left), None) => Some(("Left team won".to_string(), TEXT)),
+        (Some(TeamPosition::Right), None) => Some(("Right team won".to_string(), TEXT)),
+        (None, _) => None,
+    };
+    let result_width = result
+        .as_ref()
+        .map_or(0.0, |(text, _)| fonts.text_width(text, 26.0));
+    if let Some((text, color)) = &result {
+        let x = (WIDTH - PADDING) as f32 - result_width;
+        fonts.draw(&mut image, text, x, 46.0, 26.0, *color);
+    }
+    let title = fonts.fit(
+        &format!("{} · {}", card.mode, card.map),
+        26.0,
+        (WIDTH - 3 * PADDING) as f32 - result_width,
+    );
+    fonts.draw(&mut image, &title, PADDING as f32, 46.0, 26.0, TEXT);
+
+    let row_colors = |position: TeamPosition| match card.winner {
+        Some(winner) if winner == position => ROW_WON,
+        Some(_) => ROW_LOST,
+        None => ROW,
+    };
+    let right_x = PADDING + COLUMN_WIDTH + GAP;
+    draw_team(
+        &mut image,
+        fonts,
+        &icon,
+        &card.left,
+        PADDING,
+        row_colors(TeamPosition::Left),
+    );
+    draw_team(
+        &mut image,
+        fonts,
+        &icon,
+        &card.right,
+        right_x,
+        row_colors(TeamPosition::Right),
+    );
+
+    let vs_width = fonts.text_width("VS", 22.0);
+    let vs_y = HEADER_HEIGHT + rows * ROW_HEIGHT / 2 + 8;
+    let vs_x = (WIDTH as f32 - vs_width) / 2.0;
+    fonts.draw(&mut image, "VS", vs_x, vs_y as f32, 22.0, DIMMED);
+
+    let footer = "coh3stats.com";
+    let footer_x = (WIDTH - PADDING) as f32 - fonts.text_width(footer, 14.0);
+    fonts.draw(
+        &mut image,
+        footer,
+        footer_x,
+        (height - 14) as f32,
+        14.0,
+        DIMMED,
+    );
+    image
+}
+
+pub fn encode_png(image: &RgbaImage) -> Result<Vec<u8>, String> {
+    let mut png = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        .map_err(|e| format!("Could not encode the card: {}", e))?;
+    Ok(png)
+}
+
+/// What a drawn card shows apart from the ratings, which are looked up when it is drawn.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CardKey {
+    timestamp: String,
+    map: String,
+    players: Vec<String>,
+    winner: Option<TeamPosition>,
+    with_result: bool,
+}
+
+impl CardKey {
+    pub fn of(game: &LogFileData, with_result: bool) -> Self {
+        Self {
+            timestamp: game.timestamp.clone(),
+            map: game.map.clone(),
+            players: game
+                .left
+                .players
+                .iter()
+                .chain(&game.right.players)
+                .map(|player| player.relic_id.clone())
+                .collect(),
+            winner: game.winner.filter(|_| with_result),
+            with_result,
+        }
+    }
+
+    fn same_lobby(&self, other: &CardKey) -> bool {
+        self.timestamp == other.timestamp && self.map == other.map && self.players == other.players
+    }
+}
+
+/// PNGs of the lobby's cards, so OBS sources refreshing `/card.png` don't look up every
+/// player and draw the card again each time.
+#[derive(Default)]
+pub struct CardCache {
+    /// One cell per card of the lobby; concurrent requests wait on the same cell.
+    cards: Mutex<Vec<(CardKey, Arc<OnceCell<Vec<u8>>>)>>,
+}
+
+impl CardCache {
+    /// Helper to safely lock the mutex, recovering from poison if needed
+    fn lock_cards(&self) -> std::sync::MutexGuard<'_, Vec<(CardKey, Arc<OnceCell<Vec<u8>>>)>> {
+        self.cards.lock().unwrap_or_else(|poisoned| {
+            warn!("CardCache mutex was poisoned, recovering");
+            poisoned.into_inner()
+        })
+    }
+
+    /// The cell holding the card for `key`. Cards of other lobbies are dropped.
+    pub fn cell(&self, key: &CardKey) -> Arc<OnceCell<Vec<u8>>> {
+        let mut cards = self.lock_cards();
+        cards.retain(|(cached, _)| cached.same_lobby(key));
+        if let Some((_, cell)) = cards.iter().find(|(cached, _)| cached == key) {
+            return cell.clone();
+        }
+        let cell = Arc::new(OnceCell::new());
+        cards.push((key.clone(), cell.clone()));
+        cell
+    }
+}
+
+/// The game the card is drawn for: the current lobby, or the last match before one is seen.
+fn current_card_game<R: Runtime>(handle: &AppHandle<R>) -> Option<LogFileData> {
+    card_game(
+        current_game::current(handle),
+        current_game::last_match(handle),
+    )
+}
+
+/// The card for `game`, with ratings from the profile lookups.
+async fn card_of<R: Runtime>(
+    handle: &AppHandle<R>,
+    game: &LogFileData,
+    with_result: bool,
+) -> MatchupCard {
+    let mode = session::mode_of(game);
+    let profiles = handle.state::<PlayerProfilesState>();
+    let mut standings = HashMap::new();
+    for player in game.left.players.iter().chain(&game.right.players) {
+        let faction = session::normalize_faction(&player.faction);
+        let leaderboard_id = session::leaderboard_id(&mode, &faction);
+        let Some(leaderboard_id) = leaderboard_id.filter(|_| !player.ai) else {
+            continue;
+        };
+        match profiles.service.get(&player.relic_id).await {
+            Ok(profile) => {
+                if let Some(standing) = session::standing(&profile, leaderboard_id) {
+                    standings.insert(player.relic_id.clone(), standing);
+                }
+            }
+            Err(e) => warn!("Matchup card could not look up {}: {}", player.name, e),
+        }
+    }
+    MatchupCard::from_game(game, with_result, |player| {
+        standings.get(&player.relic_id).cloned()
+    })
+}
+
+/// The card for the current or last lobby, with ratings from the profile lookups.
+pub async fn load_card<R: Runtime>(
+    handle: &AppHandle<R>,
+    with_result: bool,
+) -> Option<MatchupCard> {
+    let game = current_card_game(handle)?;
+    Some(card_of(handle, &game, with_result).await)
+}
+
+fn draw<R: Runtime>(handle: &AppHandle<R>, card: &MatchupCard) -> Result<RgbaImage, String> {
+    let fonts = Fonts::system()?;
+    Ok(render(card, fonts, |path| {
+        Some(handle.asset_resolver().get(format!("/{}", path))?.bytes)
+    }))
+}
+
+/// The card for the current or last lobby, drawn.
+pub async fn render_current<R: Runtime>(
+    handle: &AppHandle<R>,
+    with_result: bool,
+) -> Result<RgbaImage, String> {
+    let card = load_card(handle, with_result)
+        .await
+        .ok_or("No lobby to draw yet")?;
+    draw_blocking(handle, card, Ok).await
+}
+
+/// Draw `card` and `finish` the image on a blocking thread - drawing is CPU work, keep
+/// it off the async workers.
+async fn draw_blocking<R: Runtime, T: Send + 'static>(
+    handle: &AppHandle<R>,
+    card: MatchupCard,
+    finish: impl FnOnce(RgbaImage) -> Result<T, String> + Send + 'static,
+) -> Result<T, String> {
+    let handle = handle.clone();
+    tauri::async_runtime::spawn_blocking(move || draw(&handle, &card).and_then(finish))
+        .await
+        .map_err(|e| format!("Could not draw the card: {}", e))?
+}
+
+/// The card for the current or last lobby as a PNG, drawn once per lobby and result and
+/// served from the `CardCache` after that. Failed draws are tried again next time.
+pub async fn current_png<R: Runtime>(
+    handle: &AppHandle<R>,
+    with_result: bool,
+) -> Result<Vec<u8>, String> {
+    let game = current_card_game(handle).ok_or("No lobby to draw yet")?;
+    let cell = handle
+        .state::<CardCache>()
+        .cell(&CardKey::of(&game, with_result));
+    let png = cell
+        .get_or_try_init(|| async {
+            let card = card_of(handle, &game, with_result).await;
+            draw_blocking(handle, card, |image| encode_png(&image)).await
+        })
+        .await?;
+    Ok(png.clone())
+}
+
+/// Where the user wants the card saved, asked with the save dialog. `None` when they
+/// cancelled it.
+async fn pick_save_path<R: Runtime>(handle: &AppHandle<R>) -> Result<Option<PathBuf>, String> {
+    let (sender, picked) = tokio::sync::oneshot::channel();
+    handle
+        .dialog()
+        .file()
+        .set_title("Save matchup card")
+        .set_file_name("matchup.png")
+        .add_filter("PNG image", &["png"])
+        .save_file(move |path| {
+            let _ = sender.send(path);
+        });
+    let Some(path) = picked.await.ok().flatten() else {
+        return Ok(None);
+    };
+    let path = path
+        .into_path()
+        .map_err(|e| format!("Could not save the card there: {}", e))?;
+    let is_png = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("png"));
+    if !is_png {
+        return Err("The card can only be saved as a .png file".to_string());
+    }
+    Ok(Some(path))
+}
+
+/// Tauri command saving the matchup card as a PNG where the user picks in the save
+/// dialog. Returns the path, or `None` when the dialog was cancelled.
+#[tauri::command]
+pub async fn save_matchup_card<R: Runtime>(
+    handle: AppHandle<R>,
+    with_result: bool,
+) -> Result<Option<String>, String> {
+    let Some(path) = pick_save_path(&handle).await? else {
+        return Ok(None);
+    };
+    let png = current_png(&handle, with_result).await?;
+    std::fs::write(&path, png).map_err(|e| format!("Could not save the card: {}", e))?;
+    let path = path.display().to_string();
+    info!("Matchup card saved to {}", path);
+    Ok(Some(path))
+}
+
+/// Tauri command copying the matchup card to the clipboard
+#[tauri::command]
+pub async fn copy_matchup_card<R: Runtime>(
+    handle: AppHandle<R>,
+    with_result: bool,
+) -> Result<(), String> {
+    let image = render_current(&handle, with_result).await?;
+    let (width, height) = image.dimensions();
+    handle
+        .clipboard()
+        .write_image(&tauri::image::Image::new(image.as_raw(), width, height))
+        .map_err(|e| format!("Could not copy the card: {}", e))
+}
@@ -0,0 +1,300 @@
+//! The one HTTP client every backend fetcher goes through.
+//!
+//! `Net` owns a `reqwest::Client` set up once with the app's user agent and proxy (the
+//! system one, or `httpProxy` from the store), retries idempotent GETs with exponential
+//! backoff and jitter, and keeps track of whether the network is reachable at all. While
+//! it is not, requests fail straight away with `NetError::Offline` instead of each
+//! waiting out its own timeout; one request per `offline_probe_interval` is still let
+//! through to notice when the connection is back. Changes are emitted to the frontend
+//! as `NETWORK_STATUS_EVENT`.
+
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+
+/// Event carrying the new `NetworkStatus` whenever it changes.
+pub const NETWORK_STATUS_EVENT: &str = "network-status";
+
+pub const DEFAULT_TIMEOUT_SECS: u64 = 30;
+const CONNECT_TIMEOUT_SECS: u64 = 10;
+const OFFLINE_PROBE_INTERVAL_SECS: u64 = 30;
+
+const USER_AGENT: &str = concat!("coh3-stats-desktop-app/", env!("CARGO_PKG_VERSION"));
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NetworkStatus {
+    Online,
+    Offline,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum NetError {
+    #[error("No network connection")]
+    Offline,
+    #[error("Invalid proxy {0}: {1}")]
+    Proxy(String, reqwest::Error),
+    #[error(transparent)]
+    Request(#[from] reqwest::Error),
+}
+
+/// How often and how far apart failed GETs are retried.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Attempts including the first one.
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(8),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Wait before retry number `retry` (0 for the first retry): the exponential delay,
+    /// of which the upper half is randomised by `jitter` so clients that failed together
+    /// do not all come back at the same moment.
+    pub fn delay(&self, retry: u32, jitter: u64) -> Duration {
+        let exponential = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(retry))
+            .min(self.max_delay);
+        let half = exponential / 2;
+        let spread = half.as_millis() as u64;
+        let random = if spread == 0 {
+            0
+        } else {
+            jitter % (spread + 1)
+        };
+        half + Duration::from_millis(random)
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct NetConfig {
+    /// Explicit proxy URL, e.g. `http://proxy:8080`. `None` uses the system proxy.
+    pub proxy: Option<String>,
+    pub retry: RetryPolicy,
+    /// `None` for `OFFLINE_PROBE_INTERVAL_SECS`.
+    pub offline_probe_interval: Option<Duration>,
+}
+
+type StatusListener = Box<dyn Fn(NetworkStatus) + Send + Sync>;
+
+struct StatusTracker {
+    online: AtomicBool,
+    /// When the last request was let through while offline.
+    last_probe: Mutex<Option<Instant>>,
+    listener: Mutex<Option<StatusListener>>,
+}
+
+/// Shared, cheaply cloneable HTTP client.
+#[derive(Clone)]
+pub struct Net {
+    client: reqwest::Client,
+    retry: RetryPolicy,
+    offline_probe_interval: Duration,
+    status: Arc<StatusTracker>,
+}
+
+fn random_u64() -> u64 {
+    RandomState::new().build_hasher().finish()
+}
+
+/// Errors worth another attempt: the connection failed or the answer took too long.
+/// Bad URLs, TLS failures and the like fail the same way every time.
+fn is_transient(e: &reqwest::Error) -> bool {
+    e.is_connect() || e.is_timeout()
+}
+
+fn is_retryable_status(status: reqwest::StatusCode) -> bool {
+    status.is_server_error() || status == reqwest::StatusCode::REQUEST_TIMEOUT
+}
+
+impl Net {
+    pub fn new(config: NetConfig) -> Result<Self, NetError> {
+        let mut builder = reqwest::Client::builder()
+            .user_agent(USER_AGENT)
+            .timeout(Duration::from_secs(DEFAULT_TIMEOUT_SECS))
+            .connect_timeout(Duration::from_secs(CONNECT_TIMEOUT_SECS));
+
+        if let Some(proxy_url) = config.proxy.as_deref().filter(|p| !p.trim().is_empty()) {
+            let proxy = reqwest::Proxy::all(proxy_url.trim())
+                .map_err(|e| NetError::Proxy(proxy_url.to_string(), e))?;
+            builder = builder.proxy(proxy);
+        }
+
+        Ok(Self {
+            client: builder.build()?,
+            retry: config.retry,
+            offline_probe_interval: config
+                .offline_probe_interval
+                .unwrap_or(Duration::from_secs(OFFLINE_PROBE_INTERVAL_SECS)),
+            status: Arc::new(StatusTracker {
+                online: AtomicBool::new(true),
+                last_probe: Mutex::new(None),
+                listener: Mutex::new(None),
+            }),
+        })
+    }
+
+    pub fn status(&self) -> NetworkStatus {
+        if self.status.online.load(Ordering::SeqCst) {
+            NetworkStatus::Online
+        } else {
+            NetworkStatus::Offline
+        }
+    }
+
+    /// Calls `listener` with the new status every time it changes.
+    pub fn on_status_change(&self, listener: impl Fn(NetworkStatus) + Send + Sync + 'static) {
+        *self
+            .status
+            .listener
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(Box::new(listener));
+    }
+
+    fn set_status(&self, status: NetworkStatus) {
+        let online = status == NetworkStatus::Online;
+        if self.status.online.swap(online, Ordering::SeqCst) == online {
+            return;
+        }
+        match status {
+            NetworkStatus::Online => info!("Network is reachable again"),
+            NetworkStatus::Offline => {
+                warn!("Network is unreachable, holding requests back");
+                // The failed request counts as the first probe.
+                *self
+                    .status
+                    .last_probe
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(Instant::now());
+            }
+        }
+        let listener = self
+            .status
+            .listener
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(listener) = listener.as_ref() {
+            listener(status);
+        }
+    }
+
+    /// While offline, lets one request per probe interval through.
+    fn may_send(&self) -> bool {
+        if self.status() == NetworkStatus::Online {
+            return true;
+        }
+        let mut last_probe = self
+            .status
+            .last_probe
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if last_probe.is_some_and(|at| at.elapsed() < self.offline_probe_interval) {
+            return false;
+        }
+        *last_probe = Some(Instant::now());
+        true
+    }
+
+    /// GETs `url`, retrying connection failures, timeouts and 5xx responses. Any other
+    /// response is returned as is - callers decide what a 404 or 429 means to them.
+    pub async fn get(&self, url: &str, timeout: Duration) -> Result<reqwest::Response, NetError> {
+        if !self.may_send() {
+            return Err(NetError::Offline);
+        }
+
+        let mut retry = 0;
+        loop {
+            let last_attempt = retry + 1 >= self.retry.max_attempts;
+            match self.client.get(url).timeout(timeout).send().await {
+                Ok(response) => {
+                    self.set_status(NetworkStatus::Online);
+                    if last_attempt || !is_retryable_status(response.status()) {
+                        return Ok(response);
+                    }
+                    warn!("GET {} returned {}, retrying", url, response.status());
+                }
+                Err(e) if !last_attempt && is_transient(&e) => {
+                    warn!("GET {} failed, retrying: {}", url, e);
+                }
+                Err(e) => {
+                    if e.is_connect() {
+                        self.set_status(NetworkStatus::Offline);
+                    }
+                    return Err(e.into());
+                }
+            }
+            tokio::time::sleep(self.retry.delay(retry, random_u64())).await;
+            retry += 1;
+        }
+    }
+
+    /// GETs `url` and parses a successful response as JSON.
+    pub async fn get_json(&self, url: &str, timeout: Duration) -> Result<Value, NetError> {
+        let response = self.get(url, timeout).await?.error_for_status()?;
+        Ok(response.json::<Value>().await?)
+    }
+}
+
+/// State for the shared client
+pub struct NetState {
+    pub net: Net,
+}
+
+/// Builds the shared client from the stored settings and registers it as managed state.
+/// Called from setup, before anything fetches.
+pub fn init<R: Runtime>(handle: &AppHandle<R>) -> Net {
+    let config = NetConfig {
+        proxy: crate::dp_utils::load_from_store::<R, String>(handle.clone(), "httpProxy"),
+        ..NetConfig::default()
+    };
+    if let Some(proxy) = &config.proxy {
+        info!("Using HTTP proxy {}", proxy);
+    }
+
+    let net = Net::new(config).unwrap_or_else(|e| {
+        error!("Failed to set up the HTTP client: {}", e);
+        sentry::capture_message(
+            &format!("HTTP client setup error: {}", e),
+            sentry::Level::Error,
+        );
+        // Without the broken proxy setting - the system proxy may still work.
+        Net::new(NetConfig::default()).expect("Default HTTP client must build")
+    });
+
+    let emitter = handle.clone();
+    net.on_status_change(move |status| {
+        if let Err(e) = emitter.emit(NETWORK_STATUS_EVENT, status) {
+            error!("Failed to emit network status: {}", e);
+        }
+    });
+
+    handle.manage(NetState { net: net.clone() });
+    net
+}
+
+/// The shared client
+pub fn net<R: Runtime>(handle: &AppHandle<R>) -> Net {
+    handle.state::<NetState>().net.clone()
+}
+
+/// Tauri command returning whether the backend can currently reach the network
+#[tauri::command]
+pub fn network_status<R: Runtime>(handle: AppHandle<R>) -> NetworkStatus {
+    net(&handle).status()
+}
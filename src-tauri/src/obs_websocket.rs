@@ -0,0 +1,561 @@
+//! Optional OBS integration switching scenes as games come and go.
+//!
+//! Talks to the obs-websocket server built into OBS 28 and newer (protocol v5). The
+//! streamer maps game-state transitions - back in the menu, loading, in game, game over,
+//! game closed - to scene switches or source visibility toggles, and every transition
+//! seen in the log runs the actions mapped to it.
+//!
+//! The connection lives on its own thread and reconnects with a growing delay, OBS is
+//! often started after the app. A rejected password stops it, trying again would not
+//! help. Transitions while OBS is away are dropped rather than replayed late.
+
+use crate::config::{OBS_WEBSOCKET_HOST, OBS_WEBSOCKET_PORT};
+use crate::dp_utils::load_from_store;
+use crate::parse_log_file::{GameState, LogFileData};
+use crate::session::match_key;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::io;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, Runtime};
+use tungstenite::protocol::frame::coding::CloseCode;
+use tungstenite::{Message, WebSocket};
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+/// How long OBS gets to answer a request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+/// Reads wait at most this long, so triggers and `stop` are picked up quickly.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+const MIN_RECONNECT_DELAY: Duration = Duration::from_secs(2);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(60);
+
+/// The obs-websocket protocol version spoken here.
+const RPC_VERSION: u64 = 1;
+/// Close code obs-websocket uses for a wrong password.
+const AUTHENTICATION_FAILED: u16 = 4009;
+
+const ENABLED_STORE_KEY: &str = "obsEnabled";
+const HOST_STORE_KEY: &str = "obsHost";
+const PORT_STORE_KEY: &str = "obsPort";
+const PASSWORD_STORE_KEY: &str = "obsPassword";
+const RULES_STORE_KEY: &str = "obsRules";
+
+/// A game-state transition actions can be mapped to.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GameTrigger {
+    Menu,
+    Loading,
+    InGame,
+    /// The result of the match came in.
+    GameOver,
+    Closed,
+}
+
+impl From<&GameState> for GameTrigger {
+    fn from(state: &GameState) -> Self {
+        match state {
+            GameState::Closed => GameTrigger::Closed,
+            GameState::Menu => GameTrigger::Menu,
+            GameState::Loading => GameTrigger::Loading,
+            GameState::InGame => GameTrigger::InGame,
+        }
+    }
+}
+
+/// Transitions between two parses of the log. Nothing at startup, what the log shows
+/// then happened before the app was watching.
+pub fn triggers(prev: Option<&LogFileData>, next: &LogFileData) -> Vec<GameTrigger> {
+    let Some(prev) = prev else {
+        return Vec::new();
+    };
+    let mut triggers = Vec::new();
+    if prev.game_state != next.game_state {
+        triggers.push(GameTrigger::from(&next.game_state));
+    }
+    // After the state change of the same parse, so its scene is the one left showing.
+    if prev.winner.is_none() && next.winner.is_some() && match_key(prev) == match_key(next) {
+        triggers.push(GameTrigger::GameOver);
+    }
+    triggers
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ObsAction {
+    SwitchScene {
+        scene: String,
+    },
+    SetSourceVisible {
+        scene: String,
+        source: String,
+        visible: bool,
+    },
+}
+
+/// One configured action, stored flat: `{"trigger": "Loading", "type": "switch_scene",
+/// "scene": "Loading"}`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ObsRule {
+    pub trigger: GameTrigger,
+    #[serde(flatten)]
+    pub action: ObsAction,
+}
+
+#[derive(Clone, Debug)]
+pub struct ObsConfig {
+    pub host: String,
+    pub port: u16,
+    /// Empty when OBS has authentication turned off.
+    pub password: String,
+    pub rules: Vec<ObsRule>,
+}
+
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+pub struct ObsStatus {
+    pub running: bool,
+    /// Connected and identified.
+    pub connected: bool,
+    pub obs_websocket_version: Option<String>,
+    /// Why the last connection ended, or the last action failed.
+    pub error: Option<String>,
+}
+
+/// The `authentication` string answering OBS's challenge.
+pub fn authentication(password: &str, salt: &str, challenge: &str) -> String {
+    let secret = BASE64.encode(Sha256::digest(format!("{}{}", password, salt)));
+    BASE64.encode(Sha256::digest(format!("{}{}", secret, challenge)))
+}
+
+enum Ended {
+    Stopped,
+    AuthFailed(String),
+    Lost(String),
+}
+
+enum RequestError {
+    Ended(Ended),
+    /// OBS answered, but could not do it, e.g. the scene does not exist.
+    Failed(String),
+}
+
+impl From<Ended> for RequestError {
+    fn from(ended: Ended) -> Self {
+        RequestError::Ended(ended)
+    }
+}
+
+struct Shared {
+    stop: AtomicBool,
+    status: Mutex<ObsStatus>,
+}
+
+impl Shared {
+    fn stopped(&self) -> bool {
+        self.stop.load(Ordering::SeqCst)
+    }
+
+    fn lock_status(&self) -> std::sync::MutexGuard<'_, ObsStatus> {
+        self.status.lock().unwrap_or_else(|poisoned| {
+            warn!("OBS status mutex was poisoned, recovering");
+            poisoned.into_inner()
+        })
+    }
+}
+
+fn connect(host: &str, port: u16) -> Result<WebSocket<TcpStream>, String> {
+    let mut last_error = io::Error::new(io::ErrorKind::NotFound, "Host has no address");
+    let mut stream = None;
+    let addrs = (host, port).to_socket_addrs().map_err(|e| e.to_string())?;
+    for addr in addrs {
+        match TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT) {
+            Ok(connected) => {
+                stream = Some(connected);
+                break;
+            }
+            Err(e) => last_error = e,
+        }
+    }
+    let stream = stream.ok_or_else(|| last_error.to_string())?;
+    let _ = stream.set_nodelay(true);
+    stream
+        .set_read_timeout(Some(CONNECT_TIMEOUT))
+        .map_err(|e| e.to_string())?;
+
+    let url = format!("ws://{}:{}", host, port);
+    let (socket, _) = tungstenite::client(url.as_str(), stream).map_err(|e| e.to_string())?;
+    socket
+        .get_ref()
+        .set_read_timeout(Some(POLL_INTERVAL))
+        .map_err(|e| e.to_string())?;
+    Ok(socket)
+}
+
+/// One identified connection to OBS.
+struct Connection<'a> {
+    socket: WebSocket<TcpStream>,
+    shared: &'a Shared,
+    next_request_id: u64,
+}
+
+impl Connection<'_> {
+    /// Says goodbye to OBS, so it does not log the connection as lost.
+    fn close(&mut self) {
+        let _ = self.socket.close(None);
+        let _ = self.socket.flush();
+    }
+
+    /// The next message from OBS, or `None` if nothing came within `POLL_INTERVAL`.
+    fn poll(&mut self) -> Result<Option<Value>, Ended> {
+        if self.shared.stopped() {
+            return Err(Ended::Stopped);
+        }
+        match self.socket.read() {
+            Ok(Message::Text(text)) => match serde_json::from_str(&text) {
+                Ok(value) => Ok(Some(value)),
+                Err(e) => {
+                    warn!("Ignoring a message from OBS that is not JSON: {}", e);
+                    Ok(None)
+                }
+            },
+            Ok(Message::Close(frame)) => {
+                let (code, reason) = frame
+                    .map(|frame| (u16::from(frame.code), frame.reason.into_owned()))
+                    .unwrap_or((u16::from(CloseCode::Status), String::new()));
+                if code == AUTHENTICATION_FAILED {
+                    return Err(Ended::AuthFailed(
+                        "OBS rejected the password, check the WebSocket server settings in OBS"
+                            .to_string(),
+                    ));
+                }
+                Err(Ended::Lost(format!(
+                    "OBS closed the connection ({} {})",
+                    code, reason
+                )))
+            }
+            // Pings are answered by tungstenite
+            Ok(_) => Ok(None),
+            Err(tungstenite::Error::Io(e))
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) =>
+            {
+                Ok(None)
+            }
+            Err(e) => Err(Ended::Lost(e.to_string())),
+        }
+    }
+
+    /// Waits for a message with opcode `op` that `matches`, skipping the others.
+    fn wait_for(&mut self, op: u64, matches: impl Fn(&Value) -> bool) -> Result<Value, Ended> {
+        let deadline = Instant::now() + REQUEST_TIMEOUT;
+        while Instant::now() < deadline {
+            if let Some(message) = self.poll()? {
+                if message["op"].as_u64() == Some(op) && matches(&message["d"]) {
+                    return Ok(message["d"].clone());
+                }
+            }
+        }
+        Err(Ended::Lost("OBS did not answer in time".to_string()))
+    }
+
+    fn send(&mut self, message: Value) -> Result<(), Ended> {
+        self.socket
+            .send(Message::Text(message.to_string()))
+            .map_err(|e| Ended::Lost(e.to_string()))
+    }
+
+    /// Hello, Identify, Identified. Returns the obs-websocket version.
+    fn identify(&mut self, password: &str) -> Result<String, Ended> {
+        let hello = self.wait_for(0, |_| true)?;
+        let mut identify = json!({ "rpcVersion": RPC_VERSION, "eventSubscriptions": 0 });
+        if let Some(auth) = hello.get("authentication") {
+            if password.is_empty() {
+                return Err(Ended::AuthFailed(
+                    "OBS asks for a password, set it in the settings".to_string(),
+                ));
+            }
+            identify["authentication"] = json!(authentication(
+                password,
+                auth["salt"].as_str().unwrap_or_default(),
+                auth["challenge"].as_str().unwrap_or_default(),
+            ));
+        }
+        self.send(json!({ "op": 1, "d": identify }))?;
+        self.wait_for(2, |_| true)?;
+        Ok(hello["obsWebSocketVersion"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string())
+    }
+
+    /// Sends a request and returns its `responseData`.
+    fn request(&mut self, request_type: &str, data: Value) -> Result<Value, RequestError> {
+        self.next_request_id += 1;
+        let id = self.next_request_id.to_string();
+        self.send(json!({
+            "op": 6,
+            "d": { "requestType": request_type, "requestId": id, "requestData": data },
+        }))?;
+        let response = self.wait_for(7, |d| d["requestId"].as_str() == Some(id.as_str()))?;
+
+        let status = &response["requestStatus"];
+        if status["result"].as_bool() != Some(true) {
+            return Err(RequestError::Failed(format!(
+                "{} failed: {}",
+                request_type,
+                status["comment"]
+                    .as_str()
+                    .map(str::to_string)
+                    .unwrap_or_else(|| format!("code {}", status["code"]))
+            )));
+        }
+        Ok(response["responseData"].clone())
+    }
+
+    fn apply(&mut self, action: &ObsAction) -> Result<(), RequestError> {
+        match action {
+            ObsAction::SwitchScene { scene } => {
+                self.request("SetCurrentProgramScene", json!({ "sceneName": scene }))?;
+            }
+            ObsAction::SetSourceVisible {
+                scene,
+                source,
+                visible,
+            } => {
+                let found = self.request(
+                    "GetSceneItemId",
+                    json!({ "sceneName": scene, "sourceName": source }),
+                )?;
+                self.request(
+                    "SetSceneItemEnabled",
+                    json!({
+                        "sceneName": scene,
+                        "sceneItemId": found["sceneItemId"],
+                        "sceneItemEnabled": visible,
+                    }),
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// One connection, from the handshake until it ends.
+fn run_connection(config: &ObsConfig, triggers: &Receiver<GameTrigger>, shared: &Shared) -> Ended {
+    let socket = match connect(&config.host, config.port) {
+        Ok(socket) => socket,
+        Err(e) => return Ended::Lost(format!("Could not connect to OBS: {}", e)),
+    };
+    let mut connection = Connection {
+        socket,
+        shared,
+        next_request_id: 0,
+    };
+    let version = match connection.identify(&config.password) {
+        Ok(version) => version,
+        Err(ended) => return ended,
+    };
+    info!("Connected to OBS, obs-websocket {}", version);
+    {
+        let mut status = shared.lock_status();
+        status.connected = true;
+        status.obs_websocket_version = Some(version);
+        status.error = None;
+    }
+
+    loop {
+        match triggers.try_recv() {
+            Ok(trigger) => {
+                let actions = config.rules.iter().filter(|rule| rule.trigger == trigger);
+                for rule in actions {
+                    match connection.apply(&rule.action) {
+                        Ok(()) => {}
+                        Err(RequestError::Failed(error)) => {
+                            warn!("OBS action for {:?}: {}", trigger, error);
+                            shared.lock_status().error = Some(error);
+                        }
+                        Err(RequestError::Ended(ended)) => return ended,
+                    }
+                }
+            }
+            Err(TryRecvError::Empty) => {}
+            // The client was dropped.
+            Err(TryRecvError::Disconnected) => {
+                connection.close();
+                return Ended::Stopped;
+            }
+        }
+        // Events are not subscribed to, this keeps the connection answered.
+        if let Err(ended) = connection.poll() {
+            if matches!(ended, Ended::Stopped) {
+                connection.close();
+            }
+            return ended;
+        }
+    }
+}
+
+fn run(config: ObsConfig, triggers: Receiver<GameTrigger>, shared: Arc<Shared>) {
+    let mut delay = MIN_RECONNECT_DELAY;
+    while !shared.stopped() {
+        let ended = run_connection(&config, &triggers, &shared);
+        let was_connected = std::mem::take(&mut shared.lock_status().connected);
+        match ended {
+            Ended::Stopped => break,
+            Ended::AuthFailed(error) => {
+                warn!("OBS connection stopped: {}", error);
+                shared.lock_status().error = Some(error);
+                break;
+            }
+            Ended::Lost(error) => {
+                // OBS not running yet is the usual case, only log when a connection ended.
+                if was_connected {
+                    warn!("OBS connection lost: {}", error);
+                }
+                shared.lock_status().error = Some(error);
+            }
+        }
+
+        if was_connected {
+            delay = MIN_RECONNECT_DELAY;
+        }
+        let retry_at = Instant::now() + delay;
+        while !shared.stopped() && Instant::now() < retry_at {
+            // Nothing to switch while away
+            while triggers.try_recv().is_ok() {}
+            thread::sleep(POLL_INTERVAL);
+        }
+        delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+    }
+    shared.lock_status().running = false;
+}
+
+/// A running OBS connection. Dropping it disconnects.
+pub struct ObsClient {
+    shared: Arc<Shared>,
+    triggers: Sender<GameTrigger>,
+}
+
+impl ObsClient {
+    pub fn start(config: ObsConfig) -> Self {
+        let shared = Arc::new(Shared {
+            stop: AtomicBool::new(false),
+            status: Mutex::new(ObsStatus {
+                running: true,
+                ..ObsStatus::default()
+            }),
+        });
+        let (triggers, receiver) = mpsc::channel();
+        let thread_shared = shared.clone();
+        thread::spawn(move || run(config, receiver, thread_shared));
+        Self { shared, triggers }
+    }
+
+    /// Runs the actions mapped to `trigger`, if OBS is connected.
+    pub fn trigger(&self, trigger: GameTrigger) {
+        let _ = self.triggers.send(trigger);
+    }
+
+    pub fn status(&self) -> ObsStatus {
+        self.shared.lock_status().clone()
+    }
+}
+
+impl Drop for ObsClient {
+    fn drop(&mut self) {
+        // Tells the thread to stop without waiting for it, it may be in the middle of a
+        // connect or handshake for seconds. It does not act on triggers anymore.
+        self.shared.stop.store(true, Ordering::SeqCst);
+    }
+}
+
+/// State for the OBS connection
+#[derive(Default)]
+pub struct ObsState {
+    client: Mutex<Option<ObsClient>>,
+}
+
+fn lock_client(state: &ObsState) -> std::sync::MutexGuard<'_, Option<ObsClient>> {
+    state.client.lock().unwrap_or_else(|poisoned| {
+        warn!("ObsState mutex was poisoned, recovering");
+        poisoned.into_inner()
+    })
+}
+
+/// Whether OBS should be connected to, per the settings.
+pub fn is_enabled<R: Runtime>(handle: &AppHandle<R>) -> bool {
+    load_from_store(handle.clone(), ENABLED_STORE_KEY).unwrap_or(false)
+}
+
+/// The connection settings from the store.
+pub fn configured<R: Runtime>(handle: &AppHandle<R>) -> ObsConfig {
+    ObsConfig {
+        host: load_from_store::<R, String>(handle.clone(), HOST_STORE_KEY)
+            .map(|host| host.trim().to_string())
+            .filter(|host| !host.is_empty())
+            .unwrap_or_else(|| OBS_WEBSOCKET_HOST.to_string()),
+        port: load_from_store(handle.clone(), PORT_STORE_KEY).unwrap_or(OBS_WEBSOCKET_PORT),
+        password: load_from_store(handle.clone(), PASSWORD_STORE_KEY).unwrap_or_default(),
+        rules: load_from_store(handle.clone(), RULES_STORE_KEY).unwrap_or_default(),
+    }
+}
+
+/// Connects with the stored settings, replacing a running connection.
+pub fn start<R: Runtime>(handle: &AppHandle<R>) -> ObsStatus {
+    let config = configured(handle);
+    let state = handle.state::<ObsState>();
+    let mut client = lock_client(&state);
+    client.take();
+    info!(
+        "Connecting to OBS at {}:{} with {} actions",
+        config.host,
+        config.port,
+        config.rules.len()
+    );
+    let started = ObsClient::start(config);
+    let status = started.status();
+    *client = Some(started);
+    status
+}
+
+/// Runs the actions for the transitions from one parse of the log to the next.
+pub fn observe<R: Runtime>(handle: &AppHandle<R>, prev: Option<&LogFileData>, next: &LogFileData) {
+    let state = handle.state::<ObsState>();
+    let client = lock_client(&state);
+    if let Some(client) = client.as_ref() {
+        for trigger in triggers(prev, next) {
+            client.trigger(trigger);
+        }
+    }
+}
+
+/// Tauri command (re)connecting to OBS with the stored settings
+#[tauri::command]
+pub fn start_obs_connection<R: Runtime>(handle: AppHandle<R>) -> ObsStatus {
+    start(&handle)
+}
+
+/// Tauri command disconnecting from OBS
+#[tauri::command]
+pub fn stop_obs_connection<R: Runtime>(handle: AppHandle<R>) -> ObsStatus {
+    let state = handle.state::<ObsState>();
+    lock_client(&state).take();
+    ObsStatus::default()
+}
+
+/// Tauri command returning whether the OBS connection runs and is connected
+#[tauri::command]
+pub fn obs_connection_status<R: Runtime>(handle: AppHandle<R>) -> ObsStatus {
+    let state = handle.state::<ObsState>();
+    let client = lock_client(&state);
+    client.as_ref().map(ObsClient::status).unwrap_or_default()
+}
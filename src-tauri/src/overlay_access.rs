@@ -0,0 +1,170 @@
+//! Who may use the streamer overlay server.
+//!
+//! By default the server only listens on 127.0.0.1. In LAN mode it listens on a chosen
+//! interface so OBS on another PC can reach it, and requests from other machines have
+//! to present the access token: as `?token=` in the URL, in an `Authorization: Bearer`
+//! header, or in the cookie set when a page is opened with the token in its URL (so the
+//! page's own requests for assets and events get through as well).
+//!
+//! In both modes the `Host` header has to be `localhost` or an IP address. Otherwise a
+//! web page open in a browser on this machine could point a domain of its own at
+//! 127.0.0.1 (DNS rebinding) and use the server as if it were that page's origin.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, UdpSocket};
+
+use crate::overlay_http::Request;
+
+pub const TOKEN_PARAM: &str = "token";
+pub const TOKEN_COOKIE: &str = "overlay_token";
+
+/// Random bytes in a token, shown as hex.
+const TOKEN_BYTES: usize = 16;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AccessPolicy {
+    /// Required from other machines. `None` outside LAN mode.
+    pub token: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Access {
+    Allowed,
+    /// Allowed by the token in the URL. Pages should get it as a cookie.
+    AllowedByUrlToken,
+    Denied(u16, &'static str),
+}
+
+/// A new random access token.
+pub fn generate_token() -> Result<String, String> {
+    let mut bytes = [0u8; TOKEN_BYTES];
+    getrandom::fill(&mut bytes).map_err(|e| format!("No random numbers available: {}", e))?;
+    Ok(bytes.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// Whether `host` (a `Host` header value) names this machine by `localhost` or an IP
+/// address rather than by a domain name.
+pub(crate) fn is_allowed_host(host: &str) -> bool {
+    let is_port = |port: &str| !port.is_empty() && port.chars().all(|c| c.is_ascii_digit());
+
+    if let Some(rest) = host.strip_prefix('[') {
+        return match rest.split_once(']') {
+            Some((ip, "")) => ip.parse::<Ipv6Addr>().is_ok(),
+            Some((ip, port)) => {
+                ip.parse::<Ipv6Addr>().is_ok() && port.strip_prefix(':').is_some_and(is_port)
+            }
+            None => false,
+        };
+    }
+    let name = match host.split_once(':') {
+        Some((name, port)) if is_port(port) => name,
+        Some(_) => return false,
+        None => host,
+    };
+    name.eq_ignore_ascii_case("localhost") || name.parse::<Ipv4Addr>().is_ok()
+}
+
+/// Compares without stopping at the first difference, so response times do not tell
+/// how much of a guessed token was right.
+fn token_matches(given: &str, token: &str) -> bool {
+    given.len() == token.len()
+        && given
+            .bytes()
+            .zip(token.bytes())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+fn query_token(url: &str) -> Option<&str> {
+    let (_, query) = url.split_once('?')?;
+    query.split('&').find_map(|pair| {
+        pair.strip_prefix(TOKEN_PARAM)
+            .and_then(|rest| rest.strip_prefix('='))
+    })
+}
+
+fn cookie_token(cookie_header: &str) -> Option<&str> {
+    cookie_header.split(';').find_map(|cookie| {
+        cookie
+            .trim()
+            .strip_prefix(TOKEN_COOKIE)
+            .and_then(|rest| rest.strip_prefix('='))
+    })
+}
+
+/// Decides on a request from its URL, headers and the address it came from.
+pub(crate) fn check_access(
+    policy: &AccessPolicy,
+    url: &str,
+    host: Option<&str>,
+    authorization: Option<&str>,
+    cookie: Option<&str>,
+    remote: Option<IpAddr>,
+) -> Access {
+    if !host.is_some_and(is_allowed_host) {
+        return Access::Denied(403, "Use localhost or the IP address of this computer");
+    }
+    let Some(token) = policy.token.as_deref() else {
+        return Access::Allowed;
+    };
+    // OBS on this computer keeps working with the plain URL.
+    if remote.is_some_and(|ip| ip.is_loopback()) {
+        return Access::Allowed;
+    }
+
+    let bearer = authorization.and_then(|value| value.strip_prefix("Bearer "));
+    if bearer
+        .or_else(|| cookie.and_then(cookie_token))
+        .is_some_and(|t| token_matches(t, token))
+    {
+        return Access::Allowed;
+    }
+    if query_token(url).is_some_and(|t| token_matches(t, token)) {
+        return Access::AllowedByUrlToken;
+    }
+    Access::Denied(401, "Access token required")
+}
+
+/// `check_access` for a request to the overlay server.
+pub(crate) fn check_request(policy: &AccessPolicy, request: &Request) -> Access {
+    check_access(
+        policy,
+        request.url(),
+        request.header("Host"),
+        request.header("Authorization"),
+        request.header("Cookie"),
+        Some(request.remote_addr().ip()),
+    )
+}
+
+/// `url` without the token parameter.
+pub(crate) fn without_token(url: &str) -> String {
+    let Some((path, query)) = url.split_once('?') else {
+        return url.to_string();
+    };
+    let rest: Vec<&str> = query
+        .split('&')
+        .filter(|pair| pair.split('=').next() != Some(TOKEN_PARAM))
+        .collect();
+    if rest.is_empty() {
+        path.to_string()
+    } else {
+        format!("{}?{}", path, rest.join("&"))
+    }
+}
+
+/// `Set-Cookie` value handing `token` to a page.
+pub(crate) fn token_cookie(token: &str) -> String {
+    format!(
+        "{}={}; Path=/; HttpOnly; SameSite=Strict",
+        TOKEN_COOKIE, token
+    )
+}
+
+/// The address other machines most likely reach this one on: the one of the interface
+/// the default route goes through. Connecting a UDP socket sends nothing.
+pub fn primary_lan_address() -> Option<IpAddr> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).ok()?;
+    socket.connect((Ipv4Addr::new(192, 0, 2, 1), 9)).ok()?;
+    let ip = socket.local_addr().ok()?.ip();
+    (!ip.is_unspecified() && !ip.is_loopback()).then_some(ip)
+}
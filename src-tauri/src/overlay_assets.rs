@@ -0,0 +1,238 @@
+//! Files the streamer overlay server serves next to the overlay page.
+//!
+//! - `/assets/...` - icons bundled with the app (faction and rank webp images)
+//! - `/theme/...` - anything in the user's theme folder (`OVERLAY_THEME_DIRNAME` in the
+//!   app data directory): stylesheets, fonts, images, scripts
+//! - `/overlay/<name>` - a named overlay layout, `templates/<name>.html` in the theme
+//!   folder. `/overlay/default` is the overlay page the app renders itself.
+//!
+//! Only whitelisted file types are served, and request paths are restricted to plain
+//! file names so nothing outside these directories can be reached.
+
+use log::error;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::overlay_http::{Request, Response};
+
+const BUNDLED_PREFIX: &str = "/assets/";
+const THEME_PREFIX: &str = "/theme/";
+const TEMPLATE_PREFIX: &str = "/overlay/";
+
+/// Template name of the overlay page rendered by the app.
+pub const DEFAULT_TEMPLATE: &str = "default";
+pub const TEMPLATES_DIRNAME: &str = "templates";
+
+/// Bundled directories overlays may load from, and the one file type allowed in each.
+const BUNDLED_ASSETS: &[(&str, &str)] = &[("factions/", "webp"), ("icons/ranks/", "webp")];
+
+/// File types served from the theme folder.
+const MIME_TYPES: &[(&str, &str)] = &[
+    ("html", "text/html; charset=utf-8"),
+    ("css", "text/css; charset=utf-8"),
+    ("js", "text/javascript; charset=utf-8"),
+    ("json", "application/json"),
+    ("png", "image/png"),
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("gif", "image/gif"),
+    ("webp", "image/webp"),
+    ("svg", "image/svg+xml"),
+    ("woff", "font/woff"),
+    ("woff2", "font/woff2"),
+    ("ttf", "font/ttf"),
+    ("otf", "font/otf"),
+];
+
+/// Bundled icons only change with an app update.
+const BUNDLED_CACHE_CONTROL: &str = "public, max-age=86400";
+/// Theme files are edited while OBS shows them, so browsers have to revalidate.
+const THEME_CACHE_CONTROL: &str = "no-cache";
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum AssetRequest {
+    /// Frontend asset compiled into the app, by its path in the bundle.
+    Bundled(String),
+    /// File in the theme folder, relative to it.
+    Theme(PathBuf),
+    /// Named overlay layout.
+    Template(String),
+    /// Under one of the asset prefixes, but not a path that may be served.
+    Forbidden,
+}
+
+/// MIME type of a file type that may be served, by extension.
+pub(crate) fn mime_type(path: &Path) -> Option<&'static str> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    MIME_TYPES
+        .iter()
+        .find(|(ext, _)| *ext == extension)
+        .map(|(_, mime)| *mime)
+}
+
+fn percent_decode(input: &str) -> Option<String> {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = input.get(i + 1..i + 3)?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+fn is_safe_segment(segment: &str) -> bool {
+    !segment.is_empty()
+        && !segment.starts_with('.')
+        && segment
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ' '))
+}
+
+/// `path` as a relative path of plain names - no `..`, no absolute paths, no drive
+/// letters or backslashes, no hidden files. Percent-escapes are decoded first so they
+/// cannot smuggle any of these in.
+pub(crate) fn safe_relative_path(path: &str) -> Option<PathBuf> {
+    let decoded = percent_decode(path)?;
+    let segments: Vec<&str> = decoded.split('/').collect();
+    if !segments.iter().all(|s| is_safe_segment(s)) {
+        return None;
+    }
+    Some(segments.iter().collect())
+}
+
+/// What `url` asks for, `None` when it is not an asset URL at all.
+pub(crate) fn parse_asset_request(url: &str) -> Option<AssetRequest> {
+    let path = url.split('?').next().unwrap_or(url);
+
+    if let Some(rest) = path.strip_prefix(BUNDLED_PREFIX) {
+        let allowed = safe_relative_path(rest)
+            .and_then(|_| percent_decode(rest))
+            .filter(|relative| {
+                BUNDLED_ASSETS.iter().any(|(dir, ext)| {
+                    relative.strip_prefix(dir).is_some_and(|name| {
+                        !name.contains('/') && Path::new(name).extension() == Some((*ext).as_ref())
+                    })
+                })
+            });
+        return Some(allowed.map_or(AssetRequest::Forbidden, AssetRequest::Bundled));
+    }
+
+    if let Some(rest) = path.strip_prefix(THEME_PREFIX) {
+        return Some(match safe_relative_path(rest) {
+            Some(relative) if mime_type(&relative).is_some() => AssetRequest::Theme(relative),
+            _ => AssetRequest::Forbidden,
+        });
+    }
+
+    if let Some(name) = path.strip_prefix(TEMPLATE_PREFIX) {
+        let valid = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        return Some(if valid {
+            AssetRequest::Template(name.to_string())
+        } else {
+            AssetRequest::Forbidden
+        });
+    }
+
+    None
+}
+
+/// Theme file of the template `name`, relative to the theme folder.
+pub(crate) fn template_path(name: &str) -> PathBuf {
+    Path::new(TEMPLATES_DIRNAME).join(format!("{}.html", name))
+}
+
+fn respond(request: Request, response: Response) {
+    if let Err(err) = request.respond(response) {
+        error!("Failed to send overlay asset: {:?}", err);
+    }
+}
+
+pub(crate) fn respond_status(request: Request, status: u16) {
+    respond(request, Response::empty(status));
+}
+
+/// Serves a bundled asset, looked up with `lookup` (the app's asset resolver).
+pub(crate) fn serve_bundled(
+    request: Request,
+    path: &str,
+    lookup: impl FnOnce(&str) -> Option<(Vec<u8>, String)>,
+) {
+    let Some(expected) = mime_type(Path::new(path)) else {
+        respond_status(request, 403);
+        return;
+    };
+    match lookup(path) {
+        // The resolver falls back to index.html for unknown paths.
+        Some((bytes, mime)) if mime == expected => {
+            let response = Response::from_data(bytes)
+                .with_header("Content-Type", expected)
+                .with_header("Cache-Control", BUNDLED_CACHE_CONTROL);
+            respond(request, response);
+        }
+        _ => respond_status(request, 404),
+    }
+}
+
+/// Serves `relative` from `theme_dir`, answering `304` when the browser's copy is current.
+pub(crate) fn serve_theme_file(request: Request, theme_dir: &Path, relative: &Path) {
+    let Some(mime) = mime_type(relative) else {
+        respond_status(request, 403);
+        return;
+    };
+
+    // Symlinks must not lead out of the theme folder either.
+    let resolved = match (
+        theme_dir.canonicalize(),
+        theme_dir.join(relative).canonicalize(),
+    ) {
+        (Ok(root), Ok(file)) if file.starts_with(&root) && file.is_file() => file,
+        (Ok(_), Ok(_)) => return respond_status(request, 403),
+        _ => return respond_status(request, 404),
+    };
+
+    let etag = fs::metadata(&resolved).ok().map(|metadata| {
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |since| since.as_millis());
+        format!("\"{:x}-{:x}\"", metadata.len(), modified)
+    });
+    let cached = etag.is_some() && request.header("If-None-Match") == etag.as_deref();
+
+    let with_headers = |response: Response| {
+        let response = response.with_header("Cache-Control", THEME_CACHE_CONTROL);
+        match etag.as_deref() {
+            Some(etag) => response.with_header("ETag", etag),
+            None => response,
+        }
+    };
+
+    if cached {
+        respond(request, with_headers(Response::empty(304)));
+        return;
+    }
+
+    match File::open(&resolved) {
+        Ok(file) => {
+            let response =
+                with_headers(Response::from_file(file)).with_header("Content-Type", mime);
+            respond(request, response);
+        }
+        Err(err) => {
+            error!("Failed to open overlay theme file {:?}: {}", resolved, err);
+            respond_status(request, 404);
+        }
+    }
+}
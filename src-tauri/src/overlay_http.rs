@@ -0,0 +1,525 @@
+//! A small HTTP/1.1 server for the streamer overlay.
+//!
+//! Overlay clients are few - OBS browser sources, overlay pages and bots - but some keep
+//! their connection open for hours (event streams), and one that stops reading must not
+//! hold up the others. So connections are accepted and their requests read on the async
+//! runtime, where a slow client costs no thread, and only a complete request is handed to
+//! the handler on a blocking task. Event streams are the exception: the handler writes
+//! each from a thread of its own for as long as it is open. Connections beyond the limit
+//! are turned away with `503` right away. Every response closes its connection.
+
+use log::{error, warn};
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::sync::Notify;
+
+/// Request line and headers together, more is refused.
+const MAX_HEAD_BYTES: usize = 16 * 1024;
+
+/// How long a turned away client gets to take its `503`.
+const REJECT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// How long `ServerHandle::stop` waits for the listener to be closed.
+const STOP_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(Clone, Debug)]
+pub struct Limits {
+    /// Open connections, event streams included.
+    pub max_connections: usize,
+    /// For a client to send its request, and for each write to it.
+    pub timeout: Duration,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_connections: 64,
+            timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+/// Lets through at most `max` events per `window`, for errors that can come up on every
+/// request and should not all be reported.
+pub struct RateLimiter {
+    max: u32,
+    window: Duration,
+    /// Start of the current window and the events let through in it.
+    state: Mutex<Option<(Instant, u32)>>,
+}
+
+impl RateLimiter {
+    pub const fn new(max: u32, window: Duration) -> Self {
+        Self {
+            max,
+            window,
+            state: Mutex::new(None),
+        }
+    }
+
+    pub fn allow(&self) -> bool {
+        self.allow_at(Instant::now())
+    }
+
+    pub(crate) fn allow_at(&self, now: Instant) -> bool {
+        let mut state = self
+            .state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        match *state {
+            Some((start, count)) if now.saturating_duration_since(start) < self.window => {
+                if count >= self.max {
+                    return false;
+                }
+                *state = Some((start, count + 1));
+                true
+            }
+            _ => {
+                *state = Some((now, 1));
+                self.max > 0
+            }
+        }
+    }
+}
+
+/// Counts open connections against the limit.
+struct Slots {
+    open: AtomicUsize,
+    max: usize,
+}
+
+/// One open connection, given back when dropped.
+struct Slot(Arc<Slots>);
+
+impl Slots {
+    fn take(self: &Arc<Self>) -> Option<Slot> {
+        self.open
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |open| {
+                (open < self.max).then_some(open + 1)
+            })
+            .ok()
+            .map(|_| Slot(self.clone()))
+    }
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        self.0.open.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// An accepted connection and the slot it takes up.
+struct Connection {
+    stream: TcpStream,
+    _slot: Slot,
+}
+
+pub struct Request {
+    method: String,
+    url: String,
+    headers: Vec<(String, String)>,
+    remote_addr: SocketAddr,
+    connection: Connection,
+}
+
+impl Request {
+    pub fn method(&self) -> &str {
+        &self.method
+    }
+
+    /// Path and query string, as sent.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Value of the first header called `name`, in any case.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(field, _)| field.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn remote_addr(&self) -> SocketAddr {
+        self.remote_addr
+    }
+
+    pub fn respond(mut self, response: Response) -> io::Result<()> {
+        response.write_to(&mut self.connection.stream)
+    }
+
+    /// The connection, to write a response of unknown length to (an event stream). It
+    /// keeps its slot until dropped.
+    pub fn into_writer(self) -> ResponseWriter {
+        ResponseWriter(self.connection)
+    }
+}
+
+pub struct ResponseWriter(Connection);
+
+impl Write for ResponseWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.stream.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.stream.flush()
+    }
+}
+
+enum Body {
+    Data(Vec<u8>),
+    File(File),
+}
+
+pub struct Response {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: Body,
+}
+
+impl Response {
+    pub fn empty(status: u16) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            body: Body::Data(Vec::new()),
+        }
+    }
+
+    pub fn from_data(data: Vec<u8>) -> Self {
+        Self {
+            body: Body::Data(data),
+            ..Self::empty(200)
+        }
+    }
+
+    pub fn from_string(text: impl Into<String>) -> Self {
+        Self::from_data(text.into().into_bytes())
+    }
+
+    pub fn from_file(file: File) -> Self {
+        Self {
+            body: Body::File(file),
+            ..Self::empty(200)
+        }
+    }
+
+    pub fn with_status(mut self, status: u16) -> Self {
+        self.status = status;
+        self
+    }
+
+    /// Adds a header. Values that would break the response are left out.
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        if [name, value].iter().any(|s| s.contains(['\r', '\n'])) {
+            warn!("Leaving out overlay response header {:?}", name);
+            return self;
+        }
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    fn write_to(self, writer: &mut impl Write) -> io::Result<()> {
+        let length = match &self.body {
+            Body::Data(data) => data.len() as u64,
+            Body::File(file) => file.metadata()?.len(),
+        };
+        let mut head = format!("HTTP/1.1 {} {}\r\n", self.status, reason(self.status));
+        for (name, value) in &self.headers {
+            head.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        head.push_str(&format!(
+            "Content-Length: {}\r\nConnection: close\r\n\r\n",
+            length
+        ));
+        writer.write_all(head.as_bytes())?;
+        match self.body {
+            Body::Data(data) => writer.write_all(&data)?,
+            Body::File(file) => {
+                io::copy(&mut file.take(length), writer)?;
+            }
+        }
+        writer.flush()
+    }
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        303 => "See Other",
+        304 => "Not Modified",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        408 => "Request Timeout",
+        431 => "Request Header Fields Too Large",
+        500 => "Internal Server Error",
+        503 => "Service Unavailable",
+        _ => "",
+    }
+}
+
+/// Reads up to the end of the headers. The caller puts a deadline on the whole read, so
+/// sending a byte now and then does not keep a connection open. `Err` carries the status
+/// to answer with, `None` if the client is gone.
+async fn read_head(stream: &mut tokio::net::TcpStream) -> Result<Vec<u8>, Option<u16>> {
+    let mut head = Vec::new();
+    let mut buf = [0u8; 1024];
+    loop {
+        match stream.read(&mut buf).await {
+            Ok(0) => return Err(None),
+            Ok(read) => head.extend_from_slice(&buf[..read]),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(_) => return Err(None),
+        }
+        // A request body, if any, is not needed: every method but GET is refused.
+        if let Some(end) = head.windows(4).position(|w| w == b"\r\n\r\n") {
+            head.truncate(end);
+            return Ok(head);
+        }
+        if head.len() > MAX_HEAD_BYTES {
+            return Err(Some(431));
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct RequestHead {
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+}
+
+/// Request line and headers, up to but without the blank line.
+pub(crate) fn parse_head(head: &str) -> Option<RequestHead> {
+    let mut lines = head.split("\r\n");
+    let mut request_line = lines.next()?.split(' ');
+    let (method, url, version) = (
+        request_line.next()?,
+        request_line.next()?,
+        request_line.next()?,
+    );
+    if request_line.next().is_some() || !version.starts_with("HTTP/1.") || !url.starts_with('/') {
+        return None;
+    }
+    let headers = lines
+        .map(|line| {
+            let (name, value) = line.split_once(':')?;
+            Some((name.trim().to_string(), value.trim().to_string()))
+        })
+        .collect::<Option<Vec<_>>>()?;
+    Some(RequestHead {
+        method: method.to_string(),
+        url: url.to_string(),
+        headers,
+    })
+}
+
+type Handler = dyn Fn(Request) + Send + Sync;
+
+/// `response` as bytes, for answers written on the async runtime.
+fn response_bytes(response: Response) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    // Writing to a `Vec` only fails for file bodies, which these answers don't have.
+    let _ = response.write_to(&mut bytes);
+    bytes
+}
+
+/// Writes `response` within `timeout`, and closes the sending side.
+async fn send_async(stream: &mut tokio::net::TcpStream, response: Response, timeout: Duration) {
+    let bytes = response_bytes(response);
+    let _ = tokio::time::timeout(timeout, async {
+        stream.write_all(&bytes).await?;
+        stream.shutdown().await
+    })
+    .await;
+}
+
+/// Reads the request on `stream` and hands it to `handler` on a blocking task.
+async fn handle_connection(
+    mut stream: tokio::net::TcpStream,
+    slot: Slot,
+    handler: Arc<Handler>,
+    timeout: Duration,
+) {
+    let Ok(remote_addr) = stream.peer_addr() else {
+        return;
+    };
+    let parsed = match tokio::time::timeout(timeout, read_head(&mut stream)).await {
+        Ok(head) => head.and_then(|head| {
+            let head = String::from_utf8(head).map_err(|_| Some(400))?;
+            parse_head(&head).ok_or(Some(400))
+        }),
+        Err(_) => Err(Some(408)),
+    };
+    let head = match parsed {
+        Ok(head) => head,
+        Err(Some(status)) => {
+            return send_async(&mut stream, Response::empty(status), timeout).await
+        }
+        Err(None) => return,
+    };
+
+    // Handlers write their responses blocking, with a timeout for each write.
+    let stream = match stream.into_std().and_then(|stream| {
+        stream.set_nonblocking(false)?;
+        stream.set_write_timeout(Some(timeout))?;
+        Ok(stream)
+    }) {
+        Ok(stream) => stream,
+        Err(e) => {
+            warn!("Failed to prepare an overlay connection: {}", e);
+            return;
+        }
+    };
+    let request = Request {
+        method: head.method,
+        url: head.url,
+        headers: head.headers,
+        remote_addr,
+        connection: Connection {
+            stream,
+            _slot: slot,
+        },
+    };
+    let answered = tauri::async_runtime::spawn_blocking(move || handler(request)).await;
+    if answered.is_err() {
+        error!("Overlay request handler panicked");
+    }
+}
+
+/// Answers a connection over the limit with `503`.
+async fn reject(mut stream: tokio::net::TcpStream) {
+    let response = response_bytes(Response::empty(503).with_header("Retry-After", "2"));
+    let _ = tokio::time::timeout(REJECT_TIMEOUT, async {
+        stream.write_all(&response).await?;
+        stream.shutdown().await?;
+        // Closing with the request unread would reset the connection before the client
+        // gets to read the response.
+        let mut request = vec![0u8; MAX_HEAD_BYTES];
+        stream.read(&mut request).await
+    })
+    .await;
+}
+
+/// A bound listener, not yet answering requests.
+pub struct Server {
+    listener: TcpListener,
+    limits: Limits,
+}
+
+impl Server {
+    pub fn bind(addr: impl ToSocketAddrs, limits: Limits) -> io::Result<Self> {
+        Ok(Self {
+            listener: TcpListener::bind(addr)?,
+            limits,
+        })
+    }
+
+    /// Answers requests with `handler` until stopped.
+    pub fn serve(
+        self,
+        handler: impl Fn(Request) + Send + Sync + 'static,
+    ) -> io::Result<ServerHandle> {
+        let addr = self.listener.local_addr()?;
+        self.listener.set_nonblocking(true)?;
+        let listener = self.listener;
+        let limits = self.limits;
+        let slots = Arc::new(Slots {
+            open: AtomicUsize::new(0),
+            max: limits.max_connections,
+        });
+        let handler: Arc<Handler> = Arc::new(handler);
+        let closing = Arc::new(Notify::new());
+        let (closed, closed_receiver) = mpsc::channel();
+
+        let accept_closing = closing.clone();
+        tauri::async_runtime::spawn(async move {
+            static ACCEPT_ERRORS: RateLimiter = RateLimiter::new(5, Duration::from_secs(60));
+            let listener = match tokio::net::TcpListener::from_std(listener) {
+                Ok(listener) => listener,
+                Err(e) => {
+                    error!("Overlay server failed to listen: {}", e);
+                    let _ = closed.send(());
+                    return;
+                }
+            };
+            loop {
+                let accepted = tokio::select! {
+                    accepted = listener.accept() => accepted,
+                    _ = accept_closing.notified() => break,
+                };
+                let stream = match accepted {
+                    Ok((stream, _)) => stream,
+                    Err(e) => {
+                        if ACCEPT_ERRORS.allow() {
+                            warn!("Overlay server failed to accept a connection: {}", e);
+                        }
+                        // Out of file handles, for example. Give them a moment to free up.
+                        tokio::time::sleep(Duration::from_millis(10)).await;
+                        continue;
+                    }
+                };
+                match slots.take() {
+                    Some(slot) => {
+                        tauri::async_runtime::spawn(handle_connection(
+                            stream,
+                            slot,
+                            handler.clone(),
+                            limits.timeout,
+                        ));
+                    }
+                    None => {
+                        tauri::async_runtime::spawn(reject(stream));
+                    }
+                }
+            }
+            drop(listener);
+            let _ = closed.send(());
+        });
+
+        Ok(ServerHandle {
+            addr,
+            closing,
+            closed: closed_receiver,
+        })
+    }
+}
+
+/// A server answering requests until stopped.
+pub struct ServerHandle {
+    addr: SocketAddr,
+    closing: Arc<Notify>,
+    /// Signalled once the listener is closed.
+    closed: mpsc::Receiver<()>,
+}
+
+impl ServerHandle {
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Stops accepting connections and returns once the port is free again. Requests
+    /// already taken are still answered, and event streams stay open until their
+    /// writers end.
+    pub fn stop(self) {
+        self.closing.notify_one();
+        if self.closed.recv_timeout(STOP_TIMEOUT).is_err() {
+            error!("Overlay server did not close its listener in time");
+        }
+    }
+
+    /// Stops accepting connections without waiting for the port to be freed, for when
+    /// nothing binds it again - the app exiting.
+    pub fn close(self) {
+        self.closing.notify_one();
+    }
+}
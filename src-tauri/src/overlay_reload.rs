@@ -0,0 +1,105 @@
+//! Reloads overlay pages when the files they are made of change.
+//!
+//! While the overlay server runs, `streamerOverlay.html` and the theme folder are watched.
+//! Changes are collected until things have been quiet for `QUIET_PERIOD` - an editor
+//! saving a file often writes it several times - and then announced once, as a `reload`
+//! event on the live event stream. The overlay page reloads itself when it gets one.
+
+use log::{error, info};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::config::{OVERLAY_THEME_DIRNAME, STREAMER_OVERLAY_FILENAME};
+
+/// How long no further change has to come in before the batch is announced.
+pub const QUIET_PERIOD: Duration = Duration::from_millis(300);
+/// A batch is announced after this long even if changes keep coming.
+pub const MAX_BATCH_DELAY: Duration = Duration::from_secs(2);
+
+/// Waits for the next item and collects everything that follows within `quiet` of the
+/// previous one, for at most `max` in total. `None` once the sender is gone.
+pub(crate) fn next_batch<T>(
+    receiver: &Receiver<T>,
+    quiet: Duration,
+    max: Duration,
+) -> Option<Vec<T>> {
+    let mut batch = vec![receiver.recv().ok()?];
+    let started = Instant::now();
+    loop {
+        let remaining = max.saturating_sub(started.elapsed());
+        match receiver.recv_timeout(quiet.min(remaining)) {
+            Ok(item) => batch.push(item),
+            Err(RecvTimeoutError::Timeout) => return Some(batch),
+            // Announce what came in before the watcher went away.
+            Err(RecvTimeoutError::Disconnected) => return Some(batch),
+        }
+        if started.elapsed() >= max {
+            return Some(batch);
+        }
+    }
+}
+
+/// Whether `event` changes something an overlay page shows: the overlay page itself or
+/// anything in the theme folder. Reads of files do not count.
+pub(crate) fn is_overlay_change(event: &Event, theme_dir: &Path) -> bool {
+    if matches!(event.kind, EventKind::Access(_)) {
+        return false;
+    }
+    event.paths.iter().any(|path| {
+        path.starts_with(theme_dir)
+            || path
+                .file_name()
+                .is_some_and(|name| name == STREAMER_OVERLAY_FILENAME)
+    })
+}
+
+/// Watches the overlay files until dropped.
+pub struct OverlayWatcher {
+    _watcher: RecommendedWatcher,
+}
+
+impl OverlayWatcher {
+    /// Starts watching the overlay files in `app_data_dir`, calling `on_change` once per
+    /// batch of changes.
+    pub fn start(
+        app_data_dir: &Path,
+        on_change: impl Fn() + Send + 'static,
+    ) -> notify::Result<Self> {
+        let theme_dir = app_data_dir.join(OVERLAY_THEME_DIRNAME);
+        // The watcher reports paths below the watched ones as given, except on macOS,
+        // where they are canonical.
+        let canonical_theme_dir = theme_dir
+            .canonicalize()
+            .unwrap_or_else(|_| theme_dir.clone());
+
+        let (sender, receiver) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(sender)?;
+        // The overlay page is replaced rather than edited in place, so its directory is
+        // watched - without recursing, the store and caches live there too.
+        watcher.watch(app_data_dir, RecursiveMode::NonRecursive)?;
+        watcher.watch(&theme_dir, RecursiveMode::Recursive)?;
+
+        thread::spawn(move || {
+            while let Some(batch) = next_batch(&receiver, QUIET_PERIOD, MAX_BATCH_DELAY) {
+                let changed = batch.iter().any(|event| match event {
+                    Ok(event) => [&theme_dir, &canonical_theme_dir]
+                        .into_iter()
+                        .any(|dir| is_overlay_change(event, dir)),
+                    Err(e) => {
+                        error!("Overlay file watcher error: {}", e);
+                        false
+                    }
+                });
+                if changed {
+                    info!("Overlay files changed, reloading overlay pages");
+                    on_change();
+                }
+            }
+        });
+
+        Ok(Self { _watcher: watcher })
+    }
+}
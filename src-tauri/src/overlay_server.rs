@@ -1,66 +1,751 @@
-use log::{error, info};
+use log::{error, info, warn};
+use serde::Serialize;
 use std::fs::File;
-use std::path::PathBuf;
+use std::io::Write;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::Path;
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
 
-use tiny_http::{Response, Server, StatusCode};
+use tauri::{AppHandle, Manager, Runtime};
 
-use crate::config::OVERLAY_PORT;
+use crate::config::{OVERLAY_PORT, OVERLAY_THEME_DIRNAME, STREAMER_OVERLAY_FILENAME};
+use crate::current_game::{self, LastMatch};
+use crate::dp_utils::{load_from_store, save_to_store};
+use crate::live_events::{LiveEvent, LiveEvents};
+use crate::matchup_card;
+use crate::overlay_access::{
+    check_request, generate_token, primary_lan_address, token_cookie, without_token, Access,
+    AccessPolicy, TOKEN_PARAM,
+};
+use crate::overlay_assets::{
+    parse_asset_request, respond_status, serve_bundled, serve_theme_file, template_path,
+    AssetRequest, DEFAULT_TEMPLATE, TEMPLATES_DIRNAME,
+};
+use crate::overlay_http::{Limits, RateLimiter, Request, Response, Server, ServerHandle};
+use crate::overlay_reload::OverlayWatcher;
+use crate::parse_log_file::LogFileData;
+use crate::session::{self, Scoreboard};
 
-pub fn run_http_server(streamer_overlay_path: PathBuf) {
-    // Ideally we would allow setting up port in the settings
-    info!("Starting streamer overlay server on port {}", OVERLAY_PORT);
+/// Prefix of the versioned JSON API. Everything else serves the overlay HTML.
+const API_PREFIX: &str = "/api/v1/";
 
-    let server = match Server::http(format!("127.0.0.1:{}", OVERLAY_PORT)) {
-        Ok(server) => server,
+/// Ports tried after the configured one when it is taken.
+const FALLBACK_PORT_ATTEMPTS: u16 = 10;
+
+/// Server-Sent Events stream of `LiveEvent`s.
+const EVENTS_PATH: &str = "/api/v1/events";
+
+/// The matchup card as a PNG. `?result=false` leaves the result off.
+const CARD_PATH: &str = "/card.png";
+
+/// Comment sent on idle event streams, so proxies keep them open and closed
+/// connections are noticed.
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Store key of the LAN mode access token.
+const TOKEN_STORE_KEY: &str = "streamerOverlayToken";
+
+/// Whether `url` is an API request rather than one for the overlay page.
+pub(crate) fn is_api_path(url: &str) -> bool {
+    url.starts_with("/api/")
+}
+
+fn json_error(status: u16, message: &str) -> (u16, String) {
+    (status, serde_json::json!({ "error": message }).to_string())
+}
+
+fn json_body<T: serde::Serialize>(value: Option<&T>, missing: &str) -> (u16, String) {
+    match value.map(serde_json::to_string) {
+        Some(Ok(body)) => (200, body),
+        Some(Err(e)) => {
+            error!("Failed to serialize overlay API response: {}", e);
+            json_error(500, "Failed to serialize response")
+        }
+        None => json_error(404, missing),
+    }
+}
+
+/// Status and JSON body for an API request.
+///
+/// - `GET /api/v1/game` - the current parsed game: state, map, teams and players
+/// - `GET /api/v1/last-match` - the most recent match with a result
+/// - `GET /api/v1/session` - the session scoreboard, by mode and faction
+///
+/// `GET /api/v1/events` is the live event stream, see `handle_events_request`.
+pub(crate) fn api_response(
+    url: &str,
+    game: Option<&LogFileData>,
+    last_match: Option<&LastMatch>,
+    session: Option<&Scoreboard>,
+) -> (u16, String) {
+    // Query strings are accepted (cache busting) but not used.
+    let path = url.split('?').next().unwrap_or(url);
+    match path.strip_prefix(API_PREFIX) {
+        Some("game") => json_body(game, "No game has been read from the log yet"),
+        Some("last-match") => json_body(last_match, "No finished match yet"),
+        Some("session") => json_body(session, "No session yet"),
+        _ => json_error(404, "Unknown API endpoint"),
+    }
+}
+
+fn respond_json(request: Request, status: u16, body: String) {
+    let response = Response::from_string(body)
+        .with_status(status)
+        .with_header("Content-Type", "application/json; charset=utf-8")
+        .with_header("Cache-Control", "no-store");
+    if let Err(err) = request.respond(response) {
+        error!("Failed to send overlay API response: {:?}", err);
+    }
+}
+
+/// Whether a `/card.png` request wants the result on the card, it does unless
+/// `result=false` is in the query.
+pub(crate) fn card_wants_result(url: &str) -> bool {
+    let query = url.split_once('?').map_or("", |(_, query)| query);
+    !query.split('&').any(|param| param == "result=false")
+}
+
+fn handle_card_request<R: Runtime>(handle: &AppHandle<R>, request: Request) {
+    let with_result = card_wants_result(request.url());
+    // Handlers run on blocking tasks, waiting for the card holds up no other request.
+    match tauri::async_runtime::block_on(matchup_card::current_png(handle, with_result)) {
+        Ok(png) => {
+            let response = Response::from_data(png)
+                .with_header("Content-Type", "image/png")
+                .with_header("Cache-Control", "no-store");
+            if let Err(err) = request.respond(response) {
+                error!("Failed to send matchup card: {:?}", err);
+            }
+        }
+        Err(e) => {
+            let (status, body) = json_error(404, &e);
+            respond_json(request, status, body);
+        }
+    }
+}
+
+fn write_events(
+    writer: &mut dyn Write,
+    replay: Vec<LiveEvent>,
+    receiver: Receiver<LiveEvent>,
+) -> std::io::Result<()> {
+    writer.write_all(
+        b"HTTP/1.1 200 OK\r\n\
+          Content-Type: text/event-stream\r\n\
+          Cache-Control: no-store\r\n\
+          Connection: keep-alive\r\n\r\n\
+          retry: 2000\n\n",
+    )?;
+    writer.flush()?;
+
+    let send = |writer: &mut dyn Write, event: &LiveEvent| -> std::io::Result<()> {
+        match event.to_sse() {
+            Ok(message) => writer.write_all(message.as_bytes())?,
+            Err(e) => error!("Failed to serialize overlay event {}: {}", event.name(), e),
+        }
+        writer.flush()
+    };
+
+    for event in &replay {
+        send(writer, event)?;
+    }
+    loop {
+        match receiver.recv_timeout(KEEP_ALIVE_INTERVAL) {
+            Ok(event) => send(writer, &event)?,
+            Err(RecvTimeoutError::Timeout) => {
+                writer.write_all(b": keep-alive\n\n")?;
+                writer.flush()?;
+            }
+            Err(RecvTimeoutError::Disconnected) => return Ok(()),
+        }
+    }
+}
+
+/// Answers `request` with an event stream of `events`, replaying the current state
+/// first. The stream is written from its own thread until the client disconnects.
+pub(crate) fn handle_events_request(request: Request, events: &LiveEvents) {
+    if request.method() != "GET" {
+        let (status, body) = json_error(405, "Only GET is supported");
+        respond_json(request, status, body);
+        return;
+    }
+
+    let (replay, receiver) = events.subscribe();
+    info!(
+        "Overlay event stream opened, {} subscribers",
+        events.subscriber_count()
+    );
+    let mut writer = request.into_writer();
+    thread::spawn(move || {
+        // Disconnected and stuck clients show up as write errors.
+        let _ = write_events(&mut writer, replay, receiver);
+        info!("Overlay event stream closed");
+    });
+}
+
+fn handle_api_request<R: Runtime>(handle: &AppHandle<R>, request: Request) {
+    if request.method() != "GET" {
+        let (status, body) = json_error(405, "Only GET is supported");
+        respond_json(request, status, body);
+        return;
+    }
+
+    let game = current_game::current(handle);
+    let last_match = current_game::last_match(handle);
+    // Loading the session reads the settings store, only do it when it is asked for.
+    let session = request
+        .url()
+        .starts_with("/api/v1/session")
+        .then(|| session::current(handle));
+    let (status, body) = api_response(
+        request.url(),
+        game.as_ref(),
+        last_match.as_ref(),
+        session.as_ref(),
+    );
+    respond_json(request, status, body);
+}
+
+fn serve_overlay_page(request: Request, streamer_overlay_path: &Path) {
+    let file = match File::open(streamer_overlay_path) {
+        Ok(file) => file,
         Err(err) => {
-            error!("Couldn't start the streamer overlay server: {:?}", err);
+            error!(
+                "Failed to open overlay file at {:?}: {}",
+                streamer_overlay_path, err
+            );
+            // Every request fails the same way, report only a few to Sentry.
+            static REPORTS: RateLimiter = RateLimiter::new(3, Duration::from_secs(60 * 60));
+            if REPORTS.allow() {
+                sentry::capture_message(
+                    &format!(
+                        "Overlay file access error: {:?} - {}",
+                        streamer_overlay_path, err
+                    ),
+                    sentry::Level::Warning,
+                );
+            }
+            let _ = request.respond(Response::empty(404));
+            return;
+        }
+    };
+
+    let response = Response::from_file(file);
+    if let Err(err) = request.respond(response) {
+        error!("Failed to send overlay response: {:?}", err);
+    }
+}
+
+/// Sends a page opened with the token in its URL on to the same URL without it, handing
+/// the token over as a cookie for the page's own requests.
+fn redirect_with_token_cookie(request: Request, token: &str) {
+    let response = Response::empty(303)
+        .with_header("Location", &without_token(request.url()))
+        .with_header("Set-Cookie", &token_cookie(token))
+        .with_header("Cache-Control", "no-store");
+    if let Err(err) = request.respond(response) {
+        error!("Failed to send overlay redirect: {:?}", err);
+    }
+}
+
+fn handle_request<R: Runtime>(
+    handle: &AppHandle<R>,
+    request: Request,
+    app_data_dir: &Path,
+    policy: &AccessPolicy,
+) {
+    match check_request(policy, &request) {
+        Access::Denied(status, message) => {
+            let (status, body) = json_error(status, message);
+            respond_json(request, status, body);
+            return;
+        }
+        // API clients keep sending the token themselves.
+        Access::AllowedByUrlToken if !is_api_path(request.url()) => {
+            if let Some(token) = policy.token.as_deref() {
+                redirect_with_token_cookie(request, token);
+                return;
+            }
+        }
+        _ => {}
+    }
+
+    if request.url().split('?').next() == Some(EVENTS_PATH) {
+        handle_events_request(request, &handle.state::<LiveEvents>());
+        return;
+    }
+    if is_api_path(request.url()) {
+        handle_api_request(handle, request);
+        return;
+    }
+    if request.url().split('?').next() == Some(CARD_PATH) {
+        handle_card_request(handle, request);
+        return;
+    }
+
+    let streamer_overlay_path = app_data_dir.join(STREAMER_OVERLAY_FILENAME);
+    let theme_dir = app_data_dir.join(OVERLAY_THEME_DIRNAME);
+    match parse_asset_request(request.url()) {
+        Some(AssetRequest::Bundled(path)) => serve_bundled(request, &path, |path| {
+            let asset = handle.asset_resolver().get(format!("/{}", path))?;
+            Some((asset.bytes, asset.mime_type))
+        }),
+        Some(AssetRequest::Theme(relative)) => serve_theme_file(request, &theme_dir, &relative),
+        Some(AssetRequest::Template(name)) if name == DEFAULT_TEMPLATE => {
+            serve_overlay_page(request, &streamer_overlay_path)
+        }
+        Some(AssetRequest::Template(name)) => {
+            serve_theme_file(request, &theme_dir, &template_path(&name))
+        }
+        Some(AssetRequest::Forbidden) => respond_status(request, 403),
+        // Any other path is the overlay page, as OBS sources have always been set up.
+        None => serve_overlay_page(request, &streamer_overlay_path),
+    }
+}
+
+/// Listens on `ip` and port `preferred`, or on the first free one of the
+/// `fallback_attempts` ports after it when it is taken (another app, or a second instance
+/// of this one).
+pub(crate) fn bind(
+    ip: IpAddr,
+    preferred: u16,
+    fallback_attempts: u16,
+) -> Result<(Server, u16), String> {
+    let mut last_error = String::new();
+    for port in (preferred..=preferred.saturating_add(fallback_attempts)).filter(|p| *p != 0) {
+        match Server::bind((ip, port), Limits::default()) {
+            Ok(server) => return Ok((server, port)),
+            Err(err) => {
+                warn!("Streamer overlay port {} is not available: {}", port, err);
+                last_error = err.to_string();
+            }
+        }
+    }
+    Err(format!(
+        "No free port between {} and {}: {}",
+        preferred,
+        preferred.saturating_add(fallback_attempts),
+        last_error
+    ))
+}
+
+/// Where the server should listen.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ListenSettings {
+    /// May differ from the port in use after a fallback.
+    pub port: u16,
+    /// Interface to listen on in LAN mode. `None` outside it, only this computer can
+    /// connect then.
+    pub lan_address: Option<IpAddr>,
+}
+
+impl ListenSettings {
+    pub fn local(port: u16) -> Self {
+        Self {
+            port,
+            lan_address: None,
+        }
+    }
+
+    pub fn ip(&self) -> IpAddr {
+        self.lan_address.unwrap_or(IpAddr::V4(Ipv4Addr::LOCALHOST))
+    }
+}
+
+/// A server answering requests until stopped.
+pub(crate) struct RunningServer {
+    server: ServerHandle,
+    settings: ListenSettings,
+    watcher: Option<OverlayWatcher>,
+}
+
+impl RunningServer {
+    pub(crate) fn spawn(
+        server: Server,
+        settings: ListenSettings,
+        handler: impl Fn(Request) + Send + Sync + 'static,
+    ) -> Result<Self, String> {
+        let server = server.serve(handler).map_err(|e| e.to_string())?;
+        Ok(Self {
+            server,
+            settings,
+            watcher: None,
+        })
+    }
+
+    /// Keeps `watcher` running for as long as the server.
+    pub(crate) fn watch(&mut self, watcher: OverlayWatcher) {
+        self.watcher = Some(watcher);
+    }
+
+    pub(crate) fn port(&self) -> u16 {
+        self.server.addr().port()
+    }
+
+    /// Stops accepting requests and frees the port. Requests taken are still answered.
+    pub(crate) fn stop(self) {
+        drop(self.watcher);
+        self.server.stop();
+    }
+
+    /// Stops accepting requests without waiting for the port to be freed.
+    pub(crate) fn close(self) {
+        drop(self.watcher);
+        self.server.close();
+    }
+}
+
+/// What the settings show: whether the server runs and the URL to give OBS.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct OverlayServerStatus {
+    pub running: bool,
+    pub requested_port: u16,
+    /// Port in use, differs from `requested_port` when that one was taken.
+    pub port: Option<u16>,
+    pub url: Option<String>,
+    pub lan_mode: bool,
+    /// Interface listened on in LAN mode, `0.0.0.0` for all of them.
+    pub bind_address: Option<String>,
+    /// URL for other computers in LAN mode, with the access token.
+    pub lan_url: Option<String>,
+    pub token: Option<String>,
+}
+
+/// State for the streamer overlay server, `None` while stopped
+#[derive(Default)]
+pub struct OverlayServerState {
+    server: Mutex<Option<RunningServer>>,
+    /// Shared with the request handler, so a new token applies at once.
+    access: Arc<Mutex<AccessPolicy>>,
+}
+
+/// Helper to safely lock the mutex, recovering from poison if needed
+fn lock_server(state: &OverlayServerState) -> std::sync::MutexGuard<'_, Option<RunningServer>> {
+    state.server.lock().unwrap_or_else(|poisoned| {
+        warn!("OverlayServerState mutex was poisoned, recovering");
+        poisoned.into_inner()
+    })
+}
+
+fn lock_access(access: &Mutex<AccessPolicy>) -> std::sync::MutexGuard<'_, AccessPolicy> {
+    access.lock().unwrap_or_else(|poisoned| {
+        warn!("Overlay access policy mutex was poisoned, recovering");
+        poisoned.into_inner()
+    })
+}
+
+/// Port from the settings, `OVERLAY_PORT` if none is set
+fn configured_port<R: Runtime>(handle: &AppHandle<R>) -> u16 {
+    load_from_store::<R, u16>(handle.clone(), "streamerOverlayPort")
+        .filter(|port| *port != 0)
+        .unwrap_or(OVERLAY_PORT)
+}
+
+/// `address` as the interface to listen on in LAN mode, all of them if empty.
+pub(crate) fn parse_bind_address(address: &str) -> Result<IpAddr, String> {
+    let address = address.trim();
+    if address.is_empty() {
+        return Ok(IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+    }
+    address
+        .parse()
+        .map_err(|_| format!("Not an IP address: {}", address))
+}
+
+/// The settings for the server: the given values, the stored ones for the others.
+pub fn listen_settings<R: Runtime>(
+    handle: &AppHandle<R>,
+    port: Option<u16>,
+    lan_mode: Option<bool>,
+    bind_address: Option<String>,
+) -> Result<ListenSettings, String> {
+    let port = port.unwrap_or_else(|| configured_port(handle));
+    let lan_mode = lan_mode.unwrap_or_else(|| {
+        load_from_store::<R, bool>(handle.clone(), "streamerOverlayLanMode").unwrap_or(false)
+    });
+    if !lan_mode {
+        return Ok(ListenSettings::local(port));
+    }
+    let bind_address = bind_address
+        .or_else(|| load_from_store::<R, String>(handle.clone(), "streamerOverlayBindAddress"))
+        .unwrap_or_default();
+    Ok(ListenSettings {
+        port,
+        lan_address: Some(parse_bind_address(&bind_address)?),
+    })
+}
+
+/// Stored access token, a new one if there is none yet.
+fn stored_token<R: Runtime>(handle: &AppHandle<R>) -> Result<String, String> {
+    match load_from_store::<R, String>(handle.clone(), TOKEN_STORE_KEY) {
+        Some(token) if !token.is_empty() => Ok(token),
+        _ => new_token(handle),
+    }
+}
+
+fn new_token<R: Runtime>(handle: &AppHandle<R>) -> Result<String, String> {
+    let token = generate_token()?;
+    save_to_store(handle.clone(), TOKEN_STORE_KEY, &token)?;
+    Ok(token)
+}
+
+fn status_of(
+    server: Option<&RunningServer>,
+    requested_port: u16,
+    policy: &AccessPolicy,
+) -> OverlayServerStatus {
+    let Some(server) = server else {
+        return OverlayServerStatus {
+            running: false,
+            requested_port,
+            port: None,
+            url: None,
+            lan_mode: false,
+            bind_address: None,
+            lan_url: None,
+            token: None,
+        };
+    };
+
+    let lan_url = server.settings.lan_address.and_then(|ip| {
+        // Listening on all interfaces, other computers use the one with the default route.
+        let host = if ip.is_unspecified() {
+            primary_lan_address()?
+        } else {
+            ip
+        };
+        let token = policy.token.as_deref()?;
+        Some(format!(
+            "http://{}/?{}={}",
+            SocketAddr::new(host, server.port()),
+            TOKEN_PARAM,
+            token
+        ))
+    });
+    let ip = server.settings.ip();
+    // Bound to one network interface, this computer has to use it as well.
+    let url = if ip.is_unspecified() || ip.is_loopback() {
+        Some(format!("http://localhost:{}", server.port()))
+    } else {
+        lan_url.clone()
+    };
+
+    OverlayServerStatus {
+        running: true,
+        requested_port: server.settings.port,
+        port: Some(server.port()),
+        url,
+        lan_mode: server.settings.lan_address.is_some(),
+        bind_address: server.settings.lan_address.map(|ip| ip.to_string()),
+        lan_url,
+        token: policy.token.clone(),
+    }
+}
+
+/// Starts the server with `settings`. A server already running with the same settings is
+/// kept, one with other settings is restarted.
+pub fn start<R: Runtime>(
+    handle: &AppHandle<R>,
+    settings: ListenSettings,
+) -> Result<OverlayServerStatus, String> {
+    let requested_port = settings.port;
+    let state = handle.state::<OverlayServerState>();
+    let mut running = lock_server(&state);
+
+    let token = match settings.lan_address {
+        Some(_) => Some(stored_token(handle)?),
+        None => None,
+    };
+    let policy = AccessPolicy { token };
+    *lock_access(&state.access) = policy.clone();
+
+    if let Some(server) = running.as_ref() {
+        if server.settings == settings {
+            return Ok(status_of(Some(server), requested_port, &policy));
+        }
+    }
+    if let Some(server) = running.take() {
+        info!(
+            "Restarting streamer overlay server on port {}",
+            requested_port
+        );
+        server.stop();
+    }
+
+    let app_data_dir = handle.path().app_data_dir().map_err(|e| {
+        error!("Failed to get app data directory for overlay: {}", e);
+        sentry::capture_message(
+            &format!("App data directory access error (overlay): {}", e),
+            sentry::Level::Error,
+        );
+        e.to_string()
+    })?;
+    info!(
+        "Expecting the streamerOverlay at {:?}",
+        app_data_dir.join(STREAMER_OVERLAY_FILENAME)
+    );
+    // So users find where to put their theme files and templates
+    let templates_dir = app_data_dir
+        .join(OVERLAY_THEME_DIRNAME)
+        .join(TEMPLATES_DIRNAME);
+    if let Err(e) = std::fs::create_dir_all(&templates_dir) {
+        warn!(
+            "Failed to create overlay theme folder {:?}: {}",
+            templates_dir, e
+        );
+    }
+
+    info!(
+        "Starting streamer overlay server on {}",
+        SocketAddr::new(settings.ip(), requested_port)
+    );
+    if settings.lan_address.is_some() {
+        info!("LAN mode is on, other computers need the access token");
+    }
+    let (server, port) =
+        bind(settings.ip(), requested_port, FALLBACK_PORT_ATTEMPTS).map_err(|err| {
+            error!("Couldn't start the streamer overlay server: {}", err);
             sentry::capture_message(
                 &format!(
                     "Overlay server startup error on port {}: {}",
-                    OVERLAY_PORT, err
+                    requested_port, err
                 ),
                 sentry::Level::Error,
             );
-            return;
-        }
-    };
-
+            err
+        })?;
+    if port != requested_port {
+        warn!(
+            "Port {} is taken, streamer overlay server falls back to port {}",
+            requested_port, port
+        );
+    }
     info!(
         "Streamer overlay server started successfully on port {}",
-        OVERLAY_PORT
+        port
     );
 
-    for request in server.incoming_requests() {
-        let file = match File::open(&streamer_overlay_path) {
-            Ok(file) => file,
-            Err(err) => {
-                error!(
-                    "Failed to open overlay file at {:?}: {}",
-                    streamer_overlay_path, err
-                );
-                // Only report to Sentry on first few errors to avoid spam
-                static mut ERROR_COUNT: u32 = 0;
-                unsafe {
-                    if ERROR_COUNT < 3 {
-                        sentry::capture_message(
-                            &format!(
-                                "Overlay file access error: {:?} - {}",
-                                streamer_overlay_path, err
-                            ),
-                            sentry::Level::Warning,
-                        );
-                        ERROR_COUNT += 1;
-                    }
-                }
-                let response = Response::new_empty(StatusCode(404));
-                let _ = request.respond(response);
-                continue;
-            }
-        };
+    let server_handle = handle.clone();
+    let served_dir = app_data_dir.clone();
+    let access = state.access.clone();
+    let mut server = RunningServer::spawn(server, settings, move |request| {
+        let policy = lock_access(&access).clone();
+        handle_request(&server_handle, request, &served_dir, &policy)
+    })
+    .map_err(|err| {
+        error!("Couldn't start the streamer overlay server: {}", err);
+        err
+    })?;
+    let events_handle = handle.clone();
+    match OverlayWatcher::start(&app_data_dir, move || {
+        events_handle
+            .state::<LiveEvents>()
+            .publish(LiveEvent::Reload)
+    }) {
+        Ok(watcher) => server.watch(watcher),
+        // Overlays still work, they just have to be refreshed by hand.
+        Err(e) => warn!("Failed to watch the overlay files: {}", e),
+    }
+
+    let status = status_of(Some(&server), requested_port, &policy);
+    *running = Some(server);
+    Ok(status)
+}
 
-        let response = Response::from_file(file);
-        if let Err(err) = request.respond(response) {
-            error!("Failed to send overlay response: {:?}", err);
+/// Stops the server if it runs and ends the open event streams.
+pub fn stop<R: Runtime>(handle: &AppHandle<R>) {
+    let state = handle.state::<OverlayServerState>();
+    let server = lock_server(&state).take();
+    if let Some(server) = server {
+        info!("Stopping streamer overlay server on port {}", server.port());
+        server.stop();
+        handle.state::<LiveEvents>().disconnect_all();
+    }
+}
+
+/// Like `stop`, but without waiting for the port to be freed - for the app exiting, on
+/// the main thread.
+pub fn shut_down<R: Runtime>(handle: &AppHandle<R>) {
+    let state = handle.state::<OverlayServerState>();
+    let server = lock_server(&state).take();
+    if let Some(server) = server {
+        info!(
+            "Shutting down streamer overlay server on port {}",
+            server.port()
+        );
+        server.close();
+        handle.state::<LiveEvents>().disconnect_all();
+    }
+}
+
+/// Tauri command starting the streamer overlay server. Settings not given are the
+/// configured ones. Async so binding and stopping a running server stay off the main
+/// thread.
+#[tauri::command]
+pub async fn start_overlay_server<R: Runtime>(
+    handle: AppHandle<R>,
+    port: Option<u16>,
+    lan_mode: Option<bool>,
+    bind_address: Option<String>,
+) -> Result<OverlayServerStatus, String> {
+    let settings = listen_settings(&handle, port, lan_mode, bind_address)?;
+    start(&handle, settings)
+}
+
+/// Tauri command stopping the streamer overlay server
+#[tauri::command]
+pub async fn stop_overlay_server<R: Runtime>(handle: AppHandle<R>) -> OverlayServerStatus {
+    stop(&handle);
+    status_of(None, configured_port(&handle), &AccessPolicy::default())
+}
+
+/// Tauri command returning whether the streamer overlay server runs, and where
+#[tauri::command]
+pub fn overlay_server_status<R: Runtime>(handle: AppHandle<R>) -> OverlayServerStatus {
+    let state = handle.state::<OverlayServerState>();
+    let running = lock_server(&state);
+    let policy = lock_access(&state.access).clone();
+    status_of(running.as_ref(), configured_port(&handle), &policy)
+}
+
+/// Tauri command replacing the LAN mode access token. Other computers need the new
+/// URL, their open event streams are closed.
+#[tauri::command]
+pub fn rotate_overlay_token<R: Runtime>(
+    handle: AppHandle<R>,
+) -> Result<OverlayServerStatus, String> {
+    let token = new_token(&handle)?;
+    let state = handle.state::<OverlayServerState>();
+    let running = lock_server(&state);
+    let lan_mode = running
+        .as_ref()
+        .is_some_and(|server| server.settings.lan_address.is_some());
+    let policy = {
+        let mut policy = lock_access(&state.access);
+        if lan_mode {
+            policy.token = Some(token);
         }
+        policy.clone()
+    };
+    if lan_mode {
+        info!("Overlay access token replaced");
+        handle.state::<LiveEvents>().disconnect_all();
     }
+    Ok(status_of(
+        running.as_ref(),
+        configured_port(&handle),
+        &policy,
+    ))
+}
+
+/// Tauri command listing the addresses LAN mode can listen on: all interfaces, and the
+/// one other computers most likely reach this one on.
+#[tauri::command]
+pub fn overlay_lan_addresses() -> Vec<String> {
+    let mut addresses = vec![Ipv4Addr::UNSPECIFIED.to_string()];
+    addresses.extend(primary_lan_address().map(|ip| ip.to_string()));
+    addresses
 }
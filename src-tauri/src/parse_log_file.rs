@@ -25,6 +25,13 @@ pub enum TeamSide {
     Mixed,
 }
 
+/// One of the two teams, as the game lays them out on the loading screen.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TeamPosition {
+    Left,
+    Right,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct PlayerData {
     pub ai: bool,
@@ -52,13 +59,32 @@ pub struct LogFileData {
     pub win_condition: String,
     pub left: TeamData,
     pub right: TeamData,
+    /// Team that won, once the game has reported the result of the match.
+    pub winner: Option<TeamPosition>,
     pub player_name: String,
     pub player_steam_id: String,
     pub player_profile_id: String,
     pub language_code: String,
 }
 
-#[tauri::command]
+impl LogFileData {
+    /// The team the local player is on, `None` when they are not in the lobby (observing
+    /// or replays).
+    pub fn local_team(&self) -> Option<TeamPosition> {
+        let is_local = |p: &PlayerData| {
+            (!self.player_profile_id.is_empty() && p.relic_id == self.player_profile_id)
+                || (!self.player_name.is_empty() && p.name == self.player_name)
+        };
+        if self.left.players.iter().any(is_local) {
+            Some(TeamPosition::Left)
+        } else if self.right.players.iter().any(is_local) {
+            Some(TeamPosition::Right)
+        } else {
+            None
+        }
+    }
+}
+
 pub fn parse_log_file_reverse(path: String) -> LogFileData {
     let mut full_game = false;
     let mut game_running = true;
@@ -69,6 +95,7 @@ pub fn parse_log_file_reverse(path: String) -> LogFileData {
     let mut win_condition = "".to_string();
     let mut timestamp = "".to_string();
     let mut game_duration: u64 = 0;
+    let mut winning_team: Option<u8> = None;
     // Buckets keyed by the log's own team index, not by screen position - the mapping to
     // left/right happens once, at the end of this function.
     let mut team_0: Vec<PlayerData> = Vec::new();
@@ -103,6 +130,7 @@ pub fn parse_log_file_reverse(path: String) -> LogFileData {
                     players: Vec::new(),
                     side: TeamSide::Mixed,
                 },
+                winner: None,
                 player_name: "".to_string(),
                 player_steam_id: "".to_string(),
                 player_profile_id: "".to_string(),
@@ -147,6 +175,14 @@ pub fn parse_log_file_reverse(path: String) -> LogFileData {
                 continue;
             }
 
+            // Is the line with the result of a match - only the latest match's counts
+            if let Ok((_, team)) = get_game_result_winner(tail) {
+                if !full_game && winning_team.is_none() {
+                    winning_team = Some(team);
+                }
+                continue;
+            }
+
             if let Ok((tail, param)) = get_param_line(tail) {
                 if param == "GAME" {
                     if let Ok((tail, sub_param)) = get_game_sub_param(tail) {
@@ -317,6 +353,14 @@ pub fn parse_log_file_reverse(path: String) -> LogFileData {
         win_condition,
         left: left_team,
         right: right_team,
+        // Same team index as the player buckets: 0 is the left team.
+        winner: winning_team.map(|team| {
+            if team == 0 {
+                TeamPosition::Left
+            } else {
+                TeamPosition::Right
+            }
+        }),
         player_name,
         player_steam_id,
         player_profile_id,
@@ -463,6 +507,21 @@ fn get_game_player_profile_id(timestamped_tail: &str) -> nom::IResult<&str, ()>
     Ok((profile_id, ()))
 }
 
+// look for the result the server sends after a match, e.g.:
+// Read bytes [0,"GameResultNotificationMessage",3264,[[[3264,20,203852,1,[0],15990,...
+// Every player entry is [profile id, match type, race, team id, [winning team ids], ...],
+// so the first entry's list is enough - the line is cut off for big matches anyway.
+fn get_game_result_winner(timestamped_tail: &str) -> nom::IResult<&str, u8> {
+    let (tail, _) = nom::bytes::complete::tag("Read bytes [0,\"GameResultNotificationMessage\",")(
+        timestamped_tail,
+    )?;
+    let (tail, _) = nom::bytes::complete::take_until1("[[[")(tail)?;
+    let (tail, _) = nom::bytes::complete::tag("[[[")(tail)?;
+    let (tail, _) = nom::bytes::complete::take_until1("[")(tail)?;
+    let (tail, _) = nom::bytes::complete::tag("[")(tail)?;
+    nom::character::complete::u8(tail)
+}
+
 fn get_map_name(scenario_tail: &str) -> nom::IResult<&str, &str> {
     let (tail, front) = nom::bytes::complete::take_until1("\\")(scenario_tail)?;
     let (tail, _) = nom::bytes::complete::tag("\\")(tail)?;
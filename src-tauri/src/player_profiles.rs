@@ -0,0 +1,226 @@
+//! Relic player profile lookups shared by every window.
+//!
+//! Both the main window and the overlay used to fetch the same lobby's profiles through
+//! `tauri-plugin-http` on their own. They now go through one `ProfileService`, which
+//! caches each profile for `PROFILE_CACHE_TTL_SECS`, lets concurrent lookups of the same
+//! ID share a single request and spaces requests out so the Relic API is not hammered
+//! on every loading screen.
+
+use crate::config::RELIC_API_BASE_URL;
+use crate::net::Net;
+use log::warn;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, Runtime};
+use tokio::sync::OnceCell;
+
+const REQUEST_TIMEOUT_SECS: u64 = 15;
+const PROFILE_CACHE_TTL_SECS: u64 = 5 * 60;
+/// At most ten requests a second.
+const MIN_REQUEST_INTERVAL_MS: u64 = 100;
+/// Pause after a 429 that did not say how long to wait.
+const DEFAULT_RETRY_AFTER_SECS: u64 = 5;
+
+type FetchResult = Result<Value, String>;
+
+struct CachedProfile {
+    data: Value,
+    fetched_at: Instant,
+}
+
+#[derive(Default)]
+struct Inner {
+    cache: HashMap<String, CachedProfile>,
+    /// One cell per ID being fetched right now; later callers wait on the same cell.
+    in_flight: HashMap<String, Arc<OnceCell<FetchResult>>>,
+}
+
+// The ID ends up in the query string.
+fn validate_relic_id(relic_id: &str) -> Result<(), String> {
+    if relic_id.is_empty() || !relic_id.chars().all(|c| c.is_ascii_digit()) {
+        return Err(format!("Invalid relic profile ID: {}", relic_id));
+    }
+    Ok(())
+}
+
+/// Fetches `getpersonalstat` responses by relic profile ID.
+pub struct ProfileService {
+    base_url: String,
+    ttl: Duration,
+    min_interval: Duration,
+    net: Net,
+    inner: Mutex<Inner>,
+    /// Earliest moment the next request may start.
+    next_slot: tokio::sync::Mutex<Instant>,
+}
+
+impl ProfileService {
+    pub fn new(net: Net, base_url: &str) -> Self {
+        Self::with_limits(
+            net,
+            base_url,
+            Duration::from_secs(PROFILE_CACHE_TTL_SECS),
+            Duration::from_millis(MIN_REQUEST_INTERVAL_MS),
+        )
+    }
+
+    pub fn with_limits(net: Net, base_url: &str, ttl: Duration, min_interval: Duration) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            ttl,
+            min_interval,
+            net,
+            inner: Mutex::new(Inner::default()),
+            next_slot: tokio::sync::Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Helper to safely lock the mutex, recovering from poison if needed
+    fn lock_inner(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(|poisoned| {
+            warn!("ProfileService mutex was poisoned, recovering");
+            poisoned.into_inner()
+        })
+    }
+
+    pub fn profile_url(&self, relic_id: &str) -> String {
+        format!(
+            "{}/community/leaderboard/getpersonalstat?profile_ids=[{}]&title=coh3",
+            self.base_url, relic_id
+        )
+    }
+
+    /// The profile of `relic_id`, from the cache while it is fresh.
+    pub async fn get(&self, relic_id: &str) -> FetchResult {
+        validate_relic_id(relic_id)?;
+
+        let cell = {
+            let mut inner = self.lock_inner();
+            if let Some(cached) = inner.cache.get(relic_id) {
+                if cached.fetched_at.elapsed() < self.ttl {
+                    return Ok(cached.data.clone());
+                }
+            }
+            inner
+                .in_flight
+                .entry(relic_id.to_string())
+                .or_default()
+                .clone()
+        };
+
+        let result = cell.get_or_init(|| self.fetch(relic_id)).await.clone();
+
+        let mut inner = self.lock_inner();
+        // Only the first caller to get here finds its own cell still registered.
+        if inner
+            .in_flight
+            .get(relic_id)
+            .is_some_and(|current| Arc::ptr_eq(current, &cell))
+        {
+            inner.in_flight.remove(relic_id);
+            if let Ok(data) = &result {
+                let ttl = self.ttl;
+                inner
+                    .cache
+                    .retain(|_, cached| cached.fetched_at.elapsed() < ttl);
+                inner.cache.insert(
+                    relic_id.to_string(),
+                    CachedProfile {
+                        data: data.clone(),
+                        fetched_at: Instant::now(),
+                    },
+                );
+            }
+        }
+        result
+    }
+
+    /// The profile of `relic_id` as the API has it now, for data that changes while a
+    /// cached copy is still fresh (ratings after a match). Updates the cache.
+    pub async fn refresh(&self, relic_id: &str) -> FetchResult {
+        validate_relic_id(relic_id)?;
+        let result = self.fetch(relic_id).await;
+        if let Ok(data) = &result {
+            self.lock_inner().cache.insert(
+                relic_id.to_string(),
+                CachedProfile {
+                    data: data.clone(),
+                    fetched_at: Instant::now(),
+                },
+            );
+        }
+        result
+    }
+
+    /// Waits for the next free request slot.
+    async fn wait_for_slot(&self) {
+        let mut next_slot = self.next_slot.lock().await;
+        let now = Instant::now();
+        if *next_slot > now {
+            tokio::time::sleep(*next_slot - now).await;
+        }
+        *next_slot = Instant::now().max(*next_slot) + self.min_interval;
+    }
+
+    /// Holds every request back until `delay` from now has passed.
+    async fn back_off(&self, delay: Duration) {
+        let mut next_slot = self.next_slot.lock().await;
+        *next_slot = (*next_slot).max(Instant::now() + delay);
+    }
+
+    async fn fetch(&self, relic_id: &str) -> FetchResult {
+        self.wait_for_slot().await;
+
+        let response = self
+            .net
+            .get(
+                &self.profile_url(relic_id),
+                Duration::from_secs(REQUEST_TIMEOUT_SECS),
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+        if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = response
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.trim().parse::<u64>().ok())
+                .unwrap_or(DEFAULT_RETRY_AFTER_SECS);
+            warn!(
+                "Relic API rate limited profile lookups, pausing for {}s",
+                retry_after
+            );
+            self.back_off(Duration::from_secs(retry_after)).await;
+            return Err("Rate limited by the Relic API".to_string());
+        }
+
+        let response = response.error_for_status().map_err(|e| e.to_string())?;
+        response.json::<Value>().await.map_err(|e| e.to_string())
+    }
+}
+
+/// State for the shared profile service
+pub struct PlayerProfilesState {
+    pub service: ProfileService,
+}
+
+impl PlayerProfilesState {
+    pub fn new(net: Net) -> Self {
+        Self {
+            service: ProfileService::new(net, RELIC_API_BASE_URL),
+        }
+    }
+}
+
+/// Tauri command returning the `getpersonalstat` response of one player
+#[tauri::command]
+pub async fn get_player_profile<R: Runtime>(
+    handle: AppHandle<R>,
+    relic_id: String,
+) -> Result<Value, String> {
+    let state = handle.state::<PlayerProfilesState>();
+    state.service.get(&relic_id).await
+}
@@ -8,7 +8,9 @@
 //! - Foreground window state
 //! - Audio mute/unmute based on settings
 
-use log::{debug, error, info};
+use log::debug;
+#[cfg(target_os = "windows")]
+use log::{error, info};
 use std::sync::{Arc, Mutex};
 use std::thread::{self, JoinHandle};
 use tauri::{AppHandle, Manager, Runtime};
@@ -85,6 +87,40 @@ pub fn find_game_process_id() -> Option<u32> {
     }
 }
 
+/// Whether a process is the game, from its `comm` and the first word of its command
+/// line. Under Proton `comm` is the Windows executable's name, cut to 15 characters,
+/// and the command line starts with its Windows path.
+// Only Linux looks processes up this way; the tests cover it everywhere.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+pub fn is_game_process(comm: &str, argv0: &str) -> bool {
+    let exe = argv0.rsplit(['\\', '/']).next().unwrap_or(argv0);
+    comm.trim().eq_ignore_ascii_case(GAME_PROCESS_NAME)
+        || exe.eq_ignore_ascii_case(GAME_PROCESS_NAME)
+}
+
+/// Find the game process ID by searching `/proc` for the game running under Proton
+#[cfg(target_os = "linux")]
+pub fn find_game_process_id() -> Option<u32> {
+    let entries = std::fs::read_dir("/proc").ok()?;
+    for entry in entries.flatten() {
+        let Some(pid) = entry
+            .file_name()
+            .to_str()
+            .and_then(|name| name.parse().ok())
+        else {
+            continue;
+        };
+        let comm = std::fs::read_to_string(entry.path().join("comm")).unwrap_or_default();
+        let cmdline = std::fs::read(entry.path().join("cmdline")).unwrap_or_default();
+        let argv0 = cmdline.split(|byte| *byte == 0).next().unwrap_or_default();
+        if is_game_process(&comm, &String::from_utf8_lossy(argv0)) {
+            debug!("Found game process: {} (PID: {})", comm.trim(), pid);
+            return Some(pid);
+        }
+    }
+    None
+}
+
 /// Start watching for game process events and audio monitoring
 #[cfg(target_os = "windows")]
 pub fn start_watching<R: Runtime>(handle: AppHandle<R>) -> Result<(), String> {
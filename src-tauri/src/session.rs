@@ -0,0 +1,563 @@
+//! The streamer session scoreboard: "today: 7W-3L, +64 ELO".
+//!
+//! Every match the local player finishes while the app runs is recorded with its mode,
+//! faction and result. For automatch games the rating on that leaderboard is looked up
+//! when the lobby loads and again once the Relic API has counted the match, which gives
+//! the rating change.
+//!
+//! The session is kept in `session.json` in the app data directory, so restarting the
+//! app mid-stream keeps the numbers. A session whose last match is older than the
+//! window (`sessionWindowHours`, `SESSION_WINDOW_HOURS` by default) is over, the next
+//! look at it starts a new one. `reset_session` starts one right away.
+//!
+//! Only results that come in while the app watches are recorded. The log still holds
+//! the result of the last match when the app starts, and it must not be counted again.
+
+use crate::config::{SESSION_FILENAME, SESSION_WINDOW_HOURS};
+use crate::dp_utils::load_from_store;
+use crate::game_overlay;
+use crate::live_events::{LiveEvent, LiveEvents};
+use crate::parse_log_file::{GameType, LogFileData, PlayerData, TeamPosition};
+use crate::player_profiles::PlayerProfilesState;
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager, Runtime};
+
+/// Store key of the window in hours.
+const WINDOW_STORE_KEY: &str = "sessionWindowHours";
+
+/// The Relic API counts a match a little while after it ended.
+const RATING_POLL_INTERVAL: Duration = Duration::from_secs(20);
+const RATING_POLL_ATTEMPTS: u32 = 9;
+
+/// Automatch leaderboard IDs by mode and faction, as in `leaderboardsIDAsObject` on the
+/// frontend.
+const LEADERBOARDS: [(&str, [(&str, u64); 4]); 4] = [
+    (
+        "1v1",
+        [
+            ("american", 2130255),
+            ("british", 2130257),
+            ("dak", 2130259),
+            ("german", 2130261),
+        ],
+    ),
+    (
+        "2v2",
+        [
+            ("american", 2130300),
+            ("british", 2130302),
+            ("dak", 2130304),
+            ("german", 2130306),
+        ],
+    ),
+    (
+        "3v3",
+        [
+            ("american", 2130329),
+            ("british", 2130331),
+            ("dak", 2130333),
+            ("german", 2130335),
+        ],
+    ),
+    (
+        "4v4",
+        [
+            ("american", 2130353),
+            ("british", 2130356),
+            ("dak", 2130358),
+            ("german", 2130360),
+        ],
+    ),
+];
+
+/// One finished match of the local player.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SessionMatch {
+    /// Identifies the match, so it is never recorded twice.
+    pub key: String,
+    /// Unix seconds of when the result came in.
+    pub finished_at: u64,
+    /// `1v1` .. `4v4` for automatch games, `ai` or `custom` otherwise.
+    pub mode: String,
+    /// `american`, `british`, `dak` or `german`.
+    pub faction: String,
+    pub map: String,
+    pub won: bool,
+    /// Rating on the mode's leaderboard when the lobby loaded.
+    pub rating_before: Option<i64>,
+    /// Rating once the Relic API has counted the match.
+    pub rating_after: Option<i64>,
+}
+
+impl SessionMatch {
+    /// `None` until both ratings are known.
+    pub fn rating_change(&self) -> Option<i64> {
+        Some(self.rating_after? - self.rating_before?)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Session {
+    /// Unix seconds of the start, or of the last reset.
+    pub started_at: u64,
+    pub matches: Vec<SessionMatch>,
+}
+
+impl Session {
+    pub fn new(now: u64) -> Self {
+        Self {
+            started_at: now,
+            matches: Vec::new(),
+        }
+    }
+
+    /// Unix seconds of the last match, or the start when there was none.
+    pub fn last_activity(&self) -> u64 {
+        self.matches
+            .iter()
+            .map(|m| m.finished_at)
+            .max()
+            .unwrap_or(self.started_at)
+            .max(self.started_at)
+    }
+
+    /// Whether nothing happened for longer than `window`.
+    pub fn is_expired(&self, now: u64, window: Duration) -> bool {
+        now.saturating_sub(self.last_activity()) > window.as_secs()
+    }
+
+    /// Adds `finished` unless a match with the same key is in already.
+    pub fn record(&mut self, finished: SessionMatch) -> bool {
+        if self.matches.iter().any(|m| m.key == finished.key) {
+            return false;
+        }
+        self.matches.push(finished);
+        true
+    }
+
+    /// Sets the rating after the match `key`. Returns whether anything changed.
+    pub fn set_rating_after(&mut self, key: &str, rating: i64) -> bool {
+        match self.matches.iter_mut().find(|m| m.key == key) {
+            Some(m) if m.rating_after != Some(rating) => {
+                m.rating_after = Some(rating);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct Record {
+    pub wins: u32,
+    pub losses: u32,
+    /// Sum of the rating changes known so far.
+    pub rating_change: i64,
+}
+
+impl Record {
+    fn add(&mut self, m: &SessionMatch) {
+        if m.won {
+            self.wins += 1;
+        } else {
+            self.losses += 1;
+        }
+        self.rating_change += m.rating_change().unwrap_or(0);
+    }
+}
+
+/// What overlays show: the session's totals and their break down by mode and faction.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Scoreboard {
+    pub started_at: u64,
+    pub total: Record,
+    pub by_mode: BTreeMap<String, Record>,
+    pub by_faction: BTreeMap<String, Record>,
+    /// Oldest first.
+    pub matches: Vec<SessionMatch>,
+}
+
+pub fn scoreboard(session: &Session) -> Scoreboard {
+    let mut total = Record::default();
+    let mut by_mode: BTreeMap<String, Record> = BTreeMap::new();
+    let mut by_faction: BTreeMap<String, Record> = BTreeMap::new();
+    for m in &session.matches {
+        total.add(m);
+        by_mode.entry(m.mode.clone()).or_default().add(m);
+        by_faction.entry(m.faction.clone()).or_default().add(m);
+    }
+    Scoreboard {
+        started_at: session.started_at,
+        total,
+        by_mode,
+        by_faction,
+        matches: session.matches.clone(),
+    }
+}
+
+/// Faction as the leaderboards name it, from its name in the log.
+pub fn normalize_faction(log_faction: &str) -> String {
+    let faction = log_faction.trim_end_matches("_campaign");
+    match faction {
+        "afrika_korps" => "dak",
+        "americans" => "american",
+        "british_africa" | "british" => "british",
+        "germans" => "german",
+        other => other,
+    }
+    .to_string()
+}
+
+/// `1v1` .. `4v4` for automatch games, `ai` or `custom` otherwise.
+pub fn mode_of(game: &LogFileData) -> String {
+    match game.game_type {
+        GameType::Classic => {
+            let size = game.left.players.len().max(game.right.players.len());
+            format!("{}v{}", size, size)
+        }
+        GameType::AI => "ai".to_string(),
+        GameType::Custom => "custom".to_string(),
+    }
+}
+
+pub fn leaderboard_id(mode: &str, faction: &str) -> Option<u64> {
+    let (_, factions) = LEADERBOARDS.iter().find(|(m, _)| *m == mode)?;
+    factions
+        .iter()
+        .find(|(f, _)| *f == faction)
+        .map(|(_, id)| *id)
+}
+
+/// Identifies a match: the same lobby parsed again has the same key.
+pub fn match_key(game: &LogFileData) -> String {
+    let players: Vec<&str> = game
+        .left
+        .players
+        .iter()
+        .chain(game.right.players.iter())
+        .map(|p| p.relic_id.as_str())
+        .collect();
+    format!("{}|{}|{}", game.timestamp, game.map, players.join(","))
+}
+
+/// The local player's entry in the lobby.
+pub fn local_player(game: &LogFileData) -> Option<&PlayerData> {
+    let team = match game.local_team()? {
+        TeamPosition::Left => &game.left,
+        TeamPosition::Right => &game.right,
+    };
+    team.players.iter().find(|p| {
+        (!game.player_profile_id.is_empty() && p.relic_id == game.player_profile_id)
+            || (!game.player_name.is_empty() && p.name == game.player_name)
+    })
+}
+
+/// A player's place on one leaderboard.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Standing {
+    /// `None` before the first game on the leaderboard.
+    pub rating: Option<i64>,
+    /// Games counted so far.
+    pub games: u64,
+    /// `None` while unranked.
+    pub rank: Option<i64>,
+}
+
+/// The standing on `leaderboard_id` in a `getpersonalstat` response. `None` when the
+/// response is not a successful one.
+pub fn standing(profile: &Value, leaderboard_id: u64) -> Option<Standing> {
+    let stats = profile.get("leaderboardStats")?.as_array()?;
+    let entry = stats
+        .iter()
+        .find(|s| s["leaderboard_id"].as_u64() == Some(leaderboard_id));
+    Some(match entry {
+        Some(entry) => Standing {
+            rating: entry["rating"].as_i64(),
+            games: entry["wins"].as_u64().unwrap_or(0) + entry["losses"].as_u64().unwrap_or(0),
+            rank: entry["rank"].as_i64().filter(|rank| *rank > 0),
+        },
+        None => Standing {
+            rating: None,
+            games: 0,
+            rank: None,
+        },
+    })
+}
+
+/// The match `next` finished, if its result came in since `prev`. Results already in the
+/// log when the app started are not counted: the lobby has to be seen without a result
+/// first.
+pub fn finished_match(
+    prev: Option<&LogFileData>,
+    next: &LogFileData,
+    now: u64,
+) -> Option<SessionMatch> {
+    let prev = prev?;
+    let winner = next.winner?;
+    if prev.winner.is_some() || match_key(prev) != match_key(next) {
+        return None;
+    }
+    let player = local_player(next)?;
+    Some(SessionMatch {
+        key: match_key(next),
+        finished_at: now,
+        mode: mode_of(next),
+        faction: normalize_faction(&player.faction),
+        map: next.map.clone(),
+        won: next.local_team() == Some(winner),
+        rating_before: None,
+        rating_after: None,
+    })
+}
+
+/// Reads the session in `path`, or starts a new one when there is none or it is over.
+pub fn load_session(path: &Path, now: u64, window: Duration) -> Session {
+    let stored = fs::read_to_string(path).ok().and_then(|content| {
+        match serde_json::from_str::<Session>(&content) {
+            Ok(session) => Some(session),
+            Err(e) => {
+                warn!("Ignoring unreadable session file {:?}: {}", path, e);
+                None
+            }
+        }
+    });
+    match stored {
+        Some(session) if !session.is_expired(now, window) => session,
+        _ => Session::new(now),
+    }
+}
+
+pub fn save_session(path: &Path, session: &Session) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let json_string = serde_json::to_string(session)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    fs::write(path, json_string)
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// The lobby being played, with the standing from before it.
+struct Pending {
+    key: String,
+    relic_id: String,
+    leaderboard_id: u64,
+    baseline: Option<Standing>,
+}
+
+/// State for the session tracker
+#[derive(Default)]
+pub struct SessionState {
+    /// Loaded from disk on first use.
+    session: Mutex<Option<Session>>,
+    pending: Mutex<Option<Pending>>,
+}
+
+fn lock_session(state: &SessionState) -> std::sync::MutexGuard<'_, Option<Session>> {
+    state.session.lock().unwrap_or_else(|poisoned| {
+        warn!("SessionState mutex was poisoned, recovering");
+        poisoned.into_inner()
+    })
+}
+
+fn lock_pending(state: &SessionState) -> std::sync::MutexGuard<'_, Option<Pending>> {
+    state.pending.lock().unwrap_or_else(|poisoned| {
+        warn!("SessionState pending mutex was poisoned, recovering");
+        poisoned.into_inner()
+    })
+}
+
+/// Gets the session file path
+pub fn get_session_path<R: Runtime>(handle: &AppHandle<R>) -> Option<PathBuf> {
+    handle.path().app_data_dir().ok().map(|mut p| {
+        p.push(SESSION_FILENAME);
+        p
+    })
+}
+
+fn window<R: Runtime>(handle: &AppHandle<R>) -> Duration {
+    let hours =
+        load_from_store::<R, u64>(handle.clone(), WINDOW_STORE_KEY).unwrap_or(SESSION_WINDOW_HOURS);
+    Duration::from_secs(hours * 60 * 60)
+}
+
+fn save<R: Runtime>(handle: &AppHandle<R>, session: &Session) {
+    let Some(path) = get_session_path(handle) else {
+        error!("Could not determine app data directory for the session");
+        return;
+    };
+    if let Err(e) = save_session(&path, session) {
+        error!("Failed to save the session: {}", e);
+        sentry::capture_message(
+            &format!("Session save error: {}", e),
+            sentry::Level::Warning,
+        );
+    }
+}
+
+/// Runs `f` on the current session, loading it first or starting a new one when it is
+/// over. With `changed` the session is saved and published afterwards.
+fn with_session<R: Runtime, T>(
+    handle: &AppHandle<R>,
+    f: impl FnOnce(&mut Session) -> (T, bool),
+) -> T {
+    let now = unix_now();
+    let window = window(handle);
+    let state = handle.state::<SessionState>();
+    let mut guard = lock_session(&state);
+    let session = match guard.as_mut() {
+        Some(session) if !session.is_expired(now, window) => session,
+        _ => {
+            let session = match get_session_path(handle) {
+                Some(path) => load_session(&path, now, window),
+                None => Session::new(now),
+            };
+            guard.insert(session)
+        }
+    };
+
+    let (result, changed) = f(session);
+    if changed {
+        save(handle, session);
+        handle
+            .state::<LiveEvents>()
+            .publish(LiveEvent::Session(scoreboard(session)));
+    }
+    result
+}
+
+/// The current scoreboard.
+pub fn current<R: Runtime>(handle: &AppHandle<R>) -> Scoreboard {
+    with_session(handle, |session| (scoreboard(session), false))
+}
+
+/// Follows the local player's matches from one parse of the log to the next.
+pub fn observe<R: Runtime>(handle: &AppHandle<R>, prev: Option<&LogFileData>, next: &LogFileData) {
+    let state = handle.state::<SessionState>();
+
+    if let Some(mut finished) = finished_match(prev, next, unix_now()) {
+        let pending = lock_pending(&state)
+            .take()
+            .filter(|pending| pending.key == finished.key);
+        finished.rating_before = pending.as_ref().and_then(|p| p.baseline?.rating);
+        info!(
+            "Session: {} {} as {}",
+            if finished.won { "won" } else { "lost" },
+            finished.mode,
+            finished.faction
+        );
+        let key = finished.key.clone();
+        if with_session(handle, |session| {
+            let recorded = session.record(finished);
+            (recorded, recorded)
+        }) {
+            if let Some(pending) = pending {
+                watch_rating(handle.clone(), key, pending);
+            }
+        }
+        return;
+    }
+
+    // A new automatch lobby: note the rating it starts from.
+    let key = match_key(next);
+    if next.winner.is_some() || prev.is_some_and(|p| match_key(p) == key) {
+        return;
+    }
+    let Some(player) = local_player(next) else {
+        return;
+    };
+    let Some(leaderboard_id) = leaderboard_id(&mode_of(next), &normalize_faction(&player.faction))
+    else {
+        return;
+    };
+    *lock_pending(&state) = Some(Pending {
+        key: key.clone(),
+        relic_id: player.relic_id.clone(),
+        leaderboard_id,
+        baseline: None,
+    });
+
+    let handle = handle.clone();
+    let relic_id = player.relic_id.clone();
+    tauri::async_runtime::spawn(async move {
+        let profiles = handle.state::<PlayerProfilesState>();
+        let baseline = match profiles.service.refresh(&relic_id).await {
+            Ok(profile) => standing(&profile, leaderboard_id),
+            Err(e) => {
+                warn!("Session: no rating before the match: {}", e);
+                None
+            }
+        };
+        let state = handle.state::<SessionState>();
+        let mut pending = lock_pending(&state);
+        if let Some(pending) = pending.as_mut().filter(|p| p.key == key) {
+            pending.baseline = baseline;
+        }
+    });
+}
+
+/// Looks the rating up until the Relic API has counted the match `key`.
+fn watch_rating<R: Runtime>(handle: AppHandle<R>, key: String, pending: Pending) {
+    let Some(baseline) = pending.baseline else {
+        return;
+    };
+    tauri::async_runtime::spawn(async move {
+        for _ in 0..RATING_POLL_ATTEMPTS {
+            tokio::time::sleep(RATING_POLL_INTERVAL).await;
+            let profiles = handle.state::<PlayerProfilesState>();
+            let after = match profiles.service.refresh(&pending.relic_id).await {
+                Ok(profile) => standing(&profile, pending.leaderboard_id),
+                Err(e) => {
+                    warn!("Session: rating lookup failed: {}", e);
+                    continue;
+                }
+            };
+            if let Some(Standing {
+                rating: Some(rating),
+                games,
+                ..
+            }) = after
+            {
+                if games > baseline.games {
+                    let changed = with_session(&handle, |session| {
+                        let changed = session.set_rating_after(&key, rating);
+                        (changed, changed)
+                    });
+                    if changed {
+                        game_overlay::session_changed(&handle);
+                    }
+                    return;
+                }
+            }
+        }
+        info!("Session: the rating after the match did not come in");
+    });
+}
+
+/// Tauri command returning the session scoreboard
+#[tauri::command]
+pub fn get_session_scoreboard<R: Runtime>(handle: AppHandle<R>) -> Scoreboard {
+    current(&handle)
+}
+
+/// Tauri command starting a new session
+#[tauri::command]
+pub fn reset_session<R: Runtime>(handle: AppHandle<R>) -> Scoreboard {
+    with_session(&handle, |session| {
+        *session = Session::new(unix_now());
+        (scoreboard(session), true)
+    })
+}
@@ -1,5 +1,43 @@
 // Test modules
 mod test_replay_parser;
 mod tests_game_overlay;
+mod tests_game_overlay_hotkey;
+mod tests_game_overlay_interactive;
+mod tests_game_overlay_modes;
+mod tests_game_overlay_payload;
+mod tests_game_overlay_watcher;
+mod tests_game_overlay_x11;
 mod tests_lib;
+mod tests_live_events;
+mod tests_map_stats_history;
+mod tests_matchup_card;
+mod tests_net;
+mod tests_obs_websocket;
+mod tests_overlay_access;
+mod tests_overlay_assets;
+mod tests_overlay_http;
+mod tests_overlay_reload;
+mod tests_overlay_server;
 mod tests_parser;
+mod tests_player_profiles;
+mod tests_session;
+mod tests_twitch_chat;
+mod tests_win_probability;
+
+use std::fs;
+use std::path::PathBuf;
+
+/// Creates a uniquely-named temp directory under the OS temp folder.
+/// The `label` makes the name human-readable when debugging failures.
+pub(crate) fn make_temp_dir(label: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "coh3_test_{}_{}",
+        label,
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .subsec_nanos()
+    ));
+    fs::create_dir_all(&dir).expect("Failed to create temp test directory");
+    dir
+}
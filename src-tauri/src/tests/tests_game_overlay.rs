@@ -1,9 +1,12 @@
-//! Tests for the in-game overlay geometry and edition gating.
+//! Tests for the in-game overlay geometry, layout presets and edition gating.
 //!
 //! Pure maths and config reading - no Win32 calls - so these run on every platform
 //! including CI.
 
-use crate::game_overlay::geometry::{centre_in, overlay_rect, overlay_size, scale_for_dpi, Bounds};
+use crate::game_overlay::geometry::{
+    centre_in, display_mode, overlay_rect, overlay_size, safe_area, scale_for_dpi, Anchor, Bounds,
+    DisplayMode, LayoutPreset, WindowStyle,
+};
 use crate::game_overlay::is_ms_store_edition;
 
 /// The overlay is a Microsoft Store only feature: the window must not be created in any
@@ -104,7 +107,7 @@ fn scales_lengths_for_dpi() {
 
 #[test]
 fn sizes_overlay_as_a_fraction_of_the_game_window() {
-    let (w, h) = overlay_size(Bounds::new(0, 0, 1920, 1080), 96);
+    let (w, h) = overlay_size(Bounds::new(0, 0, 1920, 1080), 96, &LayoutPreset::default());
     assert_eq!(w, 1728); // 90%
     assert_eq!(h, 518); // 48%
 }
@@ -112,16 +115,16 @@ fn sizes_overlay_as_a_fraction_of_the_game_window() {
 #[test]
 fn clamps_overlay_on_very_wide_and_very_small_windows() {
     // 5120 * 0.90 = 4608 -> clamped to the 2880 max
-    let (w, _) = overlay_size(Bounds::new(0, 0, 5120, 1440), 96);
+    let (w, _) = overlay_size(Bounds::new(0, 0, 5120, 1440), 96, &LayoutPreset::default());
     assert_eq!(w, 2880);
 
     // 800 * 0.90 = 720 -> raised to the 840 min, but never wider than the game window
-    let (w, h) = overlay_size(Bounds::new(0, 0, 800, 600), 96);
+    let (w, h) = overlay_size(Bounds::new(0, 0, 800, 600), 96, &LayoutPreset::default());
     assert_eq!(w, 800);
     assert_eq!(h, 312); // 288 raised to the min height
 
     // Tiny windowed game: overlay may not exceed the window itself
-    let (w, h) = overlay_size(Bounds::new(0, 0, 400, 200), 96);
+    let (w, h) = overlay_size(Bounds::new(0, 0, 400, 200), 96, &LayoutPreset::default());
     assert_eq!(w, 400);
     assert_eq!(h, 200);
 }
@@ -130,7 +133,7 @@ fn clamps_overlay_on_very_wide_and_very_small_windows() {
 fn clamps_are_dpi_scaled() {
     // At 150% the minimum width grows with the UI, so a 800px-wide game window
     // is fully covered rather than getting a narrow strip.
-    let (w, _) = overlay_size(Bounds::new(0, 0, 800, 600), 144);
+    let (w, _) = overlay_size(Bounds::new(0, 0, 800, 600), 144, &LayoutPreset::default());
     assert_eq!(w, 800); // min 1260 capped to the game width
 }
 
@@ -146,14 +149,14 @@ fn centres_inside_the_game_window() {
 
 #[test]
 fn starts_at_the_vertical_middle_of_the_game_window() {
-    let rect = overlay_rect(Bounds::new(0, 0, 1920, 1080), 96);
+    let rect = overlay_rect(Bounds::new(0, 0, 1920, 1080), 96, &LayoutPreset::default());
     assert_eq!(rect.y, 540); // top edge on the halfway line, not centred
     assert_eq!(rect.x, 96); // still horizontally centred
 
     // A short window where half + height would overflow gets pushed back up so the
     // overlay still fits: 400 * 0.48 = 192 raised to the 312 min, half is 200.
     let game = Bounds::new(0, 0, 1920, 400);
-    let rect = overlay_rect(game, 96);
+    let rect = overlay_rect(game, 96, &LayoutPreset::default());
     assert_eq!(rect.y, 88);
     assert_eq!(rect.y + rect.height, game.height);
 }
@@ -163,11 +166,22 @@ fn centres_on_a_secondary_monitor_with_a_negative_origin() {
     // Monitor to the left of the primary: the game window origin is negative and the
     // overlay must follow it instead of landing on the primary screen.
     let game = Bounds::new(-1920, -200, 1920, 1080);
-    let rect = overlay_rect(game, 96);
+    let rect = overlay_rect(game, 96, &LayoutPreset::default());
     // Horizontally centred, and starting at the vertical middle (-200 + 540).
     assert_eq!(rect, Bounds::new(-1824, 340, 1728, 518));
 }
 
+#[test]
+fn the_pointer_is_inside_up_to_but_not_on_the_far_edges() {
+    let rect = Bounds::new(-100, 50, 200, 100);
+    assert!(rect.contains(-100, 50));
+    assert!(rect.contains(0, 100));
+    assert!(rect.contains(99, 149));
+    assert!(!rect.contains(100, 100));
+    assert!(!rect.contains(0, 150));
+    assert!(!rect.contains(-101, 100));
+}
+
 #[test]
 fn overlay_never_spills_outside_the_game_window() {
     for game in [
@@ -178,7 +192,7 @@ fn overlay_never_spills_outside_the_game_window() {
         Bounds::new(0, 0, 3840, 2160),
     ] {
         for dpi in [96, 120, 144, 192] {
-            let rect = overlay_rect(game, dpi);
+            let rect = overlay_rect(game, dpi, &LayoutPreset::default());
             assert!(rect.width > 0 && rect.height > 0, "{:?} @ {}", rect, dpi);
             assert!(rect.x >= game.x, "{:?} @ {} left of game", rect, dpi);
             assert!(rect.y >= game.y, "{:?} @ {} above game", rect, dpi);
@@ -197,3 +211,326 @@ fn overlay_never_spills_outside_the_game_window() {
         }
     }
 }
+
+fn preset(anchor: Anchor, width_ratio: f64, height_ratio: f64, margin: i32) -> LayoutPreset {
+    LayoutPreset {
+        anchor,
+        width_ratio,
+        height_ratio,
+        margin_x: margin,
+        margin_y: margin,
+        opacity: 1.0,
+    }
+}
+
+#[test]
+fn the_default_preset_is_the_original_layout() {
+    let game = Bounds::new(0, 0, 1920, 1080);
+    assert_eq!(
+        overlay_rect(game, 96, &LayoutPreset::default()),
+        Bounds::new(96, 540, 1728, 518)
+    );
+    // What an older store without some of the fields deserialises to
+    let stored: LayoutPreset = serde_json::from_str(r#"{"anchor": "centre"}"#).unwrap();
+    assert_eq!(stored, LayoutPreset::default());
+}
+
+#[test]
+fn every_anchor_places_the_overlay_against_its_edge() {
+    let game = Bounds::new(0, 0, 1920, 1080);
+    // 60% x 20% with a 20px margin: 1152 x 216
+    let cases = [
+        (Anchor::Centre, 384, 540),
+        (Anchor::Top, 384, 20),
+        (Anchor::Bottom, 384, 844),
+        (Anchor::TopLeft, 20, 20),
+        (Anchor::TopRight, 748, 20),
+        (Anchor::BottomLeft, 20, 844),
+        (Anchor::BottomRight, 748, 844),
+    ];
+    for (anchor, x, y) in cases {
+        assert_eq!(
+            overlay_rect(game, 96, &preset(anchor, 0.6, 0.2, 20)),
+            Bounds::new(x, y, 1152, 216),
+            "{:?}",
+            anchor
+        );
+    }
+}
+
+#[test]
+fn margins_are_dpi_scaled_and_follow_the_game_window() {
+    // 150% on a monitor left of the primary: the 20px margin becomes 30px.
+    let game = Bounds::new(-2560, 100, 2560, 1440);
+    let cases = [
+        (Anchor::TopLeft, -2530, 130),
+        (Anchor::TopRight, -30 - 1024, 130),
+        (Anchor::BottomLeft, -2530, 100 + 1440 - 30 - 288),
+        (Anchor::BottomRight, -30 - 1024, 100 + 1440 - 30 - 288),
+    ];
+    for (anchor, x, y) in cases {
+        let rect = overlay_rect(game, 144, &preset(anchor, 0.4, 0.2, 20));
+        assert_eq!((rect.x, rect.y), (x, y), "{:?}", anchor);
+        assert_eq!((rect.width, rect.height), (1024, 288), "{:?}", anchor);
+    }
+}
+
+#[test]
+fn a_narrow_strip_is_not_raised_to_the_default_minimum() {
+    // 720 * 0.12 = 86, well under the default 312 minimum height. The minimum
+    // shrinks with the ratio to 312 * 0.12 / 0.48 = 78, so the strip stays a strip.
+    let (_, h) = overlay_size(
+        Bounds::new(0, 0, 1280, 720),
+        96,
+        &preset(Anchor::Top, 0.9, 0.12, 0),
+    );
+    assert_eq!(h, 86);
+    // Below the scaled minimum it is still raised: 400 * 0.12 = 48 -> 78
+    let (_, h) = overlay_size(
+        Bounds::new(0, 0, 800, 400),
+        96,
+        &preset(Anchor::Top, 0.9, 0.12, 0),
+    );
+    assert_eq!(h, 78);
+}
+
+#[test]
+fn no_anchor_spills_outside_the_game_window() {
+    let anchors = [
+        Anchor::Centre,
+        Anchor::Top,
+        Anchor::Bottom,
+        Anchor::TopLeft,
+        Anchor::TopRight,
+        Anchor::BottomLeft,
+        Anchor::BottomRight,
+    ];
+    let games = [
+        Bounds::new(0, 0, 1280, 720),
+        Bounds::new(0, 0, 1920, 1080),
+        Bounds::new(0, 0, 3440, 1440),
+        Bounds::new(-2560, 0, 2560, 1440),
+        Bounds::new(100, -300, 3840, 2160),
+        Bounds::new(0, 0, 500, 300),
+    ];
+    for anchor in anchors {
+        for (width_ratio, height_ratio, margin) in
+            [(0.9, 0.48, 0), (0.3, 0.15, 40), (1.0, 1.0, 960)]
+        {
+            let layout = preset(anchor, width_ratio, height_ratio, margin);
+            for game in games {
+                for dpi in [96, 120, 144, 192] {
+                    let rect = overlay_rect(game, dpi, &layout);
+                    let case = format!("{:?} in {:?} @ {}: {:?}", layout, game, dpi, rect);
+                    assert!(rect.width > 0 && rect.height > 0, "{}", case);
+                    assert!(rect.x >= game.x && rect.y >= game.y, "{}", case);
+                    assert!(rect.x + rect.width <= game.x + game.width, "{}", case);
+                    assert!(rect.y + rect.height <= game.y + game.height, "{}", case);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn stored_presets_are_brought_into_range() {
+    let wild = LayoutPreset {
+        anchor: Anchor::BottomRight,
+        width_ratio: 3.0,
+        height_ratio: f64::NAN,
+        margin_x: -50,
+        margin_y: 100_000,
+        opacity: 0.0,
+    }
+    .sanitised();
+    assert_eq!(
+        wild,
+        LayoutPreset {
+            anchor: Anchor::BottomRight,
+            width_ratio: 1.0,
+            height_ratio: 0.48,
+            margin_x: 0,
+            margin_y: 960,
+            opacity: 0.2,
+        }
+    );
+    let serialised = serde_json::to_value(LayoutPreset::default()).unwrap();
+    assert_eq!(serialised["anchor"], "centre");
+    assert_eq!(
+        serde_json::from_value::<Anchor>(serde_json::json!("bottom_left")).unwrap(),
+        Anchor::BottomLeft
+    );
+}
+
+#[test]
+fn safe_area_keeps_the_middle_of_wide_and_tall_windows() {
+    let cases = [
+        // 16:9 and 16:10 use the whole window
+        (Bounds::new(0, 0, 1920, 1080), Bounds::new(0, 0, 1920, 1080)),
+        (Bounds::new(0, 0, 1920, 1200), Bounds::new(0, 0, 1920, 1200)),
+        (Bounds::new(0, 0, 3840, 2160), Bounds::new(0, 0, 3840, 2160)),
+        // Ultrawide and super-ultrawide: the centred 16:9 area
+        (
+            Bounds::new(0, 0, 2560, 1080),
+            Bounds::new(320, 0, 1920, 1080),
+        ),
+        (
+            Bounds::new(0, 0, 3440, 1440),
+            Bounds::new(440, 0, 2560, 1440),
+        ),
+        (
+            Bounds::new(0, 0, 5120, 1440),
+            Bounds::new(1280, 0, 2560, 1440),
+        ),
+        // 4:3 is still the whole window, 5:4 and portrait get the centred 4:3 band
+        (Bounds::new(0, 0, 1600, 1200), Bounds::new(0, 0, 1600, 1200)),
+        (Bounds::new(0, 0, 1280, 1024), Bounds::new(0, 32, 1280, 960)),
+        (
+            Bounds::new(0, 0, 1080, 1920),
+            Bounds::new(0, 555, 1080, 810),
+        ),
+        // Follows the window onto other monitors
+        (
+            Bounds::new(-5120, -200, 5120, 1440),
+            Bounds::new(-3840, -200, 2560, 1440),
+        ),
+    ];
+    for (game, area) in cases {
+        assert_eq!(safe_area(game), area, "{:?}", game);
+    }
+}
+
+#[test]
+fn places_the_overlay_in_the_safe_area_on_common_resolutions() {
+    let cases = [
+        // Ultrawide: 90% of the 16:9 area, not of the whole width
+        (
+            Bounds::new(0, 0, 2560, 1080),
+            96,
+            Bounds::new(416, 540, 1728, 518),
+        ),
+        (
+            Bounds::new(0, 0, 3440, 1440),
+            96,
+            Bounds::new(568, 720, 2304, 691),
+        ),
+        (
+            Bounds::new(0, 0, 3440, 1440),
+            144,
+            Bounds::new(568, 720, 2304, 691),
+        ),
+        (
+            Bounds::new(0, 0, 5120, 1440),
+            96,
+            Bounds::new(1408, 720, 2304, 691),
+        ),
+        // 4K: the max width clamp still applies, until the DPI raises it
+        (
+            Bounds::new(0, 0, 3840, 2160),
+            96,
+            Bounds::new(480, 1080, 2880, 1037),
+        ),
+        (
+            Bounds::new(0, 0, 3840, 2160),
+            144,
+            Bounds::new(192, 1080, 3456, 1037),
+        ),
+        // 5:4 and portrait: placed against the 4:3 band
+        (
+            Bounds::new(0, 0, 1280, 1024),
+            96,
+            Bounds::new(64, 512, 1152, 461),
+        ),
+        (
+            Bounds::new(0, 0, 1080, 1920),
+            96,
+            Bounds::new(54, 960, 972, 389),
+        ),
+    ];
+    for (game, dpi, rect) in cases {
+        assert_eq!(
+            overlay_rect(game, dpi, &LayoutPreset::default()),
+            rect,
+            "{:?} @ {}",
+            game,
+            dpi
+        );
+    }
+}
+
+#[test]
+fn corners_are_the_corners_of_the_safe_area() {
+    // 32:9 - a top right overlay sits at the right edge of the middle 16:9 area, not
+    // out at the far end of the monitor.
+    let game = Bounds::new(0, 0, 5120, 1440);
+    let rect = overlay_rect(game, 96, &preset(Anchor::TopRight, 0.5, 0.3, 20));
+    assert_eq!(rect, Bounds::new(1280 + 2560 - 1280 - 20, 20, 1280, 432));
+
+    // Portrait - a bottom left overlay sits on the bottom of the 4:3 band.
+    let game = Bounds::new(0, 0, 1080, 1920);
+    let rect = overlay_rect(game, 96, &preset(Anchor::BottomLeft, 0.5, 0.3, 20));
+    assert_eq!(rect, Bounds::new(20, 555 + 810 - 243 - 20, 540, 243));
+}
+
+#[test]
+fn tells_the_display_mode_from_the_monitor_and_the_styles() {
+    let monitor = Bounds::new(0, 0, 2560, 1440);
+    let frameless = WindowStyle::default();
+    let framed = WindowStyle {
+        framed: true,
+        exclusive: false,
+    };
+    let exclusive = WindowStyle {
+        framed: false,
+        exclusive: true,
+    };
+
+    assert_eq!(
+        display_mode(monitor, monitor, frameless),
+        DisplayMode::BorderlessFullscreen
+    );
+    assert_eq!(
+        display_mode(monitor, monitor, exclusive),
+        DisplayMode::ExclusiveFullscreen
+    );
+    // Hanging over the edges by a few pixels is still the whole monitor
+    assert_eq!(
+        display_mode(Bounds::new(-1, -1, 2562, 1442), monitor, frameless),
+        DisplayMode::BorderlessFullscreen
+    );
+    // Maximised, with its title bar
+    assert_eq!(
+        display_mode(monitor, monitor, framed),
+        DisplayMode::Windowed
+    );
+    // Smaller than the monitor, framed or not - the exclusive report is for another app
+    for style in [frameless, framed, exclusive] {
+        assert_eq!(
+            display_mode(Bounds::new(100, 100, 1920, 1080), monitor, style),
+            DisplayMode::Windowed
+        );
+    }
+
+    // Fullscreen on the second monitor
+    let second = Bounds::new(2560, 0, 1920, 1080);
+    assert_eq!(
+        display_mode(second, second, frameless),
+        DisplayMode::BorderlessFullscreen
+    );
+    assert_eq!(
+        display_mode(second, monitor, frameless),
+        DisplayMode::Windowed
+    );
+
+    assert!(!DisplayMode::ExclusiveFullscreen.supports_overlay());
+    assert!(DisplayMode::BorderlessFullscreen.supports_overlay());
+    assert!(DisplayMode::Windowed.supports_overlay());
+}
+
+#[test]
+fn the_display_mode_has_the_frontends_names() {
+    assert_eq!(
+        serde_json::to_value(DisplayMode::ExclusiveFullscreen).unwrap(),
+        "exclusive_fullscreen"
+    );
+}
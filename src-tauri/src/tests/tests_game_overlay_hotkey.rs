@@ -0,0 +1,199 @@
+//! Tests for the overlay hotkey: key-binding parsing, conflict checks and hold/toggle.
+
+use crate::game_overlay::hotkey::{
+    check_conflicts, parse_binding, validate_binding, BindingError, HotkeyConfig, HotkeyMode, Peek,
+};
+use tauri_plugin_global_shortcut::Shortcut;
+
+#[test]
+fn bindings_are_read_in_any_case_spacing_and_order() {
+    let cases = [
+        ("Ctrl+Shift+O", "Ctrl+Shift+O"),
+        ("ctrl+shift+o", "Ctrl+Shift+O"),
+        (" Shift + Control + o ", "Ctrl+Shift+O"),
+        ("alt+`", "Alt+Backquote"),
+        ("Option+Return", "Alt+Enter"),
+        ("CTRL+ALT+pgdn", "Ctrl+Alt+PageDown"),
+        ("ctrl+Digit5", "Ctrl+5"),
+        ("Num7", "Numpad7"),
+        ("f10", "F10"),
+        ("Win+Up", "Super+ArrowUp"),
+    ];
+    for (text, canonical) in cases {
+        let binding = parse_binding(text).unwrap_or_else(|e| panic!("{}: {}", text, e));
+        assert_eq!(binding.to_string(), canonical, "{}", text);
+        // The canonical form reads back as itself
+        assert_eq!(parse_binding(canonical), Ok(binding), "{}", canonical);
+    }
+}
+
+#[test]
+fn malformed_bindings_say_what_is_wrong() {
+    let cases = [
+        ("", BindingError::Empty),
+        ("   ", BindingError::Empty),
+        ("Ctrl++O", BindingError::EmptyPart),
+        ("Ctrl+", BindingError::EmptyPart),
+        ("Ctrl+Shift", BindingError::NoKey),
+        ("Ctrl+O+P", BindingError::MoreThanOneKey),
+        ("Ctrl+ctrl+O", BindingError::DuplicateModifier("Ctrl")),
+        (
+            "Ctrl+Hyper+O",
+            BindingError::UnknownKey("Hyper".to_string()),
+        ),
+        ("Ctrl+F13", BindingError::UnknownKey("F13".to_string())),
+        (
+            "Ctrl+PrintScreen",
+            BindingError::UnknownKey("PrintScreen".to_string()),
+        ),
+    ];
+    for (text, error) in cases {
+        assert_eq!(parse_binding(text), Err(error), "{:?}", text);
+    }
+}
+
+#[test]
+fn bindings_clashing_with_windows_or_the_game_are_refused() {
+    let cases = [
+        // Windows
+        ("Alt+Tab", Err(BindingError::ReservedBySystem)),
+        ("Alt+Shift+Tab", Err(BindingError::ReservedBySystem)),
+        ("Alt+F4", Err(BindingError::ReservedBySystem)),
+        ("Alt+Esc", Err(BindingError::ReservedBySystem)),
+        ("Alt+Space", Err(BindingError::ReservedBySystem)),
+        ("Ctrl+Esc", Err(BindingError::ReservedBySystem)),
+        ("Ctrl+Shift+Esc", Err(BindingError::ReservedBySystem)),
+        ("Ctrl+Alt+Delete", Err(BindingError::ReservedBySystem)),
+        ("Super+D", Err(BindingError::ReservedBySystem)),
+        // Unit commands, camera, chat and control groups
+        ("O", Err(BindingError::UsedByTheGame)),
+        ("Shift+O", Err(BindingError::UsedByTheGame)),
+        ("5", Err(BindingError::UsedByTheGame)),
+        ("Ctrl+5", Err(BindingError::UsedByTheGame)),
+        ("Ctrl+Shift+5", Err(BindingError::UsedByTheGame)),
+        ("Space", Err(BindingError::UsedByTheGame)),
+        ("Shift+Enter", Err(BindingError::UsedByTheGame)),
+        ("Up", Err(BindingError::UsedByTheGame)),
+        ("Backquote", Err(BindingError::UsedByTheGame)),
+        // Fine
+        ("Ctrl+Shift+O", Ok(())),
+        ("Alt+O", Ok(())),
+        ("Alt+5", Ok(())),
+        ("Ctrl+Tab", Ok(())),
+        ("Ctrl+Alt+Esc", Ok(())),
+        ("F9", Ok(())),
+        ("Shift+F4", Ok(())),
+        ("Insert", Ok(())),
+        ("Numpad0", Ok(())),
+        ("Pause", Ok(())),
+    ];
+    for (text, expected) in cases {
+        let binding = parse_binding(text).unwrap();
+        assert_eq!(check_conflicts(&binding), expected, "{}", text);
+    }
+    assert!(validate_binding(HotkeyConfig::default().binding.as_str()).is_ok());
+}
+
+#[test]
+fn canonical_bindings_are_accepted_by_the_shortcut_plugin() {
+    for text in [
+        "Ctrl+Shift+O",
+        "Alt+Backquote",
+        "Ctrl+Alt+PageDown",
+        "Alt+5",
+        "Numpad7",
+        "Shift+F12",
+        "Ctrl+ArrowLeft",
+        "ScrollLock",
+    ] {
+        let binding = parse_binding(text).unwrap();
+        let shortcut: Shortcut = binding
+            .to_string()
+            .parse()
+            .unwrap_or_else(|e| panic!("{}: {}", text, e));
+        // Same shortcut however it was typed
+        let typed: Shortcut = text.parse().unwrap();
+        assert_eq!(shortcut, typed, "{}", text);
+    }
+}
+
+#[test]
+fn hold_shows_the_overlay_while_the_key_is_down() {
+    let mut peek = Peek::default();
+    assert_eq!(peek.key(HotkeyMode::Hold, true), Some(true));
+    // Key repeat
+    assert_eq!(peek.key(HotkeyMode::Hold, true), None);
+    assert_eq!(peek.key(HotkeyMode::Hold, true), None);
+    assert_eq!(peek.key(HotkeyMode::Hold, false), Some(false));
+    // A release without a press that counted
+    assert_eq!(peek.key(HotkeyMode::Hold, false), None);
+    assert!(!peek.shown);
+}
+
+#[test]
+fn toggle_flips_the_overlay_on_each_press() {
+    let mut peek = Peek::default();
+    assert_eq!(peek.key(HotkeyMode::Toggle, true), Some(true));
+    assert_eq!(peek.key(HotkeyMode::Toggle, true), None); // key repeat
+    assert_eq!(peek.key(HotkeyMode::Toggle, false), None);
+    assert!(peek.shown);
+    assert_eq!(peek.key(HotkeyMode::Toggle, true), Some(false));
+    assert_eq!(peek.key(HotkeyMode::Toggle, false), None);
+    assert_eq!(peek.key(HotkeyMode::Toggle, true), Some(true));
+
+    // The match ends with the overlay up
+    assert!(peek.reset());
+    assert!(!peek.reset());
+    assert_eq!(peek.key(HotkeyMode::Toggle, true), Some(true));
+}
+
+#[test]
+fn hotkey_settings_are_stored_with_defaults() {
+    let stored: HotkeyConfig = serde_json::from_str(r#"{"enabled": true}"#).unwrap();
+    assert_eq!(
+        stored,
+        HotkeyConfig {
+            enabled: true,
+            ..HotkeyConfig::default()
+        }
+    );
+    let stored: HotkeyConfig =
+        serde_json::from_str(r#"{"enabled": true, "binding": "Alt+O", "mode": "hold"}"#).unwrap();
+    assert_eq!(stored.mode, HotkeyMode::Hold);
+}
+
+#[test]
+fn both_hotkeys_are_checked_and_must_differ() {
+    let config = HotkeyConfig {
+        enabled: true,
+        binding: "shift+ctrl+o".to_string(),
+        interact_binding: "alt+i".to_string(),
+        ..HotkeyConfig::default()
+    };
+    let validated = config.validated().unwrap();
+    assert_eq!(validated.binding, "Ctrl+Shift+O");
+    assert_eq!(validated.interact_binding, "Alt+I");
+    assert!(HotkeyConfig::default().validated().is_ok());
+
+    let same = HotkeyConfig {
+        interact_binding: "Ctrl+Shift+O".to_string(),
+        ..HotkeyConfig::default()
+    };
+    assert_eq!(
+        same.validated(),
+        Err(format!("Interact hotkey: {}", BindingError::AlreadyUsed))
+    );
+    let clashing = HotkeyConfig {
+        interact_binding: "I".to_string(),
+        ..HotkeyConfig::default()
+    };
+    assert_eq!(
+        clashing.validated(),
+        Err(format!("Interact hotkey: {}", BindingError::UsedByTheGame))
+    );
+    let broken = HotkeyConfig {
+        binding: "Ctrl+".to_string(),
+        ..HotkeyConfig::default()
+    };
+    assert_eq!(broken.validated(), Err(BindingError::EmptyPart.to_string()));
+}
@@ -0,0 +1,101 @@
+//! Tests for the overlay's interactive mode: when it goes back to click-through.
+
+use crate::game_overlay::interactive::{Exit, Interaction, Look, TIMEOUT};
+use std::time::{Duration, Instant};
+
+/// Overlay up, pointer elsewhere, game in front - nothing that ends interactive mode.
+const CALM: Look = Look {
+    shown: true,
+    pointer_inside: Some(false),
+    game_in_foreground: Some(true),
+};
+
+fn at(start: Instant, millis: u64) -> Instant {
+    start + Duration::from_millis(millis)
+}
+
+#[test]
+fn the_pointer_leaving_ends_it_only_after_it_was_over_the_overlay() {
+    let start = Instant::now();
+    let mut interaction = Interaction::new(start);
+    // Entered by hotkey with the pointer somewhere over the game
+    assert_eq!(interaction.check(at(start, 500), CALM), None);
+    let inside = Look {
+        pointer_inside: Some(true),
+        ..CALM
+    };
+    assert_eq!(interaction.check(at(start, 1_000), inside), None);
+    // Unknown pointer position changes nothing
+    let unknown = Look {
+        pointer_inside: None,
+        ..CALM
+    };
+    assert_eq!(interaction.check(at(start, 1_500), unknown), None);
+    assert_eq!(
+        interaction.check(at(start, 2_000), CALM),
+        Some(Exit::PointerLeft)
+    );
+}
+
+#[test]
+fn it_times_out_even_with_the_pointer_on_the_overlay() {
+    let start = Instant::now();
+    let mut interaction = Interaction::new(start);
+    let inside = Look {
+        pointer_inside: Some(true),
+        ..CALM
+    };
+    let just_before = TIMEOUT.as_millis() as u64 - 1;
+    assert_eq!(interaction.check(at(start, just_before), inside), None);
+    assert_eq!(
+        interaction.check(start + TIMEOUT, inside),
+        Some(Exit::TimedOut)
+    );
+}
+
+#[test]
+fn the_game_coming_back_to_the_front_ends_it() {
+    let start = Instant::now();
+    let away = Look {
+        game_in_foreground: Some(false),
+        ..CALM
+    };
+
+    // Entered from the app's window: the game is away from the start
+    let mut interaction = Interaction::new(start);
+    assert_eq!(interaction.check(at(start, 500), away), None);
+    let unknown = Look {
+        game_in_foreground: None,
+        ..CALM
+    };
+    assert_eq!(interaction.check(at(start, 1_000), unknown), None);
+    assert_eq!(
+        interaction.check(at(start, 1_500), CALM),
+        Some(Exit::GameFocused)
+    );
+
+    // Entered by hotkey: the game keeps the foreground while the overlay is clicked...
+    let mut interaction = Interaction::new(start);
+    assert_eq!(interaction.check(at(start, 500), CALM), None);
+    assert_eq!(interaction.check(at(start, 1_000), CALM), None);
+    // ...until a click opens a profile in the browser, and the user comes back
+    assert_eq!(interaction.check(at(start, 1_500), away), None);
+    assert_eq!(
+        interaction.check(at(start, 2_000), CALM),
+        Some(Exit::GameFocused)
+    );
+}
+
+#[test]
+fn the_overlay_going_off_screen_ends_it() {
+    let start = Instant::now();
+    let mut interaction = Interaction::new(start);
+    let hidden = Look {
+        shown: false,
+        ..CALM
+    };
+    assert_eq!(
+        interaction.check(at(start, 500), hidden),
+        Some(Exit::Hidden)
+    );
+}
@@ -0,0 +1,181 @@
+//! Tests for the overlay modes: timeouts and the post-match results panel.
+
+use crate::current_game::LastMatch;
+use crate::game_overlay::modes::{
+    finished, left_results, match_results, ModeTimeouts, OverlayMode, Showing,
+};
+use crate::parse_log_file::{parse_log_file_reverse, GameState, GameType, LogFileData};
+use crate::session::{match_key, scoreboard, Record, Session, SessionMatch};
+use std::time::{Duration, Instant};
+
+fn game(log: &str) -> LogFileData {
+    parse_log_file_reverse(format!("./test_assets/{}", log))
+}
+
+/// The same match before its result came in.
+fn in_progress(game: &LogFileData) -> LogFileData {
+    LogFileData {
+        winner: None,
+        game_state: GameState::InGame,
+        ..game.clone()
+    }
+}
+
+#[test]
+fn timeouts_are_stored_with_defaults_and_kept_in_range() {
+    let stored: ModeTimeouts = serde_json::from_str(r#"{"results_secs": 45}"#).unwrap();
+    assert_eq!(
+        stored,
+        ModeTimeouts {
+            results_secs: 45,
+            ..ModeTimeouts::default()
+        }
+    );
+
+    let extreme = ModeTimeouts {
+        matchup_secs: 0,
+        results_secs: 100_000,
+    };
+    assert_eq!(
+        extreme.sanitised(),
+        ModeTimeouts {
+            matchup_secs: 30,
+            results_secs: 300,
+        }
+    );
+    assert_eq!(ModeTimeouts::default().sanitised(), ModeTimeouts::default());
+}
+
+#[test]
+fn each_mode_times_out_after_its_own_timeout() {
+    let timeouts = ModeTimeouts {
+        matchup_secs: 120,
+        results_secs: 20,
+    };
+    let start = Instant::now();
+    let matchup = Showing {
+        mode: OverlayMode::Matchup,
+        since: start,
+    };
+    let results = Showing {
+        mode: OverlayMode::Results,
+        since: start,
+    };
+
+    let at = |secs| start + Duration::from_secs(secs);
+    assert!(!results.timed_out(at(19), &timeouts));
+    assert!(results.timed_out(at(20), &timeouts));
+    assert!(!matchup.timed_out(at(20), &timeouts));
+    assert!(matchup.timed_out(at(120), &timeouts));
+
+    // A shorter timeout set while the overlay is up applies right away
+    let shorter = ModeTimeouts {
+        results_secs: 10,
+        ..timeouts
+    };
+    assert!(results.timed_out(at(10), &shorter));
+    // A clock reading from before the show never counts as timed out
+    assert!(!results.timed_out(start, &timeouts));
+}
+
+#[test]
+fn the_results_panel_comes_up_when_a_result_comes_in() {
+    let ended = game("warnings-2.log");
+    let lobby = in_progress(&ended);
+
+    let last = finished(Some(&lobby), &ended).expect("a new result");
+    assert_eq!(last.winner, ended.winner.unwrap());
+    assert_eq!(last.local_player_won, Some(true));
+
+    // At startup, parsed again, and after another lobby
+    assert!(finished(None, &ended).is_none());
+    assert!(finished(Some(&ended), &ended).is_none());
+    assert!(finished(Some(&in_progress(&game("warnings-1.log"))), &ended).is_none());
+
+    // No matchup was shown for these, so no results either
+    let vs_ai = LogFileData {
+        game_type: GameType::AI,
+        ..ended.clone()
+    };
+    assert!(finished(Some(&in_progress(&vs_ai)), &vs_ai).is_none());
+    let sandbox = LogFileData {
+        win_condition: "no_win_condition".to_string(),
+        ..ended.clone()
+    };
+    assert!(finished(Some(&in_progress(&sandbox)), &sandbox).is_none());
+}
+
+#[test]
+fn the_results_panel_shows_the_rating_change_and_the_session() {
+    let ended = game("warnings-2.log");
+    let last = LastMatch::from_game(&ended).unwrap();
+    let earlier = SessionMatch {
+        key: "earlier".to_string(),
+        finished_at: 1_000,
+        mode: "1v1".to_string(),
+        faction: "british".to_string(),
+        map: ended.map.clone(),
+        won: false,
+        rating_before: Some(1210),
+        rating_after: Some(1200),
+    };
+    let this = SessionMatch {
+        key: match_key(&ended),
+        finished_at: 2_000,
+        rating_before: Some(1200),
+        rating_after: None,
+        won: true,
+        ..earlier.clone()
+    };
+    let mut session = Session {
+        started_at: 0,
+        matches: vec![earlier, this],
+    };
+
+    // Before the Relic API has counted the match
+    let results = match_results(&last, &scoreboard(&session));
+    assert_eq!(results.map, ended.map);
+    assert_eq!(results.mode, "1v1");
+    assert_eq!(results.local_player_won, Some(true));
+    assert_eq!(results.duration, ended.duration);
+    assert_eq!(results.rating_before, Some(1200));
+    assert_eq!(results.rating_change, None);
+    assert_eq!(
+        results.session,
+        Record {
+            wins: 1,
+            losses: 1,
+            rating_change: -10,
+        }
+    );
+
+    // And after
+    session.set_rating_after(&match_key(&ended), 1216);
+    let results = match_results(&last, &scoreboard(&session));
+    assert_eq!(results.rating_after, Some(1216));
+    assert_eq!(results.rating_change, Some(16));
+    assert_eq!(results.session.rating_change, 6);
+
+    // A match the session does not have, e.g. one the local player only watched
+    let results = match_results(&last, &scoreboard(&Session::new(0)));
+    assert_eq!(results.rating_before, None);
+    assert_eq!(results.rating_change, None);
+    assert_eq!(results.session, Record::default());
+}
+
+#[test]
+fn the_results_panel_goes_once_the_log_moves_on() {
+    // `Game Over` puts the log back in the menu
+    let ended = LogFileData {
+        game_state: GameState::Menu,
+        ..game("warnings-2.log")
+    };
+    assert!(!left_results(&ended));
+    for state in [GameState::Loading, GameState::InGame, GameState::Closed] {
+        let next = LogFileData {
+            game_state: state.clone(),
+            ..ended.clone()
+        };
+        assert!(left_results(&next), "{:?}", state);
+    }
+}
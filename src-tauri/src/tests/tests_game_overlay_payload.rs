@@ -0,0 +1,220 @@
+//! Tests for the matchup payload the backend puts together for the overlay.
+
+use crate::game_overlay::payload::{
+    matchup, matchup_ended, matchup_started, unique_id, wants_matchup,
+};
+use crate::parse_log_file::{
+    get_team_data, GameState, GameType, LogFileData, PlayerData, TeamSide,
+};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+fn player(name: &str, relic_id: &str, faction: &str, ai: bool) -> PlayerData {
+    PlayerData {
+        ai,
+        faction: faction.to_string(),
+        relic_id: relic_id.to_string(),
+        name: name.to_string(),
+        position: 0,
+        steam_id: "".to_string(),
+        rank: -1,
+    }
+}
+
+fn lobby(state: GameState, left: Vec<PlayerData>, right: Vec<PlayerData>) -> LogFileData {
+    LogFileData {
+        game_state: state,
+        game_type: GameType::Classic,
+        timestamp: "12:00:00.00".to_string(),
+        duration: 0,
+        map: "twin_beach_2p_mkii".to_string(),
+        win_condition: "VictoryPoint".to_string(),
+        left: get_team_data(left),
+        right: get_team_data(right),
+        winner: None,
+        player_name: "me".to_string(),
+        player_steam_id: "".to_string(),
+        player_profile_id: "1".to_string(),
+        language_code: "en".to_string(),
+    }
+}
+
+fn one_v_one(state: GameState) -> LogFileData {
+    lobby(
+        state,
+        vec![player("me", "1", "americans", false)],
+        vec![player("them", "2", "germans", false)],
+    )
+}
+
+fn profile(relic_id: u64, country: &str, leaderboard_stats: Value) -> Value {
+    json!({
+        "result": { "code": 0, "message": "SUCCESS" },
+        "statGroups": [{ "members": [
+            { "profile_id": relic_id, "country": country, "name": "/steam/7656" },
+        ] }],
+        "leaderboardStats": leaderboard_stats,
+    })
+}
+
+#[test]
+fn the_match_id_is_the_one_the_frontend_builds() {
+    let game = one_v_one(GameState::Loading);
+    assert_eq!(
+        unique_id(&game),
+        "12:00:00.00twin_beach_2p_mkiiVictoryPoint1,2"
+    );
+}
+
+#[test]
+fn the_matchup_comes_up_on_the_loading_screen_and_goes_with_it() {
+    let loading = one_v_one(GameState::Loading);
+    let in_game = one_v_one(GameState::InGame);
+    assert!(matchup_started(None, &loading));
+    assert!(matchup_started(Some(&one_v_one(GameState::Menu)), &loading));
+    // Parsed again
+    assert!(!matchup_started(Some(&loading), &loading));
+    assert!(!matchup_ended(Some(&loading), &loading));
+
+    assert!(matchup_ended(Some(&loading), &in_game));
+    assert!(!matchup_started(Some(&loading), &in_game));
+    assert!(!matchup_ended(Some(&in_game), &in_game));
+    assert!(!matchup_ended(None, &in_game));
+
+    // Straight into the next lobby
+    let next = LogFileData {
+        timestamp: "12:30:00.00".to_string(),
+        ..loading.clone()
+    };
+    assert!(matchup_ended(Some(&loading), &next));
+    assert!(matchup_started(Some(&loading), &next));
+
+    // Nothing to show against bots or in a sandbox
+    let vs_ai = LogFileData {
+        game_type: GameType::AI,
+        ..loading.clone()
+    };
+    let sandbox = LogFileData {
+        win_condition: "no_win_condition".to_string(),
+        ..loading.clone()
+    };
+    for game in [&vs_ai, &sandbox] {
+        assert!(!wants_matchup(game));
+        assert!(!matchup_started(None, game));
+    }
+}
+
+#[test]
+fn players_get_their_standing_on_the_lobbys_leaderboard() {
+    let game = one_v_one(GameState::Loading);
+    let profiles = HashMap::from([
+        (
+            "1".to_string(),
+            profile(
+                1,
+                "de",
+                json!([
+                    // 1v1 american, then 1v1 german, which is not what was picked
+                    { "leaderboard_id": 2130255, "rating": 1216, "wins": 30, "losses": 20, "rank": 345 },
+                    { "leaderboard_id": 2130261, "rating": 900, "wins": 1, "losses": 9, "rank": -1 },
+                ]),
+            ),
+        ),
+        ("2".to_string(), profile(2, "pl", json!([]))),
+    ]);
+
+    let payload = matchup(&game, &profiles, None, None);
+    assert_eq!(payload.unique_id, unique_id(&game));
+    assert_eq!(payload.map, game.map);
+    assert_eq!(payload.left.side, TeamSide::Allies);
+    assert_eq!(payload.right.side, TeamSide::Axis);
+
+    let me = &payload.left.players[0];
+    assert!(me.local);
+    assert_eq!(me.faction, "american");
+    assert_eq!(me.country.as_deref(), Some("de"));
+    assert_eq!(me.rating, Some(1216));
+    assert_eq!(me.rank, Some(345));
+    assert_eq!((me.wins, me.losses), (Some(30), Some(20)));
+
+    // Never played the faction in the mode
+    let them = &payload.right.players[0];
+    assert!(!them.local);
+    assert_eq!(them.faction, "german");
+    assert_eq!(them.country.as_deref(), Some("pl"));
+    assert_eq!((them.rating, them.rank, them.wins), (None, None, None));
+
+    // Lookups that failed leave just what the log has
+    let payload = matchup(&game, &HashMap::new(), None, None);
+    assert_eq!(payload.left.players[0].name, "me");
+    assert_eq!(payload.left.players[0].country, None);
+    assert_eq!(payload.left.players[0].rating, None);
+}
+
+#[test]
+fn ai_players_are_never_looked_up() {
+    let game = lobby(
+        GameState::Loading,
+        vec![
+            player("me", "1", "british_africa", false),
+            player("friend", "3", "americans", false),
+        ],
+        vec![
+            player("CPU - Expert", "-1", "afrika_korps", true),
+            player("CPU - Expert", "-1", "germans", true),
+        ],
+    );
+    let profiles = HashMap::from([(
+        "-1".to_string(),
+        profile(
+            0,
+            "xx",
+            json!([{ "leaderboard_id": 2130258, "rating": 1, "wins": 1, "losses": 1, "rank": 1 }]),
+        ),
+    )]);
+
+    let payload = matchup(&game, &profiles, None, None);
+    for ai in &payload.right.players {
+        assert!(ai.ai);
+        assert_eq!((ai.country.as_ref(), ai.rating), (None, None));
+    }
+    assert_eq!(payload.right.players[0].faction, "dak");
+    assert_eq!(payload.left.players[0].faction, "british");
+}
+
+#[test]
+fn the_payload_has_the_overlays_field_names() {
+    let payload = matchup(&one_v_one(GameState::Loading), &HashMap::new(), None, None);
+    let json = serde_json::to_value(&payload).unwrap();
+    assert!(json["uniqueID"].is_string());
+    assert!(json["winProbability"]["probability"].is_number());
+    assert_eq!(json["left"]["side"], "Allies");
+    let me = &json["left"]["players"][0];
+    assert_eq!(me["relicID"], "1");
+    assert_eq!(me["self"], true);
+    assert_eq!(me["faction"], "american");
+    assert!(me["rating"].is_null());
+    assert!(json["battlegroups"].is_object());
+}
+
+#[test]
+fn battlegroup_info_is_sent_for_the_lobbys_factions() {
+    let info = json!({
+        "american": { "airborne": { "winRate": 0.52 } },
+        "german": { "luftwaffe": { "winRate": 0.49 } },
+        "british": { "armored": { "winRate": 0.5 } },
+    });
+    let payload = matchup(
+        &one_v_one(GameState::Loading),
+        &HashMap::new(),
+        None,
+        Some(&info),
+    );
+    assert_eq!(payload.battlegroups.len(), 2);
+    assert_eq!(payload.battlegroups["american"], info["american"]);
+    assert_eq!(payload.battlegroups["german"], info["german"]);
+
+    // Not fetched yet
+    let payload = matchup(&one_v_one(GameState::Loading), &HashMap::new(), None, None);
+    assert!(payload.battlegroups.is_empty());
+}
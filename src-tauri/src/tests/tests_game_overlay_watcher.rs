@@ -0,0 +1,545 @@
+//! Tests for the in-game overlay watcher, driven through scripted desktops.
+//!
+//! Each test plays a timeline tick by tick: the fake desktop is changed between
+//! ticks the way the real one would change, and the fake overlay window records what
+//! the watcher did to it.
+
+use crate::game_overlay::geometry::{overlay_rect, Bounds, DisplayMode, LayoutPreset};
+use crate::game_overlay::interactive::{Exit, Interaction};
+use crate::game_overlay::watcher::{
+    tick, Decision, Observation, OverlayStatus, OverlaySurface, Reason, Step, Tick, TickState,
+    Visibility, WindowSystem,
+};
+use std::time::{Duration, Instant};
+
+const GAME_PID: u32 = 4242;
+const OWN_PID: u32 = 1000;
+const OTHER_PID: u32 = 777;
+const GAME_WINDOW: isize = 0x1234;
+
+/// The desktop as it is at one tick.
+struct FakeDesktop {
+    game_running: bool,
+    window: Option<Bounds>,
+    minimised: bool,
+    display_mode: Option<DisplayMode>,
+    foreground: Option<u32>,
+    cursor: Option<(i32, i32)>,
+}
+
+impl FakeDesktop {
+    /// The game running in the foreground at 1080p.
+    fn playing() -> Self {
+        Self {
+            game_running: true,
+            window: Some(Bounds::new(0, 0, 1920, 1080)),
+            minimised: false,
+            display_mode: Some(DisplayMode::BorderlessFullscreen),
+            foreground: Some(GAME_PID),
+            cursor: None,
+        }
+    }
+
+    /// The game still starting, without a process yet.
+    fn starting() -> Self {
+        Self {
+            game_running: false,
+            window: None,
+            display_mode: None,
+            ..Self::playing()
+        }
+    }
+}
+
+impl WindowSystem for FakeDesktop {
+    fn game_pid(&self) -> Option<u32> {
+        self.game_running.then_some(GAME_PID)
+    }
+    fn find_window(&self, pid: u32) -> Option<isize> {
+        assert_eq!(pid, GAME_PID);
+        self.window.map(|_| GAME_WINDOW)
+    }
+    fn bounds(&self, window: isize) -> Option<Bounds> {
+        assert_eq!(window, GAME_WINDOW);
+        self.window
+    }
+    fn dpi(&self, _window: isize) -> u32 {
+        96
+    }
+    fn is_minimised(&self, _window: isize) -> bool {
+        self.minimised
+    }
+    fn display_mode(&self, window: isize) -> Option<DisplayMode> {
+        assert_eq!(window, GAME_WINDOW);
+        self.display_mode
+    }
+    fn foreground_pid(&self) -> Option<u32> {
+        self.foreground
+    }
+    fn own_pid(&self) -> u32 {
+        OWN_PID
+    }
+    fn cursor_position(&self) -> Option<(i32, i32)> {
+        self.cursor
+    }
+}
+
+/// The overlay window: whether it is on screen, where, and how often it was moved.
+#[derive(Default)]
+struct FakeSurface {
+    visible: bool,
+    rect: Option<Bounds>,
+    places: usize,
+    /// How many of the next `place` calls fail.
+    failing_places: usize,
+}
+
+impl OverlaySurface for FakeSurface {
+    fn place(&mut self, rect: Bounds) -> bool {
+        self.places += 1;
+        if self.failing_places > 0 {
+            self.failing_places -= 1;
+            return false;
+        }
+        self.rect = Some(rect);
+        true
+    }
+    fn show(&mut self) {
+        self.visible = true;
+    }
+    fn hide(&mut self) {
+        self.visible = false;
+    }
+}
+
+/// A watcher with the overlay wanted, as after `show`.
+struct Timeline {
+    state: TickState,
+    desktop: FakeDesktop,
+    surface: FakeSurface,
+    layout: LayoutPreset,
+    wanted: bool,
+    /// Interactive mode, as `apply` keeps it between ticks.
+    interaction: Option<Interaction>,
+    start: Instant,
+    ticks: u32,
+}
+
+impl Timeline {
+    fn new(desktop: FakeDesktop) -> Self {
+        Self {
+            state: TickState::default(),
+            desktop,
+            surface: FakeSurface::default(),
+            layout: LayoutPreset::default(),
+            wanted: true,
+            interaction: None,
+            start: Instant::now(),
+            ticks: 0,
+        }
+    }
+
+    /// Half a second after the previous tick, as the watcher ticks.
+    fn step(&mut self) -> Step {
+        self.ticks += 1;
+        let now = self.start + Duration::from_millis(500) * self.ticks;
+        let step = tick(
+            &mut self.state,
+            self.wanted,
+            self.interaction,
+            &self.desktop,
+            &mut self.surface,
+            &self.layout,
+            now,
+        );
+        self.interaction = step.interaction;
+        step
+    }
+
+    fn tick(&mut self) -> Decision {
+        self.step().decision
+    }
+
+    fn interact(&mut self) {
+        self.interaction = Some(Interaction::new(
+            self.start + Duration::from_millis(500) * self.ticks,
+        ));
+    }
+}
+
+#[test]
+fn shows_the_overlay_over_the_game() {
+    let mut timeline = Timeline::new(FakeDesktop::playing());
+    let expected = overlay_rect(Bounds::new(0, 0, 1920, 1080), 96, &LayoutPreset::default());
+
+    let decision = timeline.tick();
+    assert_eq!(
+        decision,
+        Decision::Track {
+            place: Some(expected),
+            visible: true
+        }
+    );
+    assert_eq!(decision.tick(), Tick::Continue);
+    assert!(timeline.surface.visible);
+    assert_eq!(timeline.surface.rect, Some(expected));
+    assert_eq!(timeline.state.last_rect(), Some(expected));
+
+    // Nothing moved, so nothing is placed again
+    for _ in 0..5 {
+        timeline.tick();
+    }
+    assert_eq!(timeline.surface.places, 1);
+    assert!(timeline.surface.visible);
+}
+
+#[test]
+fn alt_tab_hides_after_the_grace_tick_and_comes_back_at_once() {
+    let mut timeline = Timeline::new(FakeDesktop::playing());
+    timeline.tick();
+
+    // One tick on another app is a loading-screen handover, not an alt-tab
+    timeline.desktop.foreground = Some(OTHER_PID);
+    timeline.tick();
+    assert!(timeline.surface.visible);
+    // The second one is
+    timeline.tick();
+    assert!(!timeline.surface.visible);
+    timeline.tick();
+    assert!(!timeline.surface.visible);
+
+    timeline.desktop.foreground = Some(GAME_PID);
+    timeline.tick();
+    assert!(timeline.surface.visible);
+
+    // Tabbing back to this app, or an unknown foreground, is not leaving the game
+    for foreground in [Some(OWN_PID), None, Some(OWN_PID), None] {
+        timeline.desktop.foreground = foreground;
+        timeline.tick();
+        assert!(timeline.surface.visible, "{:?}", foreground);
+    }
+
+    // Another app in between resets the count
+    timeline.desktop.foreground = Some(OTHER_PID);
+    timeline.tick();
+    timeline.desktop.foreground = Some(GAME_PID);
+    timeline.tick();
+    timeline.desktop.foreground = Some(OTHER_PID);
+    timeline.tick();
+    assert!(timeline.surface.visible);
+}
+
+#[test]
+fn minimising_the_game_hides_the_overlay_right_away() {
+    let mut timeline = Timeline::new(FakeDesktop::playing());
+    timeline.tick();
+
+    timeline.desktop.minimised = true;
+    let decision = timeline.tick();
+    assert!(matches!(decision, Decision::Track { visible: false, .. }));
+    assert!(!timeline.surface.visible);
+
+    timeline.desktop.minimised = false;
+    timeline.tick();
+    assert!(timeline.surface.visible);
+}
+
+#[test]
+fn a_game_crash_stops_the_watcher() {
+    let mut timeline = Timeline::new(FakeDesktop::playing());
+    timeline.tick();
+    assert!(timeline.surface.visible);
+
+    timeline.desktop = FakeDesktop {
+        foreground: Some(OTHER_PID),
+        ..FakeDesktop::starting()
+    };
+    let decision = timeline.tick();
+    assert_eq!(decision, Decision::GameGone);
+    assert_eq!(decision.tick(), Tick::Stop);
+    assert!(!timeline.surface.visible);
+}
+
+#[test]
+fn waits_for_a_window_that_appears_late() {
+    let mut timeline = Timeline::new(FakeDesktop {
+        window: None,
+        ..FakeDesktop::playing()
+    });
+
+    for _ in 0..3 {
+        let decision = timeline.tick();
+        assert_eq!(decision, Decision::Wait);
+        assert_eq!(decision.tick(), Tick::Continue);
+        assert!(!timeline.surface.visible);
+        assert_eq!(timeline.surface.places, 0);
+    }
+
+    // The window shows up at 1440p
+    timeline.desktop.window = Some(Bounds::new(0, 0, 2560, 1440));
+    timeline.tick();
+    assert!(timeline.surface.visible);
+    assert_eq!(
+        timeline.surface.rect,
+        Some(overlay_rect(
+            Bounds::new(0, 0, 2560, 1440),
+            96,
+            &LayoutPreset::default()
+        ))
+    );
+
+    // And goes away again while the process lives on, e.g. switching display modes
+    timeline.desktop.window = None;
+    assert_eq!(timeline.tick(), Decision::Wait);
+    assert!(!timeline.surface.visible);
+}
+
+#[test]
+fn failed_placements_are_retried() {
+    let mut timeline = Timeline::new(FakeDesktop::playing());
+    timeline.surface.failing_places = 2;
+
+    timeline.tick();
+    assert_eq!(timeline.state.last_rect(), None);
+    timeline.tick();
+    assert_eq!(timeline.state.last_rect(), None);
+    timeline.tick();
+    assert_eq!(timeline.surface.places, 3);
+    assert!(timeline.state.last_rect().is_some());
+
+    timeline.tick();
+    assert_eq!(timeline.surface.places, 3);
+}
+
+#[test]
+fn follows_the_game_window_and_the_layout() {
+    let mut timeline = Timeline::new(FakeDesktop::playing());
+    timeline.tick();
+
+    let moved = Bounds::new(-1920, 0, 1920, 1080);
+    timeline.desktop.window = Some(moved);
+    timeline.tick();
+    assert_eq!(
+        timeline.surface.rect,
+        Some(overlay_rect(moved, 96, &LayoutPreset::default()))
+    );
+
+    timeline.layout = LayoutPreset {
+        height_ratio: 0.2,
+        ..LayoutPreset::default()
+    };
+    timeline.tick();
+    assert_eq!(
+        timeline.surface.rect,
+        Some(overlay_rect(moved, 96, &timeline.layout))
+    );
+    assert_eq!(timeline.surface.places, 3);
+}
+
+#[test]
+fn standing_down_hides_without_looking_at_the_desktop() {
+    let mut timeline = Timeline::new(FakeDesktop::playing());
+    timeline.tick();
+
+    timeline.wanted = false;
+    let decision = timeline.tick();
+    assert_eq!(decision, Decision::StandDown);
+    assert_eq!(decision.tick(), Tick::Stop);
+    assert!(!timeline.surface.visible);
+
+    // The decision alone does not depend on what the desktop shows
+    let mut state = TickState::default();
+    assert_eq!(
+        state
+            .step(
+                false,
+                Observation::NoGame,
+                &LayoutPreset::default(),
+                None,
+                Instant::now()
+            )
+            .decision,
+        Decision::StandDown
+    );
+}
+
+#[test]
+fn every_tick_settles_on_a_visibility_and_its_reason() {
+    // Asked for while the game is still starting
+    let mut timeline = Timeline::new(FakeDesktop::starting());
+    let expect = |timeline: &mut Timeline, visibility, reason| {
+        let decision = timeline.tick();
+        let status = timeline.state.status();
+        assert_eq!(
+            (status.visibility, status.reason),
+            (visibility, reason),
+            "after {:?}",
+            decision
+        );
+        assert_eq!(timeline.surface.visible, visibility == Visibility::Shown);
+    };
+
+    expect(
+        &mut timeline,
+        Visibility::WaitingForGame,
+        Reason::GameNotRunning,
+    );
+    timeline.desktop.game_running = true;
+    expect(
+        &mut timeline,
+        Visibility::WaitingForWindow,
+        Reason::NoGameWindow { pid: GAME_PID },
+    );
+    timeline.desktop = FakeDesktop::playing();
+    expect(&mut timeline, Visibility::Shown, Reason::GameInForeground);
+    timeline.desktop.foreground = Some(OWN_PID);
+    expect(&mut timeline, Visibility::Shown, Reason::AppInForeground);
+    timeline.desktop.foreground = None;
+    expect(&mut timeline, Visibility::Shown, Reason::ForegroundUnknown);
+    timeline.desktop.foreground = Some(OTHER_PID);
+    expect(
+        &mut timeline,
+        Visibility::Shown,
+        Reason::AnotherAppBriefly { pid: OTHER_PID },
+    );
+    expect(
+        &mut timeline,
+        Visibility::HiddenAway,
+        Reason::AnotherAppInForeground { pid: OTHER_PID },
+    );
+    // Minimised wins over whichever app took the foreground
+    timeline.desktop.minimised = true;
+    expect(
+        &mut timeline,
+        Visibility::HiddenMinimised,
+        Reason::GameMinimised,
+    );
+    timeline.desktop.game_running = false;
+    expect(
+        &mut timeline,
+        Visibility::NotWanted,
+        Reason::GameExited { pid: GAME_PID },
+    );
+
+    let mut timeline = Timeline::new(FakeDesktop::playing());
+    timeline.tick();
+    timeline.wanted = false;
+    expect(&mut timeline, Visibility::NotWanted, Reason::NotRequested);
+}
+
+#[test]
+fn a_game_that_is_not_running_yet_is_waited_for() {
+    let mut timeline = Timeline::new(FakeDesktop::starting());
+    for _ in 0..3 {
+        assert_eq!(timeline.tick(), Decision::Wait);
+    }
+    timeline.desktop = FakeDesktop::playing();
+    timeline.tick();
+    assert!(timeline.surface.visible);
+}
+
+#[test]
+fn statuses_are_sent_in_the_shape_the_frontend_reads() {
+    let mut timeline = Timeline::new(FakeDesktop::playing());
+    timeline.desktop.foreground = Some(OTHER_PID);
+    timeline.tick();
+    timeline.tick();
+
+    let json = serde_json::to_value(timeline.state.status()).unwrap();
+    assert_eq!(json["visibility"], "HiddenAway");
+    assert_eq!(
+        json["reason"],
+        serde_json::json!({ "kind": "AnotherAppInForeground", "pid": OTHER_PID })
+    );
+    assert_eq!(json["rect"]["width"], 1728);
+
+    let json = serde_json::to_value(OverlayStatus::not_wanted()).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({
+            "visibility": "NotWanted",
+            "reason": { "kind": "NotRequested" },
+            "rect": null,
+        })
+    );
+}
+
+#[test]
+fn warns_once_when_the_game_switches_to_exclusive_fullscreen() {
+    let mut timeline = Timeline::new(FakeDesktop::playing());
+    assert_eq!(timeline.step().unsupported_display_mode, None);
+    assert_eq!(
+        timeline.state.display_mode(),
+        Some(DisplayMode::BorderlessFullscreen)
+    );
+
+    timeline.desktop.display_mode = Some(DisplayMode::ExclusiveFullscreen);
+    assert_eq!(
+        timeline.step().unsupported_display_mode,
+        Some(DisplayMode::ExclusiveFullscreen)
+    );
+    assert_eq!(timeline.step().unsupported_display_mode, None);
+
+    // Not while the overlay waits for a window
+    timeline.desktop.window = None;
+    timeline.desktop.display_mode = Some(DisplayMode::Windowed);
+    assert_eq!(timeline.step().unsupported_display_mode, None);
+    assert_eq!(
+        timeline.state.display_mode(),
+        Some(DisplayMode::ExclusiveFullscreen)
+    );
+
+    // A new watcher knows what the last one saw
+    let mut state = TickState::with_display_mode(Some(DisplayMode::ExclusiveFullscreen));
+    let mut surface = FakeSurface::default();
+    let mut desktop = FakeDesktop::playing();
+    desktop.display_mode = Some(DisplayMode::ExclusiveFullscreen);
+    let step = tick(
+        &mut state,
+        true,
+        None,
+        &desktop,
+        &mut surface,
+        &LayoutPreset::default(),
+        Instant::now(),
+    );
+    assert_eq!(step.unsupported_display_mode, None);
+}
+
+#[test]
+fn interactive_mode_ends_when_the_pointer_leaves_the_overlay() {
+    let mut timeline = Timeline::new(FakeDesktop::playing());
+    timeline.tick();
+    let rect = timeline.state.last_rect().unwrap();
+    timeline.interact();
+
+    timeline.desktop.cursor = Some((rect.x + 10, rect.y + 10));
+    let step = timeline.step();
+    assert_eq!(step.exit, None);
+    assert!(step.interaction.is_some());
+
+    timeline.desktop.cursor = Some((rect.x - 10, rect.y - 10));
+    let step = timeline.step();
+    assert_eq!(step.exit, Some(Exit::PointerLeft));
+    assert_eq!(step.interaction, None);
+}
+
+#[test]
+fn interactive_mode_ends_when_the_game_is_back_or_the_overlay_goes() {
+    let mut timeline = Timeline::new(FakeDesktop::playing());
+    timeline.tick();
+    timeline.interact();
+
+    // A click opened a profile in the browser, then the player tabs back
+    timeline.desktop.foreground = Some(OTHER_PID);
+    assert_eq!(timeline.step().exit, None);
+    timeline.desktop.foreground = Some(GAME_PID);
+    assert_eq!(timeline.step().exit, Some(Exit::GameFocused));
+
+    timeline.interact();
+    timeline.desktop.minimised = true;
+    assert_eq!(timeline.step().exit, Some(Exit::Hidden));
+
+    // Nothing to end while it is off
+    assert_eq!(timeline.step().exit, None);
+}
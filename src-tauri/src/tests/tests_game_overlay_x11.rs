@@ -0,0 +1,265 @@
+//! Tests for the overlay on Linux: finding the game under Proton, and the X11 window
+//! lookup and styling.
+//!
+//! The X11 tests need a display and skip themselves without one. Run them headless
+//! under Xvfb, which puts up a bare X server with no window manager:
+//!
+//! ```text
+//! xvfb-run -a cargo test tests_game_overlay_x11
+//! ```
+
+use crate::process_watcher::is_game_process;
+
+#[test]
+fn the_game_is_found_under_proton() {
+    // Wine names the process after the Windows executable
+    assert!(is_game_process("RelicCoH3.exe\n", ""));
+    assert!(is_game_process(
+        "wine64-preload",
+        r"Z:\home\me\.steam\steam\steamapps\common\Company of Heroes 3\RelicCoH3.exe"
+    ));
+    assert!(is_game_process("", "/home/me/games/coh3/reliccoh3.exe"));
+
+    assert!(!is_game_process("steam", "/usr/bin/steam"));
+    // The launcher wrapping it
+    assert!(!is_game_process(
+        "start.exe",
+        r"C:\windows\system32\start.exe"
+    ));
+    assert!(!is_game_process("RelicCoH3.exe.bak", ""));
+}
+
+#[cfg(target_os = "linux")]
+mod x11 {
+    use crate::game_overlay::geometry::{Bounds, DisplayMode};
+    use crate::game_overlay::window_detector::{
+        apply_overlay_styles, display_mode, find_own_window, find_window_for_pid, foreground_pid,
+        get_window_bounds, is_minimised,
+    };
+    use x11rb::connection::Connection;
+    use x11rb::properties::WmHints;
+    use x11rb::protocol::shape::{ConnectionExt as _, SK};
+    use x11rb::protocol::xproto::{
+        Atom, AtomEnum, ConnectionExt as _, CreateWindowAux, PropMode, Window, WindowClass,
+    };
+    use x11rb::rust_connection::RustConnection;
+    use x11rb::wrapper::ConnectionExt as _;
+
+    /// A process no real window belongs to.
+    const GAME_PID: u32 = 4_000_001;
+
+    /// A dummy client window on the test display, standing in for the game.
+    struct Client {
+        conn: RustConnection,
+        root: Window,
+        window: Window,
+    }
+
+    impl Client {
+        /// `None` without a display to put it on.
+        fn new(pid: u32, x: i16, y: i16, width: u16, height: u16) -> Option<Self> {
+            if std::env::var_os("DISPLAY").is_none() {
+                eprintln!("No X display, skipped - run under Xvfb");
+                return None;
+            }
+            let (conn, screen) = RustConnection::connect(None).expect("X display");
+            let root = conn.setup().roots[screen].root;
+            let window = conn.generate_id().unwrap();
+            conn.create_window(
+                x11rb::COPY_DEPTH_FROM_PARENT,
+                window,
+                root,
+                x,
+                y,
+                width,
+                height,
+                0,
+                WindowClass::INPUT_OUTPUT,
+                x11rb::COPY_FROM_PARENT,
+                &CreateWindowAux::new(),
+            )
+            .unwrap();
+            let client = Self { conn, root, window };
+            client.set32(
+                client.window,
+                "_NET_WM_PID",
+                AtomEnum::CARDINAL.into(),
+                &[pid],
+            );
+            client.conn.map_window(window).unwrap();
+            client.sync();
+            Some(client)
+        }
+
+        fn atom(&self, name: &str) -> Atom {
+            self.conn
+                .intern_atom(false, name.as_bytes())
+                .unwrap()
+                .reply()
+                .unwrap()
+                .atom
+        }
+
+        fn set32(&self, window: Window, property: &str, type_: Atom, values: &[u32]) {
+            let property = self.atom(property);
+            self.conn
+                .change_property32(PropMode::REPLACE, window, property, type_, values)
+                .unwrap();
+            self.sync();
+        }
+
+        fn get32(&self, property: &str) -> Vec<u32> {
+            let property = self.atom(property);
+            let reply = self
+                .conn
+                .get_property(false, self.window, property, AtomEnum::ANY, 0, 64)
+                .unwrap()
+                .reply()
+                .unwrap();
+            reply.value32().map(|v| v.collect()).unwrap_or_default()
+        }
+
+        /// A round trip, so the server has everything sent so far.
+        fn sync(&self) {
+            self.conn.get_input_focus().unwrap().reply().unwrap();
+        }
+
+        fn handle(&self) -> isize {
+            self.window as isize
+        }
+    }
+
+    impl Drop for Client {
+        fn drop(&mut self) {
+            let _ = self.conn.destroy_window(self.window);
+            let _ = self.conn.flush();
+        }
+    }
+
+    #[test]
+    fn the_game_window_is_found_and_followed() {
+        let Some(game) = Client::new(GAME_PID, 40, 30, 640, 360) else {
+            return;
+        };
+
+        assert_eq!(find_window_for_pid(GAME_PID), Some(game.handle()));
+        assert_eq!(find_window_for_pid(GAME_PID + 1), None);
+        assert_eq!(
+            get_window_bounds(game.handle()),
+            Some(Bounds::new(40, 30, 640, 360))
+        );
+        assert_eq!(display_mode(game.handle()), Some(DisplayMode::Windowed));
+
+        // Moved, as a window manager or a display mode change would
+        game.conn
+            .configure_window(
+                game.window,
+                &x11rb::protocol::xproto::ConfigureWindowAux::new()
+                    .x(0)
+                    .y(0)
+                    .width(1280)
+                    .height(720),
+            )
+            .unwrap();
+        game.sync();
+        assert_eq!(
+            get_window_bounds(game.handle()),
+            Some(Bounds::new(0, 0, 1280, 720))
+        );
+
+        // Covering the screen without a frame, as a borderless game does
+        let screen = get_window_bounds(game.root as isize).expect("screen bounds");
+        game.conn
+            .configure_window(
+                game.window,
+                &x11rb::protocol::xproto::ConfigureWindowAux::new()
+                    .width(screen.width as u32)
+                    .height(screen.height as u32),
+            )
+            .unwrap();
+        game.sync();
+        assert_eq!(
+            display_mode(game.handle()),
+            Some(DisplayMode::BorderlessFullscreen)
+        );
+
+        // Minimised, by ICCCM and by EWMH
+        assert!(!is_minimised(game.handle()));
+        let wm_state = game.atom("WM_STATE");
+        game.set32(game.window, "WM_STATE", wm_state, &[3, 0]);
+        assert!(is_minimised(game.handle()));
+        game.set32(game.window, "WM_STATE", wm_state, &[1, 0]);
+        assert!(!is_minimised(game.handle()));
+        let hidden = game.atom("_NET_WM_STATE_HIDDEN");
+        game.set32(
+            game.window,
+            "_NET_WM_STATE",
+            AtomEnum::ATOM.into(),
+            &[hidden],
+        );
+        assert!(is_minimised(game.handle()));
+
+        // In the foreground
+        let window_type = AtomEnum::WINDOW.into();
+        game.set32(game.root, "_NET_ACTIVE_WINDOW", window_type, &[game.window]);
+        assert_eq!(foreground_pid(), Some(GAME_PID));
+        game.set32(game.root, "_NET_ACTIVE_WINDOW", window_type, &[0]);
+        assert_eq!(foreground_pid(), None);
+    }
+
+    #[test]
+    fn the_overlay_is_kept_on_top_and_lets_clicks_through() {
+        let Some(overlay) = Client::new(std::process::id(), 0, 0, 800, 200) else {
+            return;
+        };
+        let title = "Grenadier In-Game Overlay (test)";
+        overlay
+            .conn
+            .change_property8(
+                PropMode::REPLACE,
+                overlay.window,
+                AtomEnum::WM_NAME,
+                AtomEnum::STRING,
+                title.as_bytes(),
+            )
+            .unwrap();
+        overlay.sync();
+        assert_eq!(find_own_window(title), Some(overlay.handle()));
+        assert_eq!(find_own_window("Another window"), None);
+
+        let input_shape = |overlay: &Client| {
+            // A round trip on the detector's own connection first, so its requests are in
+            get_window_bounds(overlay.handle());
+            overlay
+                .conn
+                .shape_get_rectangles(overlay.window, SK::INPUT)
+                .unwrap()
+                .reply()
+                .unwrap()
+                .rectangles
+        };
+
+        apply_overlay_styles(overlay.handle(), true);
+        assert!(input_shape(&overlay).is_empty());
+        let states = overlay.get32("_NET_WM_STATE");
+        for state in ["_NET_WM_STATE_ABOVE", "_NET_WM_STATE_SKIP_TASKBAR"] {
+            assert!(states.contains(&overlay.atom(state)), "{}", state);
+        }
+        let hints = WmHints::get(&overlay.conn, overlay.window)
+            .unwrap()
+            .reply()
+            .unwrap()
+            .expect("WM_HINTS");
+        assert_eq!(hints.input, Some(false));
+
+        // Interactive: the whole window takes clicks again
+        apply_overlay_styles(overlay.handle(), false);
+        let shape = input_shape(&overlay);
+        assert_eq!(shape.len(), 1);
+        assert_eq!((shape[0].width, shape[0].height), (800, 200));
+
+        // Styling again does not pile up states
+        apply_overlay_styles(overlay.handle(), true);
+        assert_eq!(overlay.get32("_NET_WM_STATE").len(), states.len());
+    }
+}
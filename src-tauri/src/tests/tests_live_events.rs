@@ -0,0 +1,230 @@
+//! Tests for the live event stream served to streamer overlays.
+
+use crate::live_events::{changes, LiveEvent, LiveEvents};
+use crate::overlay_http::{Limits, Server};
+use crate::overlay_server::handle_events_request;
+use crate::parse_log_file::{parse_log_file_reverse, GameState, LogFileData, TeamPosition};
+use serde_json::Value;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+fn game(log: &str) -> LogFileData {
+    parse_log_file_reverse(format!("./test_assets/{}", log))
+}
+
+fn names(events: &[LiveEvent]) -> Vec<&'static str> {
+    events.iter().map(LiveEvent::name).collect()
+}
+
+/// Serves only the event stream of `events`.
+fn events_server(events: Arc<LiveEvents>) -> SocketAddr {
+    Server::bind("127.0.0.1:0", Limits::default())
+        .and_then(|server| server.serve(move |request| handle_events_request(request, &events)))
+        .expect("Failed to start overlay server")
+        .addr()
+}
+
+struct Client {
+    reader: BufReader<TcpStream>,
+}
+
+impl Client {
+    fn connect(addr: SocketAddr) -> Self {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.set_read_timeout(Some(TIMEOUT)).unwrap();
+        stream
+            .write_all(b"GET /api/v1/events HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .unwrap();
+        let mut client = Self {
+            reader: BufReader::new(stream),
+        };
+        let status = client.line();
+        assert_eq!(status, "HTTP/1.1 200 OK");
+        let headers: Vec<String> = std::iter::from_fn(|| Some(client.line()))
+            .take_while(|line| !line.is_empty())
+            .collect();
+        assert!(headers.contains(&"Content-Type: text/event-stream".to_string()));
+        client
+    }
+
+    fn line(&mut self) -> String {
+        let mut line = String::new();
+        self.reader
+            .read_line(&mut line)
+            .expect("read from event stream");
+        line.trim_end_matches(['\r', '\n']).to_string()
+    }
+
+    /// The next event, skipping comments and the retry hint.
+    fn next_event(&mut self) -> (String, Value) {
+        let mut name = String::new();
+        loop {
+            let line = self.line();
+            if let Some(event) = line.strip_prefix("event: ") {
+                name = event.to_string();
+            } else if let Some(data) = line.strip_prefix("data: ") {
+                return (
+                    name,
+                    serde_json::from_str(data).expect("event data is JSON"),
+                );
+            }
+        }
+    }
+}
+
+fn wait_for_subscribers(events: &LiveEvents, count: usize) {
+    let started = Instant::now();
+    while events.subscriber_count() != count {
+        assert!(started.elapsed() < TIMEOUT, "clients did not subscribe");
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+#[test]
+fn first_parse_reports_state_lobby_and_result() {
+    let finished = game("warnings-2.log");
+    assert_eq!(
+        names(&changes(None, &finished)),
+        vec!["game-state", "lobby", "match-result"]
+    );
+
+    let in_game = game("warnings-patch2-3-1.log");
+    assert_eq!(names(&changes(None, &in_game)), vec!["game-state", "lobby"]);
+}
+
+#[test]
+fn parsing_the_same_log_again_changes_nothing() {
+    let finished = game("warnings-2.log");
+    assert!(changes(Some(&finished), &finished).is_empty());
+}
+
+#[test]
+fn reports_state_transitions_and_results_of_the_same_lobby() {
+    let finished = game("warnings-2.log");
+    let mut in_game = finished.clone();
+    in_game.game_state = GameState::InGame;
+    in_game.winner = None;
+
+    let events = changes(Some(&in_game), &finished);
+    assert_eq!(names(&events), vec!["game-state", "match-result"]);
+    assert!(matches!(
+        &events[0],
+        LiveEvent::GameState(GameState::Closed)
+    ));
+
+    let events = changes(Some(&finished), &in_game);
+    assert_eq!(names(&events), vec!["game-state"]);
+}
+
+#[test]
+fn a_new_lobby_is_reported_with_its_players() {
+    let previous = game("warnings-2.log");
+    let next = game("warnings-1.log");
+
+    let events = changes(Some(&previous), &next);
+    assert_eq!(names(&events), vec!["lobby", "match-result"]);
+    match &events[0] {
+        LiveEvent::Lobby(lobby) => assert_eq!(lobby.map, "winter_line_8p_mkii"),
+        other => panic!("expected a lobby, got {:?}", other),
+    }
+}
+
+#[test]
+fn new_subscribers_get_the_latest_event_of_each_kind() {
+    let events = LiveEvents::default();
+    for event in changes(None, &game("warnings-patch2-3-1.log")) {
+        events.publish(event);
+    }
+    for event in changes(None, &game("warnings-2.log")) {
+        events.publish(event);
+    }
+
+    let (replay, _receiver) = events.subscribe();
+    assert_eq!(names(&replay), vec!["game-state", "lobby", "match-result"]);
+    assert!(matches!(
+        &replay[0],
+        LiveEvent::GameState(GameState::Closed)
+    ));
+}
+
+#[test]
+fn streams_to_several_clients_at_once() {
+    let events = Arc::new(LiveEvents::default());
+    let lobby = game("warnings-patch2-3-1.log");
+    for event in changes(None, &lobby) {
+        events.publish(event);
+    }
+    let addr = events_server(events.clone());
+
+    let mut clients: Vec<Client> = (0..3).map(|_| Client::connect(addr)).collect();
+    wait_for_subscribers(&events, 3);
+
+    for client in &mut clients {
+        let (name, data) = client.next_event();
+        assert_eq!(name, "game-state");
+        assert_eq!(data["game_state"], "InGame");
+        let (name, data) = client.next_event();
+        assert_eq!(name, "lobby");
+        assert_eq!(data["map"], lobby.map.as_str());
+    }
+
+    let mut finished = lobby.clone();
+    finished.game_state = GameState::Closed;
+    finished.winner = Some(TeamPosition::Left);
+    for event in changes(Some(&lobby), &finished) {
+        events.publish(event);
+    }
+
+    for client in &mut clients {
+        let (name, data) = client.next_event();
+        assert_eq!(name, "game-state");
+        assert_eq!(data["game_state"], "Closed");
+        let (name, data) = client.next_event();
+        assert_eq!(name, "match-result");
+        assert_eq!(data["winner"], "Left");
+    }
+}
+
+#[test]
+fn disconnected_clients_are_dropped() {
+    let events = Arc::new(LiveEvents::default());
+    let addr = events_server(events.clone());
+
+    let mut staying = Client::connect(addr);
+    let leaving = Client::connect(addr);
+    wait_for_subscribers(&events, 2);
+    drop(leaving);
+
+    // The stream only notices once a write fails.
+    let started = Instant::now();
+    while events.subscriber_count() != 1 {
+        assert!(started.elapsed() < TIMEOUT, "closed client was kept");
+        events.publish(LiveEvent::GameState(GameState::Menu));
+        thread::sleep(Duration::from_millis(20));
+    }
+
+    let (name, data) = staying.next_event();
+    assert_eq!(name, "game-state");
+    assert_eq!(data["game_state"], "Menu");
+}
+
+#[test]
+fn reloads_reach_open_pages_but_are_not_replayed() {
+    let events = LiveEvents::default();
+    let (_, receiver) = events.subscribe();
+
+    events.publish(LiveEvent::Reload);
+
+    assert!(matches!(receiver.try_recv(), Ok(LiveEvent::Reload)));
+    let (replay, _) = events.subscribe();
+    assert!(replay.is_empty());
+    assert_eq!(
+        LiveEvent::Reload.to_sse().unwrap(),
+        "event: reload\ndata: {}\n\n"
+    );
+}
@@ -0,0 +1,195 @@
+//! Tests for the per-patch map stats archive and the patch-to-patch diff.
+
+use crate::map_stats_history::{
+    archive_snapshot, diff_snapshots, has_snapshot, list_snapshots, load_snapshot,
+    snapshot_file_name, MapDiff,
+};
+use crate::tests::make_temp_dir;
+use serde_json::{json, Value};
+use std::fs;
+
+/// A map stats payload shaped like `getLatestPatchMapStats`, trimmed to what the diff
+/// reads.
+fn payload(patch: &str, analysis: Value) -> Value {
+    json!({
+        "latestPatchInfo": { "value": patch, "label": format!("Patch {}", patch) },
+        "mapStats": { "analysis": analysis, "type": "mapStats" },
+        "mapInfo": {},
+    })
+}
+
+fn find<'a>(maps: &'a [MapDiff], mode: &str, map: &str) -> &'a MapDiff {
+    maps.iter()
+        .find(|m| m.mode == mode && m.map == map)
+        .unwrap_or_else(|| panic!("{} {} missing from the diff", mode, map))
+}
+
+fn approx(value: Option<f64>, expected: f64) {
+    let value = value.expect("rate should be known");
+    assert!(
+        (value - expected).abs() < 1e-9,
+        "expected {}, got {}",
+        expected,
+        value
+    );
+}
+
+#[test]
+fn snapshot_file_names_are_safe() {
+    let name = |patch| snapshot_file_name(patch).unwrap();
+    assert_eq!(name("1.5.0"), "1.5.0.json");
+    assert_eq!(name("../../evil"), "%2E%2E%2F..%2Fevil.json");
+    assert_eq!(name("2.0 hotfix/1"), "2.0%20hotfix%2F1.json");
+    assert_eq!(name("..."), "%2E%2E%2E.json");
+    assert_eq!(snapshot_file_name(""), None);
+}
+
+#[test]
+fn distinct_patches_get_distinct_files() {
+    let patches = ["1.5/0", "1.5_0", "1.5%2F0", "1.5A", "1.5a", ".", "..", "_"];
+    let names: std::collections::HashSet<_> = patches
+        .iter()
+        .map(|patch| snapshot_file_name(patch).unwrap().to_lowercase())
+        .collect();
+    assert_eq!(names.len(), patches.len());
+}
+
+#[test]
+fn archives_one_snapshot_per_patch() {
+    let dir = make_temp_dir("map_stats_history");
+
+    let first = payload(
+        "1.5.0",
+        json!({ "1v1": { "twin_beach": { "matchCount": 10 } } }),
+    );
+    let refetched = payload(
+        "1.5.0",
+        json!({ "1v1": { "twin_beach": { "matchCount": 12 } } }),
+    );
+    let next = payload(
+        "1.6.0",
+        json!({ "1v1": { "twin_beach": { "matchCount": 3 } } }),
+    );
+
+    archive_snapshot(&dir, &first).unwrap();
+    archive_snapshot(&dir, &refetched).unwrap();
+    archive_snapshot(&dir, &next).unwrap();
+
+    let patches: Vec<String> = list_snapshots(&dir).into_iter().map(|m| m.patch).collect();
+    let stored = load_snapshot(&dir, "1.5.0");
+    let has_next = has_snapshot(&dir, "1.6.0");
+
+    fs::remove_dir_all(&dir).ok();
+
+    assert_eq!(patches, vec!["1.5.0", "1.6.0"]);
+    // A refetch of the same patch replaces its snapshot instead of adding another.
+    assert_eq!(
+        stored.unwrap()["mapStats"]["analysis"]["1v1"]["twin_beach"]["matchCount"],
+        12
+    );
+    assert!(has_next);
+}
+
+#[test]
+fn payloads_without_a_patch_are_not_archived() {
+    let dir = make_temp_dir("map_stats_history_no_patch");
+    let result = archive_snapshot(&dir, &json!({ "mapStats": {} }));
+    let snapshots = list_snapshots(&dir);
+    fs::remove_dir_all(&dir).ok();
+
+    assert!(result.is_err());
+    assert!(snapshots.is_empty());
+}
+
+#[test]
+fn diffs_win_and_pick_rates_per_matchup() {
+    let old = payload(
+        "1.5.0",
+        json!({
+            "1v1": {
+                "twin_beach": {
+                    "matchCount": 100,
+                    "factionMatrix": {
+                        "WxA": { "wins": 30, "losses": 30 },
+                        "DxB": { "wins": 10, "losses": 30 },
+                    }
+                },
+                "pachino_farmlands": { "matchCount": 300, "factionMatrix": {} },
+            }
+        }),
+    );
+    let new = payload(
+        "1.6.0",
+        json!({
+            "1v1": {
+                "twin_beach": {
+                    "matchCount": 200,
+                    "factionMatrix": {
+                        "WxA": { "wins": 90, "losses": 60 },
+                        "DxB": { "wins": 25, "losses": 25 },
+                    }
+                },
+                "pachino_farmlands": { "matchCount": 200, "factionMatrix": {} },
+            }
+        }),
+    );
+
+    let diff = diff_snapshots(&old, &new);
+    assert_eq!(diff.from_patch, "1.5.0");
+    assert_eq!(diff.to_patch, "1.6.0");
+
+    let twin_beach = find(&diff.maps, "1v1", "twin_beach");
+    // 100 of 400 games before, 200 of 400 after
+    approx(twin_beach.pick_rate.before, 0.25);
+    approx(twin_beach.pick_rate.after, 0.5);
+    approx(twin_beach.pick_rate.delta, 0.25);
+
+    let wxa = twin_beach
+        .matchups
+        .iter()
+        .find(|m| m.matchup == "WxA")
+        .unwrap();
+    approx(wxa.win_rate.before, 0.5);
+    approx(wxa.win_rate.after, 0.6);
+    approx(wxa.win_rate.delta, 0.1);
+    approx(wxa.pick_rate.before, 0.6);
+    approx(wxa.pick_rate.after, 0.75);
+    assert_eq!((wxa.games_before, wxa.games_after), (60, 150));
+
+    let dxb = twin_beach
+        .matchups
+        .iter()
+        .find(|m| m.matchup == "DxB")
+        .unwrap();
+    approx(dxb.win_rate.before, 0.25);
+    approx(dxb.win_rate.after, 0.5);
+}
+
+#[test]
+fn maps_and_matchups_from_only_one_side_are_kept() {
+    let old = payload(
+        "1.5.0",
+        json!({ "2v2": { "old_map": { "matchCount": 10, "factionMatrix": {
+            "WWxAA": { "wins": 5, "losses": 5 }
+        } } } }),
+    );
+    let new = payload(
+        "1.6.0",
+        json!({ "2v2": { "new_map": { "matchCount": 10, "factionMatrix": {} } } }),
+    );
+
+    let diff = diff_snapshots(&old, &new);
+
+    let removed = find(&diff.maps, "2v2", "old_map");
+    approx(removed.pick_rate.before, 1.0);
+    approx(removed.pick_rate.after, 0.0);
+    let matchup = &removed.matchups[0];
+    approx(matchup.win_rate.before, 0.5);
+    assert_eq!(matchup.win_rate.after, None);
+    assert_eq!(matchup.win_rate.delta, None);
+
+    let added = find(&diff.maps, "2v2", "new_map");
+    approx(added.pick_rate.before, 0.0);
+    approx(added.pick_rate.after, 1.0);
+    assert!(added.matchups.is_empty());
+}
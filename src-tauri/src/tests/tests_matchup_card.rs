@@ -0,0 +1,198 @@
+//! Tests for the matchup card, drawn with the system's fonts and the bundled icons.
+
+use crate::current_game::LastMatch;
+use crate::matchup_card::{
+    card_game, encode_png, faction_icon, rank_icon, render, CardCache, CardKey, Fonts, MatchupCard,
+};
+use crate::parse_log_file::{parse_log_file_reverse, LogFileData, TeamPosition};
+use crate::session::Standing;
+use image::Rgba;
+
+/// A 1v1 the local player UMirinBrah? won as the British.
+fn game() -> LogFileData {
+    parse_log_file_reverse("./test_assets/warnings-2.log".to_string())
+}
+
+fn standing(rating: i64, rank: Option<i64>) -> Standing {
+    Standing {
+        rating: Some(rating),
+        games: 50,
+        rank,
+    }
+}
+
+/// Icons as the app bundles them.
+fn bundled_icon(path: &str) -> Option<Vec<u8>> {
+    std::fs::read(format!("../public/{}", path)).ok()
+}
+
+#[test]
+fn rank_icons_follow_the_frontend_tiers() {
+    let cases = [
+        (standing(1216, Some(345)), "03_iron_01"),
+        (standing(0, Some(9000)), "01_brass_03"),
+        (standing(1599, Some(60)), "05_gold_02"),
+        (standing(1700, Some(51)), "05_gold_01"),
+        (standing(1700, Some(50)), "06_master_05"),
+        (standing(1800, Some(6)), "06_master_03"),
+        (standing(2000, Some(1)), "06_master_01"),
+        // Not ranked yet
+        (standing(1300, None), "00_placement"),
+        (
+            Standing {
+                rating: None,
+                games: 0,
+                rank: None,
+            },
+            "00_placement",
+        ),
+    ];
+    for (standing, tier) in cases {
+        assert_eq!(
+            rank_icon(&standing),
+            format!("icons/ranks/{}_medium.webp", tier),
+            "{:?}",
+            standing
+        );
+        assert!(bundled_icon(&rank_icon(&standing)).is_some());
+    }
+    assert!(bundled_icon(&faction_icon("british")).is_some());
+}
+
+#[test]
+fn cards_are_built_from_the_lobby() {
+    let game = game();
+    let card = MatchupCard::from_game(&game, true, |player| {
+        (player.name == "UMirinBrah?").then(|| standing(1216, Some(345)))
+    });
+
+    assert_eq!(card.mode, "1v1");
+    assert_eq!(card.winner, Some(TeamPosition::Right));
+    assert_eq!(card.local_player_won, Some(true));
+    let local = &card.right[0];
+    assert_eq!(
+        (local.name.as_str(), local.faction.as_str(), local.local),
+        ("UMirinBrah?", "british", true)
+    );
+    assert_eq!(local.standing, Some(standing(1216, Some(345))));
+    let opponent = &card.left[0];
+    assert_eq!(
+        (opponent.name.as_str(), opponent.faction.as_str()),
+        ("Imperial Dane", "german")
+    );
+    assert!(!opponent.local);
+    assert_eq!(opponent.standing, None);
+
+    let without_result = MatchupCard::from_game(&game, false, |_| None);
+    assert_eq!(without_result.winner, None);
+    assert_eq!(without_result.local_player_won, None);
+}
+
+#[test]
+fn the_last_match_is_used_until_a_lobby_is_seen() {
+    let finished = game();
+    let last_match = LastMatch::from_game(&finished);
+
+    let mut empty = finished.clone();
+    empty.left.players.clear();
+    empty.right.players.clear();
+    empty.map = "not this one".to_string();
+    assert_eq!(
+        card_game(Some(empty), last_match.clone()).map(|game| game.map),
+        Some(finished.map.clone())
+    );
+
+    let mut current = finished.clone();
+    current.map = "current".to_string();
+    assert_eq!(
+        card_game(Some(current), last_match).map(|game| game.map),
+        Some("current".to_string())
+    );
+    assert!(card_game(None, None).is_none());
+}
+
+#[test]
+fn drawn_cards_are_kept_per_lobby_and_result() {
+    let finished = game();
+    let mut running = finished.clone();
+    running.winner = None;
+    let cache = CardCache::default();
+
+    let first = cache.cell(&CardKey::of(&finished, true));
+    assert!(first.set(vec![1]).is_ok());
+    assert!(std::sync::Arc::ptr_eq(
+        &first,
+        &cache.cell(&CardKey::of(&finished, true))
+    ));
+    // The result changes the card, and only if it is shown.
+    assert_ne!(CardKey::of(&running, true), CardKey::of(&finished, true));
+    assert_eq!(CardKey::of(&running, false), CardKey::of(&finished, false));
+    assert!(cache.cell(&CardKey::of(&finished, false)).get().is_none());
+    assert_eq!(
+        cache.cell(&CardKey::of(&finished, true)).get(),
+        Some(&vec![1])
+    );
+
+    // A new lobby drops the old cards.
+    let mut next = finished.clone();
+    next.timestamp = "next".to_string();
+    assert!(cache.cell(&CardKey::of(&next, true)).get().is_none());
+    assert!(cache.cell(&CardKey::of(&finished, true)).get().is_none());
+}
+
+#[test]
+fn long_names_are_shortened_to_fit() {
+    let fonts = Fonts::system().expect("a system font");
+    let name = "A rather long player name that does not fit";
+
+    assert_eq!(fonts.fit("Short", 20.0, 300.0), "Short");
+    let fitted = fonts.fit(name, 20.0, 150.0);
+    assert!(fitted.ends_with('…'), "{}", fitted);
+    assert!(fitted.len() < name.len());
+    assert!(fonts.text_width(&fitted, 20.0) <= 150.0);
+}
+
+#[test]
+fn cards_are_drawn_with_icons_and_text() {
+    let fonts = Fonts::system().expect("a system font");
+    let card = MatchupCard::from_game(&game(), true, |_| Some(standing(1216, Some(345))));
+
+    let image = render(&card, fonts, bundled_icon);
+    // One row per player of the larger team, between header and footer
+    assert_eq!(image.dimensions(), (960, 72 + 64 + 36));
+
+    let background = Rgba([26, 27, 30, 255]);
+    let drawn = |x0: u32, y0: u32, x1: u32, y1: u32| {
+        (y0..y1)
+            .flat_map(|y| (x0..x1).map(move |x| (x, y)))
+            .filter(|(x, y)| *image.get_pixel(*x, *y) != background)
+            .count()
+    };
+    // Title
+    assert!(drawn(24, 20, 400, 50) > 100);
+    // The winning right team's row is tinted green
+    assert_eq!(*image.get_pixel(950, 100), background);
+    assert_eq!(*image.get_pixel(930, 78), Rgba([30, 58, 42, 255]));
+    // Faction and rank icons of the first player on the left
+    let icon_y = 72 + 10;
+    let row = Rgba([58, 31, 34, 255]);
+    let icon_pixels = (icon_y..icon_y + 44)
+        .flat_map(|y| (36..80).map(move |x| (x, y)))
+        .filter(|(x, y)| *image.get_pixel(*x, *y) != row)
+        .count();
+    assert!(icon_pixels > 500, "{}", icon_pixels);
+
+    let png = encode_png(&image).unwrap();
+    let decoded = image::load_from_memory(&png).unwrap();
+    assert_eq!((decoded.width(), decoded.height()), image.dimensions());
+}
+
+#[test]
+fn cards_are_drawn_without_icons() {
+    let fonts = Fonts::system().expect("a system font");
+    let card = MatchupCard::from_game(&game(), false, |_| None);
+    let image = render(&card, fonts, |_| {
+        Some(b"<html>not an image</html>".to_vec())
+    });
+    assert_eq!(image.width(), 960);
+}
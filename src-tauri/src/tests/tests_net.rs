@@ -0,0 +1,221 @@
+//! Tests for the shared HTTP client: retries, proxying and offline detection.
+
+use crate::net::{Net, NetConfig, NetError, NetworkStatus, RetryPolicy};
+use std::net::TcpListener;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tiny_http::{Response, Server};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+struct MockServer {
+    base_url: String,
+    hits: Arc<AtomicUsize>,
+    /// Request line URL and User-Agent of every request.
+    requests: Arc<Mutex<Vec<(String, String)>>>,
+}
+
+/// Answers the n-th request with `statuses[n]`, and the last status from then on.
+fn mock_server(statuses: Vec<u16>) -> MockServer {
+    let server = Server::http("127.0.0.1:0").expect("Failed to start mock server");
+    let base_url = format!("http://{}", server.server_addr());
+    let hits = Arc::new(AtomicUsize::new(0));
+    let requests = Arc::new(Mutex::new(Vec::new()));
+
+    let counter = hits.clone();
+    let seen = requests.clone();
+    thread::spawn(move || {
+        for request in server.incoming_requests() {
+            let n = counter.fetch_add(1, Ordering::SeqCst);
+            let user_agent = request
+                .headers()
+                .iter()
+                .find(|h| h.field.equiv("User-Agent"))
+                .map(|h| h.value.to_string())
+                .unwrap_or_default();
+            seen.lock()
+                .unwrap()
+                .push((request.url().to_string(), user_agent));
+            let status = statuses[n.min(statuses.len() - 1)];
+            let response = Response::from_string(r#"{"ok":true}"#).with_status_code(status);
+            let _ = request.respond(response);
+        }
+    });
+
+    MockServer {
+        base_url,
+        hits,
+        requests,
+    }
+}
+
+/// A URL nothing listens on.
+fn closed_port_url() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    drop(listener);
+    format!("http://{}/", addr)
+}
+
+fn fast_retries(max_attempts: u32) -> NetConfig {
+    NetConfig {
+        retry: RetryPolicy {
+            max_attempts,
+            base_delay: Duration::from_millis(10),
+            max_delay: Duration::from_millis(20),
+        },
+        ..NetConfig::default()
+    }
+}
+
+#[test]
+fn backoff_grows_exponentially_within_its_jitter_range() {
+    let policy = RetryPolicy {
+        max_attempts: 5,
+        base_delay: Duration::from_millis(100),
+        max_delay: Duration::from_millis(1000),
+    };
+
+    // Each delay lies between half and all of 100ms * 2^retry.
+    for (retry, full) in [(0, 100), (1, 200), (2, 400), (3, 800)] {
+        assert_eq!(policy.delay(retry, 0), Duration::from_millis(full / 2));
+        for jitter in [1, 7, 12345, u64::MAX] {
+            let delay = policy.delay(retry, jitter);
+            assert!(delay >= Duration::from_millis(full / 2), "{:?}", delay);
+            assert!(delay <= Duration::from_millis(full), "{:?}", delay);
+        }
+    }
+    // Capped at max_delay however many retries.
+    assert!(policy.delay(30, u64::MAX) <= Duration::from_millis(1000));
+}
+
+#[test]
+fn retries_server_errors_until_one_succeeds() {
+    let server = mock_server(vec![503, 500, 200]);
+    let net = Net::new(fast_retries(3)).unwrap();
+
+    let data = tauri::async_runtime::block_on(net.get_json(&server.base_url, TIMEOUT)).unwrap();
+
+    assert_eq!(data["ok"], true);
+    assert_eq!(server.hits.load(Ordering::SeqCst), 3);
+}
+
+#[test]
+fn gives_up_after_the_last_attempt() {
+    let server = mock_server(vec![503]);
+    let net = Net::new(fast_retries(2)).unwrap();
+
+    let result = tauri::async_runtime::block_on(net.get_json(&server.base_url, TIMEOUT));
+
+    assert!(matches!(result, Err(NetError::Request(_))));
+    assert_eq!(server.hits.load(Ordering::SeqCst), 2);
+    // The server answered, so the network itself is fine.
+    assert_eq!(net.status(), NetworkStatus::Online);
+}
+
+#[test]
+fn client_errors_are_not_retried() {
+    let server = mock_server(vec![404, 200]);
+    let net = Net::new(fast_retries(3)).unwrap();
+
+    let response =
+        tauri::async_runtime::block_on(net.get(&server.base_url, TIMEOUT)).expect("response");
+
+    assert_eq!(response.status().as_u16(), 404);
+    assert_eq!(server.hits.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn protocol_errors_are_not_retried() {
+    // Answers every connection with something that is not HTTP.
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/", listener.local_addr().unwrap());
+    let hits = Arc::new(AtomicUsize::new(0));
+    let counted = hits.clone();
+    thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            counted.fetch_add(1, Ordering::SeqCst);
+            let _ = std::io::Write::write_all(&mut stream, b"not http\r\n\r\n");
+        }
+    });
+    let net = Net::new(fast_retries(3)).unwrap();
+
+    let result = tauri::async_runtime::block_on(net.get(&url, TIMEOUT));
+
+    assert!(matches!(result, Err(NetError::Request(_))));
+    assert_eq!(hits.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn sends_the_app_user_agent() {
+    let server = mock_server(vec![200]);
+    let net = Net::new(NetConfig::default()).unwrap();
+
+    tauri::async_runtime::block_on(net.get_json(&server.base_url, TIMEOUT)).unwrap();
+
+    let requests = server.requests.lock().unwrap();
+    assert!(requests[0].1.starts_with("coh3-stats-desktop-app/"));
+}
+
+#[test]
+fn routes_requests_through_an_explicit_proxy() {
+    let proxy = mock_server(vec![200]);
+    let net = Net::new(NetConfig {
+        proxy: Some(proxy.base_url.clone()),
+        ..NetConfig::default()
+    })
+    .unwrap();
+
+    tauri::async_runtime::block_on(net.get_json("http://coh3stats.invalid/api/x", TIMEOUT))
+        .unwrap();
+
+    // A proxy gets the absolute URL in the request line.
+    let requests = proxy.requests.lock().unwrap();
+    assert_eq!(requests[0].0, "http://coh3stats.invalid/api/x");
+}
+
+#[test]
+fn rejects_an_invalid_proxy() {
+    let result = Net::new(NetConfig {
+        proxy: Some("not a url".to_string()),
+        ..NetConfig::default()
+    });
+
+    assert!(matches!(result, Err(NetError::Proxy(..))));
+}
+
+#[test]
+fn goes_offline_and_recovers_on_a_later_probe() {
+    let server = mock_server(vec![200]);
+    let net = Net::new(NetConfig {
+        offline_probe_interval: Some(Duration::from_millis(200)),
+        ..fast_retries(2)
+    })
+    .unwrap();
+    let changes = Arc::new(Mutex::new(Vec::new()));
+    let recorded = changes.clone();
+    net.on_status_change(move |status| recorded.lock().unwrap().push(status));
+
+    tauri::async_runtime::block_on(async {
+        assert!(net.get_json(&closed_port_url(), TIMEOUT).await.is_err());
+        assert_eq!(net.status(), NetworkStatus::Offline);
+
+        // Held back without touching the network while offline.
+        let started = Instant::now();
+        let result = net.get_json(&server.base_url, TIMEOUT).await;
+        assert!(matches!(result, Err(NetError::Offline)));
+        assert!(started.elapsed() < Duration::from_millis(100));
+        assert_eq!(server.hits.load(Ordering::SeqCst), 0);
+
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        assert!(net.get_json(&server.base_url, TIMEOUT).await.is_ok());
+    });
+
+    assert_eq!(net.status(), NetworkStatus::Online);
+    assert_eq!(
+        *changes.lock().unwrap(),
+        vec![NetworkStatus::Offline, NetworkStatus::Online]
+    );
+}
@@ -0,0 +1,326 @@
+//! Tests for the OBS integration, run against a local stand-in for obs-websocket.
+
+use crate::obs_websocket::{
+    authentication, triggers, GameTrigger, ObsAction, ObsClient, ObsConfig, ObsRule,
+};
+use crate::parse_log_file::{parse_log_file_reverse, GameState, LogFileData};
+use serde_json::{json, Value};
+use std::borrow::Cow;
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+use std::time::{Duration, Instant};
+use tungstenite::protocol::frame::coding::CloseCode;
+use tungstenite::protocol::CloseFrame;
+use tungstenite::{Message, WebSocket};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+const SALT: &str = "lM1GncleQOaCu9lT1yeUZhFYnqhsLLP1G5lAGo3ixaI=";
+const CHALLENGE: &str = "+IxH4CnCiqpX1rM9scsNynZzbOe4KhDeYcTNS3PDaeY=";
+
+fn game() -> LogFileData {
+    parse_log_file_reverse("./test_assets/warnings-2.log".to_string())
+}
+
+fn rule(trigger: GameTrigger, action: ObsAction) -> ObsRule {
+    ObsRule { trigger, action }
+}
+
+fn config(port: u16, password: &str) -> ObsConfig {
+    ObsConfig {
+        host: "127.0.0.1".to_string(),
+        port,
+        password: password.to_string(),
+        rules: vec![
+            rule(
+                GameTrigger::Loading,
+                ObsAction::SwitchScene {
+                    scene: "Loading".to_string(),
+                },
+            ),
+            rule(
+                GameTrigger::InGame,
+                ObsAction::SwitchScene {
+                    scene: "Gameplay".to_string(),
+                },
+            ),
+            rule(
+                GameTrigger::InGame,
+                ObsAction::SetSourceVisible {
+                    scene: "Gameplay".to_string(),
+                    source: "Webcam".to_string(),
+                    visible: false,
+                },
+            ),
+        ],
+    }
+}
+
+/// One client connection to the stand-in server.
+struct MockObs {
+    socket: WebSocket<TcpStream>,
+}
+
+impl MockObs {
+    /// Completes the handshake and says hello, asking for a password if there is one.
+    fn accept(listener: &TcpListener, password: Option<&str>) -> Self {
+        let (stream, _) = listener.accept().unwrap();
+        stream.set_read_timeout(Some(TIMEOUT)).unwrap();
+        let mut obs = Self {
+            socket: tungstenite::accept(stream).unwrap(),
+        };
+        let mut hello = json!({ "obsWebSocketVersion": "5.5.4", "rpcVersion": 1 });
+        if password.is_some() {
+            hello["authentication"] = json!({ "challenge": CHALLENGE, "salt": SALT });
+        }
+        obs.send(json!({ "op": 0, "d": hello }));
+        obs
+    }
+
+    fn send(&mut self, message: Value) {
+        self.socket
+            .send(Message::Text(message.to_string()))
+            .unwrap();
+    }
+
+    fn receive(&mut self) -> Value {
+        loop {
+            match self.socket.read().expect("read from the client") {
+                Message::Text(text) => return serde_json::from_str(&text).unwrap(),
+                Message::Close(_) => panic!("the client closed the connection"),
+                _ => {}
+            }
+        }
+    }
+
+    /// Reads Identify, checks the password and answers with Identified.
+    fn identify(&mut self, password: Option<&str>) {
+        let identify = self.receive();
+        assert_eq!(identify["op"], 1);
+        assert_eq!(identify["d"]["rpcVersion"], 1);
+        assert_eq!(
+            identify["d"]["authentication"].as_str(),
+            password
+                .map(|password| authentication(password, SALT, CHALLENGE))
+                .as_deref()
+        );
+        self.send(json!({ "op": 2, "d": { "negotiatedRpcVersion": 1 } }));
+    }
+
+    /// Reads a request and answers it, returning its type and data.
+    fn answer(&mut self, status: Value, data: Value) -> (String, Value) {
+        let request = self.receive();
+        assert_eq!(request["op"], 6);
+        let d = &request["d"];
+        self.send(json!({
+            "op": 7,
+            "d": {
+                "requestType": d["requestType"],
+                "requestId": d["requestId"],
+                "requestStatus": status,
+                "responseData": data,
+            },
+        }));
+        (
+            d["requestType"].as_str().unwrap().to_string(),
+            d["requestData"].clone(),
+        )
+    }
+
+    fn succeed(&mut self, data: Value) -> (String, Value) {
+        self.answer(json!({ "result": true, "code": 100 }), data)
+    }
+}
+
+fn wait_for(condition: impl Fn() -> bool) {
+    let started = Instant::now();
+    while !condition() {
+        assert!(started.elapsed() < TIMEOUT, "timed out");
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+#[test]
+fn authentication_matches_the_protocol_example() {
+    assert_eq!(
+        authentication("supersecretpassword", SALT, CHALLENGE),
+        "1Ct943GAT+6YQUUX47Ia/ncufilbe6+oD6lY+5kaCu4="
+    );
+}
+
+#[test]
+fn transitions_become_triggers() {
+    let finished = game();
+    let in_game = LogFileData {
+        game_state: GameState::InGame,
+        winner: None,
+        ..finished.clone()
+    };
+    let loading = LogFileData {
+        game_state: GameState::Loading,
+        ..in_game.clone()
+    };
+
+    // At startup
+    assert_eq!(triggers(None, &loading), vec![]);
+    assert_eq!(
+        triggers(Some(&loading), &in_game),
+        vec![GameTrigger::InGame]
+    );
+    assert_eq!(triggers(Some(&in_game), &in_game), vec![]);
+
+    // The result came in as the game went back to the menu
+    let menu = LogFileData {
+        game_state: GameState::Menu,
+        ..finished.clone()
+    };
+    assert_eq!(
+        triggers(Some(&in_game), &menu),
+        vec![GameTrigger::Menu, GameTrigger::GameOver]
+    );
+    let closed = LogFileData {
+        game_state: GameState::Closed,
+        ..menu.clone()
+    };
+    assert_eq!(triggers(Some(&menu), &closed), vec![GameTrigger::Closed]);
+
+    // A finished lobby that was never seen in progress
+    let other_lobby = LogFileData {
+        map: "another_map".to_string(),
+        ..in_game.clone()
+    };
+    assert_eq!(triggers(Some(&other_lobby), &menu), vec![GameTrigger::Menu]);
+}
+
+#[test]
+fn rules_are_stored_flat() {
+    let stored = json!([
+        { "trigger": "Loading", "type": "switch_scene", "scene": "Loading" },
+        {
+            "trigger": "GameOver",
+            "type": "set_source_visible",
+            "scene": "Gameplay",
+            "source": "Results",
+            "visible": true,
+        },
+    ]);
+    let rules: Vec<ObsRule> = serde_json::from_value(stored.clone()).unwrap();
+    assert_eq!(
+        rules[1],
+        rule(
+            GameTrigger::GameOver,
+            ObsAction::SetSourceVisible {
+                scene: "Gameplay".to_string(),
+                source: "Results".to_string(),
+                visible: true,
+            },
+        )
+    );
+    assert_eq!(serde_json::to_value(&rules).unwrap(), stored);
+}
+
+#[test]
+fn switches_scenes_and_sources_on_triggers() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let client = ObsClient::start(config(port, "supersecretpassword"));
+
+    let mut obs = MockObs::accept(&listener, Some("supersecretpassword"));
+    obs.identify(Some("supersecretpassword"));
+    wait_for(|| client.status().connected);
+    assert_eq!(
+        client.status().obs_websocket_version.as_deref(),
+        Some("5.5.4")
+    );
+
+    // Nothing is mapped to Menu, the first request is for Loading.
+    client.trigger(GameTrigger::Menu);
+    client.trigger(GameTrigger::Loading);
+    assert_eq!(
+        obs.succeed(json!({})),
+        (
+            "SetCurrentProgramScene".to_string(),
+            json!({ "sceneName": "Loading" })
+        )
+    );
+
+    client.trigger(GameTrigger::InGame);
+    assert_eq!(
+        obs.succeed(json!({})),
+        (
+            "SetCurrentProgramScene".to_string(),
+            json!({ "sceneName": "Gameplay" })
+        )
+    );
+    assert_eq!(
+        obs.succeed(json!({ "sceneItemId": 7 })),
+        (
+            "GetSceneItemId".to_string(),
+            json!({ "sceneName": "Gameplay", "sourceName": "Webcam" })
+        )
+    );
+    assert_eq!(
+        obs.succeed(json!({})),
+        (
+            "SetSceneItemEnabled".to_string(),
+            json!({ "sceneName": "Gameplay", "sceneItemId": 7, "sceneItemEnabled": false })
+        )
+    );
+
+    // A failing action is reported, the connection stays up.
+    client.trigger(GameTrigger::Loading);
+    obs.answer(
+        json!({ "result": false, "code": 600, "comment": "No scene was found by the name of `Loading`." }),
+        Value::Null,
+    );
+    wait_for(|| client.status().error.is_some());
+    assert!(client
+        .status()
+        .error
+        .unwrap()
+        .starts_with("SetCurrentProgramScene failed: No scene was found"));
+    assert!(client.status().connected);
+
+    // Stopping closes the connection.
+    drop(client);
+    assert!(matches!(obs.socket.read(), Ok(Message::Close(_))));
+}
+
+#[test]
+fn reconnects_when_obs_restarts() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let client = ObsClient::start(config(port, ""));
+
+    let mut first = MockObs::accept(&listener, None);
+    first.identify(None);
+    wait_for(|| client.status().connected);
+    drop(first);
+
+    let mut second = MockObs::accept(&listener, None);
+    second.identify(None);
+    wait_for(|| client.status().connected);
+    client.trigger(GameTrigger::Loading);
+    assert_eq!(second.succeed(json!({})).0, "SetCurrentProgramScene");
+}
+
+#[test]
+fn a_rejected_password_stops_the_connection() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let client = ObsClient::start(config(port, "wrong"));
+
+    let mut obs = MockObs::accept(&listener, Some("supersecretpassword"));
+    obs.receive();
+    obs.socket
+        .close(Some(CloseFrame {
+            code: CloseCode::from(4009),
+            reason: Cow::Borrowed("Authentication failed."),
+        }))
+        .unwrap();
+    let _ = obs.socket.flush();
+
+    wait_for(|| !client.status().running);
+    let status = client.status();
+    assert!(!status.connected);
+    assert!(status.error.unwrap().contains("password"));
+}
@@ -0,0 +1,216 @@
+//! Tests for who may use the streamer overlay server.
+
+use crate::overlay_access::{
+    check_access, check_request, generate_token, is_allowed_host, token_cookie, without_token,
+    Access, AccessPolicy,
+};
+use crate::overlay_http::{Limits, Response, Server};
+use std::io::{Read, Write};
+use std::net::{IpAddr, Ipv4Addr, TcpStream};
+
+const TOKEN: &str = "0123456789abcdef0123456789abcdef";
+const OTHER_PC: Option<IpAddr> = Some(IpAddr::V4(Ipv4Addr::new(192, 168, 1, 30)));
+const THIS_PC: Option<IpAddr> = Some(IpAddr::V4(Ipv4Addr::LOCALHOST));
+
+fn lan_policy() -> AccessPolicy {
+    AccessPolicy {
+        token: Some(TOKEN.to_string()),
+    }
+}
+
+#[test]
+fn hosts_have_to_be_localhost_or_an_ip_address() {
+    for host in [
+        "localhost",
+        "LOCALHOST:47824",
+        "127.0.0.1",
+        "127.0.0.1:47824",
+        "192.168.1.20:47824",
+        "[::1]",
+        "[::1]:47824",
+    ] {
+        assert!(is_allowed_host(host), "{}", host);
+    }
+
+    for host in [
+        "",
+        "evil.example.com",
+        "evil.example.com:47824",
+        "localhost.evil.example.com",
+        "127.0.0.1.nip.io:47824",
+        "localhost:",
+        "localhost:80:80",
+        "[::1",
+        "[::1]x",
+        "::1",
+    ] {
+        assert!(!is_allowed_host(host), "{}", host);
+    }
+}
+
+#[test]
+fn local_mode_only_checks_the_host() {
+    let policy = AccessPolicy::default();
+
+    assert_eq!(
+        check_access(&policy, "/", Some("localhost:47824"), None, None, THIS_PC),
+        Access::Allowed
+    );
+    assert_eq!(
+        check_access(
+            &policy,
+            "/",
+            Some("rebound.example.com"),
+            None,
+            None,
+            THIS_PC
+        ),
+        Access::Denied(403, "Use localhost or the IP address of this computer")
+    );
+    assert!(matches!(
+        check_access(&policy, "/", None, None, None, THIS_PC),
+        Access::Denied(403, _)
+    ));
+}
+
+#[test]
+fn lan_mode_requires_the_token_from_other_computers() {
+    let policy = lan_policy();
+    let host = Some("192.168.1.20:47824");
+
+    assert_eq!(
+        check_access(&policy, "/", host, None, None, OTHER_PC),
+        Access::Denied(401, "Access token required")
+    );
+    assert_eq!(
+        check_access(&policy, "/?token=wrong", host, None, None, OTHER_PC),
+        Access::Denied(401, "Access token required")
+    );
+    assert_eq!(
+        check_access(
+            &policy,
+            &format!("/?token={}", TOKEN),
+            host,
+            None,
+            None,
+            OTHER_PC
+        ),
+        Access::AllowedByUrlToken
+    );
+    assert_eq!(
+        check_access(
+            &policy,
+            "/api/v1/game",
+            host,
+            Some(&format!("Bearer {}", TOKEN)),
+            None,
+            OTHER_PC
+        ),
+        Access::Allowed
+    );
+    assert_eq!(
+        check_access(
+            &policy,
+            "/api/v1/events",
+            host,
+            None,
+            Some(&format!("theme=dark; overlay_token={}", TOKEN)),
+            OTHER_PC
+        ),
+        Access::Allowed
+    );
+
+    // A token one character short, or with one more
+    let short = &TOKEN[1..];
+    let long = format!("{}0", TOKEN);
+    for token in [short, long.as_str(), ""] {
+        assert!(matches!(
+            check_access(
+                &policy,
+                "/",
+                host,
+                Some(&format!("Bearer {}", token)),
+                Some(&format!("overlay_token={}", token)),
+                OTHER_PC
+            ),
+            Access::Denied(401, _)
+        ));
+    }
+}
+
+#[test]
+fn lan_mode_keeps_this_computer_working_without_the_token() {
+    let policy = lan_policy();
+
+    assert_eq!(
+        check_access(&policy, "/", Some("localhost:47824"), None, None, THIS_PC),
+        Access::Allowed
+    );
+    // The token does not make up for a foreign host name.
+    assert!(matches!(
+        check_access(
+            &policy,
+            &format!("/?token={}", TOKEN),
+            Some("rebound.example.com"),
+            None,
+            None,
+            OTHER_PC
+        ),
+        Access::Denied(403, _)
+    ));
+}
+
+#[test]
+fn the_token_is_taken_out_of_redirected_urls() {
+    assert_eq!(without_token("/?token=abc"), "/");
+    assert_eq!(
+        without_token("/overlay/1v1?token=abc&scale=2"),
+        "/overlay/1v1?scale=2"
+    );
+    assert_eq!(without_token("/?a=1&token=abc&b=2"), "/?a=1&b=2");
+    assert_eq!(without_token("/?tokens=1"), "/?tokens=1");
+    assert_eq!(without_token("/overlay/1v1"), "/overlay/1v1");
+
+    let cookie = token_cookie("abc");
+    assert!(cookie.starts_with("overlay_token=abc;"));
+    assert!(cookie.contains("HttpOnly"));
+    assert!(cookie.contains("SameSite=Strict"));
+}
+
+#[test]
+fn tokens_are_random_hex() {
+    let a = generate_token().unwrap();
+    let b = generate_token().unwrap();
+    assert_eq!(a.len(), 32);
+    assert!(a.chars().all(|c| c.is_ascii_hexdigit()));
+    assert_ne!(a, b);
+}
+
+#[test]
+fn requests_are_checked_by_their_headers() {
+    let server = Server::bind("127.0.0.1:0", Limits::default())
+        .and_then(|server| {
+            server.serve(|request| {
+                let access = check_request(&AccessPolicy::default(), &request);
+                let _ = request.respond(Response::from_string(format!("{:?}", access)));
+            })
+        })
+        .unwrap();
+    let addr = server.addr();
+
+    let get = |host: &str| {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(
+            stream,
+            "GET / HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n\r\n",
+            host
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    };
+
+    assert!(get(&addr.to_string()).ends_with("Allowed"));
+    assert!(get("rebound.example.com").contains("Denied(403"));
+}
@@ -0,0 +1,267 @@
+//! Tests for the static files served by the streamer overlay server.
+
+use crate::overlay_assets::{
+    mime_type, parse_asset_request, serve_bundled, serve_theme_file, template_path, AssetRequest,
+};
+use crate::overlay_http::{Limits, Request, Response, Server};
+use crate::tests::make_temp_dir;
+use std::fs;
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::path::{Path, PathBuf};
+
+/// Serves `theme_dir` like the overlay server does, and a single bundled faction icon.
+fn asset_server(theme_dir: PathBuf) -> SocketAddr {
+    let handler = move |request: Request| match parse_asset_request(request.url()) {
+        Some(AssetRequest::Bundled(path)) => serve_bundled(request, &path, |path| {
+            Some(if path == "factions/german.webp" {
+                (b"RIFF-webp".to_vec(), "image/webp".to_string())
+            } else {
+                // What the app's resolver does for anything it does not know
+                (b"<html>".to_vec(), "text/html".to_string())
+            })
+        }),
+        Some(AssetRequest::Theme(relative)) => serve_theme_file(request, &theme_dir, &relative),
+        Some(AssetRequest::Template(name)) => {
+            serve_theme_file(request, &theme_dir, &template_path(&name))
+        }
+        _ => {
+            let _ = request.respond(Response::empty(403));
+        }
+    };
+    Server::bind("127.0.0.1:0", Limits::default())
+        .and_then(|server| server.serve(handler))
+        .expect("Failed to start asset server")
+        .addr()
+}
+
+struct Reply {
+    status: u16,
+    headers: Vec<String>,
+    body: String,
+}
+
+impl Reply {
+    fn header(&self, name: &str) -> Option<&str> {
+        let prefix = format!("{}: ", name.to_ascii_lowercase());
+        self.headers
+            .iter()
+            .find(|h| h.to_ascii_lowercase().starts_with(&prefix))
+            .map(|h| &h[prefix.len()..])
+    }
+}
+
+fn get(addr: SocketAddr, path: &str, extra_headers: &str) -> Reply {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    write!(
+        stream,
+        "GET {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n{}\r\n",
+        path, extra_headers
+    )
+    .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+
+    let (head, body) = response.split_once("\r\n\r\n").unwrap();
+    let mut lines = head.lines();
+    let status = lines
+        .next()
+        .unwrap()
+        .split(' ')
+        .nth(1)
+        .unwrap()
+        .parse()
+        .unwrap();
+    Reply {
+        status,
+        headers: lines.map(str::to_string).collect(),
+        body: body.to_string(),
+    }
+}
+
+#[test]
+fn bundled_assets_are_limited_to_the_whitelist() {
+    assert_eq!(
+        parse_asset_request("/assets/factions/german.webp"),
+        Some(AssetRequest::Bundled("factions/german.webp".to_string()))
+    );
+    assert_eq!(
+        parse_asset_request("/assets/icons/ranks/05_gold_02_medium.webp?v=2"),
+        Some(AssetRequest::Bundled(
+            "icons/ranks/05_gold_02_medium.webp".to_string()
+        ))
+    );
+
+    for url in [
+        "/assets/index.html",
+        "/assets/factions/german.png",
+        "/assets/factions/plain/german.webp",
+        "/assets/hoorah.wav",
+        "/assets/",
+    ] {
+        assert_eq!(
+            parse_asset_request(url),
+            Some(AssetRequest::Forbidden),
+            "{}",
+            url
+        );
+    }
+}
+
+#[test]
+fn theme_paths_cannot_escape_the_theme_folder() {
+    assert_eq!(
+        parse_asset_request("/theme/fonts/My%20Font.woff2"),
+        Some(AssetRequest::Theme(
+            Path::new("fonts").join("My Font.woff2")
+        ))
+    );
+
+    for url in [
+        "/theme/../config.dat",
+        "/theme/css/../../config.dat",
+        "/theme/%2e%2e/streamerOverlay.html",
+        "/theme/%2E%2E%2Fconfig.css",
+        "/theme/..%5c..%5cwindows%5cwin.ini",
+        "/theme//etc/passwd.css",
+        "/theme/C:%5cWindows%5cstyle.css",
+        "/theme/.hidden.css",
+        "/theme/style.css%00.png",
+        "/theme/bad%zz.css",
+        "/theme/program.exe",
+        "/theme/notes",
+    ] {
+        assert_eq!(
+            parse_asset_request(url),
+            Some(AssetRequest::Forbidden),
+            "{}",
+            url
+        );
+    }
+}
+
+#[test]
+fn templates_are_plain_names() {
+    assert_eq!(
+        parse_asset_request("/overlay/team-games_4v4"),
+        Some(AssetRequest::Template("team-games_4v4".to_string()))
+    );
+    assert_eq!(
+        template_path("1v1"),
+        Path::new("templates").join("1v1.html")
+    );
+    for url in [
+        "/overlay/",
+        "/overlay/../x",
+        "/overlay/a/b",
+        "/overlay/x.html",
+    ] {
+        assert_eq!(
+            parse_asset_request(url),
+            Some(AssetRequest::Forbidden),
+            "{}",
+            url
+        );
+    }
+}
+
+#[test]
+fn other_paths_are_not_assets() {
+    assert_eq!(parse_asset_request("/"), None);
+    assert_eq!(parse_asset_request("/streamerOverlay.html"), None);
+    assert_eq!(parse_asset_request("/assetsfoo"), None);
+}
+
+#[test]
+fn mime_types_follow_the_extension() {
+    for (file, mime) in [
+        ("a.css", "text/css; charset=utf-8"),
+        ("a.WOFF2", "font/woff2"),
+        ("a.webp", "image/webp"),
+        ("a.svg", "image/svg+xml"),
+        ("a.html", "text/html; charset=utf-8"),
+    ] {
+        assert_eq!(mime_type(Path::new(file)), Some(mime), "{}", file);
+    }
+    assert_eq!(mime_type(Path::new("a.exe")), None);
+    assert_eq!(mime_type(Path::new("Makefile")), None);
+}
+
+#[test]
+fn serves_bundled_icons_with_long_caching() {
+    let addr = asset_server(make_temp_dir("overlay_bundled"));
+
+    let reply = get(addr, "/assets/factions/german.webp", "");
+    assert_eq!(reply.status, 200);
+    assert_eq!(reply.header("Content-Type"), Some("image/webp"));
+    assert_eq!(reply.header("Cache-Control"), Some("public, max-age=86400"));
+    // Same-origin only, other local web pages must not be able to read it.
+    assert_eq!(reply.header("Access-Control-Allow-Origin"), None);
+    assert_eq!(reply.body, "RIFF-webp");
+
+    // Not in the bundle: the resolver's index.html fallback is not passed on.
+    assert_eq!(get(addr, "/assets/factions/british.webp", "").status, 404);
+}
+
+#[test]
+fn serves_theme_files_and_revalidates_them() {
+    let theme = make_temp_dir("overlay_theme");
+    fs::create_dir_all(theme.join("css")).unwrap();
+    fs::write(theme.join("css").join("style.css"), "body { color: red; }").unwrap();
+    let addr = asset_server(theme);
+
+    let reply = get(addr, "/theme/css/style.css", "");
+    assert_eq!(reply.status, 200);
+    assert_eq!(
+        reply.header("Content-Type"),
+        Some("text/css; charset=utf-8")
+    );
+    assert_eq!(reply.header("Cache-Control"), Some("no-cache"));
+    assert_eq!(reply.body, "body { color: red; }");
+    let etag = reply
+        .header("ETag")
+        .expect("theme files have an ETag")
+        .to_string();
+
+    let reply = get(
+        addr,
+        "/theme/css/style.css",
+        &format!("If-None-Match: {}\r\n", etag),
+    );
+    assert_eq!(reply.status, 304);
+    assert!(reply.body.is_empty());
+
+    assert_eq!(get(addr, "/theme/css/missing.css", "").status, 404);
+    assert_eq!(get(addr, "/theme/../secret.css", "").status, 403);
+}
+
+#[test]
+fn serves_named_templates_from_the_theme_folder() {
+    let theme = make_temp_dir("overlay_templates");
+    fs::create_dir_all(theme.join("templates")).unwrap();
+    fs::write(theme.join("templates").join("1v1.html"), "<p>1v1</p>").unwrap();
+    fs::write(theme.join("templates").join("team.html"), "<p>team</p>").unwrap();
+    let addr = asset_server(theme);
+
+    let reply = get(addr, "/overlay/1v1", "");
+    assert_eq!(reply.status, 200);
+    assert_eq!(
+        reply.header("Content-Type"),
+        Some("text/html; charset=utf-8")
+    );
+    assert_eq!(reply.body, "<p>1v1</p>");
+    assert_eq!(get(addr, "/overlay/team", "").body, "<p>team</p>");
+    assert_eq!(get(addr, "/overlay/unknown", "").status, 404);
+}
+
+#[cfg(unix)]
+#[test]
+fn symlinks_out_of_the_theme_folder_are_refused() {
+    let outside = make_temp_dir("overlay_outside");
+    fs::write(outside.join("secret.css"), "secret").unwrap();
+    let theme = make_temp_dir("overlay_symlink");
+    std::os::unix::fs::symlink(outside.join("secret.css"), theme.join("linked.css")).unwrap();
+    let addr = asset_server(theme);
+
+    assert_eq!(get(addr, "/theme/linked.css", "").status, 403);
+}
@@ -0,0 +1,221 @@
+//! Tests for the streamer overlay's HTTP server: limits, timeouts and load.
+
+use crate::overlay_http::{
+    parse_head, Limits, RateLimiter, Request, RequestHead, Response, ResponseWriter, Server,
+    ServerHandle,
+};
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+fn serve(limits: Limits, handler: impl Fn(Request) + Send + Sync + 'static) -> ServerHandle {
+    Server::bind("127.0.0.1:0", limits)
+        .and_then(|server| server.serve(handler))
+        .expect("Failed to start overlay server")
+}
+
+/// Status code and body of a `GET path`.
+fn get(addr: SocketAddr, path: &str) -> (u16, String) {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(10)))
+        .unwrap();
+    write!(stream, "GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path).unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    let (head, body) = response.split_once("\r\n\r\n").unwrap_or((&response, ""));
+    let status = head
+        .split(' ')
+        .nth(1)
+        .and_then(|status| status.parse().ok())
+        .unwrap_or(0);
+    (status, body.to_string())
+}
+
+#[test]
+fn request_heads_are_parsed() {
+    assert_eq!(
+        parse_head("GET /api/v1/game?x=1 HTTP/1.1\r\nHost: localhost:47824\r\nCookie: a=b"),
+        Some(RequestHead {
+            method: "GET".to_string(),
+            url: "/api/v1/game?x=1".to_string(),
+            headers: vec![
+                ("Host".to_string(), "localhost:47824".to_string()),
+                ("Cookie".to_string(), "a=b".to_string()),
+            ],
+        })
+    );
+
+    for head in [
+        "",
+        "GET / HTTP/1.1 extra",
+        "GET http://example.com/ HTTP/1.1",
+        "GET / SPDY/3",
+        "GET / HTTP/1.1\r\nno colon",
+    ] {
+        assert_eq!(parse_head(head), None, "{:?}", head);
+    }
+}
+
+#[test]
+fn the_rate_limiter_lets_a_few_through_per_window() {
+    let limiter = RateLimiter::new(3, Duration::from_secs(60));
+    let start = Instant::now();
+
+    let allowed: Vec<bool> = (0..5)
+        .map(|i| limiter.allow_at(start + Duration::from_secs(i)))
+        .collect();
+    assert_eq!(allowed, [true, true, true, false, false]);
+
+    // The next window starts over.
+    assert!(limiter.allow_at(start + Duration::from_secs(61)));
+    assert!(limiter.allow_at(start + Duration::from_secs(62)));
+}
+
+#[test]
+fn clients_that_send_nothing_do_not_block_others() {
+    let limits = Limits {
+        max_connections: 16,
+        timeout: Duration::from_millis(1500),
+    };
+    let server = serve(limits, |request| {
+        let _ = request.respond(Response::from_string("ok"));
+    });
+
+    // More half sent requests than there are CPUs to read them on.
+    let mut stuck: Vec<TcpStream> = (0..12)
+        .map(|_| {
+            let mut stream = TcpStream::connect(server.addr()).unwrap();
+            stream.write_all(b"GET / HT").unwrap();
+            stream
+        })
+        .collect();
+    thread::sleep(Duration::from_millis(50));
+
+    let started = Instant::now();
+    assert_eq!(get(server.addr(), "/"), (200, "ok".to_string()));
+    assert!(started.elapsed() < Duration::from_secs(1));
+
+    // The stuck ones are timed out rather than kept forever.
+    for stream in &mut stuck {
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 408"), "{}", response);
+    }
+}
+
+#[test]
+fn connections_over_the_limit_are_turned_away() {
+    let limits = Limits {
+        max_connections: 2,
+        timeout: Duration::from_secs(5),
+    };
+    // Keeps "/hold" connections open, like event streams.
+    let held: Arc<Mutex<Vec<ResponseWriter>>> = Arc::default();
+    let holder = held.clone();
+    let server = serve(limits, move |request| {
+        if request.url() == "/hold" {
+            let mut writer = request.into_writer();
+            let _ = writer.write_all(b"HTTP/1.1 200 OK\r\n\r\n");
+            holder.lock().unwrap().push(writer);
+        } else {
+            let _ = request.respond(Response::from_string("ok"));
+        }
+    });
+
+    let mut streams = Vec::new();
+    for _ in 0..2 {
+        let mut stream = TcpStream::connect(server.addr()).unwrap();
+        stream
+            .write_all(b"GET /hold HTTP/1.1\r\nHost: localhost\r\n\r\n")
+            .unwrap();
+        streams.push(stream);
+    }
+    let started = Instant::now();
+    while held.lock().unwrap().len() < 2 {
+        assert!(started.elapsed() < Duration::from_secs(5));
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    assert_eq!(get(server.addr(), "/").0, 503);
+
+    held.lock().unwrap().clear();
+    assert_eq!(get(server.addr(), "/"), (200, "ok".to_string()));
+}
+
+#[test]
+fn many_clients_at_once_all_get_an_answer() {
+    let limits = Limits {
+        max_connections: 32,
+        timeout: Duration::from_secs(5),
+    };
+    let server = serve(limits, |request| {
+        // Some work, so requests overlap
+        thread::sleep(Duration::from_millis(5));
+        let _ = request.respond(Response::from_string("ok"));
+    });
+    let addr = server.addr();
+
+    let clients: Vec<_> = (0..200)
+        .map(|_| thread::spawn(move || get(addr, "/").0))
+        .collect();
+    let statuses: Vec<u16> = clients
+        .into_iter()
+        .map(|client| client.join().unwrap())
+        .collect();
+
+    assert!(
+        statuses
+            .iter()
+            .all(|status| *status == 200 || *status == 503),
+        "{:?}",
+        statuses
+    );
+    assert!(statuses.iter().filter(|status| **status == 200).count() >= 32);
+
+    // Nothing is left behind: the server still answers, and stops.
+    assert_eq!(get(addr, "/"), (200, "ok".to_string()));
+    let started = Instant::now();
+    server.stop();
+    assert!(started.elapsed() < Duration::from_secs(2));
+    assert!(TcpStream::connect(addr).is_err());
+}
+
+#[test]
+fn a_panicking_handler_does_not_take_the_server_down() {
+    let server = serve(Limits::default(), |request| {
+        if request.url() == "/panic" {
+            panic!("handler bug");
+        }
+        let _ = request.respond(Response::from_string("ok"));
+    });
+
+    let mut stream = TcpStream::connect(server.addr()).unwrap();
+    stream
+        .write_all(b"GET /panic HTTP/1.1\r\nHost: localhost\r\n\r\n")
+        .unwrap();
+    let _ = stream.read(&mut [0u8; 16]);
+
+    assert_eq!(get(server.addr(), "/"), (200, "ok".to_string()));
+}
+
+#[test]
+fn closing_returns_at_once_and_the_port_is_freed_soon_after() {
+    let server = serve(Limits::default(), |request| {
+        let _ = request.respond(Response::from_string("ok"));
+    });
+    let addr = server.addr();
+    assert_eq!(get(addr, "/"), (200, "ok".to_string()));
+
+    let started = Instant::now();
+    server.close();
+    assert!(started.elapsed() < Duration::from_millis(100));
+
+    let deadline = Instant::now() + Duration::from_secs(2);
+    while TcpStream::connect(addr).is_ok() {
+        assert!(Instant::now() < deadline, "the listener was never closed");
+        thread::sleep(Duration::from_millis(10));
+    }
+}
@@ -0,0 +1,136 @@
+//! Tests for reloading overlay pages when their files change.
+
+use crate::overlay_reload::{is_overlay_change, next_batch, OverlayWatcher, QUIET_PERIOD};
+use crate::tests::make_temp_dir;
+use notify::event::{AccessKind, CreateKind, ModifyKind};
+use notify::{Event, EventKind};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+fn event(kind: EventKind, path: &str) -> Event {
+    Event::new(kind).add_path(PathBuf::from(path))
+}
+
+#[test]
+fn a_burst_is_one_batch() {
+    let (sender, receiver) = mpsc::channel();
+    for i in 0..5 {
+        sender.send(i).unwrap();
+    }
+    let quiet = Duration::from_millis(50);
+
+    assert_eq!(
+        next_batch(&receiver, quiet, Duration::from_secs(1)),
+        Some(vec![0, 1, 2, 3, 4])
+    );
+
+    // A change after a quiet period starts the next batch.
+    thread::spawn(move || {
+        sender.send(5).unwrap();
+        thread::sleep(Duration::from_millis(20));
+        sender.send(6).unwrap();
+        thread::sleep(Duration::from_millis(200));
+        sender.send(7).unwrap();
+    });
+    assert_eq!(
+        next_batch(&receiver, quiet, Duration::from_secs(1)),
+        Some(vec![5, 6])
+    );
+    assert_eq!(
+        next_batch(&receiver, quiet, Duration::from_secs(1)),
+        Some(vec![7])
+    );
+    assert_eq!(next_batch(&receiver, quiet, Duration::from_secs(1)), None);
+}
+
+#[test]
+fn a_batch_ends_after_the_max_delay_even_if_changes_continue() {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        for i in 0..100 {
+            if sender.send(i).is_err() {
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+    });
+
+    let started = Instant::now();
+    let batch = next_batch(
+        &receiver,
+        Duration::from_millis(50),
+        Duration::from_millis(200),
+    )
+    .unwrap();
+
+    assert!(started.elapsed() < Duration::from_millis(500));
+    assert!(batch.len() > 1 && batch.len() < 100, "{}", batch.len());
+}
+
+#[test]
+fn only_changes_to_overlay_files_count() {
+    let theme = Path::new("/data/overlay-theme");
+    let modify = EventKind::Modify(ModifyKind::Any);
+
+    assert!(is_overlay_change(
+        &event(modify, "/data/streamerOverlay.html"),
+        theme
+    ));
+    assert!(is_overlay_change(
+        &event(
+            EventKind::Create(CreateKind::File),
+            "/data/overlay-theme/fonts/a.woff2"
+        ),
+        theme
+    ));
+    assert!(!is_overlay_change(
+        &event(modify, "/data/config.dat"),
+        theme
+    ));
+    assert!(!is_overlay_change(
+        &event(modify, "/data/map_stats.json"),
+        theme
+    ));
+    assert!(!is_overlay_change(
+        &event(
+            EventKind::Access(AccessKind::Any),
+            "/data/overlay-theme/style.css"
+        ),
+        theme
+    ));
+}
+
+#[test]
+fn editing_overlay_files_triggers_a_single_reload() {
+    let app_data = make_temp_dir("overlay_reload");
+    fs::create_dir_all(app_data.join("overlay-theme")).unwrap();
+    let reloads = Arc::new(AtomicUsize::new(0));
+    let counter = reloads.clone();
+    let _watcher = OverlayWatcher::start(&app_data, move || {
+        counter.fetch_add(1, Ordering::SeqCst);
+    })
+    .unwrap();
+
+    // Not an overlay file
+    fs::write(app_data.join("config.dat"), "{}").unwrap();
+    thread::sleep(QUIET_PERIOD * 3);
+    assert_eq!(reloads.load(Ordering::SeqCst), 0);
+
+    // An editor saving twice, plus the overlay page being regenerated
+    fs::write(app_data.join("overlay-theme").join("style.css"), "a").unwrap();
+    fs::write(app_data.join("overlay-theme").join("style.css"), "b").unwrap();
+    fs::write(app_data.join("streamerOverlay.html"), "<html>").unwrap();
+
+    let started = Instant::now();
+    while reloads.load(Ordering::SeqCst) == 0 {
+        assert!(started.elapsed() < Duration::from_secs(5), "no reload");
+        thread::sleep(Duration::from_millis(20));
+    }
+    thread::sleep(QUIET_PERIOD * 3);
+    assert_eq!(reloads.load(Ordering::SeqCst), 1);
+}
@@ -0,0 +1,199 @@
+//! Tests for the streamer overlay server's JSON API.
+
+use crate::current_game::LastMatch;
+use crate::overlay_http::Response;
+use crate::overlay_server::{
+    api_response, bind, card_wants_result, is_api_path, parse_bind_address, ListenSettings,
+    RunningServer,
+};
+use crate::parse_log_file::{parse_log_file_reverse, GameState, TeamPosition};
+use crate::session::{scoreboard, Session};
+use serde_json::Value;
+use std::io::{Read, Write};
+use std::net::{IpAddr, Ipv4Addr, TcpListener, TcpStream};
+
+fn parse_body(body: &str) -> Value {
+    serde_json::from_str(body).expect("API responses are JSON")
+}
+
+#[test]
+fn only_api_paths_are_routed_to_the_api() {
+    assert!(is_api_path("/api/v1/game"));
+    assert!(is_api_path("/api/v2/anything"));
+    assert!(!is_api_path("/"));
+    assert!(!is_api_path("/streamerOverlay.html"));
+    assert!(!is_api_path("/apiary"));
+}
+
+#[test]
+fn the_card_shows_the_result_unless_asked_not_to() {
+    assert!(card_wants_result("/card.png"));
+    assert!(card_wants_result("/card.png?t=123"));
+    assert!(!card_wants_result("/card.png?result=false"));
+    assert!(!card_wants_result("/card.png?t=123&result=false"));
+}
+
+#[test]
+fn game_endpoint_returns_the_parsed_game() {
+    let game = parse_log_file_reverse("./test_assets/warnings-2.log".to_string());
+
+    let (status, body) = api_response("/api/v1/game", Some(&game), None, None);
+    assert_eq!(status, 200);
+    let json = parse_body(&body);
+    assert_eq!(json["map"], game.map.as_str());
+    assert_eq!(json["game_state"], "Closed");
+    assert_eq!(json["left"]["players"].as_array().unwrap().len(), 1);
+    assert_eq!(
+        json["right"]["players"][0]["name"],
+        game.right.players[0].name
+    );
+
+    // Query strings are ignored
+    let (status, _) = api_response("/api/v1/game?t=123", Some(&game), None, None);
+    assert_eq!(status, 200);
+}
+
+#[test]
+fn endpoints_without_data_are_not_found() {
+    let (status, body) = api_response("/api/v1/game", None, None, None);
+    assert_eq!(status, 404);
+    assert!(parse_body(&body)["error"].is_string());
+
+    let (status, _) = api_response("/api/v1/last-match", None, None, None);
+    assert_eq!(status, 404);
+
+    let (status, _) = api_response("/api/v1/unknown", None, None, None);
+    assert_eq!(status, 404);
+    let (status, _) = api_response("/api/v2/game", None, None, None);
+    assert_eq!(status, 404);
+}
+
+#[test]
+fn last_match_endpoint_reports_the_result() {
+    // Wolfsindis played on the left and lost
+    let game = parse_log_file_reverse("./test_assets/warnings-1.log".to_string());
+    let last_match = LastMatch::from_game(&game).expect("the match has a result");
+
+    let (status, body) = api_response("/api/v1/last-match", None, Some(&last_match), None);
+    assert_eq!(status, 200);
+    let json = parse_body(&body);
+    assert_eq!(json["winner"], "Right");
+    assert_eq!(json["local_player_won"], false);
+    assert_eq!(json["game"]["map"], "winter_line_8p_mkii");
+}
+
+#[test]
+fn session_endpoint_returns_the_scoreboard() {
+    let board = scoreboard(&Session::new(1_000));
+
+    let (status, body) = api_response("/api/v1/session", None, None, Some(&board));
+    assert_eq!(status, 200);
+    let json = parse_body(&body);
+    assert_eq!(json["started_at"], 1_000);
+    assert_eq!(json["total"]["wins"], 0);
+    assert!(json["by_mode"].as_object().unwrap().is_empty());
+}
+
+#[test]
+fn last_match_needs_a_result() {
+    let game = parse_log_file_reverse("./test_assets/warnings-patch2-3-1.log".to_string());
+    assert_eq!(game.game_state, GameState::InGame);
+    assert!(LastMatch::from_game(&game).is_none());
+}
+
+#[test]
+fn last_match_of_an_observer_has_no_local_outcome() {
+    let mut game = parse_log_file_reverse("./test_assets/warnings-2.log".to_string());
+    assert_eq!(game.winner, Some(TeamPosition::Right));
+    game.player_name = "someone else".to_string();
+    game.player_profile_id = "".to_string();
+
+    let last_match = LastMatch::from_game(&game).unwrap();
+    assert_eq!(last_match.winner, TeamPosition::Right);
+    assert_eq!(last_match.local_player_won, None);
+}
+
+const LOCALHOST: IpAddr = IpAddr::V4(Ipv4Addr::LOCALHOST);
+
+/// A port nothing listens on, followed by `extra` more free ones.
+fn free_ports(extra: u16) -> u16 {
+    loop {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        drop(listener);
+        if port.checked_add(extra).is_some()
+            && (port..=port + extra).all(|p| TcpListener::bind(("127.0.0.1", p)).is_ok())
+        {
+            return port;
+        }
+    }
+}
+
+fn get(port: u16) -> String {
+    let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    stream
+        .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+        .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    response
+}
+
+#[test]
+fn binds_the_requested_port_when_it_is_free() {
+    let port = free_ports(0);
+    let (_server, bound) = bind(LOCALHOST, port, 10).unwrap();
+    assert_eq!(bound, port);
+}
+
+#[test]
+fn falls_back_to_the_next_free_port() {
+    let port = free_ports(2);
+    let _taken = TcpListener::bind(("127.0.0.1", port)).unwrap();
+
+    let (_server, bound) = bind(LOCALHOST, port, 2).unwrap();
+    assert_eq!(bound, port + 1);
+}
+
+#[test]
+fn fails_when_every_fallback_port_is_taken() {
+    let port = free_ports(1);
+    let _taken = TcpListener::bind(("127.0.0.1", port)).unwrap();
+    let _also_taken = TcpListener::bind(("127.0.0.1", port + 1)).unwrap();
+
+    assert!(bind(LOCALHOST, port, 1).is_err());
+}
+
+#[test]
+fn stopping_the_server_frees_its_port() {
+    let port = free_ports(0);
+    let (server, bound) = bind(LOCALHOST, port, 0).unwrap();
+    assert_eq!(bound, port);
+    let running = RunningServer::spawn(server, ListenSettings::local(port), |request| {
+        let _ = request.respond(Response::from_string("overlay"));
+    })
+    .unwrap();
+    assert!(get(port).ends_with("overlay"));
+
+    running.stop();
+
+    assert!(TcpStream::connect(("127.0.0.1", port)).is_err());
+    // ...and it can be started on it again.
+    let (_server, bound) = bind(LOCALHOST, port, 0).unwrap();
+    assert_eq!(bound, port);
+}
+
+#[test]
+fn bind_addresses_are_ip_addresses() {
+    assert_eq!(
+        parse_bind_address(""),
+        Ok(IpAddr::V4(Ipv4Addr::UNSPECIFIED))
+    );
+    assert_eq!(
+        parse_bind_address(" 192.168.1.20 "),
+        Ok(IpAddr::V4(Ipv4Addr::new(192, 168, 1, 20)))
+    );
+    assert!(parse_bind_address("::").is_ok());
+    assert!(parse_bind_address("my-pc.local").is_err());
+    assert!(parse_bind_address("192.168.1.20:47824").is_err());
+}
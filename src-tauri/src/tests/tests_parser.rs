@@ -1,5 +1,5 @@
 use crate::parse_log_file::{
-    parse_log_file_reverse, GameState, GameType, PlayerData, TeamData, TeamSide,
+    parse_log_file_reverse, GameState, GameType, PlayerData, TeamData, TeamPosition, TeamSide,
 };
 
 // ============================================================================
@@ -951,3 +951,40 @@ fn test_determine_game_type_custom_same_side() {
         GameType::Custom
     );
 }
+
+// ============================================================================
+// Match Result Tests
+// ============================================================================
+
+#[test]
+fn test_match_winner_from_result_notification() {
+    // 4v4 whose result line is cut off after the first players - the winning team list
+    // comes early enough to survive. Wolfsindis (left) lost to team index 1.
+    let result = parse_log_file_reverse("./test_assets/warnings-1.log".to_string());
+    assert_eq!(result.winner, Some(TeamPosition::Right));
+    assert_eq!(result.local_team(), Some(TeamPosition::Left));
+
+    // 1v1 won by the local player, who is on the right
+    let result = parse_log_file_reverse("./test_assets/warnings-2.log".to_string());
+    assert_eq!(result.winner, Some(TeamPosition::Right));
+    assert_eq!(result.local_team(), Some(TeamPosition::Right));
+
+    // 2v2 won by the local player's team on the left. Earlier matches in the same log
+    // were won by the other team index and must not leak into this one.
+    let result = parse_log_file_reverse("./test_assets/warnings-2v2-july2024.log".to_string());
+    assert_eq!(result.winner, Some(TeamPosition::Left));
+    assert_eq!(result.local_team(), Some(TeamPosition::Left));
+}
+
+#[test]
+fn test_match_winner_unknown_without_a_result() {
+    // Still in game
+    let result = parse_log_file_reverse("./test_assets/warnings-patch2-3-1.log".to_string());
+    assert_eq!(result.game_state, GameState::InGame);
+    assert_eq!(result.winner, None);
+
+    // Nothing played at all
+    let result = parse_log_file_reverse("./test_assets/warnings-clean-menu.log".to_string());
+    assert_eq!(result.winner, None);
+    assert_eq!(result.local_team(), None);
+}
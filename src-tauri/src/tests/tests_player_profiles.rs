@@ -0,0 +1,198 @@
+//! Tests for the shared player profile service, run against a local mock of the Relic API.
+
+use crate::net::{Net, NetConfig, RetryPolicy};
+use crate::player_profiles::ProfileService;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use tiny_http::{Header, Response, Server};
+
+struct MockRelicApi {
+    base_url: String,
+    hits: Arc<AtomicUsize>,
+}
+
+/// Serves `getpersonalstat` with `status`, echoing the requested URL back in the body.
+/// Every response is held back by `delay`, so concurrent lookups overlap.
+fn mock_relic_api(status: u16, delay: Duration) -> MockRelicApi {
+    let server = Server::http("127.0.0.1:0").expect("Failed to start mock server");
+    let base_url = format!("http://{}", server.server_addr());
+    let hits = Arc::new(AtomicUsize::new(0));
+
+    let counter = hits.clone();
+    thread::spawn(move || {
+        for request in server.incoming_requests() {
+            counter.fetch_add(1, Ordering::SeqCst);
+            thread::sleep(delay);
+            let body = format!(
+                r#"{{"result":{{"code":0,"message":"SUCCESS"}},"url":"{}"}}"#,
+                request.url()
+            );
+            let mut response = Response::from_string(body).with_status_code(status);
+            if status == 429 {
+                response.add_header(Header::from_bytes("Retry-After", "1").unwrap());
+            }
+            let _ = request.respond(response);
+        }
+    });
+
+    MockRelicApi { base_url, hits }
+}
+
+/// A client that does not retry, so every lookup is exactly one request.
+fn net() -> Net {
+    Net::new(NetConfig {
+        retry: RetryPolicy {
+            max_attempts: 1,
+            ..RetryPolicy::default()
+        },
+        ..NetConfig::default()
+    })
+    .unwrap()
+}
+
+fn service(api: &MockRelicApi, ttl: Duration) -> ProfileService {
+    ProfileService::with_limits(net(), &api.base_url, ttl, Duration::ZERO)
+}
+
+#[test]
+fn fetches_a_profile_by_relic_id() {
+    let api = mock_relic_api(200, Duration::ZERO);
+    let profiles = service(&api, Duration::from_secs(60));
+
+    let data = tauri::async_runtime::block_on(profiles.get("12345")).unwrap();
+
+    assert_eq!(data["result"]["message"], "SUCCESS");
+    assert_eq!(
+        data["url"],
+        "/community/leaderboard/getpersonalstat?profile_ids=[12345]&title=coh3"
+    );
+}
+
+#[test]
+fn serves_repeated_lookups_from_the_cache() {
+    let api = mock_relic_api(200, Duration::ZERO);
+    let profiles = service(&api, Duration::from_secs(60));
+
+    tauri::async_runtime::block_on(async {
+        profiles.get("1").await.unwrap();
+        profiles.get("1").await.unwrap();
+        profiles.get("2").await.unwrap();
+    });
+
+    assert_eq!(api.hits.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn refetches_once_the_ttl_has_passed() {
+    let api = mock_relic_api(200, Duration::ZERO);
+    let profiles = service(&api, Duration::from_millis(50));
+
+    tauri::async_runtime::block_on(async {
+        profiles.get("1").await.unwrap();
+        thread::sleep(Duration::from_millis(100));
+        profiles.get("1").await.unwrap();
+    });
+
+    assert_eq!(api.hits.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn refreshing_skips_the_cache_and_updates_it() {
+    let api = mock_relic_api(200, Duration::ZERO);
+    let profiles = service(&api, Duration::from_secs(60));
+
+    tauri::async_runtime::block_on(async {
+        profiles.get("1").await.unwrap();
+        profiles.refresh("1").await.unwrap();
+        profiles.get("1").await.unwrap();
+        assert!(profiles.refresh("1; drop").await.is_err());
+    });
+
+    assert_eq!(api.hits.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn concurrent_lookups_of_one_id_share_a_request() {
+    let api = mock_relic_api(200, Duration::from_millis(200));
+    let profiles = Arc::new(service(&api, Duration::from_secs(60)));
+
+    let results = tauri::async_runtime::block_on(async {
+        let tasks: Vec<_> = (0..8)
+            .map(|_| {
+                let profiles = profiles.clone();
+                tauri::async_runtime::spawn(async move { profiles.get("42").await })
+            })
+            .collect();
+        let mut results = Vec::new();
+        for task in tasks {
+            results.push(task.await.unwrap());
+        }
+        results
+    });
+
+    assert!(results.iter().all(|r| r.is_ok()));
+    assert_eq!(api.hits.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn failures_are_not_cached() {
+    let api = mock_relic_api(500, Duration::ZERO);
+    let profiles = service(&api, Duration::from_secs(60));
+
+    tauri::async_runtime::block_on(async {
+        assert!(profiles.get("1").await.is_err());
+        assert!(profiles.get("1").await.is_err());
+    });
+
+    assert_eq!(api.hits.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn spaces_requests_out() {
+    let api = mock_relic_api(200, Duration::ZERO);
+    let profiles = ProfileService::with_limits(
+        net(),
+        &api.base_url,
+        Duration::from_secs(60),
+        Duration::from_millis(100),
+    );
+
+    let started = Instant::now();
+    tauri::async_runtime::block_on(async {
+        for id in ["1", "2", "3"] {
+            profiles.get(id).await.unwrap();
+        }
+    });
+
+    // The second and third request each wait for their slot.
+    assert!(started.elapsed() >= Duration::from_millis(200));
+}
+
+#[test]
+fn a_rate_limit_response_pauses_further_requests() {
+    let limited = mock_relic_api(429, Duration::ZERO);
+    let profiles = service(&limited, Duration::from_secs(60));
+
+    let started = Instant::now();
+    tauri::async_runtime::block_on(async {
+        assert!(profiles.get("1").await.is_err());
+        // Retry-After: 1
+        assert!(profiles.get("2").await.is_err());
+    });
+
+    assert!(started.elapsed() >= Duration::from_secs(1));
+    assert_eq!(limited.hits.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn rejects_ids_that_are_not_numeric() {
+    let api = mock_relic_api(200, Duration::ZERO);
+    let profiles = service(&api, Duration::from_secs(60));
+
+    let result = tauri::async_runtime::block_on(profiles.get("1]&title=coh3xbl"));
+
+    assert!(result.is_err());
+    assert_eq!(api.hits.load(Ordering::SeqCst), 0);
+}
@@ -0,0 +1,198 @@
+//! Tests for the streamer session scoreboard.
+
+use crate::parse_log_file::{parse_log_file_reverse, GameType, LogFileData};
+use crate::session::{
+    finished_match, leaderboard_id, load_session, mode_of, normalize_faction, save_session,
+    scoreboard, standing, Record, Session, SessionMatch, Standing,
+};
+use crate::tests::make_temp_dir;
+use serde_json::json;
+use std::fs;
+use std::time::Duration;
+
+const HOUR: u64 = 60 * 60;
+
+fn game(log: &str) -> LogFileData {
+    parse_log_file_reverse(format!("./test_assets/{}", log))
+}
+
+/// The same match before its result came in.
+fn in_progress(game: &LogFileData) -> LogFileData {
+    LogFileData {
+        winner: None,
+        ..game.clone()
+    }
+}
+
+fn session_match(
+    key: &str,
+    mode: &str,
+    faction: &str,
+    won: bool,
+    ratings: (i64, i64),
+) -> SessionMatch {
+    SessionMatch {
+        key: key.to_string(),
+        finished_at: 1_000,
+        mode: mode.to_string(),
+        faction: faction.to_string(),
+        map: "winter_line_8p_mkii".to_string(),
+        won,
+        rating_before: Some(ratings.0),
+        rating_after: Some(ratings.1),
+    }
+}
+
+#[test]
+fn factions_and_modes_use_the_leaderboard_names() {
+    assert_eq!(normalize_faction("afrika_korps"), "dak");
+    assert_eq!(normalize_faction("british_africa"), "british");
+    assert_eq!(normalize_faction("americans_campaign"), "american");
+    assert_eq!(normalize_faction("germans"), "german");
+
+    assert_eq!(mode_of(&game("warnings-1.log")), "4v4");
+    assert_eq!(mode_of(&game("warnings-2.log")), "1v1");
+    let custom = LogFileData {
+        game_type: GameType::Custom,
+        ..game("warnings-2.log")
+    };
+    assert_eq!(mode_of(&custom), "custom");
+
+    assert_eq!(leaderboard_id("1v1", "british"), Some(2130257));
+    assert_eq!(leaderboard_id("4v4", "german"), Some(2130360));
+    assert_eq!(leaderboard_id("custom", "german"), None);
+}
+
+#[test]
+fn results_are_recorded_when_they_come_in() {
+    // UMirinBrah? won a 1v1 as the British
+    let finished = game("warnings-2.log");
+    let lobby = in_progress(&finished);
+
+    let recorded = finished_match(Some(&lobby), &finished, 5_000).expect("a new result");
+    assert_eq!(recorded.mode, "1v1");
+    assert_eq!(recorded.faction, "british");
+    assert!(recorded.won);
+    assert_eq!(recorded.finished_at, 5_000);
+
+    // Wolfsindis lost a 4v4 as Wehrmacht
+    let finished = game("warnings-1.log");
+    let recorded = finished_match(Some(&in_progress(&finished)), &finished, 5_000).unwrap();
+    assert_eq!(
+        (recorded.mode.as_str(), recorded.faction.as_str()),
+        ("4v4", "german")
+    );
+    assert!(!recorded.won);
+}
+
+#[test]
+fn results_already_in_the_log_are_not_recorded() {
+    let finished = game("warnings-2.log");
+
+    // At startup
+    assert!(finished_match(None, &finished, 0).is_none());
+    // Parsed again
+    assert!(finished_match(Some(&finished), &finished, 0).is_none());
+    // Another lobby was seen last
+    assert!(finished_match(Some(&in_progress(&game("warnings-1.log"))), &finished, 0).is_none());
+}
+
+#[test]
+fn the_scoreboard_breaks_the_session_down() {
+    let mut session = Session::new(0);
+    assert!(session.record(session_match("a", "1v1", "british", true, (1200, 1216))));
+    assert!(session.record(session_match("b", "1v1", "british", false, (1216, 1201))));
+    assert!(session.record(session_match("c", "2v2", "american", true, (1100, 1112))));
+    // The same match twice
+    assert!(!session.record(session_match("c", "2v2", "american", true, (1100, 1112))));
+    // Not rated (yet)
+    let mut unrated = session_match("d", "ai", "dak", true, (0, 0));
+    unrated.rating_after = None;
+    assert!(session.record(unrated));
+
+    let board = scoreboard(&session);
+    assert_eq!(
+        board.total,
+        Record {
+            wins: 3,
+            losses: 1,
+            rating_change: 13,
+        }
+    );
+    assert_eq!(
+        board.by_mode["1v1"],
+        Record {
+            wins: 1,
+            losses: 1,
+            rating_change: 1,
+        }
+    );
+    assert_eq!(board.by_mode["ai"].rating_change, 0);
+    assert_eq!(board.by_faction["american"].wins, 1);
+    assert_eq!(board.matches.len(), 4);
+
+    assert!(session.set_rating_after("d", 0));
+    assert!(!session.set_rating_after("d", 0));
+    assert!(!session.set_rating_after("unknown", 1));
+}
+
+#[test]
+fn standings_are_read_from_the_profile() {
+    let profile = json!({
+        "result": { "code": 0, "message": "SUCCESS" },
+        "leaderboardStats": [
+            { "leaderboard_id": 2130257, "rating": 1216, "wins": 30, "losses": 20, "rank": 345 },
+            { "leaderboard_id": 2130300, "rating": 1100, "wins": 1, "losses": 0, "rank": -1 },
+        ],
+    });
+
+    assert_eq!(
+        standing(&profile, 2130257),
+        Some(Standing {
+            rating: Some(1216),
+            games: 50,
+            rank: Some(345),
+        })
+    );
+    assert_eq!(standing(&profile, 2130300).unwrap().rank, None);
+    // Never played there
+    assert_eq!(
+        standing(&profile, 2130259),
+        Some(Standing {
+            rating: None,
+            games: 0,
+            rank: None,
+        })
+    );
+    assert_eq!(standing(&json!({ "result": { "code": 5 } }), 2130257), None);
+}
+
+#[test]
+fn sessions_survive_restarts_within_the_window() {
+    let dir = make_temp_dir("session");
+    let path = dir.join("session.json");
+    let window = Duration::from_secs(12 * HOUR);
+
+    // Nothing stored yet
+    assert_eq!(load_session(&path, 100, window), Session::new(100));
+
+    let mut session = Session::new(100);
+    let mut played = session_match("a", "1v1", "british", true, (1200, 1216));
+    played.finished_at = 2 * HOUR;
+    session.record(played);
+    save_session(&path, &session).unwrap();
+
+    assert_eq!(load_session(&path, 13 * HOUR, window), session);
+    // Counted from the last match, not the start
+    assert!(!session.is_expired(14 * HOUR, window));
+    assert!(session.is_expired(14 * HOUR + 1, window));
+    assert_eq!(
+        load_session(&path, 15 * HOUR, window),
+        Session::new(15 * HOUR)
+    );
+
+    fs::write(&path, "not json").unwrap();
+    assert_eq!(load_session(&path, 100, window), Session::new(100));
+
+    fs::remove_dir_all(&dir).ok();
+}
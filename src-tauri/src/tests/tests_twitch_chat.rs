@@ -0,0 +1,264 @@
+//! Tests for the Twitch chat bot, run against a local stand-in for Twitch's IRC server.
+
+use crate::current_game::LastMatch;
+use crate::parse_log_file::{parse_log_file_reverse, LogFileData};
+use crate::session::Standing;
+use crate::twitch_chat::{
+    answer, parse_line, ChatBot, ChatBotConfig, ChatCommand, ChatData, CommandSettings, Cooldowns,
+    Endpoint, IrcMessage,
+};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A 1v1 the local player UMirinBrah? won as the British.
+fn game() -> LogFileData {
+    parse_log_file_reverse("./test_assets/warnings-2.log".to_string())
+}
+
+struct FakeData {
+    game: Option<LogFileData>,
+}
+
+impl ChatData for FakeData {
+    fn game(&self) -> Option<LogFileData> {
+        self.game.clone()
+    }
+
+    fn last_match(&self) -> Option<LastMatch> {
+        self.game.as_ref().and_then(LastMatch::from_game)
+    }
+
+    fn standing(&self, _relic_id: &str, leaderboard_id: u64) -> Option<Standing> {
+        // Only the British 1v1 leaderboard has a rank.
+        (leaderboard_id == 2130257).then_some(Standing {
+            rating: Some(1216),
+            games: 50,
+            rank: Some(345),
+        })
+    }
+}
+
+fn config(port: u16) -> ChatBotConfig {
+    let mut commands = CommandSettings::default();
+    commands.elo.cooldown_secs = 60;
+    ChatBotConfig {
+        endpoint: Endpoint {
+            host: "127.0.0.1".to_string(),
+            port,
+            tls: false,
+        },
+        ..ChatBotConfig::new("#Streamer", "", "oauth:secret", commands)
+    }
+}
+
+/// One client connection to the stand-in server.
+struct Client {
+    reader: BufReader<TcpStream>,
+}
+
+impl Client {
+    fn accept(listener: &TcpListener) -> Self {
+        let (stream, _) = listener.accept().unwrap();
+        stream.set_read_timeout(Some(TIMEOUT)).unwrap();
+        Self {
+            reader: BufReader::new(stream),
+        }
+    }
+
+    fn line(&mut self) -> String {
+        let mut line = String::new();
+        self.reader.read_line(&mut line).expect("read from the bot");
+        line.trim_end().to_string()
+    }
+
+    fn send(&mut self, line: &str) {
+        let stream = self.reader.get_mut();
+        write!(stream, "{}\r\n", line).unwrap();
+    }
+
+    fn chat(&mut self, text: &str) {
+        self.send(&format!(
+            ":viewer!viewer@viewer.tmi.twitch.tv PRIVMSG #streamer :{}",
+            text
+        ));
+    }
+
+    /// Reads the login and answers it like Twitch.
+    fn welcome(&mut self) {
+        assert_eq!(self.line(), "PASS oauth:secret");
+        assert_eq!(self.line(), "NICK streamer");
+        assert_eq!(self.line(), "JOIN #streamer");
+        self.send(":tmi.twitch.tv 001 streamer :Welcome, GLHF!");
+    }
+}
+
+fn wait_for(condition: impl Fn() -> bool) {
+    let started = Instant::now();
+    while !condition() {
+        assert!(started.elapsed() < TIMEOUT, "timed out");
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+#[test]
+fn irc_lines_are_parsed() {
+    assert_eq!(
+        parse_line(":viewer!viewer@viewer.tmi.twitch.tv PRIVMSG #streamer :!elo now\r\n"),
+        Some(IrcMessage {
+            prefix: Some("viewer!viewer@viewer.tmi.twitch.tv".to_string()),
+            command: "PRIVMSG".to_string(),
+            params: vec!["#streamer".to_string(), "!elo now".to_string()],
+        })
+    );
+    assert_eq!(
+        parse_line("PING :tmi.twitch.tv"),
+        Some(IrcMessage {
+            prefix: None,
+            command: "PING".to_string(),
+            params: vec!["tmi.twitch.tv".to_string()],
+        })
+    );
+    // Message tags are skipped
+    let tagged = parse_line("@badges=;color= :a!a@a PRIVMSG #streamer :hi").unwrap();
+    assert_eq!(tagged.command, "PRIVMSG");
+    assert_eq!(tagged.params[1], "hi");
+
+    assert_eq!(parse_line(""), None);
+    assert_eq!(parse_line(":prefix-only"), None);
+}
+
+#[test]
+fn commands_are_matched_by_their_trigger() {
+    let mut commands = CommandSettings::default();
+    assert_eq!(commands.command_of("!elo"), Some(ChatCommand::Elo));
+    assert_eq!(commands.command_of("!ELO pls"), Some(ChatCommand::Elo));
+    assert_eq!(
+        commands.command_of("  !lastgame"),
+        Some(ChatCommand::LastGame)
+    );
+    assert_eq!(commands.command_of("what is your !elo"), None);
+    assert_eq!(commands.command_of("!elos"), None);
+
+    commands.matchup.enabled = false;
+    commands.elo.trigger = "!rating".to_string();
+    assert_eq!(commands.command_of("!matchup"), None);
+    assert_eq!(commands.command_of("!elo"), None);
+    assert_eq!(commands.command_of("!rating"), Some(ChatCommand::Elo));
+}
+
+#[test]
+fn each_command_has_its_own_cooldown() {
+    let mut cooldowns = Cooldowns::default();
+    let cooldown = Duration::from_secs(30);
+    let start = Instant::now();
+
+    assert!(cooldowns.try_use(ChatCommand::Elo, cooldown, start));
+    assert!(!cooldowns.try_use(ChatCommand::Elo, cooldown, start + Duration::from_secs(29)));
+    assert!(cooldowns.try_use(
+        ChatCommand::Matchup,
+        cooldown,
+        start + Duration::from_secs(29)
+    ));
+    assert!(cooldowns.try_use(ChatCommand::Elo, cooldown, start + Duration::from_secs(30)));
+}
+
+#[test]
+fn answers_are_built_from_the_log() {
+    let data = FakeData { game: Some(game()) };
+
+    assert_eq!(
+        answer(ChatCommand::Elo, &data),
+        "UMirinBrah? - 1v1 British Forces: 1216 ELO, rank #345"
+    );
+    let matchup = answer(ChatCommand::Matchup, &data);
+    assert!(matchup.starts_with("1v1 on "), "{}", matchup);
+    assert!(
+        matchup.ends_with(
+            "Imperial Dane (Wehrmacht) vs UMirinBrah? (British Forces, 1216 ELO, rank #345)"
+        ),
+        "{}",
+        matchup
+    );
+    let last_game = answer(ChatCommand::LastGame, &data);
+    assert!(last_game.starts_with("Won 1v1 on "), "{}", last_game);
+    assert!(last_game.contains(" as British Forces ("), "{}", last_game);
+
+    let nothing = FakeData { game: None };
+    assert_eq!(answer(ChatCommand::Elo, &nothing), "No game found yet.");
+    assert_eq!(answer(ChatCommand::Matchup, &nothing), "No lobby yet.");
+    assert_eq!(
+        answer(ChatCommand::LastGame, &nothing),
+        "No finished game yet."
+    );
+}
+
+#[test]
+fn answers_chat_commands_with_cooldowns() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let bot = ChatBot::start(config(port), Arc::new(FakeData { game: Some(game()) }));
+
+    let mut client = Client::accept(&listener);
+    client.welcome();
+    wait_for(|| bot.status().connected);
+
+    client.send("PING :tmi.twitch.tv");
+    assert_eq!(client.line(), "PONG :tmi.twitch.tv");
+
+    client.chat("!elo");
+    assert_eq!(
+        client.line(),
+        "PRIVMSG #streamer :UMirinBrah? - 1v1 British Forces: 1216 ELO, rank #345"
+    );
+    // On cooldown: no answer, the next line is the one for !lastgame.
+    client.chat("!elo");
+    client.chat("hello chat");
+    client.chat("!lastgame");
+    assert!(client.line().starts_with("PRIVMSG #streamer :Won 1v1 on "));
+
+    // Stopping closes the connection.
+    drop(bot);
+    assert_eq!(client.line(), "");
+}
+
+#[test]
+fn reconnects_when_the_connection_drops() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let bot = ChatBot::start(config(port), Arc::new(FakeData { game: Some(game()) }));
+
+    let mut first = Client::accept(&listener);
+    first.welcome();
+    first.chat("!elo");
+    assert!(first.line().starts_with("PRIVMSG"));
+    drop(first);
+
+    let mut second = Client::accept(&listener);
+    second.welcome();
+    wait_for(|| bot.status().connected);
+    // The cooldown outlives the connection.
+    second.chat("!elo");
+    second.send("PING :again");
+    assert_eq!(second.line(), "PONG :again");
+}
+
+#[test]
+fn a_rejected_login_stops_the_bot() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let bot = ChatBot::start(config(port), Arc::new(FakeData { game: None }));
+
+    let mut client = Client::accept(&listener);
+    client.line();
+    client.send(":tmi.twitch.tv NOTICE * :Login authentication failed");
+
+    wait_for(|| !bot.status().running);
+    let status = bot.status();
+    assert!(!status.connected);
+    assert!(status.error.unwrap().contains("OAuth token"));
+}
@@ -0,0 +1,227 @@
+//! Tests for the lobby win-probability estimator.
+
+use crate::parse_log_file::{
+    get_team_data, GameState, GameType, LogFileData, PlayerData, TeamPosition, TeamSide,
+};
+use crate::win_probability::{
+    estimate, faction_matrix_key, game_mode, local_team, Confidence, FactorKind,
+};
+use serde_json::{json, Value};
+
+fn player(name: &str, relic_id: &str, faction: &str, rank: i64) -> PlayerData {
+    PlayerData {
+        ai: false,
+        faction: faction.to_string(),
+        relic_id: relic_id.to_string(),
+        name: name.to_string(),
+        position: 0,
+        steam_id: "".to_string(),
+        rank,
+    }
+}
+
+fn lobby(map: &str, left: Vec<PlayerData>, right: Vec<PlayerData>) -> LogFileData {
+    LogFileData {
+        game_state: GameState::Loading,
+        game_type: GameType::Classic,
+        timestamp: "".to_string(),
+        duration: 0,
+        map: map.to_string(),
+        win_condition: "VictoryPoint".to_string(),
+        left: get_team_data(left),
+        right: get_team_data(right),
+        winner: None,
+        player_name: "me".to_string(),
+        player_steam_id: "".to_string(),
+        player_profile_id: "1".to_string(),
+        language_code: "en".to_string(),
+    }
+}
+
+fn map_stats(mode: &str, map: &str, entry: Value) -> Value {
+    json!({ "mapStats": { "analysis": { mode: { map: entry } } } })
+}
+
+#[test]
+fn finds_the_local_team_by_profile_id_or_name() {
+    let game = lobby(
+        "twin_beach",
+        vec![player("them", "2", "americans", -1)],
+        vec![player("me", "1", "germans", -1)],
+    );
+    assert_eq!(local_team(&game), TeamPosition::Right);
+
+    let mut by_name = game.clone();
+    by_name.player_profile_id = "".to_string();
+    assert_eq!(local_team(&by_name), TeamPosition::Right);
+
+    // Not in the lobby at all (e.g. observing): the left team is assumed.
+    let mut observer = game.clone();
+    observer.player_profile_id = "99".to_string();
+    observer.player_name = "someone else".to_string();
+    assert_eq!(local_team(&observer), TeamPosition::Left);
+}
+
+#[test]
+fn builds_mode_and_faction_matrix_keys() {
+    let axis = get_team_data(vec![
+        player("a", "1", "germans", -1),
+        player("b", "2", "afrika_korps", -1),
+    ]);
+    let allies = get_team_data(vec![
+        player("c", "3", "british_africa", -1),
+        player("d", "4", "americans", -1),
+    ]);
+    assert_eq!(faction_matrix_key(&axis, &allies).as_deref(), Some("DWxAB"));
+
+    let game = lobby(
+        "m",
+        axis.players.clone(),
+        vec![player("c", "3", "americans", -1)],
+    );
+    assert_eq!(game_mode(&game), None, "uneven teams have no map stats");
+    let game = lobby("m", axis.players, allies.players);
+    assert_eq!(game_mode(&game).as_deref(), Some("2v2"));
+}
+
+#[test]
+fn uses_the_matchup_win_rate_from_the_local_side() {
+    let stats = map_stats(
+        "1v1",
+        "twin_beach",
+        json!({ "factionMatrix": { "WxA": { "wins": 600, "losses": 400 } } }),
+    );
+
+    let as_axis = lobby(
+        "twin_beach",
+        vec![player("me", "1", "germans", -1)],
+        vec![player("them", "2", "americans", -1)],
+    );
+    let result = estimate(&as_axis, Some(&stats));
+    assert_eq!(result.factors.len(), 1);
+    assert_eq!(result.factors[0].kind, FactorKind::MapMatchup);
+    assert!((result.factors[0].probability - 0.6).abs() < 1e-9);
+    assert_eq!(result.factors[0].sample_size, 1000);
+    assert!(result.probability > 0.5 && result.probability < 0.6);
+
+    let as_allies = lobby(
+        "twin_beach",
+        vec![player("them", "2", "germans", -1)],
+        vec![player("me", "1", "americans", -1)],
+    );
+    let result = estimate(&as_allies, Some(&stats));
+    assert_eq!(result.team, TeamPosition::Right);
+    assert!((result.factors[0].probability - 0.4).abs() < 1e-9);
+    assert!(result.probability < 0.5);
+}
+
+#[test]
+fn falls_back_to_faction_win_rates_without_matchup_games() {
+    let stats = map_stats(
+        "1v1",
+        "twin_beach",
+        json!({
+            "german": { "wins": 300, "losses": 200 },
+            "american": { "wins": 200, "losses": 300 },
+            "factionMatrix": {},
+        }),
+    );
+    let game = lobby(
+        "twin_beach",
+        vec![player("me", "1", "germans", -1)],
+        vec![player("them", "2", "americans", -1)],
+    );
+
+    let result = estimate(&game, Some(&stats));
+    assert_eq!(result.factors.len(), 1);
+    assert_eq!(result.factors[0].kind, FactorKind::MapFactions);
+    // Log5 of 0.6 against 0.4
+    assert!((result.factors[0].probability - 0.6923).abs() < 1e-3);
+    assert!(result.factors[0].weight <= 0.5);
+}
+
+#[test]
+fn better_ranked_team_is_favoured() {
+    let game = lobby(
+        "unknown_map",
+        vec![player("me", "1", "germans", 10)],
+        vec![player("them", "2", "americans", 1000)],
+    );
+
+    let result = estimate(&game, None);
+    assert_eq!(result.factors.len(), 1);
+    assert_eq!(result.factors[0].kind, FactorKind::Rankings);
+    assert!(result.factors[0].probability > 0.9);
+    assert!(result.probability > 0.5);
+    assert_eq!(result.confidence, Confidence::High);
+}
+
+#[test]
+fn unranked_and_ai_players_do_not_count_as_rankings() {
+    let mut bot = player("bot", "-1", "americans", 5);
+    bot.ai = true;
+    let game = lobby(
+        "unknown_map",
+        vec![player("me", "1", "germans", 10)],
+        vec![bot],
+    );
+
+    let result = estimate(&game, None);
+    assert!(result.factors.is_empty());
+    assert_eq!(result.probability, 0.5);
+    assert_eq!(result.confidence, Confidence::None);
+}
+
+#[test]
+fn few_games_give_low_confidence() {
+    let stats = map_stats(
+        "1v1",
+        "twin_beach",
+        json!({ "factionMatrix": { "WxA": { "wins": 8, "losses": 2 } } }),
+    );
+    let game = lobby(
+        "twin_beach",
+        vec![player("me", "1", "germans", -1)],
+        vec![player("them", "2", "americans", -1)],
+    );
+
+    let result = estimate(&game, Some(&stats));
+    assert_eq!(result.confidence, Confidence::Low);
+    // 80% on ten games is pulled most of the way back towards a coin flip.
+    assert!(result.probability > 0.5 && result.probability < 0.65);
+}
+
+#[test]
+fn mixed_teams_have_no_matchup_factor() {
+    let stats = map_stats(
+        "2v2",
+        "m",
+        json!({ "factionMatrix": { "WxA": { "wins": 50, "losses": 50 } } }),
+    );
+    let game = lobby(
+        "m",
+        vec![
+            player("me", "1", "germans", -1),
+            player("mate", "3", "americans", -1),
+        ],
+        vec![
+            player("a", "2", "americans", -1),
+            player("b", "4", "germans", -1),
+        ],
+    );
+    assert_eq!(game.left.side, TeamSide::Mixed);
+
+    let result = estimate(&game, Some(&stats));
+    assert!(result
+        .factors
+        .iter()
+        .all(|f| f.kind != FactorKind::MapMatchup));
+}
+
+#[test]
+fn empty_lobby_is_a_coin_flip() {
+    let game = lobby("", Vec::new(), Vec::new());
+    let result = estimate(&game, Some(&json!({})));
+    assert_eq!(result.probability, 0.5);
+    assert_eq!(result.confidence, Confidence::None);
+}
@@ -0,0 +1,698 @@
+//! Optional Twitch chat bot answering the questions chat asks every stream.
+//!
+//! The bot logs into Twitch chat over IRC with an OAuth token the streamer supplies,
+//! joins their channel and answers `!elo`, `!matchup` and `!lastgame` (the triggers
+//! are configurable) from the parsed log and the shared Relic profile lookups. Every
+//! command has its own cooldown, so a busy chat gets one answer rather than fifty.
+//!
+//! The connection lives on its own thread and reconnects with a growing delay when it
+//! drops. A rejected login stops it, trying again would not help.
+
+use crate::config::{TWITCH_IRC_HOST, TWITCH_IRC_TLS_PORT};
+use crate::current_game::{self, LastMatch};
+use crate::dp_utils::load_from_store;
+use crate::parse_log_file::{LogFileData, PlayerData};
+use crate::player_profiles::PlayerProfilesState;
+use crate::session::{self, Standing};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Shutdown, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, Runtime};
+
+/// Twitch drops longer messages.
+const MAX_MESSAGE_CHARS: usize = 500;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+/// Twitch pings about every five minutes, longer silence means the connection is gone.
+const READ_TIMEOUT: Duration = Duration::from_secs(6 * 60);
+const MIN_RECONNECT_DELAY: Duration = Duration::from_secs(2);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(60);
+
+const ENABLED_STORE_KEY: &str = "chatBotEnabled";
+const CHANNEL_STORE_KEY: &str = "chatBotChannel";
+const USERNAME_STORE_KEY: &str = "chatBotUsername";
+const TOKEN_STORE_KEY: &str = "chatBotToken";
+const COMMANDS_STORE_KEY: &str = "chatBotCommands";
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ChatCommand {
+    Elo,
+    Matchup,
+    LastGame,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CommandSetting {
+    pub enabled: bool,
+    /// What a chat message has to start with, e.g. `!elo`. Not case sensitive.
+    pub trigger: String,
+    pub cooldown_secs: u64,
+}
+
+impl CommandSetting {
+    fn new(trigger: &str) -> Self {
+        Self {
+            enabled: true,
+            trigger: trigger.to_string(),
+            cooldown_secs: 30,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct CommandSettings {
+    pub elo: CommandSetting,
+    pub matchup: CommandSetting,
+    pub last_game: CommandSetting,
+}
+
+impl Default for CommandSettings {
+    fn default() -> Self {
+        Self {
+            elo: CommandSetting::new("!elo"),
+            matchup: CommandSetting::new("!matchup"),
+            last_game: CommandSetting::new("!lastgame"),
+        }
+    }
+}
+
+impl CommandSettings {
+    pub fn get(&self, command: ChatCommand) -> &CommandSetting {
+        match command {
+            ChatCommand::Elo => &self.elo,
+            ChatCommand::Matchup => &self.matchup,
+            ChatCommand::LastGame => &self.last_game,
+        }
+    }
+
+    /// The command the chat message `text` asks for.
+    pub fn command_of(&self, text: &str) -> Option<ChatCommand> {
+        let word = text.split_whitespace().next()?;
+        [
+            ChatCommand::Elo,
+            ChatCommand::Matchup,
+            ChatCommand::LastGame,
+        ]
+        .into_iter()
+        .find(|command| {
+            let setting = self.get(*command);
+            setting.enabled
+                && !setting.trigger.is_empty()
+                && word.eq_ignore_ascii_case(&setting.trigger)
+        })
+    }
+}
+
+/// When each command was last answered.
+#[derive(Default)]
+pub struct Cooldowns {
+    last_answered: HashMap<ChatCommand, Instant>,
+}
+
+impl Cooldowns {
+    /// Whether `command` may be answered at `now`, and if so counts it as answered.
+    pub fn try_use(&mut self, command: ChatCommand, cooldown: Duration, now: Instant) -> bool {
+        if self
+            .last_answered
+            .get(&command)
+            .is_some_and(|last| now.saturating_duration_since(*last) < cooldown)
+        {
+            return false;
+        }
+        self.last_answered.insert(command, now);
+        true
+    }
+}
+
+/// One line of IRC, without the message tags Twitch may put in front.
+#[derive(Debug, PartialEq, Eq)]
+pub struct IrcMessage {
+    pub prefix: Option<String>,
+    pub command: String,
+    pub params: Vec<String>,
+}
+
+pub fn parse_line(line: &str) -> Option<IrcMessage> {
+    let mut rest = line.trim_end_matches(['\r', '\n']);
+    if rest.starts_with('@') {
+        rest = rest.split_once(' ')?.1;
+    }
+    let prefix = match rest.strip_prefix(':') {
+        Some(prefixed) => {
+            let (prefix, after) = prefixed.split_once(' ')?;
+            rest = after;
+            Some(prefix.to_string())
+        }
+        None => None,
+    };
+
+    let (middle, trailing) = match rest.split_once(" :") {
+        Some((middle, trailing)) => (middle, Some(trailing)),
+        None => (rest, None),
+    };
+    let mut words = middle.split(' ').filter(|word| !word.is_empty());
+    let command = words.next()?.to_string();
+    let mut params: Vec<String> = words.map(str::to_string).collect();
+    params.extend(trailing.map(str::to_string));
+    Some(IrcMessage {
+        prefix,
+        command,
+        params,
+    })
+}
+
+/// What the answers are built from.
+pub trait ChatData: Send + Sync {
+    fn game(&self) -> Option<LogFileData>;
+    fn last_match(&self) -> Option<LastMatch>;
+    /// The player's place on a leaderboard, `None` when it could not be looked up.
+    fn standing(&self, relic_id: &str, leaderboard_id: u64) -> Option<Standing>;
+}
+
+pub(crate) fn faction_name(faction: &str) -> &str {
+    match faction {
+        "german" => "Wehrmacht",
+        "dak" => "DAK",
+        "american" => "US Forces",
+        "british" => "British Forces",
+        other => other,
+    }
+}
+
+fn describe_standing(standing: Standing) -> String {
+    match (standing.rating, standing.rank) {
+        (Some(rating), Some(rank)) => format!("{} ELO, rank #{}", rating, rank),
+        (Some(rating), None) => format!("{} ELO, unranked", rating),
+        (None, _) => "no games yet".to_string(),
+    }
+}
+
+/// `player`'s standing in the mode of `game`, if it has a leaderboard.
+fn standing_in(data: &dyn ChatData, game: &LogFileData, player: &PlayerData) -> Option<Standing> {
+    let faction = session::normalize_faction(&player.faction);
+    let leaderboard_id = session::leaderboard_id(&session::mode_of(game), &faction)?;
+    data.standing(&player.relic_id, leaderboard_id)
+}
+
+fn elo_answer(data: &dyn ChatData) -> String {
+    let game = data
+        .game()
+        .filter(|game| session::local_player(game).is_some())
+        .or_else(|| data.last_match().map(|last_match| last_match.game));
+    let Some(game) = game else {
+        return "No game found yet.".to_string();
+    };
+    let Some(player) = session::local_player(&game) else {
+        return "No game found yet.".to_string();
+    };
+
+    let mode = session::mode_of(&game);
+    let faction = faction_name(&session::normalize_faction(&player.faction)).to_string();
+    match standing_in(data, &game, player) {
+        Some(standing) => format!(
+            "{} - {} {}: {}",
+            player.name,
+            mode,
+            faction,
+            describe_standing(standing)
+        ),
+        None => format!(
+            "{} - {} {}: no ranking available",
+            player.name, mode, faction
+        ),
+    }
+}
+
+fn matchup_answer(data: &dyn ChatData) -> String {
+    let Some(game) = data
+        .game()
+        .filter(|game| !game.left.players.is_empty() || !game.right.players.is_empty())
+    else {
+        return "No lobby yet.".to_string();
+    };
+
+    let describe_team = |players: &[PlayerData]| {
+        players
+            .iter()
+            .map(|player| {
+                let faction =
+                    faction_name(&session::normalize_faction(&player.faction)).to_string();
+                if player.ai {
+                    return format!("{} (AI, {})", player.name, faction);
+                }
+                match standing_in(data, &game, player) {
+                    Some(standing) => {
+                        format!(
+                            "{} ({}, {})",
+                            player.name,
+                            faction,
+                            describe_standing(standing)
+                        )
+                    }
+                    None => format!("{} ({})", player.name, faction),
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" & ")
+    };
+    format!(
+        "{} on {}: {} vs {}",
+        session::mode_of(&game),
+        game.map,
+        describe_team(&game.left.players),
+        describe_team(&game.right.players)
+    )
+}
+
+fn last_game_answer(data: &dyn ChatData) -> String {
+    let Some(last_match) = data.last_match() else {
+        return "No finished game yet.".to_string();
+    };
+    let game = &last_match.game;
+    let outcome = match last_match.local_player_won {
+        Some(true) => "Won",
+        Some(false) => "Lost",
+        None => "Watched",
+    };
+    let faction = session::local_player(game)
+        .map(|player| {
+            format!(
+                " as {}",
+                faction_name(&session::normalize_faction(&player.faction))
+            )
+        })
+        .unwrap_or_default();
+    format!(
+        "{} {} on {}{} ({} min)",
+        outcome,
+        session::mode_of(game),
+        game.map,
+        faction,
+        game.duration / 60
+    )
+}
+
+/// Keeps `text` on one line and within what Twitch accepts.
+fn chat_line(text: &str) -> String {
+    text.replace(['\r', '\n'], " ")
+        .chars()
+        .take(MAX_MESSAGE_CHARS)
+        .collect()
+}
+
+/// The reply to `command`.
+pub fn answer(command: ChatCommand, data: &dyn ChatData) -> String {
+    let text = match command {
+        ChatCommand::Elo => elo_answer(data),
+        ChatCommand::Matchup => matchup_answer(data),
+        ChatCommand::LastGame => last_game_answer(data),
+    };
+    chat_line(&text)
+}
+
+/// Where to connect, Twitch unless tests point it elsewhere.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Endpoint {
+    pub host: String,
+    pub port: u16,
+    pub tls: bool,
+}
+
+impl Default for Endpoint {
+    fn default() -> Self {
+        Self {
+            host: TWITCH_IRC_HOST.to_string(),
+            port: TWITCH_IRC_TLS_PORT,
+            tls: true,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ChatBotConfig {
+    pub endpoint: Endpoint,
+    /// Account the bot talks as, in lower case.
+    pub username: String,
+    /// OAuth token, without the `oauth:` prefix.
+    pub token: String,
+    /// Channel name without the `#`, in lower case.
+    pub channel: String,
+    pub commands: CommandSettings,
+}
+
+impl ChatBotConfig {
+    /// Tidies up what users paste: `#Channel`, `oauth:abc...`.
+    pub fn new(channel: &str, username: &str, token: &str, commands: CommandSettings) -> Self {
+        let channel = channel.trim().trim_start_matches('#').to_lowercase();
+        let username = match username.trim() {
+            "" => channel.clone(),
+            username => username.to_lowercase(),
+        };
+        Self {
+            endpoint: Endpoint::default(),
+            username,
+            token: token.trim().trim_start_matches("oauth:").to_string(),
+            channel,
+            commands,
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+pub struct ChatBotStatus {
+    pub running: bool,
+    /// Logged in and in the channel.
+    pub connected: bool,
+    pub channel: Option<String>,
+    /// Why the last connection ended, or the login failed.
+    pub error: Option<String>,
+}
+
+trait Stream: Read + Write + Send {}
+impl<T: Read + Write + Send> Stream for T {}
+
+/// Opens a connection to `endpoint`. The plain socket is returned as well, shutting it
+/// down ends blocked reads.
+fn connect(endpoint: &Endpoint) -> io::Result<(Box<dyn Stream>, TcpStream)> {
+    let mut last_error = io::Error::new(io::ErrorKind::NotFound, "Host has no address");
+    let mut socket = None;
+    for addr in (endpoint.host.as_str(), endpoint.port).to_socket_addrs()? {
+        match TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT) {
+            Ok(stream) => {
+                socket = Some(stream);
+                break;
+            }
+            Err(e) => last_error = e,
+        }
+    }
+    let socket = socket.ok_or(last_error)?;
+    socket.set_read_timeout(Some(READ_TIMEOUT))?;
+    socket.set_write_timeout(Some(CONNECT_TIMEOUT))?;
+    let control = socket.try_clone()?;
+
+    if !endpoint.tls {
+        return Ok((Box::new(socket), control));
+    }
+    let tls = native_tls::TlsConnector::new()
+        .map_err(io::Error::other)?
+        .connect(&endpoint.host, socket)
+        .map_err(|e| io::Error::other(e.to_string()))?;
+    Ok((Box::new(tls), control))
+}
+
+fn send(stream: &mut dyn Write, line: &str) -> io::Result<()> {
+    stream.write_all(line.as_bytes())?;
+    stream.write_all(b"\r\n")?;
+    stream.flush()
+}
+
+enum Ended {
+    Stopped,
+    LoginFailed(String),
+    Lost(String),
+}
+
+struct Shared {
+    stop: AtomicBool,
+    /// The current connection, for `stop` to shut down.
+    socket: Mutex<Option<TcpStream>>,
+    status: Mutex<ChatBotStatus>,
+}
+
+impl Shared {
+    fn stopped(&self) -> bool {
+        self.stop.load(Ordering::SeqCst)
+    }
+
+    fn lock_socket(&self) -> std::sync::MutexGuard<'_, Option<TcpStream>> {
+        self.socket.lock().unwrap_or_else(|poisoned| {
+            warn!("Chat bot socket mutex was poisoned, recovering");
+            poisoned.into_inner()
+        })
+    }
+
+    fn lock_status(&self) -> std::sync::MutexGuard<'_, ChatBotStatus> {
+        self.status.lock().unwrap_or_else(|poisoned| {
+            warn!("Chat bot status mutex was poisoned, recovering");
+            poisoned.into_inner()
+        })
+    }
+}
+
+/// One connection, from login until it ends.
+fn run_connection(
+    config: &ChatBotConfig,
+    data: &dyn ChatData,
+    shared: &Shared,
+    cooldowns: &mut Cooldowns,
+) -> Ended {
+    let (stream, control) = match connect(&config.endpoint) {
+        Ok(connection) => connection,
+        Err(e) => return Ended::Lost(format!("Could not connect: {}", e)),
+    };
+    *shared.lock_socket() = Some(control);
+    // `stop` may have looked for the socket just before it was stored.
+    if shared.stopped() {
+        return Ended::Stopped;
+    }
+
+    let channel = format!("#{}", config.channel);
+    let mut reader = BufReader::new(stream);
+    let login = [
+        format!("PASS oauth:{}", config.token),
+        format!("NICK {}", config.username),
+        format!("JOIN {}", channel),
+    ];
+    for line in &login {
+        if let Err(e) = send(reader.get_mut(), line) {
+            return Ended::Lost(e.to_string());
+        }
+    }
+
+    let mut line = String::new();
+    loop {
+        line.clear();
+        match reader.read_line(&mut line) {
+            Ok(0) => return Ended::Lost("Connection closed by Twitch".to_string()),
+            Ok(_) => {}
+            Err(_) if shared.stopped() => return Ended::Stopped,
+            Err(e) => return Ended::Lost(e.to_string()),
+        }
+        let Some(message) = parse_line(&line) else {
+            continue;
+        };
+
+        let reply = match message.command.as_str() {
+            "PING" => Some(format!(
+                "PONG :{}",
+                message.params.first().map_or("", String::as_str)
+            )),
+            "001" => {
+                info!("Chat bot logged in, joining {}", channel);
+                let mut status = shared.lock_status();
+                status.connected = true;
+                status.error = None;
+                None
+            }
+            "NOTICE"
+                if message.params.last().is_some_and(|text| {
+                    text.contains("Login authentication failed")
+                        || text.contains("Improperly formatted auth")
+                }) =>
+            {
+                return Ended::LoginFailed(
+                    "Twitch rejected the login, check the username and OAuth token".to_string(),
+                );
+            }
+            "RECONNECT" => return Ended::Lost("Twitch asked to reconnect".to_string()),
+            "PRIVMSG" if message.params.first() == Some(&channel) => message
+                .params
+                .get(1)
+                .and_then(|text| config.commands.command_of(text))
+                .filter(|command| {
+                    let cooldown = Duration::from_secs(config.commands.get(*command).cooldown_secs);
+                    cooldowns.try_use(*command, cooldown, Instant::now())
+                })
+                .map(|command| format!("PRIVMSG {} :{}", channel, answer(command, data))),
+            _ => None,
+        };
+        if let Some(reply) = reply {
+            if let Err(e) = send(reader.get_mut(), &reply) {
+                return Ended::Lost(e.to_string());
+            }
+        }
+    }
+}
+
+fn run(config: ChatBotConfig, data: Arc<dyn ChatData>, shared: Arc<Shared>) {
+    let mut cooldowns = Cooldowns::default();
+    let mut delay = MIN_RECONNECT_DELAY;
+    while !shared.stopped() {
+        let ended = run_connection(&config, data.as_ref(), &shared, &mut cooldowns);
+        shared.lock_socket().take();
+        let was_connected = std::mem::take(&mut shared.lock_status().connected);
+        match ended {
+            Ended::Stopped => break,
+            Ended::LoginFailed(error) => {
+                warn!("Chat bot stopped: {}", error);
+                shared.lock_status().error = Some(error);
+                break;
+            }
+            Ended::Lost(error) => {
+                warn!("Chat bot connection lost: {}", error);
+                shared.lock_status().error = Some(error);
+            }
+        }
+
+        if was_connected {
+            delay = MIN_RECONNECT_DELAY;
+        }
+        let retry_at = Instant::now() + delay;
+        while !shared.stopped() && Instant::now() < retry_at {
+            thread::sleep(Duration::from_millis(100));
+        }
+        delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+    }
+    shared.lock_status().running = false;
+}
+
+/// A running chat bot. Dropping it disconnects.
+pub struct ChatBot {
+    shared: Arc<Shared>,
+}
+
+impl ChatBot {
+    pub fn start(config: ChatBotConfig, data: Arc<dyn ChatData>) -> Self {
+        let shared = Arc::new(Shared {
+            stop: AtomicBool::new(false),
+            socket: Mutex::new(None),
+            status: Mutex::new(ChatBotStatus {
+                running: true,
+                connected: false,
+                channel: Some(config.channel.clone()),
+                error: None,
+            }),
+        });
+        let thread_shared = shared.clone();
+        thread::spawn(move || run(config, data, thread_shared));
+        Self { shared }
+    }
+
+    pub fn status(&self) -> ChatBotStatus {
+        self.shared.lock_status().clone()
+    }
+}
+
+impl Drop for ChatBot {
+    fn drop(&mut self) {
+        // Tells the thread to stop without waiting for it, it may be in the middle of a
+        // connect or a profile lookup for seconds. With its socket shut down it cannot send
+        // anything anymore, and a connection it opens later is closed before logging in.
+        self.shared.stop.store(true, Ordering::SeqCst);
+        if let Some(socket) = self.shared.lock_socket().as_ref() {
+            let _ = socket.shutdown(Shutdown::Both);
+        }
+    }
+}
+
+/// Answers from the app's current game and profile lookups.
+struct AppChatData<R: Runtime> {
+    handle: AppHandle<R>,
+}
+
+impl<R: Runtime> ChatData for AppChatData<R> {
+    fn game(&self) -> Option<LogFileData> {
+        current_game::current(&self.handle)
+    }
+
+    fn last_match(&self) -> Option<LastMatch> {
+        current_game::last_match(&self.handle)
+    }
+
+    fn standing(&self, relic_id: &str, leaderboard_id: u64) -> Option<Standing> {
+        let profiles = self.handle.state::<PlayerProfilesState>();
+        match tauri::async_runtime::block_on(profiles.service.get(relic_id)) {
+            Ok(profile) => session::standing(&profile, leaderboard_id),
+            Err(e) => {
+                warn!("Chat bot could not look up a profile: {}", e);
+                None
+            }
+        }
+    }
+}
+
+/// State for the chat bot
+#[derive(Default)]
+pub struct ChatBotState {
+    bot: Mutex<Option<ChatBot>>,
+}
+
+fn lock_bot(state: &ChatBotState) -> std::sync::MutexGuard<'_, Option<ChatBot>> {
+    state.bot.lock().unwrap_or_else(|poisoned| {
+        warn!("ChatBotState mutex was poisoned, recovering");
+        poisoned.into_inner()
+    })
+}
+
+/// Whether the bot should run, per the settings.
+pub fn is_enabled<R: Runtime>(handle: &AppHandle<R>) -> bool {
+    load_from_store(handle.clone(), ENABLED_STORE_KEY).unwrap_or(false)
+}
+
+/// The bot's settings from the store.
+pub fn configured<R: Runtime>(handle: &AppHandle<R>) -> Result<ChatBotConfig, String> {
+    let text = |key| {
+        load_from_store::<R, String>(handle.clone(), key).filter(|value| !value.trim().is_empty())
+    };
+    let channel = text(CHANNEL_STORE_KEY).ok_or("Set the Twitch channel first")?;
+    let token = text(TOKEN_STORE_KEY).ok_or("Set the OAuth token first")?;
+    let username = text(USERNAME_STORE_KEY).unwrap_or_default();
+    let commands = load_from_store(handle.clone(), COMMANDS_STORE_KEY).unwrap_or_default();
+    Ok(ChatBotConfig::new(&channel, &username, &token, commands))
+}
+
+/// Starts the bot with the stored settings, replacing a running one.
+pub fn start<R: Runtime>(handle: &AppHandle<R>) -> Result<ChatBotStatus, String> {
+    let config = configured(handle)?;
+    let state = handle.state::<ChatBotState>();
+    let mut bot = lock_bot(&state);
+    // The old one must not answer next to the new one.
+    bot.take();
+    info!("Starting chat bot for #{}", config.channel);
+    let data = Arc::new(AppChatData {
+        handle: handle.clone(),
+    });
+    let started = ChatBot::start(config, data);
+    let status = started.status();
+    *bot = Some(started);
+    Ok(status)
+}
+
+/// Tauri command (re)starting the chat bot with the stored settings
+#[tauri::command]
+pub fn start_chat_bot<R: Runtime>(handle: AppHandle<R>) -> Result<ChatBotStatus, String> {
+    start(&handle)
+}
+
+/// Tauri command stopping the chat bot
+#[tauri::command]
+pub fn stop_chat_bot<R: Runtime>(handle: AppHandle<R>) -> ChatBotStatus {
+    let state = handle.state::<ChatBotState>();
+    lock_bot(&state).take();
+    ChatBotStatus::default()
+}
+
+/// Tauri command returning whether the chat bot runs and is connected
+#[tauri::command]
+pub fn chat_bot_status<R: Runtime>(handle: AppHandle<R>) -> ChatBotStatus {
+    let state = handle.state::<ChatBotState>();
+    let bot = lock_bot(&state);
+    bot.as_ref().map(ChatBot::status).unwrap_or_default()
+}
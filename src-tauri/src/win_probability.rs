@@ -0,0 +1,292 @@
+//! Expected win probability for the local player's team in the current lobby.
+//!
+//! Combines what the app already has in memory - the parsed lobby and the coh3stats map
+//! stats - into one number plus the factors behind it. Each factor is a probability for
+//! the local team and a weight in `0..=1` saying how much it can be trusted; they are
+//! added up in log-odds, so a factor with little data barely moves the result.
+
+use crate::parse_log_file::{LogFileData, TeamData, TeamPosition};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, Runtime};
+
+/// Games of a matchup on a map at which its factor gets half weight.
+const MAP_SAMPLE_HALF_WEIGHT: f64 = 100.0;
+/// Per-faction map win rates ignore who they played against, so they count for less.
+const MAP_FACTIONS_MAX_WEIGHT: f64 = 0.5;
+/// Log-odds per unit of `ln(rank)` difference: rank 10 vs rank 100 is roughly 76%.
+const RANK_LOG_ODDS_SCALE: f64 = 0.5;
+/// No single factor may claim certainty.
+const MIN_FACTOR_PROBABILITY: f64 = 0.02;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum FactorKind {
+    /// Win rate of this exact faction matchup on this map.
+    MapMatchup,
+    /// Per-faction win rates on this map - used when the matchup itself has no games.
+    MapFactions,
+    /// Leaderboard ranks of the players, where the log has them.
+    Rankings,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Factor {
+    pub kind: FactorKind,
+    /// Win probability of the local team according to this factor alone.
+    pub probability: f64,
+    /// How far this factor is trusted, `0..=1`.
+    pub weight: f64,
+    /// Games (map factors) or ranked players (rankings) behind the factor.
+    pub sample_size: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum Confidence {
+    /// Nothing to base an estimate on - the probability is a coin flip.
+    None,
+    Low,
+    Medium,
+    High,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct WinProbability {
+    /// The side the local player is on.
+    pub team: TeamPosition,
+    pub probability: f64,
+    pub confidence: Confidence,
+    /// `0..=1`, the combined weight of the factors present: `1 - Π(1 - weight)`, so
+    /// each factor adds to it and none takes it past 1.
+    pub confidence_score: f64,
+    pub factors: Vec<Factor>,
+}
+
+/// Map stats letter of a faction, as used in the `factionMatrix` keys.
+fn faction_letter(faction: &str) -> Option<char> {
+    match faction {
+        "germans" => Some('W'),
+        "afrika_korps" => Some('D'),
+        "americans" => Some('A'),
+        "british_africa" => Some('B'),
+        _ => None,
+    }
+}
+
+/// Map stats key of a faction's own win/loss record.
+fn faction_key(faction: &str) -> Option<&'static str> {
+    match faction {
+        "germans" => Some("german"),
+        "afrika_korps" => Some("dak"),
+        "americans" => Some("american"),
+        "british_africa" => Some("british"),
+        _ => None,
+    }
+}
+
+fn is_axis(faction: &str) -> bool {
+    faction == "germans" || faction == "afrika_korps"
+}
+
+fn logit(p: f64) -> f64 {
+    let p = p.clamp(MIN_FACTOR_PROBABILITY, 1.0 - MIN_FACTOR_PROBABILITY);
+    (p / (1.0 - p)).ln()
+}
+
+fn logistic(x: f64) -> f64 {
+    1.0 / (1.0 + (-x).exp())
+}
+
+fn sample_weight(games: u64) -> f64 {
+    games as f64 / (games as f64 + MAP_SAMPLE_HALF_WEIGHT)
+}
+
+/// `1v1` .. `4v4`, or `None` for uneven or empty teams - map stats only cover those.
+pub fn game_mode(lobby: &LogFileData) -> Option<String> {
+    let size = lobby.left.players.len();
+    if size == 0 || size > 4 || size != lobby.right.players.len() {
+        return None;
+    }
+    Some(format!("{}v{}", size, size))
+}
+
+/// The team the local player is on. Falls back to the left one, which is where the game
+/// puts the local player's team on its own loading screen.
+pub fn local_team(lobby: &LogFileData) -> TeamPosition {
+    lobby.local_team().unwrap_or(TeamPosition::Left)
+}
+
+/// `"WWxAB"` style key: sorted axis letters, `x`, sorted allied letters.
+pub fn faction_matrix_key(axis: &TeamData, allies: &TeamData) -> Option<String> {
+    let letters = |team: &TeamData| -> Option<String> {
+        let mut letters = team
+            .players
+            .iter()
+            .map(|p| faction_letter(&p.faction))
+            .collect::<Option<Vec<char>>>()?;
+        letters.sort_unstable();
+        Some(letters.into_iter().collect())
+    };
+    Some(format!("{}x{}", letters(axis)?, letters(allies)?))
+}
+
+fn map_matchup_factor(own: &TeamData, opponents: &TeamData, map_entry: &Value) -> Option<Factor> {
+    let own_axis = own.players.iter().all(|p| is_axis(&p.faction));
+    let opponents_axis = opponents.players.iter().all(|p| is_axis(&p.faction));
+    let own_allies = own.players.iter().all(|p| !is_axis(&p.faction));
+    let opponents_allies = opponents.players.iter().all(|p| !is_axis(&p.faction));
+
+    let (axis, allies) = if own_axis && opponents_allies {
+        (own, opponents)
+    } else if own_allies && opponents_axis {
+        (opponents, own)
+    } else {
+        return None;
+    };
+
+    let entry = &map_entry["factionMatrix"][faction_matrix_key(axis, allies)?];
+    let wins = entry["wins"].as_u64().unwrap_or(0);
+    let losses = entry["losses"].as_u64().unwrap_or(0);
+    let games = wins + losses;
+    if games == 0 {
+        return None;
+    }
+
+    // The matrix counts wins for the axis side.
+    let axis_win_rate = wins as f64 / games as f64;
+    let probability = if own_axis {
+        axis_win_rate
+    } else {
+        1.0 - axis_win_rate
+    };
+    Some(Factor {
+        kind: FactorKind::MapMatchup,
+        probability,
+        weight: sample_weight(games),
+        sample_size: games,
+    })
+}
+
+/// Mean win rate of a team's factions on the map, and the fewest games any of them had.
+fn team_faction_win_rate(team: &TeamData, map_entry: &Value) -> Option<(f64, u64)> {
+    let mut total = 0.0;
+    let mut fewest_games = u64::MAX;
+    for player in &team.players {
+        let record = &map_entry[faction_key(&player.faction)?];
+        let wins = record["wins"].as_u64().unwrap_or(0);
+        let games = wins + record["losses"].as_u64().unwrap_or(0);
+        if games == 0 {
+            return None;
+        }
+        total += wins as f64 / games as f64;
+        fewest_games = fewest_games.min(games);
+    }
+    if team.players.is_empty() {
+        return None;
+    }
+    Some((total / team.players.len() as f64, fewest_games))
+}
+
+fn map_factions_factor(own: &TeamData, opponents: &TeamData, map_entry: &Value) -> Option<Factor> {
+    let (own_rate, own_games) = team_faction_win_rate(own, map_entry)?;
+    let (opponents_rate, opponents_games) = team_faction_win_rate(opponents, map_entry)?;
+
+    // Log5: how a side with win rate `a` fares against one with win rate `b`.
+    let denominator = own_rate * (1.0 - opponents_rate) + opponents_rate * (1.0 - own_rate);
+    if denominator <= 0.0 {
+        return None;
+    }
+    let games = own_games.min(opponents_games);
+    Some(Factor {
+        kind: FactorKind::MapFactions,
+        probability: own_rate * (1.0 - opponents_rate) / denominator,
+        weight: sample_weight(games) * MAP_FACTIONS_MAX_WEIGHT,
+        sample_size: games,
+    })
+}
+
+fn rankings_factor(own: &TeamData, opponents: &TeamData) -> Option<Factor> {
+    let ranks = |team: &TeamData| -> Vec<f64> {
+        team.players
+            .iter()
+            .filter(|p| !p.ai && p.rank > 0)
+            .map(|p| (p.rank as f64).ln())
+            .collect()
+    };
+    let own_ranks = ranks(own);
+    let opponents_ranks = ranks(opponents);
+    if own_ranks.is_empty() || opponents_ranks.is_empty() {
+        return None;
+    }
+
+    let mean = |v: &[f64]| v.iter().sum::<f64>() / v.len() as f64;
+    // A lower rank is better, so the local team is favoured when the opponents' is higher.
+    let log_odds = RANK_LOG_ODDS_SCALE * (mean(&opponents_ranks) - mean(&own_ranks));
+
+    let ranked = (own_ranks.len() + opponents_ranks.len()) as u64;
+    let players = (own.players.len() + opponents.players.len()) as u64;
+    Some(Factor {
+        kind: FactorKind::Rankings,
+        probability: logistic(log_odds),
+        weight: ranked as f64 / players as f64,
+        sample_size: ranked,
+    })
+}
+
+fn confidence_level(score: f64, factors: &[Factor]) -> Confidence {
+    if factors.is_empty() {
+        Confidence::None
+    } else if score < 0.35 {
+        Confidence::Low
+    } else if score < 0.7 {
+        Confidence::Medium
+    } else {
+        Confidence::High
+    }
+}
+
+/// Estimates the local team's chance to win `lobby`, using `map_stats` when given.
+pub fn estimate(lobby: &LogFileData, map_stats: Option<&Value>) -> WinProbability {
+    let team = local_team(lobby);
+    let (own, opponents) = match team {
+        TeamPosition::Left => (&lobby.left, &lobby.right),
+        TeamPosition::Right => (&lobby.right, &lobby.left),
+    };
+
+    let mut factors = Vec::new();
+
+    let map_entry = match (map_stats, game_mode(lobby)) {
+        (Some(stats), Some(mode)) => stats["mapStats"]["analysis"][mode.as_str()]
+            .get(&lobby.map)
+            .filter(|entry| entry.is_object()),
+        _ => None,
+    };
+    if let Some(map_entry) = map_entry {
+        match map_matchup_factor(own, opponents, map_entry) {
+            Some(factor) => factors.push(factor),
+            None => factors.extend(map_factions_factor(own, opponents, map_entry)),
+        }
+    }
+    factors.extend(rankings_factor(own, opponents));
+
+    let log_odds: f64 = factors
+        .iter()
+        .map(|f| f.weight * logit(f.probability))
+        .sum();
+    let confidence_score = 1.0 - factors.iter().map(|f| 1.0 - f.weight).product::<f64>();
+
+    WinProbability {
+        team,
+        probability: logistic(log_odds),
+        confidence: confidence_level(confidence_score, &factors),
+        confidence_score,
+        factors,
+    }
+}
+
+/// Tauri command estimating the win probability of the current lobby
+#[tauri::command]
+pub fn estimate_win_probability<R: Runtime>(handle: AppHandle<R>) -> Option<WinProbability> {
+    let lobby = crate::current_game::current(&handle)?;
+    let map_stats = crate::map_stats::get_map_stats(handle);
+    Some(estimate(&lobby, map_stats.as_ref()))
+}
@@ -0,0 +1,195 @@
+import { Checkbox, Group, Input, PasswordInput, Switch, Text } from "@mantine/core";
+import React, { useEffect, useState } from "react";
+import events from "../mixpanel/mixpanel";
+import { ChatBotStatus, getChatBotStatus, startChatBot, stopChatBot } from "./chatBot";
+import {
+  ChatBotCommands,
+  useChatBotChannel,
+  useChatBotCommands,
+  useChatBotEnabled,
+  useChatBotToken,
+  useChatBotUsername,
+} from "./chatBotConfigValues";
+
+const commandLabels: Record<keyof ChatBotCommands, string> = {
+  elo: "Your ELO and rank",
+  matchup: "Current lobby with ranks",
+  last_game: "Result of the last game",
+};
+
+export const ChatBotSettings: React.FC = () => {
+  const [enabled, setEnabled] = useChatBotEnabled();
+  const [channel, setChannel] = useChatBotChannel();
+  const [username, setUsername] = useChatBotUsername();
+  const [token, setToken] = useChatBotToken();
+  const [commands, setCommands] = useChatBotCommands();
+  const [status, setStatus] = useState<ChatBotStatus>();
+  const [error, setError] = useState<string | undefined>(undefined);
+
+  useEffect(() => {
+    const refresh = () =>
+      getChatBotStatus()
+        .then(setStatus)
+        .catch((e) => console.error("Failed to get the chat bot status", e));
+    refresh();
+    // Logging in takes a moment, and connections can drop
+    const interval = setInterval(refresh, 3000);
+    return () => clearInterval(interval);
+  }, []);
+
+  const apply = async (run: boolean) => {
+    try {
+      setStatus(run ? await startChatBot() : await stopChatBot());
+      setError(undefined);
+    } catch (e) {
+      setError(`${e}`);
+    }
+  };
+
+  /** Saves a setting, restarting the bot with it if it runs */
+  const save = async <T,>(key: string, setter: (value: T) => Promise<void>, value: T) => {
+    events.settings_changed(key, key === "chatBotToken" ? "***" : `${value}`);
+    await setter(value);
+    if (enabled) {
+      await apply(true);
+    }
+  };
+
+  const updateCommand = (
+    command: keyof ChatBotCommands,
+    changes: Partial<ChatBotCommands[keyof ChatBotCommands]>,
+  ) => {
+    if (!commands) {
+      return;
+    }
+    const updated = { ...commands, [command]: { ...commands[command], ...changes } };
+    save("chatBotCommands", setCommands, updated);
+  };
+
+  const statusText = !status?.running
+    ? "Not running"
+    : status.connected
+      ? `Connected to #${status.channel}`
+      : "Connecting...";
+
+  return (
+    <>
+      <Group>
+        <Text fw={700}>Twitch chat bot</Text>
+        <Switch
+          data-testid="chat-bot-toggle"
+          onLabel="ON"
+          offLabel="OFF"
+          size="md"
+          checked={enabled === undefined ? false : enabled}
+          onChange={(event) => {
+            const checked = event.currentTarget.checked;
+            events.settings_changed("chatBotEnabled", `${checked}`);
+            setEnabled(checked);
+            apply(checked);
+          }}
+        />
+        <Text size="sm" c={status?.connected ? "green" : "dimmed"}>
+          {statusText}
+        </Text>
+      </Group>
+      {error || status?.error ? (
+        <Text c="red" size="sm">
+          {error ?? status?.error}
+        </Text>
+      ) : null}
+      <Group>
+        <Text>Channel:</Text>
+        <Input
+          data-testid="chat-bot-channel"
+          style={{ width: 200 }}
+          placeholder="your_channel"
+          defaultValue={channel}
+          key={`channel-${channel}`}
+          onBlur={(event) => {
+            const value = event.currentTarget.value.trim();
+            if (value !== channel) {
+              save("chatBotChannel", setChannel, value);
+            }
+          }}
+        />
+        <Text>Bot account:</Text>
+        <Input
+          data-testid="chat-bot-username"
+          style={{ width: 200 }}
+          placeholder="Same as the channel"
+          defaultValue={username}
+          key={`username-${username}`}
+          onBlur={(event) => {
+            const value = event.currentTarget.value.trim();
+            if (value !== username) {
+              save("chatBotUsername", setUsername, value);
+            }
+          }}
+        />
+      </Group>
+      <Group>
+        <Text>OAuth token:</Text>
+        <PasswordInput
+          data-testid="chat-bot-token"
+          style={{ width: 300 }}
+          placeholder="oauth:..."
+          defaultValue={token}
+          key={`token-${token !== undefined}`}
+          onBlur={(event) => {
+            const value = event.currentTarget.value.trim();
+            if (value !== token) {
+              save("chatBotToken", setToken, value);
+            }
+          }}
+        />
+      </Group>
+      <Text size="sm" c="dimmed">
+        The token is a chat token of the bot account (chat:read and chat:edit). Keep it
+        private, anyone with it can write in chat as that account.
+      </Text>
+      {commands
+        ? (Object.keys(commandLabels) as Array<keyof ChatBotCommands>).map((command) => (
+            <Group key={command}>
+              <Checkbox
+                data-testid={`chat-bot-command-${command}`}
+                checked={commands[command].enabled}
+                onChange={(event) =>
+                  updateCommand(command, { enabled: event.currentTarget.checked })
+                }
+              />
+              <Input
+                style={{ width: 120 }}
+                defaultValue={commands[command].trigger}
+                key={`${command}-${commands[command].trigger}`}
+                onBlur={(event) => {
+                  const trigger = event.currentTarget.value.trim();
+                  if (trigger && trigger !== commands[command].trigger) {
+                    updateCommand(command, { trigger });
+                  }
+                }}
+              />
+              <Text>{commandLabels[command]}, once every</Text>
+              <Input
+                type="number"
+                style={{ width: 80 }}
+                defaultValue={commands[command].cooldown_secs}
+                key={`${command}-${commands[command].cooldown_secs}`}
+                onBlur={(event) => {
+                  const cooldown = Number(event.currentTarget.value);
+                  if (
+                    Number.isInteger(cooldown) &&
+                    cooldown >= 0 &&
+                    cooldown !== commands[command].cooldown_secs
+                  ) {
+                    updateCommand(command, { cooldown_secs: cooldown });
+                  }
+                }}
+              />
+              <Text>seconds</Text>
+            </Group>
+          ))
+        : null}
+    </>
+  );
+};
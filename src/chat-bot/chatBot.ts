@@ -0,0 +1,18 @@
+import { invoke } from "@tauri-apps/api/core";
+
+/** Mirrors `ChatBotStatus` in twitch_chat.rs */
+export interface ChatBotStatus {
+  running: boolean;
+  /** Logged in and in the channel */
+  connected: boolean;
+  channel: string | null;
+  /** Why the last connection ended, or the login failed */
+  error: string | null;
+}
+
+/** (Re)starts the bot with the stored settings */
+export const startChatBot = () => invoke<ChatBotStatus>("start_chat_bot");
+
+export const stopChatBot = () => invoke<ChatBotStatus>("stop_chat_bot");
+
+export const getChatBotStatus = () => invoke<ChatBotStatus>("chat_bot_status");
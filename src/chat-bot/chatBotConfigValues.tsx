@@ -0,0 +1,61 @@
+import { configValueFactory } from "../config-store/configValueFactory";
+
+/** Mirrors `CommandSetting` in twitch_chat.rs */
+export interface ChatBotCommandSetting {
+  enabled: boolean;
+  /** What a chat message has to start with, not case sensitive */
+  trigger: string;
+  cooldown_secs: number;
+}
+
+/** Mirrors `CommandSettings` in twitch_chat.rs */
+export interface ChatBotCommands {
+  elo: ChatBotCommandSetting;
+  matchup: ChatBotCommandSetting;
+  last_game: ChatBotCommandSetting;
+}
+
+const [getChatBotEnabled, useChatBotEnabled] = configValueFactory<boolean>(
+  "chatBotEnabled",
+  async () => false,
+);
+
+/** Twitch channel the bot answers in */
+const [getChatBotChannel, useChatBotChannel] = configValueFactory<string>(
+  "chatBotChannel",
+  async () => "",
+);
+
+/** Account the bot talks as, the channel's own when empty */
+const [getChatBotUsername, useChatBotUsername] = configValueFactory<string>(
+  "chatBotUsername",
+  async () => "",
+);
+
+/** OAuth token of the bot's account */
+const [getChatBotToken, useChatBotToken] = configValueFactory<string>(
+  "chatBotToken",
+  async () => "",
+);
+
+const [getChatBotCommands, useChatBotCommands] = configValueFactory<ChatBotCommands>(
+  "chatBotCommands",
+  async () => ({
+    elo: { enabled: true, trigger: "!elo", cooldown_secs: 30 },
+    matchup: { enabled: true, trigger: "!matchup", cooldown_secs: 30 },
+    last_game: { enabled: true, trigger: "!lastgame", cooldown_secs: 30 },
+  }),
+);
+
+export {
+  getChatBotEnabled,
+  useChatBotEnabled,
+  getChatBotChannel,
+  useChatBotChannel,
+  getChatBotUsername,
+  useChatBotUsername,
+  getChatBotToken,
+  useChatBotToken,
+  getChatBotCommands,
+  useChatBotCommands,
+};
@@ -41,6 +41,8 @@ export interface RawGameData {
   win_condition: string;
   left: RawTeamData;
   right: RawTeamData;
+  /** Team that won the match, once the game has reported the result. */
+  winner: "Left" | "Right" | null;
   player_name: string;
   player_steam_id: string;
   player_profile_id: string;
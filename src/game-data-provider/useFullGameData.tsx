@@ -7,8 +7,7 @@ import {
   RawTeamData,
 } from "./GameData-types";
 import { useRawGameData } from "./useRawGameData";
-import { fetch } from "@tauri-apps/plugin-http";
-import config from "../config";
+import { invoke } from "@tauri-apps/api/core";
 import { MantineColor } from "@mantine/core";
 import { renderStreamerHTML } from "../streamer-overlay/renderStreamerOverlay";
 import { useLogFilePath, usePlayerProfileID } from "./configValues";
@@ -59,30 +58,31 @@ export const useFullGameData = () => {
       const gameMode = (side.players.length + "v" + side.players.length) as leaderBoardType;
       const onlyRealPlayers = side.players.filter((player) => !player.ai);
 
-      let responses = await Promise.all(
+      // Cached and de-duplicated on the Rust side, so other windows asking for the same
+      // lobby don't hit the Relic API again. A failed lookup (rate limited, offline)
+      // leaves just that player without stats.
+      const jsonResponses = await Promise.allSettled(
         onlyRealPlayers.map((player) =>
-          fetch(
-            config.BASE_RELIC_API_URL +
-              "/community/leaderboard/getpersonalstat?profile_ids=[" +
-              player.relic_id +
-              "]&title=coh3",
-            {
-              method: "GET",
-            },
-          ),
+          invoke<RawLaddersObject>("get_player_profile", { relicId: player.relic_id }),
         ),
       );
 
-      // Parse JSON responses
-      const jsonResponses = await Promise.all(
-        responses.map(async (response) => await response.json()),
-      );
-
-      let mergedResponses = jsonResponses.map((response, index) => ({
-        response,
-        relicID: onlyRealPlayers[index].relic_id,
-        faction: logFileRaceTypeToRaceType[onlyRealPlayers[index].faction],
-      }));
+      let mergedResponses = jsonResponses.flatMap((result, index) => {
+        if (result.status === "rejected") {
+          console.warn(
+            `Player profile lookup failed for ${onlyRealPlayers[index].relic_id}:`,
+            result.reason,
+          );
+          return [];
+        }
+        return [
+          {
+            response: result.value,
+            relicID: onlyRealPlayers[index].relic_id,
+            faction: logFileRaceTypeToRaceType[onlyRealPlayers[index].faction],
+          },
+        ];
+      });
 
       let refinedPlayerData = side.players.map(
         (player, index): FullPlayerData => ({
@@ -0,0 +1,105 @@
+import { Group, Input, Select, Switch, Text } from "@mantine/core";
+import React, { useEffect, useState } from "react";
+import events from "../mixpanel/mixpanel";
+import {
+  GameOverlayHotkey,
+  getGameOverlayHotkey,
+  setGameOverlayHotkey,
+} from "./gameOverlayHotkey";
+
+/** The hotkey bringing the in-game overlay back during a match */
+export const GameOverlayHotkeySettings: React.FC<{ disabled?: boolean }> = ({ disabled }) => {
+  const [hotkey, setHotkey] = useState<GameOverlayHotkey>();
+  const [error, setError] = useState<string | undefined>(undefined);
+
+  useEffect(() => {
+    getGameOverlayHotkey()
+      .then(setHotkey)
+      .catch((e) => console.error("Failed to get the game overlay hotkey", e));
+  }, []);
+
+  const save = async (config: GameOverlayHotkey) => {
+    try {
+      setHotkey(await setGameOverlayHotkey(config));
+      setError(undefined);
+      events.settings_changed("gameOverlayHotkey", JSON.stringify(config));
+    } catch (e) {
+      setError(`${e}`);
+    }
+  };
+
+  if (!hotkey) {
+    return null;
+  }
+
+  return (
+    <>
+      <Group>
+        <Switch
+          data-testid="game-overlay-hotkey-toggle"
+          aria-label="Overlay hotkey during a match"
+          onLabel="ON"
+          offLabel="OFF"
+          size="md"
+          disabled={disabled}
+          checked={hotkey.enabled}
+          onChange={(event) => save({ ...hotkey, enabled: event.currentTarget.checked })}
+        />
+        <Text>Show during a match with</Text>
+        <Input
+          data-testid="game-overlay-hotkey"
+          style={{ width: 150 }}
+          disabled={disabled}
+          defaultValue={hotkey.binding}
+          key={hotkey.binding}
+          onBlur={(event) => {
+            const binding = event.currentTarget.value.trim();
+            if (binding !== hotkey.binding) {
+              save({ ...hotkey, binding });
+            }
+          }}
+        />
+        <Select
+          style={{ width: 170 }}
+          allowDeselect={false}
+          disabled={disabled}
+          data={[
+            { value: "toggle", label: "Press to toggle" },
+            { value: "hold", label: "Hold to show" },
+          ]}
+          value={hotkey.mode}
+          onChange={(value) =>
+            value && save({ ...hotkey, mode: value as GameOverlayHotkey["mode"] })
+          }
+        />
+      </Group>
+      <Group>
+        <Text>Click players in the overlay after pressing</Text>
+        <Input
+          data-testid="game-overlay-interact-hotkey"
+          style={{ width: 150 }}
+          disabled={disabled || !hotkey.enabled}
+          defaultValue={hotkey.interact_binding}
+          key={hotkey.interact_binding}
+          onBlur={(event) => {
+            const interact_binding = event.currentTarget.value.trim();
+            if (interact_binding !== hotkey.interact_binding) {
+              save({ ...hotkey, interact_binding });
+            }
+          }}
+        />
+      </Group>
+      {error ? (
+        <Text c="red" size="sm">
+          {error}
+        </Text>
+      ) : (
+        <Text size="sm" c="dimmed">
+          Only works while CoH3 is in front. Use Ctrl or Alt with a key, or a function key, so
+          the game keeps its own hotkeys. Clicking stays possible until the mouse leaves the
+          overlay, for 20 seconds at most.
+        </Text>
+      )}
+    </>
+  );
+};
@@ -0,0 +1,48 @@
+import { Group, Select, Text } from "@mantine/core";
+import React, { useEffect, useState } from "react";
+import events from "../mixpanel/mixpanel";
+import { OverlayLayout } from "../views/GameOverlay/types";
+import {
+  findOverlayLayoutPreset,
+  getGameOverlayLayout,
+  overlayLayoutPresets,
+  setGameOverlayLayout,
+} from "./gameOverlayLayout";
+
+/** Picks where the in-game overlay sits, switching a visible overlay right away */
+export const GameOverlayLayoutSettings: React.FC<{ disabled?: boolean }> = ({ disabled }) => {
+  const [layout, setLayout] = useState<OverlayLayout>();
+
+  useEffect(() => {
+    getGameOverlayLayout()
+      .then(setLayout)
+      .catch((e) => console.error("Failed to get the game overlay layout", e));
+  }, []);
+
+  const index = findOverlayLayoutPreset(layout);
+
+  return (
+    <Group>
+      <Text>Overlay position:</Text>
+      <Select
+        data-testid="game-overlay-layout"
+        style={{ width: 200 }}
+        allowDeselect={false}
+        disabled={disabled || layout === undefined}
+        data={overlayLayoutPresets.map((preset, i) => ({ value: `${i}`, label: preset.label }))}
+        value={index === -1 ? null : `${index}`}
+        placeholder="Custom"
+        onChange={(value) => {
+          const preset = value === null ? undefined : overlayLayoutPresets[Number(value)];
+          if (!preset) {
+            return;
+          }
+          events.settings_changed("gameOverlayLayout", preset.layout.anchor);
+          setGameOverlayLayout(preset.layout)
+            .then(setLayout)
+            .catch((e) => console.error("Failed to set the game overlay layout", e));
+        }}
+      />
+    </Group>
+  );
+};
@@ -0,0 +1,59 @@
+import { Group, NumberInput, Text } from "@mantine/core";
+import React, { useEffect, useState } from "react";
+import events from "../mixpanel/mixpanel";
+import { OverlayTimeouts } from "../views/GameOverlay/types";
+import { getGameOverlayTimeouts, setGameOverlayTimeouts } from "./gameOverlayTimeouts";
+
+/** How long the matchup and the post-match results may stay up */
+export const GameOverlayTimeoutSettings: React.FC<{ disabled?: boolean }> = ({ disabled }) => {
+  const [timeouts, setTimeouts] = useState<OverlayTimeouts>();
+
+  useEffect(() => {
+    getGameOverlayTimeouts()
+      .then(setTimeouts)
+      .catch((e) => console.error("Failed to get the game overlay timeouts", e));
+  }, []);
+
+  if (!timeouts) {
+    return null;
+  }
+
+  const save = (key: keyof OverlayTimeouts, value: string | number) => {
+    if (typeof value !== "number" || value === timeouts[key]) {
+      return;
+    }
+    events.settings_changed("gameOverlayTimeouts", `${key}=${value}`);
+    setGameOverlayTimeouts({ ...timeouts, [key]: value })
+      .then(setTimeouts)
+      .catch((e) => console.error("Failed to set the game overlay timeouts", e));
+  };
+
+  return (
+    <Group>
+      <Text>Hide the matchup after</Text>
+      <NumberInput
+        data-testid="game-overlay-matchup-timeout"
+        style={{ width: 90 }}
+        disabled={disabled}
+        min={30}
+        max={600}
+        step={30}
+        suffix=" s"
+        value={timeouts.matchup_secs}
+        onChange={(value) => save("matchup_secs", value)}
+      />
+      <Text>and the results after</Text>
+      <NumberInput
+        data-testid="game-overlay-results-timeout"
+        style={{ width: 90 }}
+        disabled={disabled}
+        min={5}
+        max={300}
+        step={5}
+        suffix=" s"
+        value={timeouts.results_secs}
+        onChange={(value) => save("results_secs", value)}
+      />
+    </Group>
+  );
+};
@@ -0,0 +1,17 @@
+import { invoke } from "@tauri-apps/api/core";
+
+/** Mirrors `hotkey::HotkeyConfig` in game_overlay/hotkey.rs */
+export interface GameOverlayHotkey {
+  enabled: boolean;
+  /** Key combination such as "Ctrl+Shift+O" */
+  binding: string;
+  mode: "hold" | "toggle";
+  /** Makes the overlay clickable while it is up */
+  interact_binding: string;
+}
+
+export const getGameOverlayHotkey = () => invoke<GameOverlayHotkey>("game_overlay_hotkey");
+
+/** Checks and stores the hotkey, rejecting with the reason when the binding is not usable */
+export const setGameOverlayHotkey = (config: GameOverlayHotkey) =>
+  invoke<GameOverlayHotkey>("game_overlay_set_hotkey", { config });
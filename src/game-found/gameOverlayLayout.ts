@@ -0,0 +1,95 @@
+import { invoke } from "@tauri-apps/api/core";
+import { OverlayLayout } from "../views/GameOverlay/types";
+
+/** The layouts offered in the settings. The first one is the backend's default. */
+export const overlayLayoutPresets: { label: string; layout: OverlayLayout }[] = [
+  {
+    label: "Centre (default)",
+    layout: {
+      anchor: "centre",
+      width_ratio: 0.9,
+      height_ratio: 0.48,
+      margin_x: 0,
+      margin_y: 0,
+      opacity: 1,
+    },
+  },
+  {
+    label: "Strip at the top",
+    layout: {
+      anchor: "top",
+      width_ratio: 0.9,
+      height_ratio: 0.3,
+      margin_x: 0,
+      margin_y: 40,
+      opacity: 0.9,
+    },
+  },
+  {
+    label: "Strip at the bottom",
+    layout: {
+      anchor: "bottom",
+      width_ratio: 0.9,
+      height_ratio: 0.3,
+      margin_x: 0,
+      margin_y: 40,
+      opacity: 0.9,
+    },
+  },
+  {
+    label: "Top left corner",
+    layout: {
+      anchor: "top_left",
+      width_ratio: 0.5,
+      height_ratio: 0.35,
+      margin_x: 24,
+      margin_y: 24,
+      opacity: 0.85,
+    },
+  },
+  {
+    label: "Top right corner",
+    layout: {
+      anchor: "top_right",
+      width_ratio: 0.5,
+      height_ratio: 0.35,
+      margin_x: 24,
+      margin_y: 24,
+      opacity: 0.85,
+    },
+  },
+  {
+    label: "Bottom left corner",
+    layout: {
+      anchor: "bottom_left",
+      width_ratio: 0.5,
+      height_ratio: 0.35,
+      margin_x: 24,
+      margin_y: 24,
+      opacity: 0.85,
+    },
+  },
+  {
+    label: "Bottom right corner",
+    layout: {
+      anchor: "bottom_right",
+      width_ratio: 0.5,
+      height_ratio: 0.35,
+      margin_x: 24,
+      margin_y: 24,
+      opacity: 0.85,
+    },
+  },
+];
+
+/** Index of the preset matching `layout`, or -1 for a layout set some other way */
+export const findOverlayLayoutPreset = (layout: OverlayLayout | undefined) =>
+  overlayLayoutPresets.findIndex(
+    (preset) => JSON.stringify(preset.layout) === JSON.stringify(layout),
+  );
+
+export const getGameOverlayLayout = () => invoke<OverlayLayout>("game_overlay_layout");
+
+/** Stores the layout and moves a visible overlay to it */
+export const setGameOverlayLayout = (layout: OverlayLayout) =>
+  invoke<OverlayLayout>("game_overlay_set_layout", { layout });
@@ -0,0 +1,8 @@
+import { invoke } from "@tauri-apps/api/core";
+import { OverlayTimeouts } from "../views/GameOverlay/types";
+
+export const getGameOverlayTimeouts = () => invoke<OverlayTimeouts>("game_overlay_timeouts");
+
+/** Stores the timeouts, returning them as the backend clamped them */
+export const setGameOverlayTimeouts = (timeouts: OverlayTimeouts) =>
+  invoke<OverlayTimeouts>("game_overlay_set_timeouts", { timeouts });
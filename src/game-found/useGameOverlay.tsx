@@ -1,28 +1,27 @@
 import { useEffect, useRef } from "react";
-import { invoke } from "@tauri-apps/api/core";
 import { emitTo, listen } from "@tauri-apps/api/event";
 import { FullGameData, FullTeamData } from "../game-data-provider/GameData-types";
 import { detectArrangedTeam } from "../utils/arranged-team-detection";
 import {
-  GAME_OVERLAY_DATA_EVENT,
+  GAME_OVERLAY_DISPLAY_MODE_WARNING_EVENT,
+  GAME_OVERLAY_OPEN_PROFILE_EVENT,
   GAME_OVERLAY_READY_EVENT,
+  GAME_OVERLAY_TEAMS_EVENT,
   GAME_OVERLAY_WINDOW_LABEL,
-  GameOverlayPayload,
-  OverlayTeam,
+  OverlayTeamInfo,
   OverlayTeamKind,
+  GameDisplayMode,
+  OverlayTeams,
 } from "../views/GameOverlay/types";
 import { useGameOverlayEnabled } from "./gameOverlayConfigValues";
 import config from "../config";
+import { open } from "@tauri-apps/plugin-shell";
+import { coh3statsPlayerProfile } from "../utils/external-routes";
+import { showNotification } from "../utils/notifications";
 
-/**
- * A stalled log parse must never leave the overlay on screen forever. Generous, so a
- * slow disk loading a 4v4 map does not lose the overlay early.
- */
-const SAFETY_TIMEOUT_MS = 180_000;
-
-const buildTeam = async (team: FullTeamData): Promise<OverlayTeam> => {
+const detectTeam = async (team: FullTeamData): Promise<OverlayTeamInfo> => {
   let teamKind: OverlayTeamKind = "random";
-  let groups: OverlayTeam["groups"] = [];
+  let groups: OverlayTeamInfo["groups"] = [];
   let teamElo: number | undefined;
 
   try {
@@ -39,44 +38,30 @@ const buildTeam = async (team: FullTeamData): Promise<OverlayTeam> => {
     console.warn("[GameOverlay] Arranged team detection failed:", e);
   }
 
-  return { side: team.side, players: team.players, teamKind, groups, teamElo };
+  return { teamKind, groups, teamElo };
 };
 
 /**
- * Shows the in-game matchup overlay over CoH3 while the game is on its loading screen.
+ * The main window's part in the in-game matchup overlay.
  *
- * Mounted once, next to useAudioManager in GameDataProvider. The window itself already
- * exists (created hidden at startup by the Rust side); this only pushes data and calls
- * show/hide.
+ * Mounted once, next to useAudioManager in GameDataProvider. The backend puts the
+ * matchup together and shows and hides the overlay itself, so it works with this window
+ * minimised. What only this window can do is added when it is awake: which sides are
+ * arranged teams or friends groups, and opening a profile clicked in the overlay. It also
+ * tells the user when the game's display mode keeps the overlay from showing.
  */
 export const useGameOverlay = (gameData: FullGameData | undefined) => {
   const [gameOverlayEnabled] = useGameOverlayEnabled();
-  /** uniqueID of the match the overlay is currently up for, or null when hidden. */
-  const shownForRef = useRef<string | null>(null);
-  /** Last payload, so we can re-push it if the overlay window mounts after we emitted. */
-  const payloadRef = useRef<GameOverlayPayload | undefined>(undefined);
-  const timeoutRef = useRef<ReturnType<typeof setTimeout> | undefined>(undefined);
-
-  const hide = () => {
-    if (timeoutRef.current !== undefined) {
-      clearTimeout(timeoutRef.current);
-      timeoutRef.current = undefined;
-    }
-    if (shownForRef.current === null) {
-      return;
-    }
-    shownForRef.current = null;
-    payloadRef.current = undefined;
-    invoke("game_overlay_hide").catch((e) =>
-      console.error("[GameOverlay] Failed to hide overlay:", e),
-    );
-  };
+  /** uniqueID of the match the teams were looked up for, or null. */
+  const detectedForRef = useRef<string | null>(null);
+  /** Last teams sent, so we can re-send them if the overlay window mounts after we emitted. */
+  const teamsRef = useRef<OverlayTeams | undefined>(undefined);
 
-  // The overlay window announces itself on mount - re-push in case we emitted first.
+  // The overlay window announces itself on mount - re-send in case we emitted first.
   useEffect(() => {
     const unlisten = listen(GAME_OVERLAY_READY_EVENT, () => {
-      if (payloadRef.current) {
-        emitTo(GAME_OVERLAY_WINDOW_LABEL, GAME_OVERLAY_DATA_EVENT, payloadRef.current).catch(
+      if (teamsRef.current) {
+        emitTo(GAME_OVERLAY_WINDOW_LABEL, GAME_OVERLAY_TEAMS_EVENT, teamsRef.current).catch(
           console.error,
         );
       }
@@ -86,9 +71,38 @@ export const useGameOverlay = (gameData: FullGameData | undefined) => {
     };
   }, []);
 
+  // A player clicked in the interactive overlay - that window cannot open links itself.
+  useEffect(() => {
+    const unlisten = listen<string>(GAME_OVERLAY_OPEN_PROFILE_EVENT, (event) => {
+      open(coh3statsPlayerProfile(event.payload)).catch((e) =>
+        console.error("[GameOverlay] Failed to open a profile:", e),
+      );
+    });
+    return () => {
+      unlisten.then((fn) => fn()).catch(console.error);
+    };
+  }, []);
+
+  // The backend only warns while the overlay is switched on.
   useEffect(() => {
-    // no_win_condition and AI matches are single-player / vs-bots - no matchup to show.
-    const shouldShow =
+    const unlisten = listen<GameDisplayMode>(GAME_OVERLAY_DISPLAY_MODE_WARNING_EVENT, () => {
+      showNotification({
+        title: "The in-game overlay cannot show",
+        message:
+          "Company of Heroes 3 is running in exclusive fullscreen, which nothing can be drawn " +
+          "over. Switch the game to borderless fullscreen or windowed to see the overlay.",
+        type: "info",
+        autoCloseInMs: 30000,
+      });
+    });
+    return () => {
+      unlisten.then((fn) => fn()).catch(console.error);
+    };
+  }, []);
+
+  useEffect(() => {
+    // Same lobbies the backend shows the matchup for.
+    const shown =
       config.MS_STORE_EDITION &&
       gameOverlayEnabled === true &&
       gameData !== undefined &&
@@ -96,57 +110,23 @@ export const useGameOverlay = (gameData: FullGameData | undefined) => {
       gameData.winCondition !== "no_win_condition" &&
       gameData.type !== "AI";
 
-    if (!shouldShow) {
-      hide();
-      return;
-    }
-
-    // Already up for this match - don't rebuild or re-show.
-    if (shownForRef.current === gameData.uniqueID) {
+    if (!shown || detectedForRef.current === gameData.uniqueID) {
       return;
     }
-    shownForRef.current = gameData.uniqueID;
+    detectedForRef.current = gameData.uniqueID;
 
     (async () => {
       const [left, right] = await Promise.all([
-        buildTeam(gameData.left),
-        buildTeam(gameData.right),
+        detectTeam(gameData.left),
+        detectTeam(gameData.right),
       ]);
-
-      // Bail out if the loading screen ended while we were fetching.
-      if (shownForRef.current !== gameData.uniqueID) {
-        return;
-      }
-
-      const payload: GameOverlayPayload = {
-        uniqueID: gameData.uniqueID,
-        map: gameData.map,
-        left,
-        right,
-      };
-      payloadRef.current = payload;
-
+      const teams: OverlayTeams = { uniqueID: gameData.uniqueID, left, right };
+      teamsRef.current = teams;
       try {
-        // Emit before show so the first paint already has data.
-        await emitTo(GAME_OVERLAY_WINDOW_LABEL, GAME_OVERLAY_DATA_EVENT, payload);
-        await invoke("game_overlay_show");
+        await emitTo(GAME_OVERLAY_WINDOW_LABEL, GAME_OVERLAY_TEAMS_EVENT, teams);
       } catch (e) {
-        console.error("[GameOverlay] Failed to show overlay:", e);
-        return;
+        console.error("[GameOverlay] Failed to send the teams:", e);
       }
-
-      // A back-to-back match reuses this effect without going through `hide`, so the
-      // previous match's timer would otherwise still be armed and pull this overlay.
-      if (timeoutRef.current !== undefined) {
-        clearTimeout(timeoutRef.current);
-      }
-      timeoutRef.current = setTimeout(() => {
-        console.warn("[GameOverlay] Safety timeout reached, hiding overlay");
-        hide();
-      }, SAFETY_TIMEOUT_MS);
     })();
   }, [gameData?.uniqueID, gameData?.state, gameData?.type, gameOverlayEnabled]);
-
-  // Never leave the overlay on screen when this unmounts.
-  useEffect(() => hide, []);
 };
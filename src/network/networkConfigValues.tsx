@@ -0,0 +1,6 @@
+import { configValueFactory } from "../config-store/configValueFactory";
+
+/** Explicit proxy for the Rust-side HTTP client. Empty uses the system proxy. */
+const [getHttpProxy, useHttpProxy] = configValueFactory<string>("httpProxy", async () => "");
+
+export { getHttpProxy, useHttpProxy };
@@ -0,0 +1,252 @@
+import {
+  ActionIcon,
+  Button,
+  Checkbox,
+  Group,
+  Input,
+  PasswordInput,
+  Select,
+  Switch,
+  Text,
+} from "@mantine/core";
+import { IconTrash } from "@tabler/icons-react";
+import React, { useEffect, useState } from "react";
+import events from "../mixpanel/mixpanel";
+import { getObsConnectionStatus, ObsStatus, startObsConnection, stopObsConnection } from "./obs";
+import {
+  ObsRule,
+  ObsTrigger,
+  useObsEnabled,
+  useObsHost,
+  useObsPassword,
+  useObsPort,
+  useObsRules,
+} from "./obsConfigValues";
+
+const triggerLabels: Record<ObsTrigger, string> = {
+  Menu: "Back in the menu",
+  Loading: "Match loading",
+  InGame: "Match started",
+  GameOver: "Game over",
+  Closed: "Game closed",
+};
+
+const actionLabels: Record<ObsRule["type"], string> = {
+  switch_scene: "Switch to scene",
+  set_source_visible: "Show / hide source",
+};
+
+export const ObsSettings: React.FC = () => {
+  const [enabled, setEnabled] = useObsEnabled();
+  const [host, setHost] = useObsHost();
+  const [port, setPort] = useObsPort();
+  const [password, setPassword] = useObsPassword();
+  const [rules, setRules] = useObsRules();
+  const [status, setStatus] = useState<ObsStatus>();
+  const [error, setError] = useState<string | undefined>(undefined);
+
+  useEffect(() => {
+    const refresh = () =>
+      getObsConnectionStatus()
+        .then(setStatus)
+        .catch((e) => console.error("Failed to get the OBS connection status", e));
+    refresh();
+    // OBS is often started after the app
+    const interval = setInterval(refresh, 3000);
+    return () => clearInterval(interval);
+  }, []);
+
+  const apply = async (run: boolean) => {
+    try {
+      setStatus(run ? await startObsConnection() : await stopObsConnection());
+      setError(undefined);
+    } catch (e) {
+      setError(`${e}`);
+    }
+  };
+
+  /** Saves a setting, reconnecting with it if connected */
+  const save = async <T,>(key: string, setter: (value: T) => Promise<void>, value: T) => {
+    events.settings_changed(key, key === "obsPassword" ? "***" : JSON.stringify(value));
+    await setter(value);
+    if (enabled) {
+      await apply(true);
+    }
+  };
+
+  const updateRule = (index: number, rule: ObsRule) => {
+    if (rules) {
+      save("obsRules", setRules, rules.map((old, i) => (i === index ? rule : old)));
+    }
+  };
+
+  /** Keeps the trigger and scene when switching between action types */
+  const changeType = (index: number, rule: ObsRule, type: ObsRule["type"]) =>
+    updateRule(
+      index,
+      type === "switch_scene"
+        ? { trigger: rule.trigger, type, scene: rule.scene }
+        : { trigger: rule.trigger, type, scene: rule.scene, source: "", visible: true },
+    );
+
+  const statusText = !status?.running
+    ? "Not running"
+    : status.connected
+      ? `Connected (obs-websocket ${status.obs_websocket_version})`
+      : "Waiting for OBS...";
+
+  return (
+    <>
+      <Group>
+        <Text fw={700}>OBS scene switching</Text>
+        <Switch
+          data-testid="obs-toggle"
+          onLabel="ON"
+          offLabel="OFF"
+          size="md"
+          checked={enabled === undefined ? false : enabled}
+          onChange={(event) => {
+            const checked = event.currentTarget.checked;
+            events.settings_changed("obsEnabled", `${checked}`);
+            setEnabled(checked);
+            apply(checked);
+          }}
+        />
+        <Text size="sm" c={status?.connected ? "green" : "dimmed"}>
+          {statusText}
+        </Text>
+      </Group>
+      {error || status?.error ? (
+        <Text c="red" size="sm">
+          {error ?? status?.error}
+        </Text>
+      ) : null}
+      <Group>
+        <Text>Host:</Text>
+        <Input
+          data-testid="obs-host"
+          style={{ width: 160 }}
+          defaultValue={host}
+          key={`host-${host}`}
+          onBlur={(event) => {
+            const value = event.currentTarget.value.trim();
+            if (value && value !== host) {
+              save("obsHost", setHost, value);
+            }
+          }}
+        />
+        <Text>Port:</Text>
+        <Input
+          data-testid="obs-port"
+          type="number"
+          style={{ width: 100 }}
+          defaultValue={port}
+          key={`port-${port}`}
+          onBlur={(event) => {
+            const value = Number(event.currentTarget.value);
+            if (Number.isInteger(value) && value > 0 && value < 65536 && value !== port) {
+              save("obsPort", setPort, value);
+            }
+          }}
+        />
+        <Text>Password:</Text>
+        <PasswordInput
+          data-testid="obs-password"
+          style={{ width: 200 }}
+          defaultValue={password}
+          key={`password-${password !== undefined}`}
+          onBlur={(event) => {
+            const value = event.currentTarget.value;
+            if (value !== password) {
+              save("obsPassword", setPassword, value);
+            }
+          }}
+        />
+      </Group>
+      <Text size="sm" c="dimmed">
+        Turn on the WebSocket server in OBS under Tools - WebSocket Server Settings, the port
+        and password are shown there.
+      </Text>
+      {rules?.map((rule, index) => (
+        <Group key={index}>
+          <Text>When</Text>
+          <Select
+            style={{ width: 170 }}
+            allowDeselect={false}
+            data={Object.entries(triggerLabels).map(([value, label]) => ({ value, label }))}
+            value={rule.trigger}
+            onChange={(value) =>
+              value && updateRule(index, { ...rule, trigger: value as ObsTrigger })
+            }
+          />
+          <Select
+            style={{ width: 180 }}
+            allowDeselect={false}
+            data={Object.entries(actionLabels).map(([value, label]) => ({ value, label }))}
+            value={rule.type}
+            onChange={(value) =>
+              value && value !== rule.type && changeType(index, rule, value as ObsRule["type"])
+            }
+          />
+          <Input
+            style={{ width: 150 }}
+            placeholder="Scene"
+            defaultValue={rule.scene}
+            key={`scene-${index}-${rule.scene}`}
+            onBlur={(event) => {
+              const scene = event.currentTarget.value.trim();
+              if (scene !== rule.scene) {
+                updateRule(index, { ...rule, scene });
+              }
+            }}
+          />
+          {rule.type === "set_source_visible" ? (
+            <>
+              <Input
+                style={{ width: 150 }}
+                placeholder="Source"
+                defaultValue={rule.source}
+                key={`source-${index}-${rule.source}`}
+                onBlur={(event) => {
+                  const source = event.currentTarget.value.trim();
+                  if (source !== rule.source) {
+                    updateRule(index, { ...rule, source });
+                  }
+                }}
+              />
+              <Checkbox
+                label="Visible"
+                checked={rule.visible}
+                onChange={(event) =>
+                  updateRule(index, { ...rule, visible: event.currentTarget.checked })
+                }
+              />
+            </>
+          ) : null}
+          <ActionIcon
+            variant="default"
+            title="Remove"
+            onClick={() => save("obsRules", setRules, rules.filter((_, i) => i !== index))}
+          >
+            <IconTrash size={16} />
+          </ActionIcon>
+        </Group>
+      ))}
+      <Group>
+        <Button
+          variant="default"
+          size="xs"
+          data-testid="obs-add-rule"
+          onClick={() =>
+            save("obsRules", setRules, [
+              ...(rules ?? []),
+              { trigger: "Loading", type: "switch_scene", scene: "" },
+            ])
+          }
+        >
+          Add action
+        </Button>
+      </Group>
+    </>
+  );
+};
@@ -0,0 +1,18 @@
+import { invoke } from "@tauri-apps/api/core";
+
+/** Mirrors `ObsStatus` in obs_websocket.rs */
+export interface ObsStatus {
+  running: boolean;
+  /** Connected and identified */
+  connected: boolean;
+  obs_websocket_version: string | null;
+  /** Why the last connection ended, or the last action failed */
+  error: string | null;
+}
+
+/** (Re)connects to OBS with the stored settings */
+export const startObsConnection = () => invoke<ObsStatus>("start_obs_connection");
+
+export const stopObsConnection = () => invoke<ObsStatus>("stop_obs_connection");
+
+export const getObsConnectionStatus = () => invoke<ObsStatus>("obs_connection_status");
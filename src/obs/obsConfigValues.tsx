@@ -0,0 +1,43 @@
+import { configValueFactory } from "../config-store/configValueFactory";
+
+/** Mirrors `GameTrigger` in obs_websocket.rs */
+export type ObsTrigger = "Menu" | "Loading" | "InGame" | "GameOver" | "Closed";
+
+/** Mirrors `ObsAction` in obs_websocket.rs */
+export type ObsAction =
+  | { type: "switch_scene"; scene: string }
+  | { type: "set_source_visible"; scene: string; source: string; visible: boolean };
+
+/** Mirrors `ObsRule` in obs_websocket.rs, stored flat */
+export type ObsRule = { trigger: ObsTrigger } & ObsAction;
+
+const [getObsEnabled, useObsEnabled] = configValueFactory<boolean>(
+  "obsEnabled",
+  async () => false,
+);
+
+/** Where OBS runs, this machine unless streaming from a second PC */
+const [getObsHost, useObsHost] = configValueFactory<string>("obsHost", async () => "127.0.0.1");
+
+const [getObsPort, useObsPort] = configValueFactory<number>("obsPort", async () => 4455);
+
+/** Password of OBS's WebSocket server, empty when authentication is off */
+const [getObsPassword, useObsPassword] = configValueFactory<string>(
+  "obsPassword",
+  async () => "",
+);
+
+const [getObsRules, useObsRules] = configValueFactory<ObsRule[]>("obsRules", async () => []);
+
+export {
+  getObsEnabled,
+  useObsEnabled,
+  getObsHost,
+  useObsHost,
+  getObsPort,
+  useObsPort,
+  getObsPassword,
+  useObsPassword,
+  getObsRules,
+  useObsRules,
+};
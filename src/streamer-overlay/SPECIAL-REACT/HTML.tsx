@@ -8,12 +8,23 @@ export interface HTMLProps {
  * You cannot use mantine here!
  * This React component is meant for the streamerOverlay where only inline styles work!
  */
+/**
+ * Served by the overlay server, the page reloads when the app tells it the overlay files
+ * changed. Opened as a local file it can only poll.
+ */
+const reloadScript = `
+if (location.protocol === "file:") {
+  setTimeout(() => location.reload(), 2000);
+} else {
+  new EventSource("/api/v1/events").addEventListener("reload", () => location.reload());
+}
+`;
+
 export const HTML: React.FC<HTMLProps> = ({ html }) => {
   return (
     <html lang="en">
       <head>
         <meta charSet="UTF-8" />
-        <meta httpEquiv="refresh" content="2" />
         <link
           rel="icon"
           type="image/png"
@@ -99,6 +110,7 @@ export const HTML: React.FC<HTMLProps> = ({ html }) => {
       </head>
       <body>
         <div id="app" dangerouslySetInnerHTML={{ __html: html }} />
+        <script dangerouslySetInnerHTML={{ __html: reloadScript }} />
       </body>
     </html>
   );
@@ -15,6 +15,28 @@ const [getStreamerOverlayEnabled, useStreamerOverlayEnabled] = configValueFactor
   async () => false,
 );
 
+/** Port of the local overlay server. The server falls back to the next free port if taken. */
+const [getStreamerOverlayPort, useStreamerOverlayPort] = configValueFactory<number>(
+  "streamerOverlayPort",
+  async () => 47824,
+);
+
+/** Lets other computers (OBS on a streaming PC) use the overlay server with the access token. */
+const [getStreamerOverlayLanMode, useStreamerOverlayLanMode] = configValueFactory<boolean>(
+  "streamerOverlayLanMode",
+  async () => false,
+);
+
+/** Network interface the overlay server listens on in LAN mode, 0.0.0.0 for all. */
+const [getStreamerOverlayBindAddress, useStreamerOverlayBindAddress] =
+  configValueFactory<string>("streamerOverlayBindAddress", async () => "0.0.0.0");
+
+/** A restarted app continues the session scoreboard if its last match is this recent. */
+const [getSessionWindowHours, useSessionWindowHours] = configValueFactory<number>(
+  "sessionWindowHours",
+  async () => 12,
+);
+
 export {
   getShowFlagsOverlay,
   useShowFlagsOverlay,
@@ -22,4 +44,12 @@ export {
   useAlwaysShowOverlay,
   getStreamerOverlayEnabled,
   useStreamerOverlayEnabled,
+  getStreamerOverlayPort,
+  useStreamerOverlayPort,
+  getStreamerOverlayLanMode,
+  useStreamerOverlayLanMode,
+  getStreamerOverlayBindAddress,
+  useStreamerOverlayBindAddress,
+  getSessionWindowHours,
+  useSessionWindowHours,
 };
@@ -0,0 +1,36 @@
+import { invoke } from "@tauri-apps/api/core";
+
+/** Mirrors `OverlayServerStatus` in overlay_server.rs */
+export interface OverlayServerStatus {
+  running: boolean;
+  requested_port: number;
+  /** Port in use, differs from requested_port when that one was taken */
+  port: number | null;
+  url: string | null;
+  lan_mode: boolean;
+  /** Interface listened on in LAN mode, 0.0.0.0 for all of them */
+  bind_address: string | null;
+  /** URL for other computers in LAN mode, with the access token */
+  lan_url: string | null;
+  token: string | null;
+}
+
+/** Settings to start the server with, the configured ones for those left out */
+export interface OverlayServerSettings {
+  port?: number;
+  lanMode?: boolean;
+  bindAddress?: string;
+}
+
+export const startOverlayServer = (settings: OverlayServerSettings = {}) =>
+  invoke<OverlayServerStatus>("start_overlay_server", { ...settings });
+
+export const stopOverlayServer = () => invoke<OverlayServerStatus>("stop_overlay_server");
+
+export const getOverlayServerStatus = () => invoke<OverlayServerStatus>("overlay_server_status");
+
+/** Replaces the LAN mode access token, other computers need the new URL */
+export const rotateOverlayToken = () => invoke<OverlayServerStatus>("rotate_overlay_token");
+
+/** Addresses LAN mode can listen on, 0.0.0.0 (all interfaces) first */
+export const getOverlayLanAddresses = () => invoke<string[]>("overlay_lan_addresses");
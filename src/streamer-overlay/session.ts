@@ -0,0 +1,38 @@
+import { invoke } from "@tauri-apps/api/core";
+
+/** Mirrors `SessionMatch` in session.rs */
+export interface SessionMatch {
+  key: string;
+  /** Unix seconds */
+  finished_at: number;
+  /** 1v1 .. 4v4, ai or custom */
+  mode: string;
+  faction: string;
+  map: string;
+  won: boolean;
+  rating_before: number | null;
+  /** Null until the Relic API has counted the match */
+  rating_after: number | null;
+}
+
+/** Mirrors `Record` in session.rs */
+export interface SessionRecord {
+  wins: number;
+  losses: number;
+  rating_change: number;
+}
+
+/** Mirrors `Scoreboard` in session.rs */
+export interface SessionScoreboard {
+  /** Unix seconds of the start or the last reset */
+  started_at: number;
+  total: SessionRecord;
+  by_mode: Record<string, SessionRecord>;
+  by_faction: Record<string, SessionRecord>;
+  matches: SessionMatch[];
+}
+
+export const getSessionScoreboard = () => invoke<SessionScoreboard>("get_session_scoreboard");
+
+/** Starts a new session, the scoreboard starts from zero */
+export const resetSession = () => invoke<SessionScoreboard>("reset_session");
@@ -1,11 +1,27 @@
 import { Button, Code, ScrollArea, Text, Title } from "@mantine/core";
-import React, { useContext } from "react";
+import React, { useContext, useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import { showNotification } from "../utils/notifications";
 import { getTeamDetails } from "../utils/coh3-stats-api";
 import { MapStatsContext } from "../providers/MapStatsProvider";
+import { GAME_OVERLAY_STATUS_EVENT, OverlayStatus } from "./GameOverlay/types";
 
 export const Debug: React.FC = () => {
   const { data, loading, error } = useContext(MapStatsContext);
+  const [overlayStatus, setOverlayStatus] = useState<OverlayStatus>();
+
+  useEffect(() => {
+    invoke<OverlayStatus>("game_overlay_status")
+      .then(setOverlayStatus)
+      .catch((e) => console.error("Failed to get the game overlay status", e));
+    const unlisten = listen<OverlayStatus>(GAME_OVERLAY_STATUS_EVENT, (event) =>
+      setOverlayStatus(event.payload),
+    );
+    return () => {
+      unlisten.then((fn) => fn()).catch(console.error);
+    };
+  }, []);
 
   return (
     <>
@@ -35,6 +51,16 @@ export const Debug: React.FC = () => {
       </Button>
       <br />
       <br />
+      <Title order={4}>In-Game Overlay</Title>
+      {overlayStatus && (
+        <Text>
+          {overlayStatus.visibility} - {JSON.stringify(overlayStatus.reason)}
+          {overlayStatus.rect &&
+            ` at ${overlayStatus.rect.width}x${overlayStatus.rect.height}` +
+              ` (${overlayStatus.rect.x}, ${overlayStatus.rect.y})`}
+        </Text>
+      )}
+      <br />
       <Title order={4}>Map Stats</Title>
       {loading && <Text>Loading map stats...</Text>}
       {error && <Text c="red">Error: {error}</Text>}
@@ -6,6 +6,7 @@ import { useLogFilePath } from "../../game-data-provider/configValues";
 import MapCard from "./components/MapCard";
 import { IconSwords } from "@tabler/icons-react";
 import SummaryCard from "./components/SummaryCard";
+import { MatchupCardActions } from "./components/MatchupCardActions";
 import { ArrangedTeamCard } from "./components/arranged-team-card";
 import React, { memo, useState } from "react";
 import { GameDataTypes } from "../../game-data-provider/GameData-types";
@@ -81,6 +82,7 @@ const GameContent = memo(
                     <SummaryCard gameData={gameData} />
                   </Grid.Col>
                 </Grid>
+                <MatchupCardActions />
               </>
             ) : (
               <Stack mt={50} align="center" justify="center" gap={"xl"}>
@@ -0,0 +1,68 @@
+import { Button, Checkbox, Group } from "@mantine/core";
+import { IconCopy, IconDownload } from "@tabler/icons-react";
+import React, { useState } from "react";
+import { showNotification } from "../../../utils/notifications";
+import { copyMatchupCard, saveMatchupCard } from "./matchupCard";
+
+/** Exports the lobby as an image, for posting in Discord */
+export const MatchupCardActions: React.FC = () => {
+  const [withResult, setWithResult] = useState(true);
+  const [busy, setBusy] = useState(false);
+
+  const run = async (action: () => Promise<string | undefined>) => {
+    setBusy(true);
+    try {
+      const message = await action();
+      if (message) {
+        showNotification({ title: "Matchup card", message, autoCloseInMs: 3000 });
+      }
+    } catch (e) {
+      showNotification({ title: "Matchup card failed", message: `${e}`, type: "error" });
+    } finally {
+      setBusy(false);
+    }
+  };
+
+  const copy = () =>
+    run(async () => {
+      await copyMatchupCard(withResult);
+      return "Copied to the clipboard";
+    });
+
+  const saveToFile = () =>
+    run(async () => {
+      const path = await saveMatchupCard(withResult);
+      if (path === null) {
+        return undefined;
+      }
+      return `Saved to ${path}`;
+    });
+
+  return (
+    <Group justify="flex-end" px={"md"} pb={"md"}>
+      <Checkbox
+        label="Include result"
+        checked={withResult}
+        onChange={(event) => setWithResult(event.currentTarget.checked)}
+      />
+      <Button
+        variant="default"
+        size="xs"
+        leftSection={<IconCopy size={14} />}
+        loading={busy}
+        onClick={copy}
+      >
+        Copy card
+      </Button>
+      <Button
+        variant="default"
+        size="xs"
+        leftSection={<IconDownload size={14} />}
+        loading={busy}
+        onClick={saveToFile}
+      >
+        Save card
+      </Button>
+    </Group>
+  );
+};
@@ -0,0 +1,12 @@
+import { invoke } from "@tauri-apps/api/core";
+
+/**
+ * Asks where to save with the save dialog, then draws the current (or last) lobby there
+ * as a PNG. Resolves to the path, or null when the dialog was cancelled.
+ */
+export const saveMatchupCard = (withResult: boolean) =>
+  invoke<string | null>("save_matchup_card", { withResult });
+
+/** Draws the current (or last) lobby and copies it to the clipboard */
+export const copyMatchupCard = (withResult: boolean) =>
+  invoke<void>("copy_matchup_card", { withResult });
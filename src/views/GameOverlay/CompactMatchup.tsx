@@ -1,10 +1,13 @@
 import React from "react";
 import { CompactPlayerRow } from "./CompactPlayerRow";
-import { OverlayTeam } from "./types";
+import { OverlayLayout, OverlayTeam, OverlayTeamInfo } from "./types";
 import { BADGE_COLORS, badgeColor, playerGroupColor } from "./overlayColors";
 import classes from "./GameOverlay.module.css";
 
-const TeamBadges: React.FC<{ team: OverlayTeam }> = ({ team }) => {
+/** How a side shows until the main window says otherwise. */
+const RANDOM_TEAM: OverlayTeamInfo = { teamKind: "random", groups: [] };
+
+const TeamBadges: React.FC<{ team: OverlayTeamInfo }> = ({ team }) => {
   if (team.teamKind === "arranged") {
     return (
       <div className={classes.badges}>
@@ -57,17 +60,22 @@ const ColumnHeader: React.FC = () => (
   </div>
 );
 
-const TeamColumn: React.FC<{ team: OverlayTeam }> = ({ team }) => (
+const TeamColumn: React.FC<{
+  team: OverlayTeam;
+  info?: OverlayTeamInfo;
+  interactive?: boolean;
+}> = ({ team, info = RANDOM_TEAM, interactive }) => (
   <div className={classes.column}>
-    <TeamBadges team={team} />
+    <TeamBadges team={info} />
     <ColumnHeader />
     {team.players.map((player) => (
       <CompactPlayerRow
         key={`${player.relicID}-${player.position}`}
         player={player}
         groupColor={
-          team.teamKind === "friends" ? playerGroupColor(player.relicID, team.groups) : undefined
+          info.teamKind === "friends" ? playerGroupColor(player.relicID, info.groups) : undefined
         }
+        interactive={interactive}
       />
     ))}
   </div>
@@ -76,12 +84,25 @@ const TeamColumn: React.FC<{ team: OverlayTeam }> = ({ team }) => (
 export const CompactMatchup: React.FC<{
   left: OverlayTeam;
   right: OverlayTeam;
-}> = ({ left, right }) => (
-  <div className={classes.panel}>
+  /** Arranged teams and friends groups, when the main window has found them. */
+  leftInfo?: OverlayTeamInfo;
+  rightInfo?: OverlayTeamInfo;
+  layout?: OverlayLayout;
+  /** Player names can be clicked. */
+  interactive?: boolean;
+}> = ({ left, right, leftInfo, rightInfo, layout, interactive }) => (
+  <div
+    className={classes.panel}
+    style={{
+      opacity: layout?.opacity,
+      // Anchored to the bottom of the game window, the tables sit on its edge.
+      justifyContent: layout?.anchor.startsWith("bottom") ? "flex-end" : undefined,
+    }}
+  >
     <div className={classes.teams}>
-      <TeamColumn team={left} />
+      <TeamColumn team={left} info={leftInfo} interactive={interactive} />
       <div className={classes.versus}>VS</div>
-      <TeamColumn team={right} />
+      <TeamColumn team={right} info={rightInfo} interactive={interactive} />
     </div>
   </div>
 );
@@ -1,5 +1,6 @@
 import React from "react";
-import { FullPlayerData } from "../../game-data-provider/GameData-types";
+import { emitTo } from "@tauri-apps/api/event";
+import { GAME_OVERLAY_OPEN_PROFILE_EVENT, OverlayPlayer } from "./types";
 import classes from "./GameOverlay.module.css";
 
 /** The friends-group bar in front of the row, same idea as the one in `PlayerCard`. */
@@ -11,16 +12,37 @@ const GroupIndicator: React.FC<{ color?: string }> = ({ color }) => (
   />
 );
 
+/** The player's name, which opens their profile when the overlay is interactive. */
+const PlayerName: React.FC<{ player: OverlayPlayer; interactive?: boolean }> = ({
+  player,
+  interactive,
+}) => {
+  if (!interactive) {
+    return <span className={classes.name}>{player.name}</span>;
+  }
+  return (
+    <span
+      className={`${classes.name} ${classes.nameLink}`}
+      onClick={() =>
+        emitTo("main", GAME_OVERLAY_OPEN_PROFILE_EVENT, player.relicID).catch(console.error)
+      }
+    >
+      {player.name}
+    </span>
+  );
+};
+
 /**
  * One player line in the in-game overlay.
  *
  * Assets come from the app bundle (`public/`), never from remote URLs like the OBS
  * streamer overlay does - the overlay has to work offline and has no http permission.
  */
-export const CompactPlayerRow: React.FC<{ player: FullPlayerData; groupColor?: string }> = ({
-  player,
-  groupColor,
-}) => {
+export const CompactPlayerRow: React.FC<{
+  player: OverlayPlayer;
+  groupColor?: string;
+  interactive?: boolean;
+}> = ({ player, groupColor, interactive }) => {
   if (player.ai) {
     return (
       <div className={classes.row}>
@@ -40,7 +62,7 @@ export const CompactPlayerRow: React.FC<{ player: FullPlayerData; groupColor?: s
     );
   }
 
-  const rank = player.rank !== undefined && player.rank > 0 ? `#${player.rank}` : "—";
+  const rank = player.rank !== null && player.rank > 0 ? `#${player.rank}` : "—";
   const wins = player.wins ?? 0;
   const losses = player.losses ?? 0;
   const played = wins + losses;
@@ -63,7 +85,7 @@ export const CompactPlayerRow: React.FC<{ player: FullPlayerData; groupColor?: s
       ) : (
         <span />
       )}
-      <span className={classes.name}>{player.name}</span>
+      <PlayerName player={player} interactive={interactive} />
       <span className={`${classes.rank} ${rank === "—" ? classes.dim : ""}`}>{rank}</span>
       <span className={classes.elo}>{player.rating ?? "—"}</span>
       <span className={classes.winRate}>
@@ -1,16 +1,61 @@
 import React, { useEffect, useState } from "react";
 import { emit, listen } from "@tauri-apps/api/event";
 import { CompactMatchup } from "./CompactMatchup";
-import { GAME_OVERLAY_DATA_EVENT, GAME_OVERLAY_READY_EVENT, GameOverlayPayload } from "./types";
+import { ResultsPanel } from "./ResultsPanel";
+import {
+  GAME_OVERLAY_DATA_EVENT,
+  GAME_OVERLAY_INTERACTIVE_EVENT,
+  GAME_OVERLAY_LAYOUT_EVENT,
+  GAME_OVERLAY_MODE_EVENT,
+  GAME_OVERLAY_READY_EVENT,
+  GAME_OVERLAY_RESULTS_EVENT,
+  GAME_OVERLAY_TEAMS_EVENT,
+  GameOverlayPayload,
+  MatchResults,
+  OverlayLayout,
+  OverlayMode,
+  OverlayTeams,
+} from "./types";
 
 /**
  * Root of the in-game overlay window.
  *
- * It is purely a renderer: no polling, no API calls, no config store. The main window
- * pushes a payload over `game-overlay:data` and this draws it.
+ * It is purely a renderer: no polling, no API calls, no config store. The backend
+ * pushes the matchup over `game-overlay:data` and this draws it, so it works with the
+ * main window minimised. The main window, when it is awake, adds which sides are
+ * arranged teams or friends groups over `game-overlay:teams`. The backend sends the
+ * layout preset over `game-overlay:layout` whenever the overlay is shown or it changes.
+ *
+ * After a match the backend sends the results itself over `game-overlay:results`, and
+ * `game-overlay:mode` says which of the two to draw.
+ *
+ * In interactive mode (`game-overlay:interactive`) the window takes clicks, and a click
+ * on a player asks the main window to open their profile - this one may not.
  */
 export const GameOverlayApp: React.FC = () => {
   const [payload, setPayload] = useState<GameOverlayPayload>();
+  const [teams, setTeams] = useState<OverlayTeams>();
+  const [layout, setLayout] = useState<OverlayLayout>();
+  const [mode, setMode] = useState<OverlayMode>("matchup");
+  const [results, setResults] = useState<MatchResults>();
+  const [interactive, setInteractive] = useState(false);
+
+  useEffect(() => {
+    const unlisten = [
+      listen<OverlayLayout>(GAME_OVERLAY_LAYOUT_EVENT, (event) => setLayout(event.payload)),
+      listen<OverlayMode>(GAME_OVERLAY_MODE_EVENT, (event) => setMode(event.payload)),
+      listen<MatchResults>(GAME_OVERLAY_RESULTS_EVENT, (event) => setResults(event.payload)),
+      listen<OverlayTeams>(GAME_OVERLAY_TEAMS_EVENT, (event) => setTeams(event.payload)),
+      listen<boolean>(GAME_OVERLAY_INTERACTIVE_EVENT, (event) => {
+        // mountGameOverlay turns pointer events off for the whole page.
+        document.body.style.pointerEvents = event.payload ? "auto" : "none";
+        setInteractive(event.payload);
+      }),
+    ];
+    return () => {
+      unlisten.forEach((promise) => promise.then((fn) => fn()).catch(console.error));
+    };
+  }, []);
 
   useEffect(() => {
     const unlisten = listen<GameOverlayPayload>(GAME_OVERLAY_DATA_EVENT, (event) => {
@@ -28,10 +73,26 @@ export const GameOverlayApp: React.FC = () => {
     };
   }, []);
 
+  if (mode === "results") {
+    return results ? <ResultsPanel results={results} layout={layout} /> : null;
+  }
+
   // Nothing to draw until data arrives - the window is transparent, so this is blank.
   if (!payload) {
     return null;
   }
 
-  return <CompactMatchup left={payload.left} right={payload.right} />;
+  // Either may come first - only use what was found for this match.
+  const found = teams?.uniqueID === payload.uniqueID ? teams : undefined;
+
+  return (
+    <CompactMatchup
+      left={payload.left}
+      right={payload.right}
+      leftInfo={found?.left}
+      rightInfo={found?.right}
+      layout={layout}
+      interactive={interactive}
+    />
+  );
 };
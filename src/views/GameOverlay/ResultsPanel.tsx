@@ -0,0 +1,96 @@
+import React from "react";
+import { MatchResults, OverlayLayout } from "./types";
+import classes from "./GameOverlay.module.css";
+
+const formatDuration = (seconds: number) => {
+  const minutes = Math.floor(seconds / 60);
+  return `${minutes}:${`${seconds % 60}`.padStart(2, "0")}`;
+};
+
+const signed = (value: number) => (value > 0 ? `+${value}` : `${value}`);
+
+const changeClass = (value: number) =>
+  value > 0 ? classes.win : value < 0 ? classes.loss : classes.dim;
+
+const Headline: React.FC<{ results: MatchResults }> = ({ results }) => {
+  if (results.local_player_won === null) {
+    return (
+      <div className={classes.resultsHeadline}>
+        {results.winner === "Left" ? "Left" : "Right"} team won
+      </div>
+    );
+  }
+  const outcome = results.local_player_won ? classes.win : classes.loss;
+  return (
+    <div className={`${classes.resultsHeadline} ${outcome}`}>
+      {results.local_player_won ? "Victory" : "Defeat"}
+    </div>
+  );
+};
+
+/** The rating before and after; the "after" comes in a while after the match. */
+const Rating: React.FC<{ results: MatchResults }> = ({ results }) => {
+  if (results.rating_before === null) {
+    return null;
+  }
+  return (
+    <div className={classes.resultsStat}>
+      <span className={classes.resultsLabel}>Rating</span>
+      <span className={classes.elo}>{results.rating_before}</span>
+      <span className={classes.dim}>→</span>
+      {results.rating_after === null || results.rating_change === null ? (
+        <span className={classes.dim}>pending</span>
+      ) : (
+        <>
+          <span className={classes.elo}>{results.rating_after}</span>
+          <span className={changeClass(results.rating_change)}>
+            ({signed(results.rating_change)})
+          </span>
+        </>
+      )}
+    </div>
+  );
+};
+
+const Session: React.FC<{ results: MatchResults }> = ({ results }) => {
+  const { wins, losses, rating_change } = results.session;
+  if (wins + losses === 0) {
+    return null;
+  }
+  return (
+    <div className={classes.resultsStat}>
+      <span className={classes.resultsLabel}>Session</span>
+      <span className={classes.record}>
+        <span className={classes.win}>{wins}W</span> -{" "}
+        <span className={classes.loss}>{losses}L</span>
+      </span>
+      {rating_change !== 0 && (
+        <span className={changeClass(rating_change)}>{signed(rating_change)}</span>
+      )}
+    </div>
+  );
+};
+
+/** Post-match panel: how the match ended, the rating change and the session so far. */
+export const ResultsPanel: React.FC<{ results: MatchResults; layout?: OverlayLayout }> = ({
+  results,
+  layout,
+}) => (
+  <div
+    className={classes.panel}
+    style={{
+      opacity: layout?.opacity,
+      justifyContent: layout?.anchor.startsWith("bottom") ? "flex-end" : undefined,
+    }}
+  >
+    <div className={classes.results}>
+      <Headline results={results} />
+      <div className={classes.resultsStat}>
+        <span className={classes.resultsLabel}>{results.mode}</span>
+        <span className={classes.record}>{formatDuration(results.duration)}</span>
+      </div>
+      <Rating results={results} />
+      <Session results={results} />
+    </div>
+  </div>
+);
@@ -1,19 +1,147 @@
-import { FullPlayerData, TeamSide } from "../../game-data-provider/GameData-types";
+import { TeamSide } from "../../game-data-provider/GameData-types";
 import { KnownFriendsGroup } from "../../utils/team-grouping";
+import { raceType } from "../../coh3-types";
 
 /** Must match `game_overlay::OVERLAY_WINDOW_LABEL` in the Rust side. */
 export const GAME_OVERLAY_WINDOW_LABEL = "game-overlay";
 
-/** Main window -> overlay window: the matchup to draw. */
+/** Backend -> overlay window: the matchup to draw. */
 export const GAME_OVERLAY_DATA_EVENT = "game-overlay:data";
-/** Overlay window -> main window: "I'm mounted, re-send if you already pushed". */
+/** Overlay window -> backend and main window: "I'm mounted, re-send if you already pushed". */
 export const GAME_OVERLAY_READY_EVENT = "game-overlay:ready";
+/** Main window -> overlay window: which sides are arranged teams or friends groups. */
+export const GAME_OVERLAY_TEAMS_EVENT = "game-overlay:teams";
+/** Backend -> overlay window: the layout preset in use. */
+export const GAME_OVERLAY_LAYOUT_EVENT = "game-overlay:layout";
+/** Backend -> all windows: what the overlay is doing and why, on every change. */
+export const GAME_OVERLAY_STATUS_EVENT = "game-overlay:status";
+/** Backend -> overlay window: what to draw, whenever the overlay is shown. */
+export const GAME_OVERLAY_MODE_EVENT = "game-overlay:mode";
+/** Backend -> overlay window: the results panel for the match that just ended. */
+export const GAME_OVERLAY_RESULTS_EVENT = "game-overlay:results";
+/** Backend -> all windows: whether the overlay takes clicks, on every change. */
+export const GAME_OVERLAY_INTERACTIVE_EVENT = "game-overlay:interactive";
+/** Overlay window -> main window: open this relic ID's coh3stats profile. */
+export const GAME_OVERLAY_OPEN_PROFILE_EVENT = "game-overlay:open-profile";
+/** Backend -> all windows: the game switched to a display mode the overlay cannot draw over. */
+export const GAME_OVERLAY_DISPLAY_MODE_WARNING_EVENT = "game-overlay:display-mode-warning";
 
-export type OverlayTeamKind = "arranged" | "friends" | "random";
+/** Mirrors `geometry::DisplayMode` on the Rust side. */
+export type GameDisplayMode = "exclusive_fullscreen" | "borderless_fullscreen" | "windowed";
+
+/** Mirrors `modes::OverlayMode` on the Rust side. */
+export type OverlayMode = "matchup" | "results";
+
+/** Mirrors `modes::ModeTimeouts` on the Rust side. */
+export interface OverlayTimeouts {
+  matchup_secs: number;
+  results_secs: number;
+}
 
+/** Mirrors `session::Record` on the Rust side. */
+export interface OverlayRecord {
+  wins: number;
+  losses: number;
+  rating_change: number;
+}
+
+/** Mirrors `modes::MatchResults` on the Rust side. */
+export interface MatchResults {
+  map: string;
+  /** `1v1` .. `4v4` for automatch games, `custom` otherwise. */
+  mode: string;
+  winner: "Left" | "Right";
+  /** null when the local player was not in the match. */
+  local_player_won: boolean | null;
+  /** Game time in seconds. */
+  duration: number;
+  rating_before: number | null;
+  /** null until the Relic API has counted the match. */
+  rating_after: number | null;
+  rating_change: number | null;
+  /** The session so far, this match included. */
+  session: OverlayRecord;
+}
+
+/** Mirrors `watcher::Visibility` on the Rust side. */
+export type OverlayVisibility =
+  | "NotWanted"
+  | "WaitingForGame"
+  | "WaitingForWindow"
+  | "Shown"
+  | "HiddenAway"
+  | "HiddenMinimised";
+
+/** Mirrors `watcher::Reason` on the Rust side. */
+export type OverlayReason =
+  | { kind: "NotRequested" }
+  | { kind: "GameNotRunning" }
+  | { kind: "GameExited"; pid: number }
+  | { kind: "NoGameWindow"; pid: number }
+  | { kind: "GameInForeground" }
+  | { kind: "AppInForeground" }
+  | { kind: "ForegroundUnknown" }
+  | { kind: "AnotherAppBriefly"; pid: number }
+  | { kind: "AnotherAppInForeground"; pid: number }
+  | { kind: "GameMinimised" }
+  | { kind: "TimedOut"; mode: OverlayMode };
+
+/** Mirrors `watcher::OverlayStatus` on the Rust side. */
+export interface OverlayStatus {
+  visibility: OverlayVisibility;
+  reason: OverlayReason;
+  /** Where the overlay was last placed, in physical pixels. */
+  rect: { x: number; y: number; width: number; height: number } | null;
+}
+
+/** Mirrors `geometry::Anchor` on the Rust side. */
+export type OverlayAnchor =
+  | "centre"
+  | "top"
+  | "bottom"
+  | "top_left"
+  | "top_right"
+  | "bottom_left"
+  | "bottom_right";
+
+/** Mirrors `geometry::LayoutPreset` on the Rust side. */
+export interface OverlayLayout {
+  anchor: OverlayAnchor;
+  width_ratio: number;
+  height_ratio: number;
+  /** Distance from the anchored edges, in pixels at 100% scaling. */
+  margin_x: number;
+  margin_y: number;
+  opacity: number;
+}
+
+/** Mirrors `payload::OverlayPlayer` on the Rust side. */
+export interface OverlayPlayer {
+  ai: boolean;
+  /** The local player. */
+  self: boolean;
+  faction: raceType;
+  relicID: string;
+  name: string;
+  position: number;
+  country: string | null;
+  /** The standing on the lobby's leaderboard - null for AI or when it is not known. */
+  rank: number | null;
+  rating: number | null;
+  wins: number | null;
+  losses: number | null;
+}
+
+/** Mirrors `payload::OverlayTeam` on the Rust side. */
 export interface OverlayTeam {
   side: TeamSide;
-  players: FullPlayerData[];
+  players: OverlayPlayer[];
+}
+
+export type OverlayTeamKind = "arranged" | "friends" | "random";
+
+/** What the main window found out about one side. */
+export interface OverlayTeamInfo {
   teamKind: OverlayTeamKind;
   /** Friends groups (with colours) when `teamKind === "friends"`, otherwise empty. */
   groups: KnownFriendsGroup[];
@@ -21,9 +149,37 @@ export interface OverlayTeam {
   teamElo?: number;
 }
 
+/** Sent over `game-overlay:teams`, for the match with the payload's `uniqueID`. */
+export interface OverlayTeams {
+  uniqueID: string;
+  left: OverlayTeamInfo;
+  right: OverlayTeamInfo;
+}
+
+/** Mirrors `win_probability::Factor` on the Rust side. */
+export interface WinProbabilityFactor {
+  kind: "MapMatchup" | "MapFactions" | "Rankings";
+  probability: number;
+  weight: number;
+  sample_size: number;
+}
+
+/** Mirrors `win_probability::WinProbability` - the estimate for the local player's team. */
+export interface WinProbability {
+  team: "Left" | "Right";
+  probability: number;
+  confidence: "None" | "Low" | "Medium" | "High";
+  confidence_score: number;
+  factors: WinProbabilityFactor[];
+}
+
+/** Mirrors `payload::MatchupPayload` on the Rust side. */
 export interface GameOverlayPayload {
   uniqueID: string;
   map: string;
   left: OverlayTeam;
   right: OverlayTeam;
+  winProbability: WinProbability;
+  /** The coh3stats battlegroup info for each faction in the lobby, by faction. */
+  battlegroups: Record<string, unknown>;
 }
@@ -17,10 +17,17 @@ import {
   Modal,
   Image,
 } from "@mantine/core";
-import { appDataDir } from "@tauri-apps/api/path";
+import { appDataDir, join } from "@tauri-apps/api/path";
 import { writeText } from "@tauri-apps/plugin-clipboard-manager";
 import React, { useEffect, useState } from "react";
-import { IconCheck, IconCopy, IconInfoCircle, IconPlayerPlay, IconX } from "@tabler/icons-react";
+import {
+  IconCheck,
+  IconCopy,
+  IconInfoCircle,
+  IconPlayerPlay,
+  IconRefresh,
+  IconX,
+} from "@tabler/icons-react";
 import { open } from "@tauri-apps/plugin-dialog";
 import { open as openLink } from "@tauri-apps/plugin-shell";
 import {
@@ -35,22 +42,45 @@ import { useAutoSwitchToGame } from "../game-found/autoSwitchConfigValues";
 import { useBringToFrontOnGameFound } from "../game-found/bringToFrontConfigValues";
 import { useAutoMuteEnabled, useMuteOnlyOutOfGame } from "../game-found/audioMuteConfigValues";
 import { useGameOverlayEnabled } from "../game-found/gameOverlayConfigValues";
+import { GameOverlayLayoutSettings } from "../game-found/GameOverlayLayoutSettings";
+import { GameOverlayHotkeySettings } from "../game-found/GameOverlayHotkeySettings";
+import { GameOverlayTimeoutSettings } from "../game-found/GameOverlayTimeoutSettings";
 import {
   useShowFlagsOverlay,
   useAlwaysShowOverlay,
   useStreamerOverlayEnabled,
+  useStreamerOverlayPort,
+  useStreamerOverlayLanMode,
+  useStreamerOverlayBindAddress,
+  useSessionWindowHours,
 } from "../streamer-overlay/configValues";
+import {
+  getSessionScoreboard,
+  resetSession,
+  SessionScoreboard,
+} from "../streamer-overlay/session";
+import {
+  getOverlayLanAddresses,
+  getOverlayServerStatus,
+  OverlayServerSettings,
+  OverlayServerStatus,
+  rotateOverlayToken,
+  startOverlayServer,
+  stopOverlayServer,
+} from "../streamer-overlay/overlayServer";
 import { playSound as playSoundFunc } from "../game-found/playSound";
 import events from "../mixpanel/mixpanel";
 import { useGameData } from "../game-data-provider/GameDataProvider";
-import { relaunch } from "@tauri-apps/plugin-process";
 import { getMapsUrlOnCDN } from "../utils/utils";
 import { MapViewSettings } from "../game-data-provider/GameData-types";
 import { ColorSchemeToggle } from "../components/ToggleCollorShemeButton";
 import { useFontScale } from "../config-store/fontScaleConfig";
+import { useHttpProxy } from "../network/networkConfigValues";
 import config from "../config";
 import { Link } from "react-router";
 import { Routes } from "../Router";
+import { ChatBotSettings } from "../chat-bot/ChatBotSettings";
+import { ObsSettings } from "../obs/ObsSettings";
 
 export const Settings: React.FC = () => {
   const gameData = useGameData();
@@ -68,10 +98,24 @@ export const Settings: React.FC = () => {
   const [mapViewSettings, setMapViewSettings] = useMapViewSettings();
   const [showExtendedPlayerInfo, setShowExtendedPlayerInfo] = useShowExtendedPlayerInfo();
   const [fontScale, setFontScale] = useFontScale();
+  const [httpProxy, setHttpProxy] = useHttpProxy();
+  const [httpProxyDraft, setHttpProxyDraft] = useState<string | undefined>(undefined);
+  const [proxyRestartRequired, setProxyRestartRequired] = useState<boolean>(false);
 
   const [streamerOverlayEnabled, setStreamerOverlayEnabled] = useStreamerOverlayEnabled();
   const [appDataPath, setAppDataPath] = useState<string>("");
-  const [restartRequired, setRestartRequired] = useState<boolean>(false);
+  const [overlayThemePath, setOverlayThemePath] = useState<string>("");
+  const [streamerOverlayPort, setStreamerOverlayPort] = useStreamerOverlayPort();
+  const [overlayPortDraft, setOverlayPortDraft] = useState<string | undefined>(undefined);
+  const [streamerOverlayLanMode, setStreamerOverlayLanMode] = useStreamerOverlayLanMode();
+  const [streamerOverlayBindAddress, setStreamerOverlayBindAddress] =
+    useStreamerOverlayBindAddress();
+  const [overlayLanAddresses, setOverlayLanAddresses] = useState<string[]>(["0.0.0.0"]);
+  const [overlayServerStatus, setOverlayServerStatus] = useState<OverlayServerStatus>();
+  const [overlayServerError, setOverlayServerError] = useState<string | undefined>(undefined);
+  const [sessionWindowHours, setSessionWindowHours] = useSessionWindowHours();
+  const [sessionWindowDraft, setSessionWindowDraft] = useState<string | undefined>(undefined);
+  const [sessionScoreboard, setSessionScoreboard] = useState<SessionScoreboard>();
   const [friendsGroupModalOpened, setFriendsGroupModalOpened] = useState<boolean>(false);
   const [gameOverlayModalOpened, setGameOverlayModalOpened] = useState<boolean>(false);
 
@@ -79,10 +123,58 @@ export const Settings: React.FC = () => {
     events.open_settings();
   }, []);
 
+  useEffect(() => {
+    getOverlayServerStatus()
+      .then(setOverlayServerStatus)
+      .catch((e) => console.error("Failed to get the overlay server status", e));
+    getOverlayLanAddresses()
+      .then(setOverlayLanAddresses)
+      .catch((e) => console.error("Failed to get the network addresses", e));
+    getSessionScoreboard()
+      .then(setSessionScoreboard)
+      .catch((e) => console.error("Failed to get the session scoreboard", e));
+  }, []);
+
+  /** Starts or stops the server, with `changes` applied to the current settings */
+  const applyOverlayServer = async (enabled: boolean, changes: OverlayServerSettings = {}) => {
+    try {
+      setOverlayServerStatus(
+        enabled
+          ? await startOverlayServer({
+              port: streamerOverlayPort,
+              lanMode: streamerOverlayLanMode,
+              bindAddress: streamerOverlayBindAddress,
+              ...changes,
+            })
+          : await stopOverlayServer(),
+      );
+      setOverlayServerError(undefined);
+    } catch (e) {
+      setOverlayServerError(`${e}`);
+    }
+  };
+
+  const overlayUrl =
+    overlayServerStatus?.url ?? `http://localhost:${streamerOverlayPort ?? 47824}`;
+  const overlayLanUrl = overlayServerStatus?.lan_url ?? "";
+  const sessionTotal = sessionScoreboard?.total;
+  const sessionRecord = sessionTotal
+    ? `${sessionTotal.wins}W-${sessionTotal.losses}L, ` +
+      `${sessionTotal.rating_change >= 0 ? "+" : ""}${sessionTotal.rating_change} ELO`
+    : "-";
+  // The stored address may belong to an interface that is gone, keep it selectable.
+  const bindAddressOptions = [
+    ...new Set([...overlayLanAddresses, streamerOverlayBindAddress ?? "0.0.0.0"]),
+  ].map((address) => ({
+    value: address,
+    label: address === "0.0.0.0" ? "All interfaces (0.0.0.0)" : address,
+  }));
+
   useEffect(() => {
     const getAppDataPath = async () => {
       const path = await appDataDir();
       setAppDataPath(path);
+      setOverlayThemePath(await join(path, "overlay-theme"));
     };
     if (appDataPath === "") {
       getAppDataPath();
@@ -418,9 +510,17 @@ export const Settings: React.FC = () => {
               </Button>
             </Group>
             <Text size="sm" c="dimmed">
-              Shows both teams over the game's loading screen. Requires CoH3 in Borderless or
-              Windowed display mode. Exclusive Fullscreen is not supported.
+              Shows both teams over the game's loading screen, and the result once the match is
+              over. Requires CoH3 in Borderless or Windowed display mode. Exclusive Fullscreen
+              is not supported.
             </Text>
+            {config.MS_STORE_EDITION && (
+              <>
+                <GameOverlayLayoutSettings disabled={gameOverlayEnabled === false} />
+                <GameOverlayHotkeySettings disabled={gameOverlayEnabled === false} />
+                <GameOverlayTimeoutSettings disabled={gameOverlayEnabled === false} />
+              </>
+            )}
 
             <Group>
               <Tooltip
@@ -627,6 +727,30 @@ export const Settings: React.FC = () => {
             </Group>
           </Group>
           <Divider mt={"md"} />
+          <Group>
+            <div>HTTP proxy:</div>
+            <Input
+              data-testid="http-proxy-input"
+              style={{ width: 300 }}
+              placeholder="System proxy (e.g. http://proxy:8080)"
+              value={httpProxyDraft ?? httpProxy ?? ""}
+              onChange={(event) => setHttpProxyDraft(event.currentTarget.value)}
+              onBlur={() => {
+                if (httpProxyDraft === undefined || httpProxyDraft.trim() === (httpProxy ?? "")) {
+                  return;
+                }
+                setHttpProxy(httpProxyDraft.trim());
+                setHttpProxyDraft(undefined);
+                setProxyRestartRequired(true);
+              }}
+            />
+            {proxyRestartRequired ? (
+              <Text c="red" size="sm">
+                Restart required to apply the proxy
+              </Text>
+            ) : null}
+          </Group>
+          <Divider mt={"md"} />
           <Group>
             <Text fw={700}>OBS Streamer Overlay</Text>
             <Switch
@@ -641,31 +765,15 @@ export const Settings: React.FC = () => {
                   `${event.currentTarget.checked}`,
                 );
                 setStreamerOverlayEnabled(event.currentTarget.checked);
-                setRestartRequired(true);
+                applyOverlayServer(event.currentTarget.checked);
               }}
             />
           </Group>
-          <Group>
-            <div>
-              {restartRequired ? (
-                <Group>
-                  <Text c="red">Restart required to enable / disable streamer overlay</Text>
-                  <Button
-                    data-testid="obs-restart-button"
-                    variant="outline"
-                    color="red"
-                    size="compact-md"
-                    onClick={async () => {
-                      // Relaunch doesn't work well in dev mode
-                      await relaunch();
-                    }}
-                  >
-                    Restart
-                  </Button>
-                </Group>
-              ) : null}
-            </div>
-          </Group>
+          {overlayServerError ? (
+            <Text c="red" size="sm">
+              Streamer overlay server could not start: {overlayServerError}
+            </Text>
+          ) : null}
           <Group>
             <div>
               <Checkbox
@@ -725,10 +833,125 @@ export const Settings: React.FC = () => {
               </Anchor>
             </Text>
           </div>
+          <Group>
+            <Text>Port:</Text>
+            <Input
+              data-testid="obs-overlay-port-input"
+              type="number"
+              style={{ width: 100 }}
+              value={overlayPortDraft ?? `${streamerOverlayPort ?? ""}`}
+              disabled={streamerOverlayEnabled === undefined ? false : !streamerOverlayEnabled}
+              onChange={(event) => setOverlayPortDraft(event.currentTarget.value)}
+              onBlur={() => {
+                const port = Number(overlayPortDraft);
+                setOverlayPortDraft(undefined);
+                if (!Number.isInteger(port) || port < 1 || port > 65535) {
+                  return;
+                }
+                if (port === streamerOverlayPort) {
+                  return;
+                }
+                events.settings_changed("streamerOverlayPort", `${port}`);
+                setStreamerOverlayPort(port);
+                if (streamerOverlayEnabled) {
+                  applyOverlayServer(true, { port });
+                }
+              }}
+            />
+          </Group>
+          <Group>
+            <div>
+              <Checkbox
+                data-testid="obs-overlay-lan-checkbox"
+                disabled={streamerOverlayEnabled === undefined ? false : !streamerOverlayEnabled}
+                checked={streamerOverlayLanMode === undefined ? false : streamerOverlayLanMode}
+                onChange={(event) => {
+                  const lanMode = event.currentTarget.checked;
+                  events.settings_changed("streamerOverlayLanMode", `${lanMode}`);
+                  setStreamerOverlayLanMode(lanMode);
+                  if (streamerOverlayEnabled) {
+                    applyOverlayServer(true, { lanMode });
+                  }
+                }}
+              />
+            </div>
+            <div>Allow other computers on the network (LAN mode, for a streaming PC)</div>
+          </Group>
+          {streamerOverlayLanMode ? (
+            <>
+              <Group>
+                <Text>Network interface:</Text>
+                <Select
+                  data-testid="obs-overlay-bind-address"
+                  style={{ width: 250 }}
+                  data={bindAddressOptions}
+                  value={streamerOverlayBindAddress ?? "0.0.0.0"}
+                  allowDeselect={false}
+                  disabled={
+                    streamerOverlayEnabled === undefined ? false : !streamerOverlayEnabled
+                  }
+                  onChange={(bindAddress) => {
+                    if (!bindAddress || bindAddress === streamerOverlayBindAddress) {
+                      return;
+                    }
+                    events.settings_changed("streamerOverlayBindAddress", bindAddress);
+                    setStreamerOverlayBindAddress(bindAddress);
+                    if (streamerOverlayEnabled) {
+                      applyOverlayServer(true, { bindAddress });
+                    }
+                  }}
+                />
+              </Group>
+              <Group>
+                <Text>From other computers:</Text>
+                <Input
+                  data-testid="obs-overlay-lan-url"
+                  value={overlayLanUrl}
+                  placeholder="Start the overlay server to get the URL"
+                  style={{ width: 400 }}
+                  readOnly
+                  disabled={
+                    streamerOverlayEnabled === undefined ? false : !streamerOverlayEnabled
+                  }
+                />
+                <Tooltip label="Copy">
+                  <ActionIcon
+                    disabled={!overlayLanUrl}
+                    onClick={() => {
+                      writeText(overlayLanUrl);
+                    }}
+                  >
+                    <IconCopy size="1.125rem" />
+                  </ActionIcon>
+                </Tooltip>
+                <Tooltip label="New access token">
+                  <ActionIcon
+                    data-testid="obs-overlay-rotate-token"
+                    disabled={!overlayServerStatus?.lan_mode}
+                    onClick={async () => {
+                      try {
+                        setOverlayServerStatus(await rotateOverlayToken());
+                        setOverlayServerError(undefined);
+                      } catch (e) {
+                        setOverlayServerError(`${e}`);
+                      }
+                    }}
+                  >
+                    <IconRefresh size="1.125rem" />
+                  </ActionIcon>
+                </Tooltip>
+              </Group>
+              <Text size="sm" c="dimmed">
+                The URL contains the access token, do not show it on stream. If it leaked, get a
+                new token and update the URL in OBS on the other computer.
+              </Text>
+            </>
+          ) : null}
           <Group>
             <Text>Streamer overlay avaliable at:</Text>
             <Input
-              value={"http://localhost:47824"}
+              data-testid="obs-overlay-url"
+              value={overlayUrl}
               style={{ width: 250 }}
               readOnly
               disabled={streamerOverlayEnabled === undefined ? false : !streamerOverlayEnabled}
@@ -737,13 +960,79 @@ export const Settings: React.FC = () => {
               <ActionIcon
                 disabled={streamerOverlayEnabled === undefined ? false : !streamerOverlayEnabled}
                 onClick={() => {
-                  writeText("http://localhost:47824");
+                  writeText(overlayUrl);
+                }}
+              >
+                <IconCopy size="1.125rem" />
+              </ActionIcon>
+            </Tooltip>
+          </Group>
+          <Group>
+            <Text>Theme folder (custom assets and layouts):</Text>
+            <Input
+              value={overlayThemePath}
+              style={{ width: 400 }}
+              readOnly
+              disabled={streamerOverlayEnabled === undefined ? false : !streamerOverlayEnabled}
+            />
+            <Tooltip label="Copy">
+              <ActionIcon
+                disabled={streamerOverlayEnabled === undefined ? false : !streamerOverlayEnabled}
+                onClick={() => {
+                  writeText(overlayThemePath);
                 }}
               >
                 <IconCopy size="1.125rem" />
               </ActionIcon>
             </Tooltip>
           </Group>
+          <Group>
+            <Text>Session scoreboard:</Text>
+            <Text data-testid="obs-overlay-session-record">
+              {sessionRecord}
+            </Text>
+            <Button
+              data-testid="obs-overlay-session-reset"
+              size="compact-sm"
+              variant="default"
+              onClick={async () => {
+                try {
+                  setSessionScoreboard(await resetSession());
+                } catch (e) {
+                  console.error("Failed to reset the session", e);
+                }
+              }}
+            >
+              Start new session
+            </Button>
+          </Group>
+          <Group>
+            <Text>Continue the session after a restart within</Text>
+            <Input
+              data-testid="obs-overlay-session-window"
+              type="number"
+              style={{ width: 80 }}
+              value={sessionWindowDraft ?? `${sessionWindowHours ?? ""}`}
+              onChange={(event) => setSessionWindowDraft(event.currentTarget.value)}
+              onBlur={() => {
+                const hours = Number(sessionWindowDraft);
+                setSessionWindowDraft(undefined);
+                if (!Number.isInteger(hours) || hours < 1 || hours === sessionWindowHours) {
+                  return;
+                }
+                events.settings_changed("sessionWindowHours", `${hours}`);
+                setSessionWindowHours(hours);
+              }}
+            />
+            <Text>hours</Text>
+          </Group>
+          {overlayServerStatus?.running &&
+          overlayServerStatus.port !== overlayServerStatus.requested_port ? (
+            <Text c="orange" size="sm">
+              Port {overlayServerStatus.requested_port} is used by another application, the
+              overlay uses port {overlayServerStatus.port} instead. Update the URL in OBS.
+            </Text>
+          ) : null}
           <Spoiler
             maxHeight={0}
             showLabel="Deprecated - local file streamerOverlay.html"
@@ -779,6 +1068,10 @@ export const Settings: React.FC = () => {
               </Group>
             </div>
           </Spoiler>
+          <Divider mt={"md"} />
+          <ChatBotSettings />
+          <Divider mt={"md"} />
+          <ObsSettings />
         </Stack>
       </Box>
     </>
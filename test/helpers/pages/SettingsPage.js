@@ -299,27 +299,12 @@ class SettingsPage extends BasePage {
   }
 
   /**
-   * Check if restart required message is shown
-   * @returns {Promise<boolean>}
-   */
-  async isRestartRequiredShown() {
-    return await this.hasText("Restart required to enable / disable streamer overlay");
-  }
-
-  /**
-   * Get the restart button element
-   * @returns {Promise<WebdriverIO.Element>}
-   */
-  async getRestartButton() {
-    return await this.getByTestId("obs-restart-button");
-  }
-
-  /**
-   * Click the restart button
+   * Get the streamer overlay URL shown in the settings (the port actually in use)
+   * @returns {Promise<string>}
    */
-  async clickRestartButton() {
-    const button = await this.getRestartButton();
-    await button.click();
+  async getOBSOverlayUrl() {
+    const input = await this.getByTestId("obs-overlay-url");
+    return await input.getValue();
   }
 
   /**
@@ -4,17 +4,14 @@ import { NavigationPage, SettingsPage, GamePage } from "../helpers/pages/index.j
 /**
  * OBS Overlay E2E Tests
  *
- * IMPORTANT: These tests require app restart to enable/disable the OBS overlay server.
- * The OBS server only starts on app launch when streamerOverlayEnabled is true.
+ * The OBS overlay server starts and stops with the settings toggle, no restart needed.
  *
  * Test Strategy:
  * 1. First, verify the server is NOT running (default state)
  * 2. Enable OBS overlay in settings
- * 3. Verify restart message appears
- * 4. Click restart button (app will restart)
- * 5. After restart, verify server IS running
- * 6. Verify HTML content contains player data
- * 7. Cleanup: Disable OBS overlay for other tests
+ * 3. Verify the server IS running on the URL shown in the settings
+ * 4. Verify HTML content contains player data
+ * 5. Cleanup: Disable OBS overlay and verify the server stopped
  */
 describe("OBS Overlay E2E Tests", () => {
   before(async () => {
@@ -50,19 +47,15 @@ describe("OBS Overlay E2E Tests", () => {
       expect(isEnabled).toBe(false);
     });
 
-    it("Should enable OBS overlay and show restart message", async () => {
+    it("Should enable OBS overlay and show the server URL", async () => {
       // Enable OBS overlay
       await SettingsPage.enableOBSOverlay();
 
       // Verify it's now enabled
       expect(await SettingsPage.isOBSOverlayEnabled()).toBe(true);
 
-      // Verify restart message appears
-      expect(await SettingsPage.isRestartRequiredShown()).toBe(true);
-
-      // Verify restart button is visible
-      const restartButton = await SettingsPage.getRestartButton();
-      expect(await restartButton.isDisplayed()).toBe(true);
+      // The URL shown is the one the server actually listens on
+      expect(await SettingsPage.getOBSOverlayUrl()).toBe(testHelpers.OBS_OVERLAY_URL);
     });
 
     it("Should be able to toggle show flags checkbox when overlay is enabled", async () => {
@@ -81,17 +74,6 @@ describe("OBS Overlay E2E Tests", () => {
       expect(await SettingsPage.isShowFlagsEnabled()).toBe(initialState);
     });
 
-    it("Should enable OBS overlay again for restart test", async () => {
-      // Enable OBS overlay
-      await SettingsPage.enableOBSOverlay();
-
-      // Verify it's now enabled
-      expect(await SettingsPage.isOBSOverlayEnabled()).toBe(true);
-
-      // Verify restart message appears
-      expect(await SettingsPage.isRestartRequiredShown()).toBe(true);
-    });
-
     it("Should uncheck 'Only show stats when loading / ingame' to make overlay always visible", async () => {
       // Disable "Only show stats when loading / ingame" so the overlay is always visible
       // This is necessary for player names to show up even when not in a game
@@ -102,21 +84,8 @@ describe("OBS Overlay E2E Tests", () => {
     });
   });
 
-  describe("OBS Overlay Server After Restart", () => {
-    before(async () => {
-      await browser.pause(3000);
-      // Reload the WebDriver session to reconnect to the restarted app
-      await browser.reloadSession();
-      await testHelpers.switchToMainWindow();
-
-      await browser.pause(10000);
-    });
-
-    after(async () => {
-      await testHelpers.cleanupTestLogFile();
-    });
-
-    it("Should verify OBS server is running after restart", async () => {
+  describe("OBS Overlay Server", () => {
+    it("Should verify OBS server is running without a restart", async () => {
       // Wait for server to be available
       const serverRunning = await testHelpers.waitForOBSOverlayServer(30000);
       expect(serverRunning).toBe(true);
@@ -162,19 +131,6 @@ describe("OBS Overlay E2E Tests", () => {
 
       // Verify it's now disabled
       expect(await SettingsPage.isOBSOverlayEnabled()).toBe(false);
-
-      // Restart message should be shown (since we toggled)
-      expect(await SettingsPage.isRestartRequiredShown()).toBe(true);
-
-      // Wait for app to close and restart
-      await browser.pause(3000);
-
-      // Reload the WebDriver session to reconnect to the restarted app
-      await browser.reloadSession();
-      await testHelpers.switchToMainWindow();
-
-      // Wait for app to fully load after restart
-      await browser.pause(10000);
     });
 
     it("Should verify OBS server is NOT running after disabling", async () => {